@@ -0,0 +1,28 @@
+//! Baselines for parsing and displaying a large result, to compare parser changes against.
+//!
+//! Segments keep owned strings in their public `type_` and `data` fields, so these
+//! measure the time of the parser, not savings from borrowed or compact text.
+
+use std::hint::black_box;
+use shindan_maker::Segments;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// A result page with as many lines and images as the largest results on the site.
+fn large_result_page() -> String {
+    let lines = (0..2000)
+        .map(|i| format!("Line {} of the result&nbsp;with some text<br><img data-src=\"https://example.com/{}.png\"><br>", i, i))
+        .collect::<String>();
+
+    format!("<html><body><span id=\"post_display\">{}</span></body></html>", lines)
+}
+
+fn bench_segments(c: &mut Criterion) {
+    let html = large_result_page();
+    let segments = Segments::from_html(&html).unwrap();
+
+    c.bench_function("parse large result", |b| b.iter(|| Segments::from_html(black_box(&html)).unwrap()));
+    c.bench_function("display large result", |b| b.iter(|| black_box(&segments).to_string()));
+}
+
+criterion_group!(benches, bench_segments);
+criterion_main!(benches);
@@ -0,0 +1,35 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    grpc::compile();
+}
+
+/// Generates the service of `proto/shindan.proto`, whose messages are written by hand in `src/grpc.rs`.
+#[cfg(feature = "grpc")]
+mod grpc {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    fn method(name: &str, route_name: &str, input: &str, output: &str) -> tonic_build::manual::MethodBuilder {
+        Method::builder()
+            .name(name)
+            .route_name(route_name)
+            .input_type(format!("crate::grpc::{}", input))
+            .output_type(format!("crate::grpc::{}", output))
+            .codec_path("tonic_prost::ProstCodec")
+    }
+
+    pub fn compile() {
+        println!("cargo:rerun-if-changed=proto/shindan.proto");
+
+        let service = Service::builder()
+            .name("ShindanService")
+            .package("shindan")
+            .method(method("run_shindan", "RunShindan", "RunShindanRequest", "ShindanResult").build())
+            .method(method("run_batch", "RunBatch", "RunBatchRequest", "BatchItem").server_streaming().build())
+            .method(method("get_metadata", "GetMetadata", "GetMetadataRequest", "Metadata").build())
+            .method(method("search", "Search", "SearchRequest", "SearchResponse").build())
+            .method(method("render_image", "RenderImage", "RunShindanRequest", "Image").build())
+            .build();
+
+        Builder::new().build_client(false).compile(&[service]);
+    }
+}
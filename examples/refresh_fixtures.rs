@@ -0,0 +1,69 @@
+use std::fs;
+use scraper::{Html, Selector};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use shindan_maker::{Cassette, ShindanClient, ShindanDomain};
+
+const FIXTURES_DIR: &str = "tests/fixtures";
+const USER_NAME: &str = "fixture_user";
+/// The value CSRF tokens of captured pages are replaced with.
+const TOKEN_PLACEHOLDER: &str = "fixture-token";
+
+/// Re-download the shindans listed in `tests/fixtures/shindans.txt`.
+///
+/// Review the snapshot diffs with `cargo insta review` afterwards.
+#[tokio::main]
+async fn main() -> Result<()> {
+    let fixtures_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(FIXTURES_DIR);
+    let list = fs::read_to_string(fixtures_dir.join("shindans.txt"))?;
+
+    let ids = list
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+    for id in ids {
+        refresh(&fixtures_dir, id).await.with_context(|| format!("Failed to refresh shindan {}", id))?;
+        println!("Refreshed {}", id);
+    }
+
+    Ok(())
+}
+
+async fn refresh(fixtures_dir: &Path, id: &str) -> Result<()> {
+    let cassette_path: PathBuf = std::env::temp_dir().join(format!("shindan-maker-fixture-{}.json", id));
+
+    let client = ShindanClient::builder(ShindanDomain::En)
+        .cassette(Cassette::record(&cassette_path))
+        .build()?;
+    client.get_segments(id, USER_NAME).await?;
+
+    let interactions = Cassette::replay(&cassette_path)?.interactions();
+    fs::remove_file(&cassette_path)?;
+
+    let page = interactions
+        .iter()
+        .find(|interaction| interaction.method == "GET")
+        .context("The shindan page was not fetched")?;
+    let result = interactions
+        .iter()
+        .rfind(|interaction| interaction.method == "POST")
+        .context("The shindan was not submitted")?;
+
+    fs::write(fixtures_dir.join(format!("{}.page.html", id)), sanitize(&page.response))?;
+    fs::write(fixtures_dir.join(format!("{}.result.html", id)), sanitize(&result.response))?;
+
+    Ok(())
+}
+
+/// Replace the session-bound CSRF tokens of a captured page, keeping the rest of its markup as served.
+fn sanitize(html: &str) -> String {
+    let selector = Selector::parse("input[name='_token'], meta[name='csrf-token']").expect("Invalid token selector");
+    let document = Html::parse_document(html);
+
+    document
+        .select(&selector)
+        .filter_map(|element| element.attr("value").or_else(|| element.attr("content")))
+        .filter(|token| !token.is_empty())
+        .fold(html.to_string(), |html, token| html.replace(token, TOKEN_PLACEHOLDER))
+}
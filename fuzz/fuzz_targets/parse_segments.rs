@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use shindan_maker::Segments;
+
+fuzz_target!(|data: &[u8]| {
+    let html = String::from_utf8_lossy(data);
+
+    let lenient = Segments::from_html_lenient(&html);
+    if let Ok(segments) = Segments::from_html(&html) {
+        assert_eq!(segments, lenient.segments);
+    }
+});
@@ -0,0 +1,52 @@
+/*
+ * The C ABI of the `ffi` feature of shindan-maker.
+ *
+ * Build the shared library with
+ *   cargo rustc --release --lib --features ffi --crate-type cdylib
+ * adding the "png" feature and defining SHINDAN_IMAGE before including this
+ * header to use shindan_get_image().
+ *
+ * Functions returning pointers return NULL on failure; shindan_last_error()
+ * then describes the failure. Free what the library returns with the matching
+ * free function.
+ */
+
+#ifndef SHINDAN_H
+#define SHINDAN_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct ShindanClient ShindanClient;
+
+/* Create a client for a domain: "jp", "en", "cn", "kr" or "th". */
+ShindanClient *shindan_client_new(const char *domain);
+void shindan_client_free(ShindanClient *client);
+
+/* Run a shindan for a name; returns the result as JSON. */
+char *shindan_run(const ShindanClient *client, const char *id, const char *name);
+
+/* Get the title of a shindan. */
+char *shindan_get_title(const ShindanClient *client, const char *id);
+
+#ifdef SHINDAN_IMAGE
+/* Capture the result of a shindan as a PNG image, storing its length in len;
+ * free it with shindan_bytes_free(). Only exported with the "png" feature. */
+uint8_t *shindan_get_image(const ShindanClient *client, const char *id, const char *name, size_t *len);
+#endif
+
+/* The failure of the last call on this thread, owned by the library, or NULL. */
+const char *shindan_last_error(void);
+
+void shindan_string_free(char *s);
+void shindan_bytes_free(uint8_t *bytes, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
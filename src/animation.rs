@@ -0,0 +1,161 @@
+use std::time::Duration;
+use anyhow::{Context, Result};
+
+use crate::image::{capture, decode_jpeg, Frame};
+use crate::html_utils;
+use crate::client::ShindanClient;
+use crate::shindan_id::{self, ShindanId};
+
+/// How [`ShindanClient::get_simulated_animation`] replays the typing and shuffling effects of a result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnimationOptions {
+    /// The number of frames the effects play over, the last one showing the final result. Defaults to 12.
+    pub frames: u32,
+    /// How long each frame but the last is shown. Defaults to 80 ms.
+    pub frame_delay: Duration,
+    /// How long the final result is shown before the animation loops. Defaults to 2 s.
+    pub hold: Duration,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        Self { frames: 12, frame_delay: Duration::from_millis(80), hold: Duration::from_secs(2) }
+    }
+}
+
+impl ShindanClient {
+    /**
+    Render the result of a shindan as a looping GIF replaying its typing and shuffling effects.
+
+    The frames are simulated rather than recorded: the site's scripts do not run, and
+    each frame is the result with its effects revealed part of the way by this crate,
+    captured by the same headless Chrome as [`get_image`](Self::get_image). The timing
+    and shuffled characters are this crate's, not the site's. Results without effects
+    give a single frame; effects whose final content holds markup are shown finished
+    throughout.
+
+    # Arguments
+    - `id` - The ID of the shindan.
+    - `name` - The name to run the shindan for.
+    - `options` - The number of frames and how long they are shown.
+
+    # Returns
+    The GIF image of the result.
+
+    # Errors
+    Returns error if the submission fails or the browser cannot capture or decode a frame.
+
+    # Examples
+    ```no_run
+    use shindan_maker::{AnimationOptions, ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::En).unwrap();
+
+        let gif = client.get_simulated_animation("1222992", "test_user", &AnimationOptions::default()).await.unwrap();
+        std::fs::write("result.gif", gif).unwrap();
+    }
+    ```
+    */
+    pub async fn get_simulated_animation(
+        &self,
+        id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>,
+        name: &str,
+        options: &AnimationOptions,
+    ) -> Result<Vec<u8>> {
+        let id = shindan_id::convert(id)?;
+        if let Some(client) = self.fallback_for(&id).await? {
+            return Box::pin(client.get_simulated_animation(id, name, options)).await;
+        }
+
+        let (page, info, response_text) = self.submit_form(id.as_str(), name).await?;
+
+        // The page is finished once, with a marker in place of each effect, then each frame fills the markers in.
+        let mut effects: Vec<(String, String)> = Vec::new();
+        let html = html_utils::get_html_frame(&self.selectors, id.as_str(), &response_text, self.preserve_whitespace, &mut |mode, content| {
+            effects.push((mode.to_string(), content.to_string()));
+            effect_marker(effects.len() - 1)
+        }).context(info)?;
+        let finished = self.finish_html(html, &response_text, &page).await;
+
+        // Seeding each frame by its index makes the shuffled characters the same from run to run.
+        let frames = options.frames.max(1);
+        let mut pages: Vec<(String, Duration)> = Vec::new();
+        for index in 1..=frames {
+            let progress = f64::from(index) / f64::from(frames);
+            let mut rng = fastrand::Rng::with_seed(u64::from(index));
+            let mut html = finished.clone();
+            for (effect, (mode, content)) in effects.iter().enumerate() {
+                html = html.replacen(&effect_marker(effect), &effect_frame(mode, content, progress, &mut rng), 1);
+            }
+
+            let delay = if index == frames { options.hold } else { options.frame_delay };
+            match pages.last_mut() {
+                Some((last, last_delay)) if *last == html => *last_delay += delay,
+                _ => pages.push((html, delay)),
+            }
+        }
+
+        let mut captured = Vec::with_capacity(pages.len());
+        for (html, delay) in pages {
+            captured.push((decode_jpeg(&capture(&html, "#title_and_result").await?)?, delay));
+        }
+        encode_gif(&captured)
+    }
+}
+
+/// The placeholder of the effect at `index` in the finished page.
+fn effect_marker(index: usize) -> String {
+    format!("<!-- EFFECT {} -->", index)
+}
+
+/**
+The content of an effect `progress` of the way through, from 0 to 1.
+
+The part not revealed yet takes its final room, hidden, so that the layout holds still.
+*/
+pub(crate) fn effect_frame(mode: &str, content: &str, progress: f64, rng: &mut fastrand::Rng) -> String {
+    if progress >= 1.0 || content.contains(['<', '&']) {
+        return content.to_string();
+    }
+
+    let chars: Vec<char> = content.chars().collect();
+    let revealed = (chars.len() as f64 * progress).ceil() as usize;
+    let (shown, rest) = chars.split_at(revealed.min(chars.len()));
+    let shown: String = shown.iter().collect();
+
+    match mode {
+        "ef_typing" => format!(r#"{}<span style="visibility: hidden">{}</span>"#, shown, rest.iter().collect::<String>()),
+        "ef_shuffle" => {
+            let pool: Vec<char> = chars.iter().copied().filter(|c| !c.is_whitespace()).collect();
+            let shuffled: String = rest.iter()
+                .map(|&c| if c.is_whitespace() || pool.is_empty() { c } else { pool[rng.usize(..pool.len())] })
+                .collect();
+            format!("{}{}", shown, shuffled)
+        }
+        _ => content.to_string(),
+    }
+}
+
+/// Assemble frames into a looping GIF, padding the smaller ones with white to the size of the largest.
+pub(crate) fn encode_gif(frames: &[(Frame, Duration)]) -> Result<Vec<u8>> {
+    let width = frames.iter().map(|(frame, _)| frame.width).max().unwrap_or(1);
+    let height = frames.iter().map(|(frame, _)| frame.height).max().unwrap_or(1);
+
+    let mut encoder = gif::Encoder::new(Vec::new(), width, height, &[])?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+    for (frame, delay) in frames {
+        let mut pixels = vec![u8::MAX; usize::from(width) * usize::from(height) * 3];
+        let row = usize::from(frame.width) * 3;
+        for (y, line) in frame.pixels.chunks_exact(row).enumerate() {
+            let start = y * usize::from(width) * 3;
+            pixels[start..start + row].copy_from_slice(line);
+        }
+
+        let mut gif_frame = gif::Frame::from_rgb_speed(width, height, &pixels, 10);
+        gif_frame.delay = u16::try_from(delay.as_millis() / 10).unwrap_or(u16::MAX);
+        encoder.write_frame(&gif_frame)?;
+    }
+    Ok(encoder.into_inner()?)
+}
@@ -0,0 +1,263 @@
+use std::fs;
+use std::path::Path;
+use std::collections::BTreeMap;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
+
+use crate::client::ShindanClient;
+#[cfg(feature = "html")]
+use crate::html_utils;
+use crate::result::ShindanResult;
+use crate::shindan_id::{self, ShindanId};
+
+/// The file an archive is described by, in its directory.
+const MANIFEST: &str = "archive.json";
+
+/**
+An offline snapshot of a result, written by [`ShindanClient::archive_result`].
+
+Paths are relative to the directory of the archive.
+*/
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Archive {
+    /// The result, with the original URLs of its images.
+    pub result: ShindanResult,
+    /// The images of the result downloaded, by their original URLs.
+    pub images: BTreeMap<String, String>,
+    /// The page of the result, with the `html` feature.
+    pub html: Option<String>,
+    /// The JPEG screenshot of the result, with the `image` feature.
+    pub screenshot: Option<String>,
+    pub archived_at: DateTime<Utc>,
+}
+
+impl Archive {
+    /**
+    Read an archive written by [`ShindanClient::archive_result`].
+
+    # Arguments
+    - `dir` - The directory of the archive.
+
+    # Errors
+    Returns error if the directory holds no archive.
+    */
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self> {
+        let path = dir.as_ref().join(MANIFEST);
+        let json = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+impl ShindanResult {
+    /**
+    Read the result of an archive written by [`ShindanClient::archive_result`], to render it again without refetching.
+
+    Images and posters that were downloaded point to their files in the archive, as
+    `file://` URLs; the others keep their original URLs, which [`Archive::load`] keeps for all.
+
+    # Arguments
+    - `dir` - The directory of the archive.
+
+    # Errors
+    Returns error if the directory holds no archive.
+    */
+    pub fn from_archive(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let dir = dir.canonicalize().with_context(|| format!("Failed to find {}", dir.display()))?;
+        let archive = Archive::load(&dir)?;
+
+        let mut result = archive.result;
+        for segment in result.segments.0.iter_mut() {
+            let key = match segment.type_.as_ref() {
+                "image" => "file",
+                "video" | "audio" => "poster",
+                _ => continue,
+            };
+            let Some(Value::String(url)) = segment.data.get_mut(key) else {
+                continue;
+            };
+            if let Some(local) = archive.images.get(url.as_str()).and_then(|file| Url::from_file_path(dir.join(file)).ok()) {
+                *url = local.to_string();
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl ShindanClient {
+    /**
+    Save a complete offline snapshot of a result, e.g. to preserve it before the shindan is deleted.
+
+    The directory receives `archive.json` describing the [`Archive`] and the images of
+    the result under `images/`. With the `html` feature, the page of the result is saved
+    as `result.html`, with its images pointing to the downloaded ones and its stylesheets
+    and scripts inlined; with the `image` feature, a screenshot is saved as `screenshot.jpeg`.
+    All of them come from a single submission. Images that cannot be downloaded, and the
+    screenshot when no browser can be started, are left out with a warning.
+
+    # Arguments
+    - `id` - The ID of the shindan.
+    - `name` - The name to run the shindan with.
+    - `dir` - The directory to write to, created if missing.
+
+    # Returns
+    The archive written.
+
+    # Errors
+    Returns error if the submission fails or the files cannot be written.
+
+    # Examples
+    ```no_run
+    use shindan_maker::{Archive, ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::En).unwrap();
+
+        let archive = client.archive_result("1222992", "test_user", "archive/1222992").await.unwrap();
+        assert_eq!(archive, Archive::load("archive/1222992").unwrap());
+    }
+    ```
+    */
+    pub async fn archive_result(
+        &self,
+        id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>,
+        name: &str,
+        dir: impl AsRef<Path>,
+    ) -> Result<Archive> {
+        let id = shindan_id::convert(id)?;
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir.join("images")).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        // The page, the screenshot and the result come from one submission, so that they agree.
+        #[cfg(feature = "html")]
+        let (result, page) = self.get_result_with_html(id.clone(), name).await?;
+        #[cfg(not(feature = "html"))]
+        let result = self.get_result(id.clone(), name).await?;
+
+        let mut images = BTreeMap::new();
+        let urls = result.segments.iter().filter_map(|segment| match segment.type_.as_ref() {
+            "image" => segment.data.get("file").and_then(Value::as_str),
+            "video" | "audio" => segment.data.get("poster").and_then(Value::as_str),
+            _ => None,
+        });
+        for url in urls {
+            if images.contains_key(url) {
+                continue;
+            }
+            match self.download(url).await {
+                Ok((bytes, extension)) => {
+                    let file = format!("images/{}.{}", images.len(), extension);
+                    write(dir, &file, &bytes)?;
+                    images.insert(url.to_string(), file);
+                }
+                Err(err) => log::warn!(id = id.as_str(); "Left an image out of the archive: {:#}", err),
+            }
+        }
+
+        #[cfg(feature = "html")]
+        let (html, screenshot) = self.archive_page(page, dir, &images).await?;
+        #[cfg(not(feature = "html"))]
+        let (html, screenshot) = (None, None);
+
+        let archive = Archive { result, images, html, screenshot, archived_at: Utc::now() };
+        write(dir, MANIFEST, serde_json::to_string_pretty(&archive)?.as_bytes())?;
+        Ok(archive)
+    }
+
+    /// Save the page of a result and its screenshot, returning their files.
+    #[cfg(feature = "html")]
+    async fn archive_page(
+        &self,
+        html: String,
+        dir: &Path,
+        images: &BTreeMap<String, String>,
+    ) -> Result<(Option<String>, Option<String>)> {
+        #[cfg(feature = "image")]
+        let screenshot = match crate::image::capture(&html, "#title_and_result").await {
+            Ok(screenshot) => {
+                write(dir, "screenshot.jpeg", &screenshot)?;
+                Some("screenshot.jpeg".to_string())
+            }
+            Err(err) => {
+                log::warn!("Left the screenshot out of the archive: {:#}", err);
+                None
+            }
+        };
+        #[cfg(not(feature = "image"))]
+        let screenshot = None;
+
+        let html = self.inline_html(html, images).await;
+        write(dir, "result.html", html.as_bytes())?;
+        Ok((Some("result.html".to_string()), screenshot))
+    }
+
+    /// Download a file through the asset cache, with the extension its content type or URL suggests.
+    async fn download(&self, url: &str) -> Result<(Vec<u8>, String)> {
+        let asset = self.fetch_asset(url).await?;
+
+        let content_type = asset.content_type.as_deref().unwrap_or_default();
+        let extension = match content_type.split(';').next().unwrap_or_default().trim() {
+            "image/jpeg" => "jpeg",
+            "image/png" => "png",
+            "image/gif" => "gif",
+            "image/webp" => "webp",
+            "image/svg+xml" => "svg",
+            _ => url
+                .split(['?', '#'])
+                .next()
+                .and_then(|path| path.rsplit_once('.'))
+                .map(|(_, extension)| extension)
+                .filter(|extension| !extension.is_empty() && extension.len() <= 4 && extension.chars().all(|c| c.is_ascii_alphanumeric()))
+                .unwrap_or("bin"),
+        };
+
+        Ok((asset.bytes, extension.to_string()))
+    }
+
+    /// Point the images of a page to their downloaded files and inline its stylesheets and scripts.
+    #[cfg(feature = "html")]
+    async fn inline_html(&self, mut html: String, images: &BTreeMap<String, String>) -> String {
+        for (url, file) in images {
+            html = html.replace(&url.replace('&', "&amp;"), file).replace(url.as_str(), file);
+        }
+
+        // The document is not `Send`, so it is dropped before downloading.
+        let assets: Vec<(String, bool)> = {
+            let document = scraper::Html::parse_document(&html);
+            let stylesheets = scraper::Selector::parse(r#"link[rel="stylesheet"][href]"#).expect("Invalid stylesheet selector");
+            let scripts = scraper::Selector::parse("script[src]").expect("Invalid script selector");
+            document
+                .select(&stylesheets)
+                .filter_map(|link| link.value().attr("href").map(|href| (href.to_string(), true)))
+                .chain(document.select(&scripts).filter_map(|script| script.value().attr("src").map(|src| (src.to_string(), false))))
+                .collect()
+        };
+
+        for (url, stylesheet) in assets {
+            let text = match self.download(&url).await {
+                Ok((bytes, _)) => String::from_utf8_lossy(&bytes).into_owned(),
+                Err(err) => {
+                    log::warn!("Left an asset of the archived page remote: {:#}", err);
+                    continue;
+                }
+            };
+            html = if stylesheet {
+                html_utils::replace_tag(&html, &url, "<link", ">", &format!("<style>{}</style>", text.replace("</style", "<\\/style")))
+            } else {
+                html_utils::replace_tag(&html, &url, "<script", "</script>", &format!("<script>{}</script>", text.replace("</script", "<\\/script")))
+            };
+        }
+
+        html
+    }
+}
+
+fn write(dir: &Path, file: &str, contents: &[u8]) -> Result<()> {
+    let path = dir.join(file);
+    fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
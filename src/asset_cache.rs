@@ -0,0 +1,232 @@
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use std::sync::atomic::{AtomicU64, Ordering};
+use anyhow::{Context, Result};
+use tokio::fs;
+use chrono::{DateTime, Utc};
+use reqwest::header::{self, HeaderMap};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::fnv::Fnv1a;
+use crate::client::ShindanClient;
+
+/// The default bound of the bytes of the assets kept by the asset cache: 64 MiB.
+pub(crate) const DEFAULT_MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+/// A static asset, e.g. a stylesheet or an image, as downloaded or read from the asset cache.
+#[derive(Debug, Clone)]
+pub(crate) struct Asset {
+    pub(crate) bytes: Vec<u8>,
+    pub(crate) content_type: Option<String>,
+}
+
+/**
+Keeps static assets on disk for as long as their `Cache-Control` headers allow.
+
+Once the assets take more than `max_bytes`, the ones stored the longest ago are evicted.
+*/
+#[derive(Debug)]
+pub(crate) struct AssetCache {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+/// What the cache knows about an asset, stored next to its bytes.
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    url: String,
+    content_type: Option<String>,
+    expires_at: DateTime<Utc>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl AssetCache {
+    pub(crate) fn new(dir: impl Into<PathBuf>, max_bytes: u64) -> Self {
+        Self { dir: dir.into(), max_bytes }
+    }
+
+    /// The paths of the entry and the bytes of a URL.
+    fn paths(&self, url: &str) -> (PathBuf, PathBuf) {
+        let mut hasher = Fnv1a::default();
+        hasher.write(url.as_bytes());
+        let hash = hasher.finish();
+        (self.dir.join(format!("{:016x}.json", hash)), self.dir.join(format!("{:016x}.bin", hash)))
+    }
+
+    async fn read(&self, url: &str) -> Option<(Entry, Vec<u8>)> {
+        let (entry_path, bytes_path) = self.paths(url);
+        let entry: Entry = serde_json::from_slice(&fs::read(entry_path).await.ok()?).ok()?;
+        if entry.url != url {
+            return None;
+        }
+        Some((entry, fs::read(bytes_path).await.ok()?))
+    }
+
+    async fn write(&self, entry: &Entry, bytes: Option<&[u8]>) -> Result<()> {
+        let (entry_path, bytes_path) = self.paths(&entry.url);
+        fs::create_dir_all(&self.dir).await.with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        if let Some(bytes) = bytes {
+            write_atomically(&bytes_path, bytes).await?;
+        }
+        write_atomically(&entry_path, &serde_json::to_vec(entry)?).await?;
+
+        if bytes.is_some() {
+            self.evict(&bytes_path).await;
+        }
+        Ok(())
+    }
+
+    async fn remove(&self, url: &str) {
+        let (entry_path, bytes_path) = self.paths(url);
+        let _ = fs::remove_file(entry_path).await;
+        let _ = fs::remove_file(bytes_path).await;
+    }
+
+    /// Remove the assets stored the longest ago, but the one just stored, until the rest fit in `max_bytes`.
+    async fn evict(&self, kept: &Path) {
+        let mut assets = Vec::new();
+        let mut total = 0;
+        let Ok(mut dir) = fs::read_dir(&self.dir).await else {
+            return;
+        };
+        while let Ok(Some(file)) = dir.next_entry().await {
+            let path = file.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("bin") {
+                continue;
+            }
+            let Ok(metadata) = file.metadata().await else {
+                continue;
+            };
+            total += metadata.len();
+            if path != kept {
+                assets.push((metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH), metadata.len(), path));
+            }
+        }
+
+        assets.sort();
+        for (_, len, path) in assets {
+            if total <= self.max_bytes {
+                break;
+            }
+            log::debug!("Evicting {} from the asset cache", path.display());
+            let _ = fs::remove_file(path.with_extension("json")).await;
+            if fs::remove_file(&path).await.is_ok() {
+                total -= len;
+            }
+        }
+    }
+}
+
+/// Write a file through a temporary file renamed over it, so that readers never see it half written.
+async fn write_atomically(path: &Path, bytes: &[u8]) -> Result<()> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let temp = path.with_extension(format!("{}-{}.tmp", std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed)));
+
+    fs::write(&temp, bytes).await.with_context(|| format!("Failed to write {}", temp.display()))?;
+    if let Err(err) = fs::rename(&temp, path).await {
+        let _ = fs::remove_file(&temp).await;
+        return Err(err).with_context(|| format!("Failed to write {}", path.display()));
+    }
+    Ok(())
+}
+
+/**
+How long a response may be reused, per its `Cache-Control` header, or `None` if it may not be stored.
+
+`no-cache` responses are stored but revalidated every time; responses without a
+`Cache-Control` header are not stored.
+*/
+fn freshness(headers: &HeaderMap) -> Option<Duration> {
+    let cache_control = headers.get(header::CACHE_CONTROL)?.to_str().ok()?.to_ascii_lowercase();
+    let age = header_str(headers, header::AGE).and_then(|age| age.parse().ok()).unwrap_or(0);
+
+    let mut max_age = None;
+    for directive in cache_control.split(',').map(str::trim) {
+        match directive.split_once('=') {
+            _ if directive == "no-store" || directive == "private" => return None,
+            _ if directive == "no-cache" => return Some(Duration::ZERO),
+            Some(("max-age", seconds)) => max_age = seconds.trim_matches('"').parse::<u64>().ok(),
+            _ => {}
+        }
+    }
+
+    max_age.map(|max_age| Duration::from_secs(max_age.saturating_sub(age)))
+}
+
+fn header_str(headers: &HeaderMap, name: header::HeaderName) -> Option<String> {
+    headers.get(name).and_then(|value| value.to_str().ok()).map(str::to_string)
+}
+
+impl ShindanClient {
+    /// Download a static asset, reusing the copy in the asset cache while it is fresh and revalidating it once stale.
+    pub(crate) async fn fetch_asset(&self, url: &str) -> Result<Asset> {
+        let Some(cache) = &self.asset_cache else {
+            return self.download_asset(url).await.map(|(asset, _)| asset);
+        };
+
+        let cached = cache.read(url).await;
+        if let Some((entry, bytes)) = &cached {
+            if entry.expires_at > Utc::now() {
+                return Ok(Asset { bytes: bytes.clone(), content_type: entry.content_type.clone() });
+            }
+        }
+
+        let mut request = self.client.get(url);
+        if let Some((entry, _)) = &cached {
+            if let Some(etag) = &entry.etag {
+                request = request.header(header::IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let response = self.send(request).await.with_context(|| format!("Failed to download {}", url))?;
+
+        if let (StatusCode::NOT_MODIFIED, Some((mut entry, bytes))) = (response.status(), cached) {
+            match freshness(response.headers()) {
+                Some(fresh) => {
+                    entry.expires_at = Utc::now() + fresh;
+                    if let Err(err) = cache.write(&entry, None).await {
+                        log::warn!("Failed to refresh a cached asset: {:#}", err);
+                    }
+                }
+                None => cache.remove(url).await,
+            }
+            return Ok(Asset { bytes, content_type: entry.content_type });
+        }
+
+        let (asset, headers) = self.read_asset(url, response).await?;
+        match freshness(&headers) {
+            Some(fresh) => {
+                let entry = Entry {
+                    url: url.to_string(),
+                    content_type: asset.content_type.clone(),
+                    expires_at: Utc::now() + fresh,
+                    etag: header_str(&headers, header::ETAG),
+                    last_modified: header_str(&headers, header::LAST_MODIFIED),
+                };
+                if let Err(err) = cache.write(&entry, Some(&asset.bytes)).await {
+                    log::warn!("Failed to cache an asset: {:#}", err);
+                }
+            }
+            None => cache.remove(url).await,
+        }
+        Ok(asset)
+    }
+
+    async fn download_asset(&self, url: &str) -> Result<(Asset, HeaderMap)> {
+        let response = self.send(self.client.get(url)).await.with_context(|| format!("Failed to download {}", url))?;
+        self.read_asset(url, response).await
+    }
+
+    async fn read_asset(&self, url: &str, response: reqwest::Response) -> Result<(Asset, HeaderMap)> {
+        let response = response.error_for_status().with_context(|| format!("Failed to download {}", url))?;
+        let headers = response.headers().clone();
+        let content_type = header_str(&headers, header::CONTENT_TYPE);
+
+        Ok((Asset { bytes: response.bytes().await?.to_vec(), content_type }, headers))
+    }
+}
@@ -0,0 +1,69 @@
+use std::fmt;
+use std::sync::Arc;
+use std::pin::Pin;
+use std::future::Future;
+use anyhow::Result;
+
+/// A future returned by a [`SessionRefresher`].
+pub type RefreshFuture = Pin<Box<dyn Future<Output = Result<String>> + Send>>;
+
+/// A callback producing a fresh `_session` cookie value.
+pub type SessionRefresher = Arc<dyn Fn() -> RefreshFuture + Send + Sync>;
+
+/// Account credentials used to log in to ShindanMaker.
+#[derive(Clone)]
+pub struct Credentials {
+    pub email: String,
+    pub password: String,
+}
+
+impl Credentials {
+    /**
+    Create new credentials.
+
+    # Arguments
+    - `email` - The email address of the account.
+    - `password` - The password of the account.
+
+    # Returns
+    New credentials.
+
+    # Examples
+    ```
+    use shindan_maker::Credentials;
+
+    let credentials = Credentials::new("user@example.com", "password");
+    ```
+    */
+    pub fn new(email: &str, password: &str) -> Self {
+        Self {
+            email: email.to_string(),
+            password: password.to_string(),
+        }
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("email", &self.email)
+            .field("password", &"***")
+            .finish()
+    }
+}
+
+/// The way a client (re-)authenticates when its session expires.
+#[derive(Clone)]
+pub(crate) enum Auth {
+    Credentials(Credentials),
+    Refresher(SessionRefresher),
+}
+
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Credentials(credentials) => f.debug_tuple("Credentials").field(credentials).finish(),
+            Self::Refresher(_) => f.write_str("Refresher"),
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use std::sync::{Arc, Mutex};
+use chrono::Utc;
+use scraper::Html;
+use anyhow::{Context, Result};
+use tokio::sync::Semaphore;
+use futures::future::join_all;
+use futures::stream::{self, Stream, StreamExt};
+
+use crate::html_utils;
+use crate::http_utils;
+use crate::client::ShindanClient;
+use crate::shindan_id::{self, ShindanId};
+use crate::segment::Segments;
+use crate::page::ShindanPage;
+use crate::result::ShindanResult;
+use crate::result_cache::CacheKey;
+use crate::response_info::ResponseInfo;
+use crate::jitter::Spacing;
+
+impl ShindanClient {
+    /**
+    Get the segments of a shindan for many names.
+
+    The form of the shindan is fetched once and reused for every name,
+    submissions run at most `concurrency` at a time, and the client's rate limit applies.
+    Like [`get_segments`](Self::get_segments), names already in the result cache are not
+    submitted again, and new results are stored in the result cache and the history.
+
+    # Arguments
+    - `id` - The ID of the shindan.
+    - `names` - The names to use for the shindan.
+    - `concurrency` - The maximum number of submissions in flight.
+
+    # Returns
+    The segments for each name, in the order of `names`.
+
+    # Errors
+    Returns error if the form of the shindan cannot be fetched; failures of
+    individual submissions are reported per name.
+
+    # Examples
+    ```
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::En).unwrap();
+
+        let results = client
+            .get_segments_batch("1222992", &["Alice", "Bob", "Carol"], 2)
+            .await
+            .unwrap();
+
+        for result in results {
+            println!("{}", result.unwrap());
+        }
+    }
+    ```
+    */
+    pub async fn get_segments_batch(
+        &self,
+        id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>,
+        names: &[&str],
+        concurrency: usize,
+    ) -> Result<Vec<Result<Segments>>> {
+        let id = shindan_id::convert(id)?;
+        if let Some(client) = self.fallback_for(&id).await? {
+            return Box::pin(client.get_segments_batch(id, names, concurrency)).await;
+        }
+
+        let id = id.as_str();
+        let url = self.url(id);
+        let page = Mutex::new(self.page(&url).await?);
+        let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+        let spacing = self.batch_spacing.map(Spacing::new);
+
+        let submissions = names.iter().map(|name| {
+            let url = &url;
+            let page = &page;
+            let spacing = &spacing;
+            let semaphore = semaphore.clone();
+
+            async move {
+                let _permit = semaphore.acquire().await?;
+
+                let key = CacheKey::new(self.domain.clone(), id, name, Utc::now());
+                if let Some(cache) = &self.result_cache {
+                    if let Some(result) = cache.get(&key).await.context("Failed to read the result cache")? {
+                        return Ok(result.segments);
+                    }
+                }
+
+                if let Some(spacing) = spacing {
+                    spacing.wait().await;
+                }
+
+                let (page, info, response_text) = self.submit_shared_form(page, url, name).await?;
+
+                if self.tracks_results() {
+                    return Ok(self.store_result(id, name, key, &page, info, &response_text).await?.segments);
+                }
+
+                html_utils::get_segments(&self.selectors, self.parse_options, Some(*name), &Html::parse_document(&response_text))
+                    .with_context(|| info.clone())
+            }
+        });
+
+        Ok(join_all(submissions).await)
+    }
+
+    /// Submit the form of a page shared by a batch, replacing the page with a fresh one if its CSRF token was rejected.
+    async fn submit_shared_form(
+        &self,
+        shared: &Mutex<Arc<ShindanPage>>,
+        url: &str,
+        name: &str,
+    ) -> Result<(Arc<ShindanPage>, ResponseInfo, String)> {
+        let page = shared.lock().unwrap().clone();
+        let response = self.post_form(&page, name).await?;
+
+        if !http_utils::is_csrf_token_mismatch(response.status()) {
+            let info = ResponseInfo::of(&response);
+            return Ok((page, info, self.read_text(response).await?));
+        }
+
+        // Another submission of the batch may have replaced the page already.
+        let current = shared.lock().unwrap().clone();
+        let page = if Arc::ptr_eq(&current, &page) {
+            if let Some(cache) = &self.form_cache {
+                cache.invalidate(url);
+            }
+
+            let page = self.page(url).await?;
+            *shared.lock().unwrap() = page.clone();
+            page
+        } else {
+            current
+        };
+
+        let response = self.post_form(&page, name).await?;
+        let info = ResponseInfo::of(&response);
+        Ok((page, info, self.read_text(response).await?))
+    }
+
+    /**
+    Run many submissions, yielding each result as soon as it is ready.
+
+    At most `concurrency` submissions are in flight, and new ones only start
+    as the stream is polled, so slow consumers apply backpressure instead of
+    results being buffered.
+
+    # Arguments
+    - `jobs` - The `(id, name)` pairs to submit.
+    - `concurrency` - The maximum number of submissions in flight.
+
+    # Returns
+    A stream of `(id, name)` keys with their results, in completion order.
+
+    # Examples
+    ```
+    use futures::StreamExt;
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::En).unwrap();
+
+        let jobs = ["Alice", "Bob"].map(|name| ("1222992".to_string(), name.to_string()));
+        let mut results = client.submit_stream(jobs, 2);
+
+        while let Some(((_id, name), result)) = results.next().await {
+            println!("{}: {}", name, result.unwrap().segments);
+        }
+    }
+    ```
+    */
+    pub fn submit_stream<'a, I>(
+        &'a self,
+        jobs: I,
+        concurrency: usize,
+    ) -> impl Stream<Item = ((String, String), Result<ShindanResult>)> + 'a
+    where
+        I: IntoIterator<Item = (String, String)>,
+        I::IntoIter: 'a,
+    {
+        let spacing = self.batch_spacing.map(|jitter| Arc::new(Spacing::new(jitter)));
+
+        stream::iter(jobs)
+            .map(move |(id, name)| {
+                let spacing = spacing.clone();
+                async move {
+                    if let Some(spacing) = spacing {
+                        spacing.wait().await;
+                    }
+
+                    let result = self.get_result(&id, &name).await;
+                    ((id, name), result)
+                }
+            })
+            .buffer_unordered(concurrency.max(1))
+    }
+}
@@ -0,0 +1,991 @@
+use std::sync::Arc;
+use anyhow::Result;
+use std::future::Future;
+use std::time::Duration;
+use reqwest::cookie::Jar;
+
+use crate::client::ShindanClient;
+use crate::form_cache::FormCache;
+use crate::rate_limit::RateLimiter;
+use crate::crawl_budget::{self, CrawlBudget};
+use crate::circuit_breaker::CircuitBreaker;
+#[cfg(any(feature = "html", feature = "archive"))]
+use crate::asset_cache::AssetCache;
+use crate::jitter::Jitter;
+use crate::selectors::{SelectorConfig, Selectors, SELECTORS};
+use crate::metrics::{Metrics, ResponseMetrics};
+use crate::metadata_cache::MetadataCache;
+#[cfg(feature = "segments")]
+use crate::result_cache::{MemoryCache, ResultCache};
+#[cfg(feature = "segments")]
+use crate::translate::Translator;
+#[cfg(feature = "segments")]
+use crate::parse_options::ParseOptions;
+#[cfg(feature = "scores")]
+use crate::scores::ScoreRule;
+#[cfg(feature = "history")]
+use crate::history::HistoryStore;
+#[cfg(feature = "cassette")]
+use crate::cassette::Cassette;
+#[cfg(feature = "html")]
+use crate::site_assets::SiteAssets;
+#[cfg(feature = "html")]
+use crate::fonts::FontStack;
+#[cfg(all(feature = "html", feature = "segments"))]
+use crate::chart::ChartOverrides;
+use crate::shindan_domain::ShindanDomain;
+use crate::auth::{Auth, Credentials};
+
+/// A builder for [`ShindanClient`].
+#[derive(Debug)]
+pub struct ShindanClientBuilder {
+    domain: ShindanDomain,
+    base_url: Option<String>,
+    fallback_domains: Vec<ShindanDomain>,
+    follow_domain_redirects: bool,
+    selectors: Option<SelectorConfig>,
+    timeout: Duration,
+    auth: Option<Auth>,
+    form_cache_ttl: Option<Duration>,
+    rate_limit: Option<(u32, Duration)>,
+    crawl_budget: Option<(u32, Duration)>,
+    circuit_breaker: Option<(u32, Duration)>,
+    #[cfg(any(feature = "html", feature = "archive"))]
+    asset_cache: Option<std::path::PathBuf>,
+    #[cfg(any(feature = "html", feature = "archive"))]
+    asset_cache_size: u64,
+    submit_delay: Option<Jitter>,
+    #[cfg(any(feature = "segments", feature = "image"))]
+    batch_spacing: Option<Jitter>,
+    seed: Option<String>,
+    retries: u32,
+    retry_submissions: bool,
+    metadata_cache_ttl: Option<Duration>,
+    compression: bool,
+    metrics: Option<Metrics>,
+    #[cfg(feature = "segments")]
+    result_cache: Option<Arc<dyn ResultCache>>,
+    #[cfg(feature = "segments")]
+    result_cache_ttl: Option<Duration>,
+    #[cfg(feature = "segments")]
+    parse_options: ParseOptions,
+    #[cfg(feature = "html")]
+    preserve_whitespace: bool,
+    #[cfg(feature = "html")]
+    site_assets: SiteAssets,
+    #[cfg(feature = "html")]
+    fonts: FontStack,
+    #[cfg(feature = "html")]
+    include_description: bool,
+    #[cfg(feature = "html")]
+    include_attribution: bool,
+    #[cfg(all(feature = "html", feature = "segments"))]
+    chart_overrides: ChartOverrides,
+    #[cfg(feature = "segments")]
+    translator: Option<Arc<dyn Translator>>,
+    #[cfg(feature = "scores")]
+    score_rules: Vec<ScoreRule>,
+    #[cfg(feature = "history")]
+    history: Option<Arc<dyn HistoryStore>>,
+    #[cfg(feature = "cassette")]
+    cassette: Option<Cassette>,
+}
+
+impl ShindanClientBuilder {
+    pub(crate) fn new(domain: ShindanDomain) -> Self {
+        const TIMEOUT_SECS: u64 = 3;
+
+        Self {
+            domain,
+            base_url: None,
+            fallback_domains: Vec::new(),
+            follow_domain_redirects: false,
+            selectors: None,
+            timeout: Duration::from_secs(TIMEOUT_SECS),
+            auth: None,
+            form_cache_ttl: None,
+            rate_limit: None,
+            crawl_budget: None,
+            circuit_breaker: None,
+            #[cfg(any(feature = "html", feature = "archive"))]
+            asset_cache: None,
+            #[cfg(any(feature = "html", feature = "archive"))]
+            asset_cache_size: crate::asset_cache::DEFAULT_MAX_BYTES,
+            submit_delay: None,
+            seed: None,
+            retries: 0,
+            retry_submissions: false,
+            #[cfg(any(feature = "segments", feature = "image"))]
+            batch_spacing: None,
+            metadata_cache_ttl: None,
+            compression: true,
+            metrics: None,
+            #[cfg(feature = "segments")]
+            result_cache: None,
+            #[cfg(feature = "segments")]
+            result_cache_ttl: None,
+            #[cfg(feature = "segments")]
+            parse_options: ParseOptions::default(),
+            #[cfg(feature = "html")]
+            preserve_whitespace: false,
+            #[cfg(feature = "html")]
+            site_assets: SiteAssets::Builtin,
+            #[cfg(feature = "html")]
+            fonts: FontStack::default(),
+            #[cfg(feature = "html")]
+            include_description: false,
+            #[cfg(feature = "html")]
+            include_attribution: false,
+            #[cfg(all(feature = "html", feature = "segments"))]
+            chart_overrides: ChartOverrides::default(),
+            #[cfg(feature = "segments")]
+            translator: None,
+            #[cfg(feature = "scores")]
+            score_rules: Vec::new(),
+            #[cfg(feature = "history")]
+            history: None,
+            #[cfg(feature = "cassette")]
+            cassette: None,
+        }
+    }
+
+    /// Set the timeout of every request. Defaults to 3 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /**
+    Retry requests failing with a connection error, a timeout, 429 or a 5xx status. Defaults to no retries.
+
+    Submissions are not retried, since the site may have run them already; see
+    [`retry_submissions`](Self::retry_submissions).
+
+    Retries wait 0.5 seconds, then twice as long before each further attempt, and
+    count towards the rate limit and crawl budget like any request.
+
+    # Arguments
+    - `retries` - The maximum number of retries per request.
+    */
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /**
+    Retry submissions failing transiently too. Defaults to `false`.
+
+    A retried submission may run the shindan twice, which counts twice on the site
+    and can give a different result for random shindans.
+    */
+    pub fn retry_submissions(mut self, retry_submissions: bool) -> Self {
+        self.retry_submissions = retry_submissions;
+        self
+    }
+
+    /**
+    Fail fast while the site is failing, instead of adding to its load. Disabled by default.
+
+    After `failures` consecutive requests fail with a 5xx status, a timeout or a connection
+    error, requests return a [`CircuitOpen`](crate::CircuitOpen) error without being sent,
+    across all clones of the client. Once the cool-down is over, the next request probes
+    the site: if it succeeds, requests are sent again; otherwise another cool-down starts.
+
+    # Arguments
+    - `failures` - The consecutive failures opening the circuit, at least 1.
+    - `cool_down` - How long to refuse requests before probing the site.
+
+    # Examples
+    ```
+    use anyhow::Result;
+    use std::time::Duration;
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    fn main() -> Result<()> {
+        let client = ShindanClient::builder(ShindanDomain::En)
+            .circuit_breaker(5, Duration::from_secs(30))
+            .build()?;
+        Ok(())
+    }
+    ```
+    */
+    pub fn circuit_breaker(mut self, failures: u32, cool_down: Duration) -> Self {
+        self.circuit_breaker = Some((failures, cool_down));
+        self
+    }
+
+    /**
+    Keep the static assets the client downloads, e.g. the [site assets](Self::site_assets) or the
+    images of archived pages, in a directory for as long as their `Cache-Control` headers allow.
+
+    Assets are reused across clients and processes sharing the directory; stale ones
+    are revalidated with their `ETag` or `Last-Modified` header when they have one.
+    Responses without a `Cache-Control` header, or marked `no-store` or `private`, are not kept.
+    Once the assets take more than [`asset_cache_size`](Self::asset_cache_size), the ones
+    stored the longest ago are evicted.
+
+    # Arguments
+    - `dir` - The directory of the cache, created if missing.
+
+    # Examples
+    ```
+    use anyhow::Result;
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    fn main() -> Result<()> {
+        let client = ShindanClient::builder(ShindanDomain::En)
+            .asset_cache(std::env::temp_dir().join("shindan-assets"))
+            .build()?;
+        Ok(())
+    }
+    ```
+    */
+    #[cfg(any(feature = "html", feature = "archive"))]
+    pub fn asset_cache(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        self.asset_cache = Some(dir.into());
+        self
+    }
+
+    /// Set how many bytes of assets the [asset cache](Self::asset_cache) keeps. Defaults to 64 MiB.
+    #[cfg(any(feature = "html", feature = "archive"))]
+    pub fn asset_cache_size(mut self, max_bytes: u64) -> Self {
+        self.asset_cache_size = max_bytes;
+        self
+    }
+
+    /**
+    Send requests to another server than the domain's, e.g. a mirror or a mock server in tests.
+
+    Results still report the configured domain; to report the server instead, build
+    the client for a [`ShindanDomain::custom`] domain.
+
+    # Arguments
+    - `base_url` - The base URL, ending with a slash, e.g. `http://127.0.0.1:8080/`.
+    */
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = Some(base_url.to_string());
+        self
+    }
+
+    /**
+    Try other domains, in order, for shindans the client's domain answers 404 for.
+
+    The first time a shindan is requested, its page is fetched from each domain until
+    one serves it, and that domain is used for the shindan from then on. Enable the
+    [form cache](Self::form_cache_ttl) to reuse the page fetched while probing.
+
+    # Arguments
+    - `domains` - The domains to try after the client's own.
+
+    # Examples
+    ```
+    use anyhow::Result;
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    fn main() -> Result<()> {
+        let client = ShindanClient::builder(ShindanDomain::Cn)
+            .fallback_domains([ShindanDomain::Jp, ShindanDomain::En])
+            .build()?;
+        Ok(())
+    }
+    ```
+    */
+    pub fn fallback_domains(mut self, domains: impl IntoIterator<Item = ShindanDomain>) -> Self {
+        self.fallback_domains = domains.into_iter().collect();
+        self
+    }
+
+    /**
+    Send requests for a shindan to the domain the site last redirected its page to, instead of the client's own.
+
+    The site can redirect between regional domains. Forms are always submitted to the
+    domain that served them, so their CSRF token stays valid; with this enabled, later
+    requests for the same shindan also go straight to that domain, while other shindans
+    keep using the client's own. Defaults to `false`.
+
+    See [`ShindanClient::redirected_domain`] to only inspect the redirects.
+    */
+    pub fn follow_domain_redirects(mut self, enabled: bool) -> Self {
+        self.follow_domain_redirects = enabled;
+        self
+    }
+
+    /**
+    Scrape pages with other CSS selectors than the built-in ones.
+
+    # Arguments
+    - `selectors` - The selector configuration.
+
+    # Errors
+    [`build`](Self::build) returns error if a selector is invalid.
+
+    See [`SelectorConfig`] for an example.
+    */
+    pub fn selectors(mut self, selectors: SelectorConfig) -> Self {
+        self.selectors = Some(selectors);
+        self
+    }
+
+    /**
+    Reuse the form of a shindan page (CSRF token, session and hidden fields) for this long.
+
+    Without it, every submission fetches the shindan page before posting the form.
+    A cached form rejected by the site is refetched transparently.
+
+    # Arguments
+    - `ttl` - How long a fetched form is reused, e.g. 10 minutes.
+
+    # Examples
+    ```
+    use anyhow::Result;
+    use std::time::Duration;
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    fn main() -> Result<()> {
+        let client = ShindanClient::builder(ShindanDomain::En)
+            .form_cache_ttl(Duration::from_secs(600))
+            .build()?;
+        Ok(())
+    }
+    ```
+    */
+    pub fn form_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.form_cache_ttl = Some(ttl);
+        self
+    }
+
+    /**
+    Limit the client, and all of its clones, to a number of requests per period.
+
+    Requests are spaced evenly across the period rather than sent in bursts.
+
+    # Arguments
+    - `requests` - The maximum number of requests per period.
+    - `period` - The length of the period.
+
+    # Examples
+    ```
+    use anyhow::Result;
+    use std::time::Duration;
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    fn main() -> Result<()> {
+        let client = ShindanClient::builder(ShindanDomain::En)
+            .rate_limit(30, Duration::from_secs(60))
+            .build()?;
+        Ok(())
+    }
+    ```
+    */
+    pub fn rate_limit(mut self, requests: u32, period: Duration) -> Self {
+        self.rate_limit = Some((requests, period));
+        self
+    }
+
+    /**
+    Cap the requests sent to each domain within any window of a period, across all clones of the client.
+
+    This is polite mode, which keeps bots built on this crate within what the site
+    tolerates; [`polite`](Self::polite) enables it with a budget of 60 requests per
+    minute per domain. Requests are sent without delay until the budget of a domain is spent; then they
+    wait for the window to move on, and an `info` message is logged through the
+    [`log`](https://docs.rs/log) crate.
+
+    # Arguments
+    - `requests` - The maximum number of requests to a domain per period.
+    - `period` - The length of the window.
+
+    # Examples
+    ```
+    use anyhow::Result;
+    use std::time::Duration;
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    fn main() -> Result<()> {
+        let client = ShindanClient::builder(ShindanDomain::En)
+            .crawl_budget(120, Duration::from_secs(60))
+            .build()?;
+        Ok(())
+    }
+    ```
+    */
+    pub fn crawl_budget(mut self, requests: u32, period: Duration) -> Self {
+        self.crawl_budget = Some((requests, period));
+        self
+    }
+
+    /**
+    Enable or disable polite mode, the [crawl budget](Self::crawl_budget). Disabled by default.
+
+    Enabling it keeps a budget set with [`crawl_budget`](Self::crawl_budget), and
+    otherwise allows 60 requests per minute per domain.
+
+    # Arguments
+    - `enabled` - Whether to cap the requests per domain.
+    */
+    pub fn polite(mut self, enabled: bool) -> Self {
+        if enabled {
+            self.crawl_budget = self.crawl_budget.or(Some(crawl_budget::DEFAULT_BUDGET));
+        } else {
+            self.crawl_budget = None;
+        }
+        self
+    }
+
+    /**
+    Wait a random delay between fetching the form of a shindan and submitting it.
+
+    Submitting instantly after the page loads looks robotic, and some operators
+    report soft bans for it; a delay of a few hundred milliseconds reads like a
+    person typing a name.
+
+    # Arguments
+    - `min` - The shortest delay.
+    - `max` - The longest delay.
+
+    # Examples
+    ```
+    use anyhow::Result;
+    use std::time::Duration;
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    fn main() -> Result<()> {
+        let client = ShindanClient::builder(ShindanDomain::En)
+            .submit_delay(Duration::from_millis(300), Duration::from_millis(900))
+            .build()?;
+        Ok(())
+    }
+    ```
+    */
+    pub fn submit_delay(mut self, min: Duration, max: Duration) -> Self {
+        self.submit_delay = Some(Jitter::new(min, max));
+        self
+    }
+
+    /**
+    Start the submissions of batch helpers a random gap apart, instead of all at once.
+
+    Applies to [`get_segments_batch`](ShindanClient::get_segments_batch),
+    [`submit_stream`](ShindanClient::submit_stream) and `render_images_batch` with the
+    `image` feature, on top of their concurrency limit.
+
+    # Arguments
+    - `min` - The shortest gap.
+    - `max` - The longest gap.
+    */
+    #[cfg(any(feature = "segments", feature = "image"))]
+    pub fn batch_spacing(mut self, min: Duration, max: Duration) -> Self {
+        self.batch_spacing = Some(Jitter::new(min, max));
+        self
+    }
+
+    /**
+    Pin the random inputs of submitted forms, so results only depend on the name and the day.
+
+    The site fills each form with a random `randname`, the name some shindans show for
+    an empty name, which makes recorded requests and snapshots of results differ per
+    fetch. Meant for tests, e.g. with [`cassette`](Self::cassette); see
+    [`ShindanPage::randname`](crate::ShindanPage::randname) for the value the site chose.
+
+    # Arguments
+    - `seed` - The `randname` to submit with every form.
+
+    # Examples
+    ```
+    use anyhow::Result;
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    fn main() -> Result<()> {
+        let client = ShindanClient::builder(ShindanDomain::En)
+            .seed("snapshot")
+            .build()?;
+        Ok(())
+    }
+    ```
+    */
+    pub fn seed(mut self, seed: impl Into<String>) -> Self {
+        self.seed = Some(seed.into());
+        self
+    }
+
+    /**
+    Ask the site to compress responses. Enabled by default.
+
+    Only the codings compiled in through the `gzip`, `brotli` and `zstd` features are
+    advertised; without any of them, responses are never compressed.
+
+    # Arguments
+    - `enabled` - Whether to negotiate compression.
+    */
+    pub fn compression(mut self, enabled: bool) -> Self {
+        self.compression = enabled;
+        self
+    }
+
+    /**
+    Report the metrics of every response once its body has been read.
+
+    # Arguments
+    - `hook` - A callback receiving the metrics, e.g. to record wire and decoded sizes.
+
+    # Examples
+    ```
+    use anyhow::Result;
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    fn main() -> Result<()> {
+        let client = ShindanClient::builder(ShindanDomain::En)
+            .on_response(|metrics| println!("{}: {} -> {} bytes", metrics.url, metrics.wire_bytes, metrics.decoded_bytes))
+            .build()?;
+        Ok(())
+    }
+    ```
+    */
+    pub fn on_response<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&ResponseMetrics) + Send + Sync + 'static,
+    {
+        self.metrics = Some(Metrics(Arc::new(hook)));
+        self
+    }
+
+    /**
+    Reuse the title and description of a shindan page for this long, then revalidate
+    them with `If-None-Match` / `If-Modified-Since` instead of downloading the page again.
+
+    # Arguments
+    - `ttl` - How long fetched metadata is reused without asking the site, e.g. 1 hour.
+
+    # Examples
+    ```
+    use anyhow::Result;
+    use std::time::Duration;
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    fn main() -> Result<()> {
+        let client = ShindanClient::builder(ShindanDomain::En)
+            .metadata_cache_ttl(Duration::from_secs(3600))
+            .build()?;
+        Ok(())
+    }
+    ```
+    */
+    pub fn metadata_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.metadata_cache_ttl = Some(ttl);
+        self
+    }
+
+    /**
+    Cache results in a [`MemoryCache`], so running a shindan again with the same name on the
+    same site day (which rolls over at midnight JST) returns the earlier result.
+
+    # Examples
+    ```
+    use anyhow::Result;
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    fn main() -> Result<()> {
+        let client = ShindanClient::builder(ShindanDomain::En)
+            .result_cache()
+            .build()?;
+        Ok(())
+    }
+    ```
+    */
+    #[cfg(feature = "segments")]
+    pub fn result_cache(mut self) -> Self {
+        self.result_cache.get_or_insert_with(|| Arc::new(MemoryCache::default()));
+        self
+    }
+
+    /**
+    Cache results like [`result_cache`](Self::result_cache), but expire them after
+    `ttl` if that comes before the site day rolls over.
+
+    # Arguments
+    - `ttl` - How long a result is reused at most.
+    */
+    #[cfg(feature = "segments")]
+    pub fn result_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.result_cache_ttl = Some(ttl);
+        self.result_cache()
+    }
+
+    /**
+    Cache results in a custom backend instead of a [`MemoryCache`], e.g. to share
+    them between the processes of a bot fleet.
+
+    # Arguments
+    - `cache` - The cache backend.
+
+    See [`ResultCache`] for an example.
+    */
+    #[cfg(feature = "segments")]
+    pub fn result_cache_backend(mut self, cache: impl ResultCache + 'static) -> Self {
+        self.result_cache = Some(Arc::new(cache));
+        self
+    }
+
+    /**
+    Keep the exact spacing of results, for shindans drawing ASCII art. Defaults to `false`.
+
+    Non-breaking spaces in parsed results become plain spaces, see
+    [`ParseOptions::preserve_whitespace`](crate::ParseOptions::preserve_whitespace), and rendered HTML uses a monospace font that
+    keeps every space. Render plain text with
+    [`PlainTextOptions::preserve_whitespace`](crate::PlainTextOptions::preserve_whitespace) to match.
+
+    # Arguments
+    - `enabled` - Whether whitespace is preserved.
+    */
+    #[cfg(any(feature = "segments", feature = "html"))]
+    pub fn preserve_whitespace(mut self, enabled: bool) -> Self {
+        #[cfg(feature = "segments")]
+        {
+            self.parse_options.preserve_whitespace = enabled;
+        }
+        #[cfg(feature = "html")]
+        {
+            self.preserve_whitespace = enabled;
+        }
+        self
+    }
+
+    /**
+    Choose where the stylesheet and scripts of the HTML built by the client come from. Defaults to [`SiteAssets::Builtin`].
+
+    The other modes download the assets when building HTML and inline them, through the
+    [asset cache](Self::asset_cache) if one is configured; assets that cannot be
+    downloaded, e.g. offline, keep linking to their built-in versions.
+
+    # Arguments
+    - `site_assets` - Where the assets come from.
+
+    # Examples
+    ```
+    use anyhow::Result;
+    use shindan_maker::{AssetVersions, ShindanClient, ShindanDomain, SiteAssets};
+
+    fn main() -> Result<()> {
+        let versions = AssetVersions { app_css: "cbfb28ec9001aee269676b04e227a3b9".to_string(), ..AssetVersions::builtin() };
+        let client = ShindanClient::builder(ShindanDomain::En)
+            .site_assets(SiteAssets::Pinned(versions))
+            .asset_cache(std::env::temp_dir().join("shindan-assets"))
+            .build()?;
+        Ok(())
+    }
+    ```
+    */
+    #[cfg(feature = "html")]
+    pub fn site_assets(mut self, site_assets: SiteAssets) -> Self {
+        self.site_assets = site_assets;
+        self
+    }
+
+    /**
+    Render the HTML built by the client, and so its images, with other fonts, e.g. to
+    embed a CJK font for containers that have none.
+
+    # Arguments
+    - `fonts` - The font files to embed and the installed families to try.
+
+    # Errors
+    Building the client returns error if a font file cannot be read.
+
+    # Examples
+    ```no_run
+    use anyhow::Result;
+    use shindan_maker::{FontStack, ShindanClient, ShindanDomain};
+
+    fn main() -> Result<()> {
+        let client = ShindanClient::builder(ShindanDomain::Jp)
+            .fonts(FontStack::default().embed("Noto Sans JP", "fonts/NotoSansJP-Regular.otf"))
+            .build()?;
+        Ok(())
+    }
+    ```
+    */
+    #[cfg(feature = "html")]
+    pub fn fonts(mut self, fonts: FontStack) -> Self {
+        self.fonts = fonts;
+        self
+    }
+
+    /**
+    Show the description of the shindan under its result in the HTML built by the client,
+    and so in its images. Defaults to `false`.
+
+    # Arguments
+    - `enabled` - Whether the description is shown.
+    */
+    #[cfg(feature = "html")]
+    pub fn include_description(mut self, enabled: bool) -> Self {
+        self.include_description = enabled;
+        self
+    }
+
+    /**
+    End the result card of the HTML built by the client, and so of its images, with a
+    footer crediting the shindan: its URL, its author and the day of the result. Defaults to `false`.
+
+    # Arguments
+    - `enabled` - Whether the footer is shown.
+
+    # Examples
+    ```
+    use anyhow::Result;
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    fn main() -> Result<()> {
+        let client = ShindanClient::builder(ShindanDomain::Jp)
+            .include_description(true)
+            .include_attribution(true)
+            .build()?;
+        Ok(())
+    }
+    ```
+    */
+    #[cfg(feature = "html")]
+    pub fn include_attribution(mut self, enabled: bool) -> Self {
+        self.include_attribution = enabled;
+        self
+    }
+
+    /**
+    Override the labels and colors of the charts in the HTML built by the client, and so
+    in its images, e.g. to translate stat names. Charts are drawn as on the site, then
+    redrawn with the overrides without animating.
+
+    # Arguments
+    - `overrides` - The labels and colors to override.
+
+    # Examples
+    ```
+    use anyhow::Result;
+    use shindan_maker::{ChartOverrides, ShindanClient, ShindanDomain};
+
+    fn main() -> Result<()> {
+        let client = ShindanClient::builder(ShindanDomain::Jp)
+            .chart_overrides(ChartOverrides::default().label("攻撃", "Attack").color("rgba(233, 30, 99, 0.4)"))
+            .build()?;
+        Ok(())
+    }
+    ```
+    */
+    #[cfg(all(feature = "html", feature = "segments"))]
+    pub fn chart_overrides(mut self, overrides: ChartOverrides) -> Self {
+        self.chart_overrides = overrides;
+        self
+    }
+
+    /**
+    Translate every result with a machine translation service into [`ShindanResult::translation`](crate::ShindanResult::translation).
+
+    Results are translated before they are cached, so cached results are not translated again.
+
+    # Arguments
+    - `translator` - The translation service.
+
+    See [`Translator`] for an example.
+    */
+    #[cfg(feature = "segments")]
+    pub fn translator(mut self, translator: impl Translator + 'static) -> Self {
+        self.translator = Some(Arc::new(translator));
+        self
+    }
+
+    /**
+    Score the text of every result with rules into [`ShindanResult::extracted_scores`](crate::ShindanResult::extracted_scores),
+    e.g. to build leaderboards.
+
+    # Arguments
+    - `rules` - The rules, see [`ScoreRule`].
+
+    # Examples
+    ```
+    use anyhow::Result;
+    use shindan_maker::{ScoreRule, ShindanClient, ShindanDomain};
+
+    fn main() -> Result<()> {
+        let client = ShindanClient::builder(ShindanDomain::Jp)
+            .score_rules([ScoreRule::rarity("rarity"), ScoreRule::points("affection")])
+            .build()?;
+        Ok(())
+    }
+    ```
+    */
+    #[cfg(feature = "scores")]
+    pub fn score_rules(mut self, rules: impl IntoIterator<Item = ScoreRule>) -> Self {
+        self.score_rules.extend(rules);
+        self
+    }
+
+    /**
+    Record every submission in a history store.
+
+    # Arguments
+    - `store` - The history store, e.g. a [`MemoryHistory`](crate::MemoryHistory).
+
+    See [`HistoryStore`] for an example.
+    */
+    #[cfg(feature = "history")]
+    pub fn history(mut self, store: impl HistoryStore + 'static) -> Self {
+        self.history = Some(Arc::new(store));
+        self
+    }
+
+    /**
+    Record the traffic of the client to a cassette, or replay it without network.
+
+    In replay mode, requests missing from the cassette fail instead of reaching the site.
+
+    # Arguments
+    - `cassette` - A recording or replaying cassette.
+
+    See [`Cassette`] for an example.
+    */
+    #[cfg(feature = "cassette")]
+    pub fn cassette(mut self, cassette: Cassette) -> Self {
+        self.cassette = Some(cassette);
+        self
+    }
+
+    /**
+    Log in with account credentials whenever the session has expired.
+
+    # Arguments
+    - `credentials` - The credentials of the ShindanMaker account.
+
+    # Examples
+    ```
+    use anyhow::Result;
+    use shindan_maker::{Credentials, ShindanClient, ShindanDomain};
+
+    fn main() -> Result<()> {
+        let client = ShindanClient::builder(ShindanDomain::Jp)
+            .credentials(Credentials::new("user@example.com", "password"))
+            .build()?;
+        Ok(())
+    }
+    ```
+    */
+    pub fn credentials(mut self, credentials: Credentials) -> Self {
+        self.auth = Some(Auth::Credentials(credentials));
+        self
+    }
+
+    /**
+    Obtain a fresh `_session` cookie from a callback whenever the session has expired.
+
+    Use this instead of [`credentials`](Self::credentials) when the account signs in
+    through a third party (e.g. X) and the session is managed outside of this crate.
+
+    # Arguments
+    - `refresher` - A callback returning the value of a logged-in `_session` cookie.
+
+    # Examples
+    ```
+    use anyhow::Result;
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    fn main() -> Result<()> {
+        let client = ShindanClient::builder(ShindanDomain::Jp)
+            .session_refresher(|| async { Ok(std::env::var("SHINDAN_SESSION")?) })
+            .build()?;
+        Ok(())
+    }
+    ```
+    */
+    pub fn session_refresher<F, Fut>(mut self, refresher: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        self.auth = Some(Auth::Refresher(Arc::new(move || Box::pin(refresher()))));
+        self
+    }
+
+    /**
+    Build the client.
+
+    # Returns
+    A new ShindanMaker client.
+
+    # Errors
+    Returns error if the underlying HTTP client cannot be created.
+    */
+    pub fn build(self) -> Result<ShindanClient> {
+        let cookie_jar = Arc::new(Jar::default());
+
+        let client = http_client(self.timeout, cookie_jar.clone())?;
+
+        Ok(ShindanClient {
+            client,
+            base_url: self.base_url.unwrap_or_else(|| self.domain.to_string()),
+            selectors: Arc::new(match &self.selectors {
+                Some(config) => Selectors::compile(config)?,
+                None => SELECTORS.clone(),
+            }),
+            domain: self.domain,
+            fallback_domains: self.fallback_domains.into(),
+            resolved_domains: Arc::default(),
+            redirected_domains: Arc::default(),
+            follow_domain_redirects: self.follow_domain_redirects,
+            cookie_jar,
+            auth: self.auth,
+            form_cache: self.form_cache_ttl.map(|ttl| Arc::new(FormCache::new(ttl))),
+            rate_limiter: self.rate_limit.map(|(requests, period)| Arc::new(RateLimiter::new(requests, period))),
+            crawl_budget: self.crawl_budget.map(|(requests, period)| Arc::new(CrawlBudget::new(requests, period))),
+            circuit_breaker: self.circuit_breaker.map(|(failures, cool_down)| Arc::new(CircuitBreaker::new(failures, cool_down))),
+            #[cfg(any(feature = "html", feature = "archive"))]
+            asset_cache: self.asset_cache.map(|dir| Arc::new(AssetCache::new(dir, self.asset_cache_size))),
+            submit_delay: self.submit_delay,
+            seed: self.seed,
+            retries: self.retries,
+            retry_submissions: self.retry_submissions,
+            timeout: self.timeout,
+            #[cfg(any(feature = "segments", feature = "image"))]
+            batch_spacing: self.batch_spacing,
+            metadata_cache: self.metadata_cache_ttl.map(|ttl| Arc::new(MetadataCache::new(ttl))),
+            page_flights: Arc::default(),
+            compression: self.compression,
+            metrics: self.metrics,
+            #[cfg(feature = "segments")]
+            result_cache: self.result_cache,
+            #[cfg(feature = "segments")]
+            result_cache_ttl: self.result_cache_ttl,
+            #[cfg(feature = "segments")]
+            parse_options: self.parse_options,
+            #[cfg(feature = "html")]
+            preserve_whitespace: self.preserve_whitespace,
+            #[cfg(feature = "html")]
+            site_assets: self.site_assets,
+            #[cfg(feature = "html")]
+            font_style: if self.fonts.is_empty() { None } else { Some(self.fonts.style(self.preserve_whitespace)?.into()) },
+            #[cfg(feature = "html")]
+            include_description: self.include_description,
+            #[cfg(feature = "html")]
+            include_attribution: self.include_attribution,
+            #[cfg(all(feature = "html", feature = "segments"))]
+            chart_overrides: self.chart_overrides,
+            #[cfg(feature = "segments")]
+            translator: self.translator,
+            #[cfg(feature = "scores")]
+            score_rules: self.score_rules.into(),
+            #[cfg(feature = "segments")]
+            result_flights: Arc::default(),
+            #[cfg(feature = "history")]
+            history: self.history,
+            #[cfg(feature = "cassette")]
+            cassette: self.cassette.map(Arc::new),
+        })
+    }
+}
+
+/// An HTTP client configured like the one of a [`ShindanClient`], with its timeout and cookies.
+pub(crate) fn http_client(timeout: Duration, cookie_jar: Arc<Jar>) -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .user_agent("shindan-maker")
+        .timeout(timeout)
+        .cookie_provider(cookie_jar)
+        .build()?)
+}
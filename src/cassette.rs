@@ -0,0 +1,217 @@
+use std::fs;
+use std::sync::Mutex;
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use reqwest::header::{self, HeaderName, HeaderValue};
+use reqwest::{Request, Response, ResponseBuilderExt, Url};
+
+use crate::compression::BodyDecoder;
+
+/// Headers describing the body as received, which no longer hold once it is stored decoded.
+const WIRE_HEADERS: [HeaderName; 3] = [header::CONTENT_ENCODING, header::CONTENT_LENGTH, header::TRANSFER_ENCODING];
+
+/// Whether a [`Cassette`] records or replays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Send requests and write each request/response pair to the cassette file.
+    Record,
+    /// Serve responses from the cassette file without touching the network.
+    Replay,
+}
+
+/// A request and the response it got, as stored in a cassette file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    pub url: String,
+    /// The request body, e.g. the submitted form.
+    pub body: Option<String>,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    /// The decoded response body.
+    pub response: String,
+}
+
+impl Interaction {
+    fn matches(&self, request: &Request) -> bool {
+        self.method == request.method().as_str()
+            && self.url == request.url().as_str()
+            && self.body == request_body(request)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Tape {
+    interactions: Vec<Interaction>,
+    played: Vec<bool>,
+}
+
+/**
+Records the traffic of a client to a file, or replays it without network.
+
+Cassettes make integration tests reproducible: record once against the site,
+commit the file, and replay it in every later run, unaffected by site changes
+and rate limits.
+
+# Examples
+```no_run
+use anyhow::Result;
+use shindan_maker::{Cassette, ShindanClient, ShindanDomain};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cassette = match std::env::var("RECORD") {
+        Ok(_) => Cassette::record("tests/cassettes/fantasy_stats.json"),
+        Err(_) => Cassette::replay("tests/cassettes/fantasy_stats.json")?,
+    };
+
+    let client = ShindanClient::builder(ShindanDomain::En)
+        .cassette(cassette)
+        .build()?;
+
+    let title = client.get_title("1222992").await?;
+    assert_eq!("Fantasy Stats", title);
+    Ok(())
+}
+```
+*/
+#[derive(Debug)]
+pub struct Cassette {
+    path: PathBuf,
+    mode: CassetteMode,
+    tape: Mutex<Tape>,
+}
+
+impl Cassette {
+    /**
+    Create a cassette recording to a file.
+
+    The file is overwritten after every interaction, so it is complete even if the test fails.
+
+    # Arguments
+    - `path` - The path of the cassette file.
+    */
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            mode: CassetteMode::Record,
+            tape: Mutex::default(),
+        }
+    }
+
+    /**
+    Load a cassette to replay.
+
+    # Arguments
+    - `path` - The path of a file written by a recording cassette.
+
+    # Errors
+    Returns error if the file cannot be read or parsed.
+    */
+    pub fn replay(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read the cassette {}", path.display()))?;
+        let interactions: Vec<Interaction> = serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse the cassette {}", path.display()))?;
+
+        Ok(Self {
+            path,
+            mode: CassetteMode::Replay,
+            tape: Mutex::new(Tape {
+                played: vec![false; interactions.len()],
+                interactions,
+            }),
+        })
+    }
+
+    /// Whether the cassette records or replays.
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    /// The path of the cassette file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The interactions recorded or loaded so far.
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.tape.lock().unwrap().interactions.clone()
+    }
+
+    /// Serve a request from the cassette, preferring interactions not played yet.
+    pub(crate) fn play(&self, request: &Request) -> Result<Response> {
+        let mut tape = self.tape.lock().unwrap();
+
+        let index = (0..tape.interactions.len())
+            .find(|&index| !tape.played[index] && tape.interactions[index].matches(request))
+            .or_else(|| tape.interactions.iter().rposition(|interaction| interaction.matches(request)))
+            .ok_or_else(|| anyhow!("No recorded interaction for {} {}", request.method(), request.url()))?;
+        tape.played[index] = true;
+
+        let interaction = &tape.interactions[index];
+        let mut response = http::Response::builder()
+            .status(interaction.status)
+            .url(Url::parse(&interaction.url)?);
+        for (name, value) in &interaction.headers {
+            response = response.header(name.as_str(), value.as_str());
+        }
+
+        Ok(Response::from(response.body(interaction.response.clone())?))
+    }
+
+    /// Record the response to a request, handing back an unread copy of it.
+    pub(crate) async fn record_response(&self, request: &Request, response: Response) -> Result<Response> {
+        let status = response.status();
+        let url = response.url().clone();
+        let headers = response.headers().clone();
+        let version = response.version();
+        let wire_body = response.bytes().await?;
+
+        let mut decoder = BodyDecoder::from_headers(&headers)?;
+        let mut body = decoder.write(&wire_body)?;
+        body.extend(decoder.finish()?);
+
+        let interaction = Interaction {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            body: request_body(request),
+            status: status.as_u16(),
+            headers: headers
+                .iter()
+                .filter(|(name, _)| !WIRE_HEADERS.contains(name))
+                .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+                .collect(),
+            response: String::from_utf8_lossy(&body).into_owned(),
+        };
+
+        {
+            let mut tape = self.tape.lock().unwrap();
+            tape.interactions.push(interaction);
+            tape.played.push(true);
+
+            let json = serde_json::to_string_pretty(&tape.interactions)?;
+            if let Some(parent) = self.path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&self.path, json)
+                .with_context(|| format!("Failed to write the cassette {}", self.path.display()))?;
+        }
+
+        let mut copy = http::Response::builder().status(status).version(version).url(url);
+        for (name, value) in &headers {
+            copy = copy.header(name, HeaderValue::clone(value));
+        }
+
+        Ok(Response::from(copy.body(wire_body)?))
+    }
+}
+
+fn request_body(request: &Request) -> Option<String> {
+    request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .map(|body| String::from_utf8_lossy(body).into_owned())
+}
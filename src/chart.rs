@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+/// Chart data of a shindan result drawn with Chart.js.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chart {
+    /// The Chart.js chart type, e.g. `radar` or `bar`.
+    pub kind: String,
+    /// The labels of the axes.
+    pub labels: Vec<String>,
+    /// The datasets plotted on the chart.
+    pub datasets: Vec<ChartDataset>,
+}
+
+/// A dataset of a [`Chart`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChartDataset {
+    pub label: Option<String>,
+    pub data: Vec<f64>,
+}
+
+impl Chart {
+    /// Get the largest value across all datasets.
+    pub fn max_value(&self) -> f64 {
+        self.datasets
+            .iter()
+            .flat_map(|dataset| dataset.data.iter().copied())
+            .fold(0.0, f64::max)
+    }
+}
+
+/**
+Overrides of the labels and colors of charts, e.g. to translate the stat names of a
+shindan for the audience of a bot.
+
+Applied to parsed charts with [`Chart::localize`], to terminal output with
+`TerminalOptions::chart_overrides`, and to built HTML and images with
+`ShindanClientBuilder::chart_overrides`.
+
+# Examples
+```
+use shindan_maker::{Chart, ChartDataset, ChartOverrides};
+
+let chart = Chart {
+    kind: "radar".to_string(),
+    labels: vec!["ATK".to_string(), "DEF".to_string()],
+    datasets: vec![ChartDataset { label: Some("stats".to_string()), data: vec![80.0, 65.0] }],
+};
+let overrides = ChartOverrides::default().label("ATK", "Attaque").label("stats", "Statistiques").color("#e91e63");
+
+let localized = chart.localize(&overrides);
+assert_eq!(vec!["Attaque", "DEF"], localized.labels);
+assert_eq!(Some("Statistiques"), localized.datasets[0].label.as_deref());
+```
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChartOverrides {
+    /// The labels of axes and datasets to replace, by their original label.
+    pub labels: HashMap<String, String>,
+    /// The CSS colors of the datasets, in order; datasets beyond keep their colors.
+    /// In HTML they color the lines, points and fills, so fills of radar charts want transparency, e.g. `rgba(233, 30, 99, 0.4)`.
+    pub colors: Vec<String>,
+}
+
+impl ChartOverrides {
+    /// Replace a label of axes or datasets.
+    pub fn label(mut self, original: impl Into<String>, replacement: impl Into<String>) -> Self {
+        self.labels.insert(original.into(), replacement.into());
+        self
+    }
+
+    /// Color the next dataset.
+    pub fn color(mut self, color: impl Into<String>) -> Self {
+        self.colors.push(color.into());
+        self
+    }
+
+    #[cfg(feature = "html")]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.labels.is_empty() && self.colors.is_empty()
+    }
+
+    /// A script applying the overrides to the Chart.js charts of a page once they are drawn.
+    #[cfg(feature = "html")]
+    pub(crate) fn script(&self) -> String {
+        let json = |value: serde_json::Value| value.to_string().replace("</", "<\\/");
+        format!(
+            r#"<script>
+window.addEventListener('load', function () {{
+    var labels = {}, colors = {};
+    var rename = function (label) {{ return Object.prototype.hasOwnProperty.call(labels, label) ? labels[label] : label; }};
+    Object.values(Chart.instances || {{}}).forEach(function (chart) {{
+        chart.data.labels = (chart.data.labels || []).map(rename);
+        chart.data.datasets.forEach(function (dataset, index) {{
+            if (dataset.label !== undefined) dataset.label = rename(dataset.label);
+            if (index < colors.length) dataset.backgroundColor = dataset.borderColor = dataset.pointBackgroundColor = colors[index];
+        }});
+        chart.update('none');
+    }});
+}});
+</script>"#,
+            json(serde_json::json!(self.labels)),
+            json(serde_json::json!(self.colors)),
+        )
+    }
+}
+
+impl Chart {
+    /// A copy of the chart with the labels of its axes and datasets replaced per overrides.
+    pub fn localize(&self, overrides: &ChartOverrides) -> Chart {
+        let rename = |label: &String| overrides.labels.get(label).unwrap_or(label).clone();
+
+        Chart {
+            kind: self.kind.clone(),
+            labels: self.labels.iter().map(rename).collect(),
+            datasets: self
+                .datasets
+                .iter()
+                .map(|dataset| ChartDataset { label: dataset.label.as_ref().map(rename), data: dataset.data.clone() })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The error of a request refused without being sent, because the site has been failing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitOpen {
+    /// How long until a request probes the site again.
+    pub retry_after: Duration,
+}
+
+impl fmt::Display for CircuitOpen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The site is failing; not sending requests for another {:?}", self.retry_after)
+    }
+}
+
+impl std::error::Error for CircuitOpen {}
+
+/// Stops sending requests for a cool-down after consecutive failures, then lets one through to probe the site.
+#[derive(Debug)]
+pub(crate) struct CircuitBreaker {
+    failures: u32,
+    cool_down: Duration,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    /// Until when requests are refused, while the circuit is open.
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(failures: u32, cool_down: Duration) -> Self {
+        Self {
+            failures: failures.max(1),
+            cool_down,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Allow a request, or refuse it while the circuit is open.
+    ///
+    /// Once the cool-down is over, one request probes the site, and the others are
+    /// refused for another cool-down unless it succeeds.
+    pub(crate) fn check(&self) -> Result<(), CircuitOpen> {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+
+        match state.open_until {
+            Some(until) if until > now => Err(CircuitOpen { retry_after: until - now }),
+            Some(_) => {
+                log::info!("Probing the site after a cool-down of {:?}", self.cool_down);
+                state.open_until = Some(now + self.cool_down);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Count the outcome of a request: a failure is a 5xx status, a timeout or a connection error.
+    pub(crate) fn record(&self, outcome: &reqwest::Result<reqwest::Response>) {
+        let failed = match outcome {
+            Ok(response) => response.status().is_server_error(),
+            Err(err) => err.is_timeout() || err.is_connect(),
+        };
+
+        let mut state = self.state.lock().unwrap();
+        if !failed {
+            if state.open_until.take().is_some() {
+                log::info!("The site recovered; sending requests again");
+            }
+            state.consecutive_failures = 0;
+            return;
+        }
+
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+        if state.consecutive_failures >= self.failures {
+            if state.open_until.is_none() {
+                log::warn!("{} consecutive requests failed; not sending requests for {:?}", state.consecutive_failures, self.cool_down);
+            }
+            state.open_until = Some(Instant::now() + self.cool_down);
+        }
+    }
+}
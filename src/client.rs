@@ -1,20 +1,122 @@
+use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 use scraper::Html;
-use reqwest::Client;
-use anyhow::Result;
-use std::time::Duration;
+use futures::future::join_all;
+use reqwest::cookie::Jar;
+use reqwest::header;
+use anyhow::{anyhow, Context, Result};
+use reqwest::{Client, Request, RequestBuilder, Response, StatusCode, Url};
 
+use crate::rt;
 use crate::http_utils;
 use crate::html_utils;
+use crate::auth::Auth;
+use crate::builder::ShindanClientBuilder;
+use crate::rate_limit::RateLimiter;
+use crate::crawl_budget::CrawlBudget;
+use crate::circuit_breaker::CircuitBreaker;
+#[cfg(any(feature = "html", feature = "archive"))]
+use crate::asset_cache::AssetCache;
+use crate::jitter::Jitter;
+use crate::selectors::Selectors;
+use crate::compression::{self, BodyDecoder};
+use crate::metrics::{Metrics, ResponseMetrics};
+use crate::singleflight::SingleFlight;
+use crate::form_cache::FormCache;
+use crate::page::ShindanPage;
+use crate::metadata::ShindanMetadata;
+#[cfg(any(feature = "segments", feature = "html"))]
+use crate::response_info::ResponseInfo;
+use crate::metadata_cache::{MetadataCache, Validators};
+use crate::shindan_id::{self, ShindanId};
 use crate::shindan_domain::ShindanDomain;
 
 #[cfg(feature = "segments")]
 use crate::segment::Segments;
+#[cfg(feature = "streaming")]
+use crate::stream_parse::SegmentExtractor;
+#[cfg(feature = "scores")]
+use crate::scores::ScoreRule;
+#[cfg(feature = "html")]
+use crate::site_assets::SiteAssets;
+#[cfg(feature = "html")]
+use crate::html_template::RESULT_EXTRAS;
+#[cfg(all(feature = "html", feature = "segments"))]
+use crate::chart::ChartOverrides;
+#[cfg(feature = "history")]
+use crate::history::HistoryStore;
+#[cfg(any(feature = "history", feature = "html"))]
+use crate::site_day;
+#[cfg(feature = "cassette")]
+use crate::cassette::{Cassette, CassetteMode};
+#[cfg(feature = "segments")]
+use {
+    chrono::Utc,
+    crate::result::ShindanResult,
+    crate::translate::Translator,
+    crate::parse_options::ParseOptions,
+    crate::result_cache::{self, CacheKey, ResultCache},
+};
 
 /// A client for interacting with ShindanMaker.
 #[derive(Clone, Debug)]
 pub struct ShindanClient {
-    client: Client,
-    domain: ShindanDomain,
+    pub(crate) client: Client,
+    pub(crate) domain: ShindanDomain,
+    pub(crate) base_url: String,
+    pub(crate) fallback_domains: Arc<[ShindanDomain]>,
+    pub(crate) resolved_domains: Arc<Mutex<HashMap<ShindanId, ShindanDomain>>>,
+    pub(crate) redirected_domains: Arc<Mutex<HashMap<String, ShindanDomain>>>,
+    pub(crate) follow_domain_redirects: bool,
+    pub(crate) selectors: Arc<Selectors>,
+    pub(crate) cookie_jar: Arc<Jar>,
+    pub(crate) auth: Option<Auth>,
+    pub(crate) form_cache: Option<Arc<FormCache>>,
+    pub(crate) rate_limiter: Option<Arc<RateLimiter>>,
+    pub(crate) crawl_budget: Option<Arc<CrawlBudget>>,
+    pub(crate) circuit_breaker: Option<Arc<CircuitBreaker>>,
+    #[cfg(any(feature = "html", feature = "archive"))]
+    pub(crate) asset_cache: Option<Arc<AssetCache>>,
+    pub(crate) submit_delay: Option<Jitter>,
+    pub(crate) seed: Option<String>,
+    pub(crate) retries: u32,
+    pub(crate) retry_submissions: bool,
+    pub(crate) timeout: Duration,
+    #[cfg(any(feature = "segments", feature = "image"))]
+    pub(crate) batch_spacing: Option<Jitter>,
+    pub(crate) page_flights: Arc<SingleFlight<String, Arc<ShindanPage>>>,
+    pub(crate) compression: bool,
+    pub(crate) metrics: Option<Metrics>,
+    pub(crate) metadata_cache: Option<Arc<MetadataCache>>,
+    #[cfg(feature = "segments")]
+    pub(crate) result_cache: Option<Arc<dyn ResultCache>>,
+    #[cfg(feature = "segments")]
+    pub(crate) result_cache_ttl: Option<Duration>,
+    #[cfg(feature = "segments")]
+    pub(crate) parse_options: ParseOptions,
+    #[cfg(feature = "html")]
+    pub(crate) preserve_whitespace: bool,
+    #[cfg(feature = "html")]
+    pub(crate) site_assets: SiteAssets,
+    #[cfg(feature = "html")]
+    pub(crate) font_style: Option<Arc<str>>,
+    #[cfg(feature = "html")]
+    pub(crate) include_description: bool,
+    #[cfg(feature = "html")]
+    pub(crate) include_attribution: bool,
+    #[cfg(all(feature = "html", feature = "segments"))]
+    pub(crate) chart_overrides: ChartOverrides,
+    #[cfg(feature = "segments")]
+    pub(crate) translator: Option<Arc<dyn Translator>>,
+    #[cfg(feature = "scores")]
+    pub(crate) score_rules: Arc<[ScoreRule]>,
+    #[cfg(feature = "segments")]
+    pub(crate) result_flights: Arc<SingleFlight<CacheKey, ShindanResult>>,
+    #[cfg(feature = "history")]
+    pub(crate) history: Option<Arc<dyn HistoryStore>>,
+    #[cfg(feature = "cassette")]
+    pub(crate) cassette: Option<Arc<Cassette>>,
 }
 
 impl ShindanClient {
@@ -42,15 +144,124 @@ impl ShindanClient {
     ```
     */
     pub fn new(domain: ShindanDomain) -> Result<Self> {
-        const TIMEOUT_SECS: u64 = 3;
-
-        Ok(Self {
-            domain,
-            client: Client::builder()
-                .user_agent("shindan-maker")
-                .timeout(Duration::from_secs(TIMEOUT_SECS))
-                .build()?,
-        })
+        Self::builder(domain).build()
+    }
+
+    /**
+    Create a builder to configure a ShindanMaker client.
+
+    # Arguments
+    - `domain` - The domain of ShindanMaker to use.
+
+    # Returns
+    A builder for a ShindanMaker client.
+
+    # Examples
+    ```
+    use anyhow::Result;
+    use std::time::Duration;
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    fn main() -> Result<()> {
+        let client = ShindanClient::builder(ShindanDomain::En)
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        Ok(())
+    }
+    ```
+    */
+    pub fn builder(domain: ShindanDomain) -> ShindanClientBuilder {
+        ShindanClientBuilder::new(domain)
+    }
+
+    /**
+    Log in using the credentials or session refresher configured on the builder.
+
+    # Errors
+    Returns error if no authentication is configured, the network request fails,
+    or the site still redirects to the login page afterwards.
+    */
+    pub async fn login(&self) -> Result<()> {
+        match self.auth.as_ref().ok_or_else(|| anyhow!("No credentials or session refresher configured"))? {
+            Auth::Credentials(credentials) => {
+                let url = self.url("login");
+
+                let login_page = self.read_text(self.dispatch(self.client.get(&url)).await?).await?;
+                let token = html_utils::extract_token(&self.selectors, &Html::parse_document(&login_page))?;
+
+                let request = self.client
+                    .post(&url)
+                    .form(&[
+                        ("_token", token.as_str()),
+                        ("email", credentials.email.as_str()),
+                        ("password", credentials.password.as_str()),
+                        ("remember", "on"),
+                    ]);
+
+                self.dispatch(request).await?.error_for_status()?;
+            }
+            Auth::Refresher(refresher) => {
+                let session_cookie = refresher().await?;
+                let url: Url = self.base_url.parse()?;
+                self.cookie_jar.add_cookie_str(&format!("_session={}; Path=/", session_cookie), &url);
+            }
+        }
+
+        if self.is_logged_in().await? {
+            Ok(())
+        } else {
+            Err(anyhow!("Failed to log in to ShindanMaker"))
+        }
+    }
+
+    /**
+    Check whether the current session is logged in.
+
+    # Returns
+    `false` if the site redirects an account-only page to the login page.
+
+    # Errors
+    Returns error if network request fails.
+    */
+    pub async fn is_logged_in(&self) -> Result<bool> {
+        let url = self.url("mypage");
+        let response = self.dispatch(self.client.get(&url)).await?;
+
+        Ok(!http_utils::is_login_redirect(response.url()))
+    }
+
+    /**
+    Make sure the session is logged in, re-authenticating if it has expired.
+
+    Requests redirected to the login page already log in again on their own; long-running
+    bots can call this before authenticated work to surface a failing login early.
+
+    # Errors
+    Returns error if no authentication is configured or re-authentication fails.
+
+    # Examples
+    ```no_run
+    use anyhow::Result;
+    use shindan_maker::{Credentials, ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let client = ShindanClient::builder(ShindanDomain::Jp)
+            .credentials(Credentials::new("user@example.com", "password"))
+            .build()?;
+
+        client.ensure_logged_in().await?;
+
+        Ok(())
+    }
+    ```
+    */
+    pub async fn ensure_logged_in(&self) -> Result<()> {
+        if self.is_logged_in().await? {
+            return Ok(());
+        }
+
+        self.login().await
     }
 
     /**
@@ -84,9 +295,8 @@ impl ShindanClient {
     }
     ```
     */
-    pub async fn get_title(&self, id: &str) -> Result<String> {
-        let document = self.fetch_document(id).await?;
-        html_utils::extract_title(&document)
+    pub async fn get_title(&self, id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>) -> Result<String> {
+        Ok(self.get_metadata(id).await?.title)
     }
 
     /**
@@ -120,9 +330,11 @@ impl ShindanClient {
     }
     ```
     */
-    pub async fn get_description(&self, id: &str) -> Result<String> {
-        let document = self.fetch_document(id).await?;
-        html_utils::extract_description(&document)
+    pub async fn get_description(&self, id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>) -> Result<String> {
+        self.get_metadata(id)
+            .await?
+            .description
+            .context("Failed to get the description of the shindan")
     }
 
     /**
@@ -157,70 +369,449 @@ impl ShindanClient {
     }
     ```
     */
-    pub async fn get_title_with_description(&self, id: &str) -> Result<(String, String)> {
-        let document = self.fetch_document(id).await?;
+    pub async fn get_title_with_description(&self, id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>) -> Result<(String, String)> {
+        let metadata = self.get_metadata(id).await?;
+        let description = metadata.description.context("Failed to get the description of the shindan")?;
 
-        Ok((
-            html_utils::extract_title(&document)?,
-            html_utils::extract_description(&document)?
-        ))
+        Ok((metadata.title, description))
     }
 
-    async fn fetch_document(&self, id: &str) -> Result<Html> {
-        let url = format!("{}{}", self.domain, id);
+    /**
+    Fetches the title and description of a shindan page.
 
-        let text = self.client
-            .get(&url)
-            .send()
-            .await?
-            .text()
+    With [`metadata_cache_ttl`](ShindanClientBuilder::metadata_cache_ttl) set, metadata is
+    reused for the TTL and then revalidated with a conditional request, so the page is only
+    downloaded again when the site reports a change.
+
+    # Arguments
+    - `id` - The ID of the shindan
+
+    # Returns
+    The metadata of the shindan page.
+
+    # Errors
+    Returns error if network request fails or the title cannot be extracted.
+
+    # Examples
+    ```
+    use anyhow::Result;
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let client = ShindanClient::new(ShindanDomain::En)?;
+
+        let metadata = client
+            .get_metadata("1222992")
             .await?;
 
-        Ok(Html::parse_document(&text))
+        println!("Title: {}", metadata.title);
+
+        Ok(())
+    }
+    ```
+    */
+    pub async fn get_metadata(&self, id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>) -> Result<ShindanMetadata> {
+        let id = shindan_id::convert(id)?;
+        if let Some(client) = self.fallback_for(&id).await? {
+            return Box::pin(client.get_metadata(id)).await;
+        }
+
+        let url = self.url(id.as_str());
+
+        if let Some(page) = self.form_cache.as_ref().and_then(|cache| cache.get(&url)) {
+            return page.metadata();
+        }
+
+        let cached = self.metadata_cache.as_ref().and_then(|cache| cache.get(&url));
+        let mut request = self.client.get(&url);
+        if let Some((entry, fresh)) = &cached {
+            if *fresh {
+                return Ok(entry.metadata.clone());
+            }
+            request = entry.validators.apply(request);
+        }
+
+        let response = self.send(request).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(http_utils::NotFound(url).into());
+        }
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let (Some(cache), Some((entry, _))) = (&self.metadata_cache, cached) {
+                cache.revalidate(&url);
+                return Ok(entry.metadata);
+            }
+        }
+
+        let validators = Validators::from_headers(response.headers());
+        let text = self.read_text(response).await?;
+        let metadata = html_utils::extract_metadata(&self.selectors, &Html::parse_document(&text))?;
+
+        if let Some(cache) = &self.metadata_cache {
+            cache.insert(&url, metadata.clone(), validators);
+        }
+
+        Ok(metadata)
+    }
+
+    #[cfg(feature = "tui")]
+    pub(crate) async fn fetch_bytes(&self, url: &str) -> Result<Vec<u8>> {
+        let response = self.send(self.client.get(url)).await?.error_for_status()?;
+
+        let mut bytes = Vec::new();
+        self.read_body(response, |chunk| {
+            bytes.extend_from_slice(chunk);
+            Ok(())
+        })
+        .await?;
+
+        Ok(bytes)
+    }
+
+    /// The URL of a path on the site the client talks to, or on the domain the page of a shindan was redirected to.
+    pub(crate) fn url(&self, path: &str) -> String {
+        if self.follow_domain_redirects {
+            if let Some(domain) = self.redirected_domains.lock().unwrap().get(path) {
+                return format!("{}{}", domain, path);
+            }
+        }
+
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Send a request, logging in again and retrying it once if the site redirects it to the login page.
+    pub(crate) async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let retry = self.auth.as_ref().and_then(|_| request.try_clone());
+        let response = self.dispatch(request).await?;
+
+        match retry {
+            Some(retry) if http_utils::is_login_redirect(response.url()) => {
+                log::debug!("Session expired, logging in again before retrying {}", response.url());
+                self.login().await?;
+                self.dispatch(retry).await
+            }
+            _ => Ok(response),
+        }
+    }
+
+    /// Send a request once the crawl budget and the rate limit allow it, or replay it from the cassette.
+    async fn dispatch(&self, mut request: RequestBuilder) -> Result<Response> {
+        if let (true, Some(accept_encoding)) = (self.compression, compression::ACCEPT_ENCODING.as_deref()) {
+            request = request.header(header::ACCEPT_ENCODING, accept_encoding);
+        }
+        let request = request.build()?;
+
+        #[cfg(feature = "cassette")]
+        if let Some(cassette) = &self.cassette {
+            if cassette.mode() == CassetteMode::Replay {
+                return cassette.play(&request);
+            }
+
+            let response = self.execute(request.try_clone().context("Failed to record a streamed request")?).await?;
+            return cassette.record_response(&request, response).await;
+        }
+
+        self.execute(request).await
+    }
+
+    /**
+    Execute a request in turn, retrying transient failures with an exponential backoff, unless the circuit is open.
+
+    Only idempotent requests are retried, unless submissions are retried too.
+    */
+    async fn execute(&self, mut request: Request) -> Result<Response> {
+        const BACKOFF: Duration = Duration::from_millis(500);
+
+        let retries = if request.method().is_idempotent() || self.retry_submissions { self.retries } else { 0 };
+        let mut attempt = 0;
+        loop {
+            let retry = if attempt < retries { request.try_clone() } else { None };
+
+            if let Some(circuit_breaker) = &self.circuit_breaker {
+                circuit_breaker.check()?;
+            }
+            self.wait_turn(&request).await;
+            let outcome = self.client.execute(request).await;
+            if let Some(circuit_breaker) = &self.circuit_breaker {
+                circuit_breaker.record(&outcome);
+            }
+
+            match retry {
+                Some(next) if http_utils::is_transient(&outcome) => {
+                    log::debug!("Retrying {} after a transient failure", next.url());
+                    rt::sleep_until(Instant::now() + BACKOFF * 2u32.saturating_pow(attempt)).await;
+                    request = next;
+                    attempt += 1;
+                }
+                _ => return Ok(outcome?),
+            }
+        }
+    }
+
+    /// Wait until the crawl budget of the host and the rate limit allow a request.
+    pub(crate) async fn wait_turn(&self, request: &Request) {
+        if let Some(crawl_budget) = &self.crawl_budget {
+            crawl_budget.acquire(request.url()).await;
+        }
+
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
     }
 
-    async fn fetch_with_form_data(
+    /// Read a response body as text, decompressing it if needed.
+    pub(crate) async fn read_text(&self, response: Response) -> Result<String> {
+        let mut body = Vec::new();
+        self.read_body(response, |chunk| {
+            body.extend_from_slice(chunk);
+            Ok(())
+        })
+        .await?;
+
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+
+    /// Read a response body chunk by chunk, decompressing it and reporting its metrics.
+    pub(crate) async fn read_body(
         &self,
-        id: &str,
-        name: &str,
-        extract_title: bool,
-    ) -> Result<(Option<String>, String)> {
-        let url = format!("{}{}", self.domain, id);
+        mut response: Response,
+        mut on_chunk: impl FnMut(&[u8]) -> Result<()>,
+    ) -> Result<()> {
+        let mut decoder = BodyDecoder::from_headers(response.headers())?;
+        let mut metrics = ResponseMetrics {
+            url: response.url().to_string(),
+            status: response.status().as_u16(),
+            content_encoding: response
+                .headers()
+                .get(header::CONTENT_ENCODING)
+                .and_then(|encoding| encoding.to_str().ok())
+                .map(String::from),
+            wire_bytes: 0,
+            decoded_bytes: 0,
+        };
+
+        while let Some(chunk) = response.chunk().await? {
+            metrics.wire_bytes += chunk.len() as u64;
+            let decoded = decoder.write(&chunk)?;
+            metrics.decoded_bytes += decoded.len() as u64;
+            on_chunk(&decoded)?;
+        }
+
+        let decoded = decoder.finish()?;
+        metrics.decoded_bytes += decoded.len() as u64;
+        on_chunk(&decoded)?;
+
+        if let Some(Metrics(hook)) = &self.metrics {
+            hook(&metrics);
+        }
+
+        Ok(())
+    }
+
+    /**
+    Fetch and parse a shindan page once.
+
+    The page can be inspected without further requests, and with
+    [`form_cache_ttl`](ShindanClientBuilder::form_cache_ttl) set it is reused by
+    later submissions of the same shindan.
+
+    # Arguments
+    - `id` - The ID of the shindan.
+
+    # Returns
+    The parsed shindan page.
+
+    # Errors
+    Returns error if network request fails or the page has no form.
+
+    # Examples
+    ```
+    use anyhow::Result;
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let client = ShindanClient::new(ShindanDomain::En)?;
+
+        let page = client.fetch_page("1222992").await?;
+
+        println!("Title: {:?}", page.title());
+        println!("Description: {:?}", page.description());
+
+        Ok(())
+    }
+    ```
+    */
+    pub async fn fetch_page(&self, id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>) -> Result<Arc<ShindanPage>> {
+        let id = shindan_id::convert(id)?;
+        if let Some(client) = self.fallback_for(&id).await? {
+            return Box::pin(client.fetch_page(id)).await;
+        }
+
+        self.page(&self.url(id.as_str())).await
+    }
+
+    /**
+    Fetch popular shindans ahead of time, so the first submissions do not wait for their pages.
+
+    Pages are fetched concurrently and kept in the caches enabled on the builder:
+    the form cache ([`form_cache_ttl`](ShindanClientBuilder::form_cache_ttl)) and the
+    metadata cache ([`metadata_cache_ttl`](ShindanClientBuilder::metadata_cache_ttl)).
+    Without either, prefetching only warms up the connection pool.
+
+    # Arguments
+    - `ids` - The IDs of the shindans.
+
+    # Returns
+    Whether each shindan was fetched, in the order of `ids`.
+
+    # Examples
+    ```
+    use anyhow::Result;
+    use std::time::Duration;
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let client = ShindanClient::builder(ShindanDomain::En)
+            .form_cache_ttl(Duration::from_secs(600))
+            .metadata_cache_ttl(Duration::from_secs(3600))
+            .build()?;
+
+        for result in client.prefetch(&["1222992", "1218842"]).await {
+            result?;
+        }
+
+        Ok(())
+    }
+    ```
+    */
+    pub async fn prefetch(&self, ids: &[&str]) -> Vec<Result<()>> {
+        let prefetches = ids.iter().map(|id| async move {
+            let page = self.fetch_page(*id).await?;
 
-        let initial_response = self.client.get(&url).send().await?;
+            if let (Some(cache), Ok(metadata)) = (&self.metadata_cache, page.metadata()) {
+                cache.insert(page.url(), metadata, Validators::default());
+            }
+
+            Ok(())
+        });
+
+        join_all(prefetches).await
+    }
+
+    /// Fetch a shindan page, reusing a cached one when enabled.
+    ///
+    /// Concurrent fetches of the same page share a single request.
+    pub(crate) async fn page(&self, url: &str) -> Result<Arc<ShindanPage>> {
+        if let Some(page) = self.form_cache.as_ref().and_then(|cache| cache.get(url)) {
+            return Ok(page);
+        }
+
+        let client = self.clone();
+        let owned_url = url.to_string();
+        self.page_flights
+            .run(owned_url.clone(), async move { client.fetch_page_uncached(&owned_url).await })
+            .await
+    }
+
+    pub(crate) async fn fetch_page_uncached(&self, url: &str) -> Result<Arc<ShindanPage>> {
+        let initial_response = self.send(self.client.get(url)).await?;
+        if initial_response.status() == StatusCode::NOT_FOUND {
+            return Err(http_utils::NotFound(url.to_string()).into());
+        }
+
+        let final_url = initial_response.url().to_string();
         let session_cookie = http_utils::extract_session_cookie(&initial_response)?;
-        let initial_response_text = initial_response.text().await?;
+        let initial_response_text = self.read_text(initial_response).await?;
 
-        let (title, form_data) = if extract_title {
-            let (title, form_data) = html_utils::extract_title_and_form_data(&initial_response_text, name)?;
-            (Some(title), form_data)
-        } else {
-            let document = Html::parse_document(&initial_response_text);
-            let form_data = html_utils::extract_form_data(&document, name)?;
-            (None, form_data)
-        };
+        let mut page = html_utils::extract_page(&self.selectors, url, &initial_response_text, session_cookie)?;
+        page.final_url = final_url;
+        if let Some(domain) = page.redirected_domain() {
+            let id = url.trim_end_matches('/').rsplit('/').next().unwrap_or_default();
+            self.redirected_domains.lock().unwrap().insert(id.to_string(), domain);
+        }
 
-        let headers = http_utils::prepare_headers(&session_cookie)?;
-        let response_text = self.client
-            .post(&url)
-            .headers(headers)
-            .form(&form_data)
-            .send()
-            .await?
-            .text()
-            .await?;
+        let page = Arc::new(page);
+
+        if let Some(cache) = &self.form_cache {
+            cache.insert(url, page.clone());
+        }
+
+        Ok(page)
+    }
+
+    /// Submit a shindan, refetching the form once if a cached CSRF token was rejected.
+    #[cfg(any(feature = "segments", feature = "html"))]
+    pub(crate) async fn submit_form(&self, id: &str, name: &str) -> Result<(Arc<ShindanPage>, ResponseInfo, String)> {
+        let (page, response) = self.submit_form_response(id, name).await?;
+        let info = ResponseInfo::of(&response);
+        Ok((page, info, self.read_text(response).await?))
+    }
+
+    /// Like [`submit_form`](Self::submit_form), but leave the body of the result page unread.
+    #[cfg(any(feature = "segments", feature = "html"))]
+    pub(crate) async fn submit_form_response(&self, id: &str, name: &str) -> Result<(Arc<ShindanPage>, Response)> {
+        let url = self.url(id);
+
+        let page = self.page(&url).await?;
+        let response = self.post_form(&page, name).await?;
+
+        if !http_utils::is_csrf_token_mismatch(response.status()) || self.form_cache.is_none() {
+            return Ok((page, response));
+        }
+
+        if let Some(cache) = &self.form_cache {
+            cache.invalidate(&url);
+        }
 
-        Ok((title, response_text))
+        let page = self.page(&url).await?;
+        let response = self.post_form(&page, name).await?;
+
+        Ok((page, response))
     }
 
-    async fn init_res(&self, id: &str, name: &str) -> Result<String> {
-        let (_, response_text) = self.fetch_with_form_data(id, name, false).await?;
-        Ok(response_text)
+    /// Submit a shindan and extract the segments of the result page.
+    ///
+    /// With the `streaming` feature the body is parsed as it arrives instead of
+    /// being buffered and parsed into a DOM.
+    #[cfg(feature = "segments")]
+    async fn submit_for_segments(&self, id: &str, name: &str) -> Result<(Arc<ShindanPage>, Segments)> {
+        #[cfg(feature = "streaming")]
+        {
+            let (page, response) = self.submit_form_response(id, name).await?;
+            let info = ResponseInfo::of(&response);
+
+            let mut extractor = SegmentExtractor::new(&self.selectors, self.parse_options, Some(name));
+            self.read_body(response, |chunk| extractor.write(chunk)).await?;
+
+            Ok((page, extractor.finish().context(info)?))
+        }
+
+        #[cfg(not(feature = "streaming"))]
+        {
+            let (page, info, response_text) = self.submit_form(id, name).await?;
+            let segments = html_utils::get_segments(&self.selectors, self.parse_options, Some(name), &Html::parse_document(&response_text)).context(info)?;
+
+            Ok((page, segments))
+        }
     }
 
-    async fn get_title_and_init_res(&self, id: &str, name: &str) -> Result<(String, String)> {
-        let (title, response_text) = self.fetch_with_form_data(id, name, true).await?;
-        Ok((title.unwrap(), response_text))
+    /// Submit the form of a page to the URL that served it, which keeps the CSRF token valid across redirects.
+    pub(crate) async fn post_form(&self, page: &ShindanPage, name: &str) -> Result<Response> {
+        if let Some(delay) = &self.submit_delay {
+            delay.sleep().await;
+        }
+
+        let headers = http_utils::prepare_headers(&page.session_cookie)?;
+
+        let request = self.client
+            .post(page.final_url())
+            .headers(headers)
+            .form(&page.form_data_for(name, self.seed.as_deref()));
+
+        self.send(request).await
     }
 
     /**
@@ -251,9 +842,17 @@ impl ShindanClient {
     ```
     */
     #[cfg(feature = "segments")]
-    pub async fn get_segments(&self, id: &str, name: &str) -> Result<Segments> {
-        let response_text = self.init_res(id, name).await?;
-        html_utils::get_segments(&response_text)
+    pub async fn get_segments(&self, id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>, name: &str) -> Result<Segments> {
+        let id = shindan_id::convert(id)?;
+        if let Some(client) = self.fallback_for(&id).await? {
+            return Box::pin(client.get_segments(id, name)).await;
+        }
+
+        if self.tracks_results() {
+            return Ok(self.get_result(&id, name).await?.segments);
+        }
+
+        Ok(self.submit_for_segments(id.as_str(), name).await?.1)
     }
 
     /**
@@ -291,14 +890,215 @@ impl ShindanClient {
     #[cfg(feature = "segments")]
     pub async fn get_segments_with_title(
         &self,
-        id: &str,
+        id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>,
         name: &str,
     ) -> Result<(Segments, String)> {
-        let (title, response_text) = self.get_title_and_init_res(id, name).await?;
+        let id = shindan_id::convert(id)?;
+        if let Some(client) = self.fallback_for(&id).await? {
+            return Box::pin(client.get_segments_with_title(id, name)).await;
+        }
+
+        if self.tracks_results() {
+            let result = self.get_result(&id, name).await?;
+            return Ok((result.segments, result.title));
+        }
+
+        let (page, segments) = self.submit_for_segments(id.as_str(), name).await?;
+
+        Ok((segments, page.metadata()?.title))
+    }
+
+    /**
+    Get the complete result of a shindan.
+
+    With the result cache enabled, concurrent calls for the same name on the
+    same site day share a single submission.
+
+    # Arguments
+    - `id` - The ID of the shindan.
+    - `name` - The name to use for the shindan.
+
+    # Returns
+    The result of the shindan, including its title and segments.
+
+    # Examples
+    ```
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::En).unwrap();
+
+        let result = client
+            .get_result("1222992", "test_user")
+            .await
+            .unwrap();
+
+        assert_eq!("Fantasy Stats", result.title);
+    }
+    ```
+    */
+    #[cfg(feature = "segments")]
+    pub async fn get_result(&self, id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>, name: &str) -> Result<ShindanResult> {
+        let id = shindan_id::convert(id)?;
+        if let Some(client) = self.fallback_for(&id).await? {
+            return Box::pin(client.get_result(id, name)).await;
+        }
+
+        let key = CacheKey::new(self.domain.clone(), id.as_str(), name, Utc::now());
+        if let Some(cache) = &self.result_cache {
+            if let Some(result) = cache.get(&key).await.context("Failed to read the result cache")? {
+                return Ok(result);
+            }
+        }
+
+        match &self.result_cache {
+            Some(_) => {
+                let client = self.clone();
+                let name = name.to_string();
+                self.result_flights
+                    .run(key.clone(), async move { client.submit_result(id.as_str(), &name, key).await })
+                    .await
+            }
+            None => self.submit_result(id.as_str(), name, key).await,
+        }
+    }
+
+    /**
+    Get the result of a shindan already run with a name on the current site day, without submitting it.
+
+    The site day rolls over at midnight JST. The result cache is checked first, then
+    the history store, so with either configured a bot can enforce once-per-day rules
+    and replay the earlier result.
+
+    # Arguments
+    - `id` - The ID of the shindan.
+    - `name` - The name the shindan was run with.
+
+    # Returns
+    The earlier result, or `None` if the shindan has not been run with the name today
+    (or neither a result cache nor a history store is configured).
+
+    # Errors
+    Returns error if the result cache or the history store fails.
+
+    # Examples
+    ```
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::builder(ShindanDomain::En)
+            .result_cache()
+            .build()
+            .unwrap();
+
+        let result = match client.already_run_today("1222992", "test_user").await.unwrap() {
+            Some(result) => result,
+            None => client.get_result("1222992", "test_user").await.unwrap(),
+        };
+
+        println!("{}", result.segments);
+    }
+    ```
+    */
+    #[cfg(feature = "segments")]
+    pub async fn already_run_today(&self, id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>, name: &str) -> Result<Option<ShindanResult>> {
+        let id = shindan_id::convert(id)?;
+        let id = id.as_str();
+        let now = Utc::now();
+
+        if let Some(cache) = &self.result_cache {
+            let key = CacheKey::new(self.domain.clone(), id, name, now);
+            if let Some(result) = cache.get(&key).await.context("Failed to read the result cache")? {
+                return Ok(Some(result));
+            }
+        }
+
+        #[cfg(feature = "history")]
+        if let Some(history) = &self.history {
+            let entry = history
+                .for_day(id, name, site_day::site_day(now))
+                .await
+                .context("Failed to read the history")?;
+            return Ok(entry.map(|entry| entry.result));
+        }
+
+        Ok(None)
+    }
 
-        let segments = html_utils::get_segments(&response_text)?;
+    /// Whether submissions must produce a full result, for the result cache or the history.
+    #[cfg(feature = "segments")]
+    pub(crate) fn tracks_results(&self) -> bool {
+        #[cfg(feature = "history")]
+        if self.history.is_some() {
+            return true;
+        }
+
+        self.result_cache.is_some()
+    }
+
+    /// Submit a shindan and store the result in the result cache, if enabled.
+    #[cfg(feature = "segments")]
+    async fn submit_result(&self, id: &str, name: &str, key: CacheKey) -> Result<ShindanResult> {
+        let (page, info, response_text) = self.submit_form(id, name).await?;
+        self.store_result(id, name, key, &page, info, &response_text).await
+    }
+
+    /// Build the result of a submitted shindan from its result page and store it in the result cache and the history, if enabled.
+    #[cfg(feature = "segments")]
+    pub(crate) async fn store_result(
+        &self,
+        id: &str,
+        name: &str,
+        key: CacheKey,
+        page: &ShindanPage,
+        info: ResponseInfo,
+        response_text: &str,
+    ) -> Result<ShindanResult> {
+        let metadata = page.metadata()?;
+        let (segments, hashtags, chart) = {
+            let result_document = Html::parse_document(response_text);
+            let segments = html_utils::get_segments(&self.selectors, self.parse_options, Some(name), &result_document).with_context(|| info.clone())?;
+            (
+                segments,
+                html_utils::get_hashtags(&self.selectors, &result_document),
+                html_utils::get_chart(&self.selectors, &result_document),
+            )
+        };
 
-        Ok((segments, title))
+        let domain = page.redirected_domain().unwrap_or_else(|| self.domain.clone());
+        let mut result = ShindanResult::new(id, name, domain, &metadata.title, segments);
+        result.description = metadata.description;
+        result.hashtags = hashtags;
+        result.chart = chart;
+        #[cfg(feature = "language")]
+        {
+            result.language = result.detect_language();
+        }
+        #[cfg(feature = "scores")]
+        {
+            result.extracted_scores = result.extract_scores(&self.score_rules);
+        }
+        result.response = Some(info);
+
+        if let Some(translator) = &self.translator {
+            let translation = result.segments.translate(translator.as_ref()).await.context("Failed to translate the result")?;
+            result.translation = Some(translation);
+        }
+
+        if let Some(cache) = &self.result_cache {
+            let expires_at = result_cache::expires_at(result.fetched_at, self.result_cache_ttl);
+            cache
+                .put(key, result.clone(), expires_at)
+                .await
+                .context("Failed to write the result cache")?;
+        }
+
+        #[cfg(feature = "history")]
+        self.record_history(&result).await?;
+
+        Ok(result)
     }
 
     /**
@@ -329,9 +1129,71 @@ impl ShindanClient {
     ```
     */
     #[cfg(feature = "html")]
-    pub async fn get_html_str(&self, id: &str, name: &str) -> Result<String> {
-        let response_text = self.init_res(id, name).await?;
-        html_utils::get_html_str(id, &response_text)
+    pub async fn get_html_str(&self, id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>, name: &str) -> Result<String> {
+        let id = shindan_id::convert(id)?;
+        if let Some(client) = self.fallback_for(&id).await? {
+            return Box::pin(client.get_html_str(id, name)).await;
+        }
+
+        let (page, info, response_text) = self.submit_form(id.as_str(), name).await?;
+        let html = html_utils::get_html_str(&self.selectors, id.as_str(), &response_text, self.preserve_whitespace).context(info)?;
+
+        Ok(self.finish_html(html, &response_text, &page).await)
+    }
+
+    /**
+    Get the result of a shindan and the HTML of its result page, from a single submission.
+
+    Use this rather than [`get_result`](Self::get_result) followed by
+    [`get_html_str`](Self::get_html_str), which would submit twice and may render
+    a different result than the one returned, e.g. for shindans with random outcomes.
+
+    # Arguments
+    - `id` - The ID of the shindan.
+    - `name` - The name to use for the shindan.
+
+    # Returns
+    The result and the HTML string of the shindan.
+
+    # Errors
+    Returns error if the submission fails or the result page cannot be parsed.
+
+    # Examples
+    ```
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::En).unwrap();
+
+        let (result, html_str) = client
+            .get_result_with_html("1222992", "test_user")
+            .await
+            .unwrap();
+
+        assert!(html_str.contains(&result.title));
+    }
+    ```
+    */
+    #[cfg(all(feature = "segments", feature = "html"))]
+    pub async fn get_result_with_html(
+        &self,
+        id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>,
+        name: &str,
+    ) -> Result<(ShindanResult, String)> {
+        let id = shindan_id::convert(id)?;
+        if let Some(client) = self.fallback_for(&id).await? {
+            return Box::pin(client.get_result_with_html(id, name)).await;
+        }
+
+        let key = CacheKey::new(self.domain.clone(), id.as_str(), name, Utc::now());
+        let (page, info, response_text) = self.submit_form(id.as_str(), name).await?;
+
+        let html = html_utils::get_html_str(&self.selectors, id.as_str(), &response_text, self.preserve_whitespace).context(info.clone())?;
+        let html = self.finish_html(html, &response_text, &page).await;
+        let result = self.store_result(id.as_str(), name, key, &page, info, &response_text).await?;
+
+        Ok((result, html))
     }
 
     /**
@@ -364,13 +1226,94 @@ impl ShindanClient {
     #[cfg(feature = "html")]
     pub async fn get_html_str_with_title(
         &self,
-        id: &str,
+        id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>,
         name: &str,
     ) -> Result<(String, String)> {
-        let (title, response_text) = self.get_title_and_init_res(id, name).await?;
+        let id = shindan_id::convert(id)?;
+        if let Some(client) = self.fallback_for(&id).await? {
+            return Box::pin(client.get_html_str_with_title(id, name)).await;
+        }
+
+        let (page, info, response_text) = self.submit_form(id.as_str(), name).await?;
+
+        let html = html_utils::get_html_str(&self.selectors, id.as_str(), &response_text, self.preserve_whitespace).context(info)?;
+        let html = self.finish_html(html, &response_text, &page).await;
+
+        Ok((html, page.metadata()?.title))
+    }
+
+    /**
+    Apply the language of the domain, the description and attribution toggles, the site
+    assets mode, fonts and chart overrides to HTML built from a result page of a shindan page.
+    */
+    #[cfg(feature = "html")]
+    pub(crate) async fn finish_html(&self, html: String, response_text: &str, page: &ShindanPage) -> String {
+        // Custom domains serve pages in any language, so theirs is read from the page.
+        let lang = self.domain.lang().map(str::to_string).or_else(|| html_utils::get_lang(response_text));
+        let html = match lang {
+            Some(lang) => html.replacen(r#"<html lang="en">"#, &format!(r#"<html lang="{}">"#, lang), 1),
+            None => html,
+        };
+        let description = page.description().filter(|_| self.include_description);
+        let attribution = self.include_attribution.then(|| (page.final_url(), page.author(), site_day::site_today()));
+        let html = html.replacen(RESULT_EXTRAS, &html_utils::get_result_extras(description, attribution), 1);
+        let html = self.apply_site_assets(html, response_text).await;
+        let html = match &self.font_style {
+            Some(style) => html.replacen("</head>", &format!("    {}\n</head>", style), 1),
+            None => html,
+        };
+
+        #[cfg(feature = "segments")]
+        let html = if !self.chart_overrides.is_empty() && html.contains("new Chart") {
+            html.replacen("</body>", &format!("{}\n</body>", self.chart_overrides.script()), 1)
+        } else {
+            html
+        };
+
+        html
+    }
+
+    /**
+    Get the HTML of just the result of a shindan, to embed it into another page or an email.
+
+    Unlike [`get_html_str`](Self::get_html_str), there is no page around it, no CSS and no
+    scripts: only the inner HTML of the result element, with animated effects replaced by
+    their final text. Only the elements and attributes of result markup are kept, without
+    inline styles, and URLs must be relative or use `http` or `https`.
+
+    # Arguments
+    - `id` - The ID of the shindan.
+    - `name` - The name to use for the shindan.
+
+    # Returns
+    The sanitized HTML of the result.
+
+    # Examples
+    ```
+    use shindan_maker::{ShindanClient, ShindanDomain};
 
-        let html = html_utils::get_html_str(id, &response_text)?;
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::En).unwrap();
 
-        Ok((html, title))
+        let fragment = client
+            .get_result_html_fragment("1222992", "test_user")
+            .await
+            .unwrap();
+
+        assert!(fragment.contains("test_user"));
+        assert!(!fragment.contains("<script"));
+    }
+    ```
+    */
+    #[cfg(feature = "html")]
+    pub async fn get_result_html_fragment(&self, id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>, name: &str) -> Result<String> {
+        let id = shindan_id::convert(id)?;
+        if let Some(client) = self.fallback_for(&id).await? {
+            return Box::pin(client.get_result_html_fragment(id, name)).await;
+        }
+
+        let (_, info, response_text) = self.submit_form(id.as_str(), name).await?;
+        html_utils::get_result_html_fragment(&self.selectors, &response_text).context(info)
     }
 }
\ No newline at end of file
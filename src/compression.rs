@@ -0,0 +1,103 @@
+use once_cell::sync::Lazy;
+use anyhow::{anyhow, Result};
+use reqwest::header::{self, HeaderMap};
+
+#[cfg(any(feature = "gzip", feature = "brotli", feature = "zstd"))]
+use std::{io::Write, mem};
+
+/// The codings compiled into the crate, in order of preference.
+const CODINGS: &[&str] = &[
+    #[cfg(feature = "zstd")]
+    "zstd",
+    #[cfg(feature = "brotli")]
+    "br",
+    #[cfg(feature = "gzip")]
+    "gzip",
+    #[cfg(feature = "gzip")]
+    "deflate",
+];
+
+/// The `Accept-Encoding` value advertising the codings compiled into the crate.
+pub(crate) static ACCEPT_ENCODING: Lazy<Option<String>> =
+    Lazy::new(|| (!CODINGS.is_empty()).then(|| CODINGS.join(", ")));
+
+/// Decodes a response body chunk by chunk, according to its `Content-Encoding`.
+pub(crate) enum BodyDecoder {
+    Identity,
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzDecoder<Vec<u8>>),
+    #[cfg(feature = "gzip")]
+    Deflate(flate2::write::ZlibDecoder<Vec<u8>>),
+    #[cfg(feature = "brotli")]
+    Brotli(Box<brotli_decompressor::DecompressorWriter<Vec<u8>>>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Decoder<'static, Vec<u8>>),
+}
+
+impl BodyDecoder {
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Result<Self> {
+        let Some(encoding) = headers.get(header::CONTENT_ENCODING) else {
+            return Ok(Self::Identity);
+        };
+
+        match encoding.to_str()?.trim() {
+            "" | "identity" => Ok(Self::Identity),
+            #[cfg(feature = "gzip")]
+            "gzip" | "x-gzip" => Ok(Self::Gzip(flate2::write::GzDecoder::new(Vec::new()))),
+            #[cfg(feature = "gzip")]
+            "deflate" => Ok(Self::Deflate(flate2::write::ZlibDecoder::new(Vec::new()))),
+            #[cfg(feature = "brotli")]
+            "br" => Ok(Self::Brotli(Box::new(brotli_decompressor::DecompressorWriter::new(Vec::new(), 4096)))),
+            #[cfg(feature = "zstd")]
+            "zstd" => Ok(Self::Zstd(zstd::stream::write::Decoder::new(Vec::new())?)),
+            encoding => Err(anyhow!("Unsupported content encoding: {}", encoding)),
+        }
+    }
+
+    /// Decode the next chunk, returning whatever output it completed.
+    pub(crate) fn write(&mut self, chunk: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Identity => Ok(chunk.to_vec()),
+            #[cfg(feature = "gzip")]
+            Self::Gzip(decoder) => {
+                decoder.write_all(chunk)?;
+                Ok(mem::take(decoder.get_mut()))
+            }
+            #[cfg(feature = "gzip")]
+            Self::Deflate(decoder) => {
+                decoder.write_all(chunk)?;
+                Ok(mem::take(decoder.get_mut()))
+            }
+            #[cfg(feature = "brotli")]
+            Self::Brotli(decoder) => {
+                decoder.write_all(chunk)?;
+                Ok(mem::take(decoder.get_mut()))
+            }
+            #[cfg(feature = "zstd")]
+            Self::Zstd(decoder) => {
+                decoder.write_all(chunk)?;
+                Ok(mem::take(decoder.get_mut()))
+            }
+        }
+    }
+
+    /// Flush the decoder at the end of the body.
+    pub(crate) fn finish(self) -> Result<Vec<u8>> {
+        match self {
+            Self::Identity => Ok(Vec::new()),
+            #[cfg(feature = "gzip")]
+            Self::Gzip(decoder) => Ok(decoder.finish()?),
+            #[cfg(feature = "gzip")]
+            Self::Deflate(decoder) => Ok(decoder.finish()?),
+            #[cfg(feature = "brotli")]
+            Self::Brotli(decoder) => decoder
+                .into_inner()
+                .map_err(|_| anyhow!("Failed to decode the brotli response body")),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(mut decoder) => {
+                decoder.flush()?;
+                Ok(mem::take(decoder.get_mut()))
+            }
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::client::ShindanClient;
+use crate::selectors::SelectorConfig;
+use crate::shindan_domain::ShindanDomain;
+use crate::builder::ShindanClientBuilder;
+
+/**
+The settings of a client and its output, loadable from a configuration file.
+
+Lets operators of bots tune a client without recompiling. Every field is optional
+in the file and falls back to the client's default; durations are in seconds.
+
+# Examples
+```
+use anyhow::Result;
+use shindan_maker::{ShindanClient, ShindanConfig, ShindanDomain};
+
+fn main() -> Result<()> {
+    let config = ShindanConfig::from_json(r#"{
+        "domain": "en",
+        "timeout_secs": 10,
+        "retries": 2,
+        "rate_limit": { "requests": 30, "period_secs": 60 }
+    }"#)?;
+
+    let client = ShindanClient::from_config(&config)?;
+
+    assert_eq!(&ShindanDomain::En, client.domain());
+    Ok(())
+}
+```
+*/
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShindanConfig {
+    /// The domain of ShindanMaker to use. Defaults to [`ShindanDomain::Jp`].
+    pub domain: ShindanDomain,
+    /// Another server to send requests to, see [`ShindanClientBuilder::base_url`].
+    pub base_url: Option<String>,
+    /// The timeout of every request. Defaults to 3 seconds.
+    pub timeout_secs: f64,
+    /// The maximum number of retries of a request failing transiently. Defaults to 0.
+    pub retries: u32,
+    /// Whether submissions are retried too, see [`ShindanClientBuilder::retry_submissions`]. Defaults to `false`.
+    pub retry_submissions: bool,
+    /// The rate limit of the client, see [`ShindanClientBuilder::rate_limit`].
+    pub rate_limit: Option<RateLimitConfig>,
+    /// Whether to cap the requests per domain, see [`ShindanClientBuilder::polite`]. Defaults to `false`.
+    pub polite: bool,
+    /// How long fetched forms are reused, see [`ShindanClientBuilder::form_cache_ttl`].
+    pub form_cache_ttl_secs: Option<f64>,
+    /// How long titles and descriptions are cached, see [`ShindanClientBuilder::metadata_cache_ttl`].
+    pub metadata_cache_ttl_secs: Option<f64>,
+    /// Whether to cache results until the site day rolls over. Defaults to `false`.
+    #[cfg(feature = "segments")]
+    pub result_cache: bool,
+    /// How long results are cached at most, see [`ShindanClientBuilder::result_cache_ttl`].
+    #[cfg(feature = "segments")]
+    pub result_cache_ttl_secs: Option<f64>,
+    /// The CSS selectors used to scrape pages; fields left out keep their built-in selectors.
+    pub selectors: Option<SelectorConfig>,
+}
+
+/// A number of requests allowed per period.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    /// The maximum number of requests per period.
+    pub requests: u32,
+    /// The length of the period, in seconds.
+    pub period_secs: f64,
+}
+
+impl Default for ShindanConfig {
+    fn default() -> Self {
+        Self {
+            domain: ShindanDomain::Jp,
+            base_url: None,
+            timeout_secs: 3.0,
+            retries: 0,
+            retry_submissions: false,
+            rate_limit: None,
+            polite: false,
+            form_cache_ttl_secs: None,
+            metadata_cache_ttl_secs: None,
+            #[cfg(feature = "segments")]
+            result_cache: false,
+            #[cfg(feature = "segments")]
+            result_cache_ttl_secs: None,
+            selectors: None,
+        }
+    }
+}
+
+impl ShindanConfig {
+    /**
+    Parse a configuration from JSON.
+
+    # Arguments
+    - `json` - The JSON configuration.
+
+    # Errors
+    Returns error if the JSON is invalid.
+    */
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).context("Failed to parse the client configuration")
+    }
+
+    /**
+    Parse a configuration from TOML.
+
+    # Arguments
+    - `toml` - The TOML configuration.
+
+    # Errors
+    Returns error if the TOML is invalid.
+    */
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        toml::from_str(toml).context("Failed to parse the client configuration")
+    }
+
+    /**
+    Load a configuration from a `.json` or, with the `toml` feature, a `.toml` file.
+
+    # Arguments
+    - `path` - The path of the configuration file.
+
+    # Errors
+    Returns error if the file cannot be read or parsed.
+    */
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read the client configuration {}", path.display()))?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            #[cfg(feature = "toml")]
+            Some("toml") => Self::from_toml(&content),
+            #[cfg(not(feature = "toml"))]
+            Some("toml") => Err(anyhow!("TOML client configurations need the \"toml\" feature")),
+            _ => Self::from_json(&content),
+        }
+    }
+
+    /**
+    Get a client builder with the settings of the configuration, to add the ones a file cannot hold.
+
+    # Errors
+    Returns error if a duration is negative or not finite.
+    */
+    pub fn builder(&self) -> Result<ShindanClientBuilder> {
+        let mut builder = ShindanClient::builder(self.domain.clone())
+            .timeout(secs("timeout_secs", self.timeout_secs)?)
+            .retries(self.retries)
+            .retry_submissions(self.retry_submissions)
+            .polite(self.polite);
+
+        if let Some(base_url) = &self.base_url {
+            builder = builder.base_url(base_url);
+        }
+        if let Some(RateLimitConfig { requests, period_secs }) = self.rate_limit {
+            builder = builder.rate_limit(requests, secs("rate_limit.period_secs", period_secs)?);
+        }
+        if let Some(ttl) = self.form_cache_ttl_secs {
+            builder = builder.form_cache_ttl(secs("form_cache_ttl_secs", ttl)?);
+        }
+        if let Some(ttl) = self.metadata_cache_ttl_secs {
+            builder = builder.metadata_cache_ttl(secs("metadata_cache_ttl_secs", ttl)?);
+        }
+        #[cfg(feature = "segments")]
+        {
+            if self.result_cache {
+                builder = builder.result_cache();
+            }
+            if let Some(ttl) = self.result_cache_ttl_secs {
+                builder = builder.result_cache_ttl(secs("result_cache_ttl_secs", ttl)?);
+            }
+        }
+        if let Some(selectors) = &self.selectors {
+            builder = builder.selectors(selectors.clone());
+        }
+
+        Ok(builder)
+    }
+}
+
+impl ShindanClient {
+    /**
+    Create a client from a configuration.
+
+    # Arguments
+    - `config` - The settings of the client.
+
+    # Returns
+    The client.
+
+    # Errors
+    Returns error if a duration is invalid, a selector cannot be parsed or the HTTP client cannot be built.
+
+    See [`ShindanConfig`] for an example.
+    */
+    pub fn from_config(config: &ShindanConfig) -> Result<Self> {
+        config.builder()?.build()
+    }
+}
+
+fn secs(field: &str, secs: f64) -> Result<Duration> {
+    Duration::try_from_secs_f64(secs).map_err(|err| anyhow!("Invalid duration {} for {}: {}", secs, field, err))
+}
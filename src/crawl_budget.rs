@@ -0,0 +1,68 @@
+use url::Url;
+use std::sync::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::rt;
+
+/// The requests per period polite mode allows on each domain by default.
+pub(crate) const DEFAULT_BUDGET: (u32, Duration) = (60, Duration::from_secs(60));
+
+/// Caps the requests sent to each host within any window of `period`, for polite mode.
+///
+/// Unlike the [rate limit](crate::ShindanClientBuilder::rate_limit), requests are sent
+/// as they come until the budget of a host is spent, and only then wait.
+#[derive(Debug)]
+pub(crate) struct CrawlBudget {
+    requests: usize,
+    period: Duration,
+    /// The send times reserved by the last `requests` requests to each host.
+    slots: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl CrawlBudget {
+    pub(crate) fn new(requests: u32, period: Duration) -> Self {
+        Self {
+            requests: requests.max(1) as usize,
+            period,
+            slots: Mutex::default(),
+        }
+    }
+
+    /// Wait until a request to the host of `url` fits in its budget.
+    pub(crate) async fn acquire(&self, url: &Url) {
+        let host = url.host_str().unwrap_or_default();
+        let now = Instant::now();
+
+        let (slot, throttling_starts) = {
+            let mut slots = self.slots.lock().unwrap();
+            let slots = slots.entry(host.to_string()).or_default();
+
+            let slot = if slots.len() >= self.requests {
+                now.max(slots[slots.len() - self.requests] + self.period)
+            } else {
+                now
+            };
+            let was_throttled = slots.back().is_some_and(|last| *last > now);
+
+            slots.push_back(slot);
+            if slots.len() > self.requests {
+                slots.pop_front();
+            }
+
+            (slot, slot > now && !was_throttled)
+        };
+
+        if throttling_starts {
+            log::info!(
+                "Polite mode: {} requests per {:?} to {} spent, waiting {:?}",
+                self.requests,
+                self.period,
+                host,
+                slot - now
+            );
+        }
+
+        rt::sleep_until(slot).await;
+    }
+}
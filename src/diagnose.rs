@@ -0,0 +1,279 @@
+use std::fmt;
+use anyhow::{anyhow, Result};
+use scraper::Html;
+use serde::{Deserialize, Serialize};
+
+use crate::http_utils;
+use crate::html_utils;
+use crate::page::ShindanPage;
+use crate::client::ShindanClient;
+use crate::selectors::Chain;
+use crate::shindan_id::{self, ShindanId};
+use crate::shindan_domain::ShindanDomain;
+
+/// The name submitted by [`ShindanClient::diagnose`].
+const DIAGNOSE_NAME: &str = "diagnose";
+
+/// A shindan known to be served by every domain, checked by [`ShindanClient::verify_domain`].
+const KNOWN_SHINDAN_ID: &str = "1222992";
+
+/// The outcome of one extraction step of a [`Diagnosis`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticStep {
+    /// The name of the step, e.g. `title` or `token`.
+    pub name: String,
+    /// Whether the step succeeded.
+    pub ok: bool,
+    /// What succeeded, e.g. the selector that matched, or why the step failed.
+    pub detail: String,
+}
+
+impl DiagnosticStep {
+    fn ok(name: &str, detail: impl Into<String>) -> Self {
+        Self { name: name.to_string(), ok: true, detail: detail.into() }
+    }
+
+    fn failed(name: &str, detail: impl Into<String>) -> Self {
+        Self { name: name.to_string(), ok: false, detail: detail.into() }
+    }
+
+    fn selector(name: &str, chain: &Chain, dom: &Html) -> Self {
+        match chain.matching(dom) {
+            Some((selector, _)) => Self::ok(name, format!("matched {}", selector)),
+            None => Self::failed(name, "no selector matched"),
+        }
+    }
+}
+
+/// What [`ShindanClient::diagnose`] found out about a shindan, to attach to bug reports.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnosis {
+    pub id: String,
+    pub url: String,
+    /// The extraction steps, in the order the client runs them.
+    pub steps: Vec<DiagnosticStep>,
+}
+
+impl Diagnosis {
+    /// Whether every step succeeded.
+    pub fn is_healthy(&self) -> bool {
+        self.steps.iter().all(|step| step.ok)
+    }
+
+    /// The steps that failed.
+    pub fn failures(&self) -> impl Iterator<Item = &DiagnosticStep> {
+        self.steps.iter().filter(|step| !step.ok)
+    }
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Diagnosis of {} ({})", self.id, self.url)?;
+        for step in &self.steps {
+            writeln!(f, "[{}] {}: {}", if step.ok { "ok" } else { "FAIL" }, step.name, step.detail)?;
+        }
+        Ok(())
+    }
+}
+
+impl ShindanClient {
+    /**
+    Fetch and submit a shindan, reporting which extraction steps succeed.
+
+    Caches are bypassed, so the report reflects the current markup of the site.
+    Steps depending on a failed one are skipped.
+
+    # Arguments
+    - `id` - The ID of the shindan.
+
+    # Returns
+    The report of every step run.
+
+    # Errors
+    Never for extraction failures, which are part of the report; only if the ID is
+    invalid or the request cannot be built.
+
+    # Examples
+    ```
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::En).unwrap();
+
+        let diagnosis = client.diagnose("1222992").await.unwrap();
+        if !diagnosis.is_healthy() {
+            eprintln!("{}", diagnosis);
+        }
+    }
+    ```
+    */
+    pub async fn diagnose(&self, id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>) -> Result<Diagnosis> {
+        let id = shindan_id::convert(id)?;
+        let url = self.url(id.as_str());
+        let mut diagnosis = Diagnosis {
+            id: id.to_string(),
+            url: url.clone(),
+            steps: Vec::new(),
+        };
+        let steps = &mut diagnosis.steps;
+
+        let response = match self.send(self.client.get(&url)).await {
+            Ok(response) => response,
+            Err(err) => {
+                steps.push(DiagnosticStep::failed("page", format!("{:#}", err)));
+                return Ok(diagnosis);
+            }
+        };
+        let status = response.status();
+        let final_url = response.url().to_string();
+        let session_cookie = http_utils::extract_session_cookie(&response);
+        let text = match self.read_text(response).await {
+            Ok(text) if status.is_success() => text,
+            Ok(_) => {
+                steps.push(DiagnosticStep::failed("page", format!("status {}", status)));
+                return Ok(diagnosis);
+            }
+            Err(err) => {
+                steps.push(DiagnosticStep::failed("page", format!("{:#}", err)));
+                return Ok(diagnosis);
+            }
+        };
+        steps.push(DiagnosticStep::ok("page", format!("status {}, {} bytes", status, text.len())));
+
+        steps.push(match &session_cookie {
+            Ok(_) => DiagnosticStep::ok("session_cookie", "found"),
+            Err(err) => DiagnosticStep::failed("session_cookie", err.to_string()),
+        });
+
+        let page = {
+            let dom = Html::parse_document(&text);
+
+            steps.push(match (self.selectors.shindan_title.matching(&dom), html_utils::extract_title(&self.selectors, &dom)) {
+                (Some((selector, _)), Ok(title)) => DiagnosticStep::ok("title", format!("matched {}: {:?}", selector, title)),
+                (_, Err(err)) => DiagnosticStep::failed("title", err.to_string()),
+                (None, Ok(_)) => unreachable!("a title was extracted without a matching selector"),
+            });
+            steps.push(DiagnosticStep::selector("description", &self.selectors.shindan_description_display, &dom));
+            for (name, chain) in ["token", "randname", "type"].into_iter().zip(&self.selectors.form) {
+                steps.push(DiagnosticStep::selector(name, chain, &dom));
+            }
+
+            match (session_cookie, html_utils::extract_form_data(&self.selectors, &dom)) {
+                (Ok(session_cookie), Ok(form_data)) => {
+                    let mut page = ShindanPage::new(&url, session_cookie, None, None, form_data);
+                    page.final_url = final_url;
+                    Some(page)
+                }
+                _ => None,
+            }
+        };
+
+        let Some(page) = page else {
+            steps.push(DiagnosticStep::failed("submit", "skipped, the form could not be extracted"));
+            return Ok(diagnosis);
+        };
+
+        let response = match self.post_form(&page, DIAGNOSE_NAME).await {
+            Ok(response) => response,
+            Err(err) => {
+                steps.push(DiagnosticStep::failed("submit", format!("{:#}", err)));
+                return Ok(diagnosis);
+            }
+        };
+        let status = response.status();
+        let text = match self.read_text(response).await {
+            Ok(text) if status.is_success() => text,
+            Ok(_) => {
+                steps.push(DiagnosticStep::failed("submit", format!("status {}", status)));
+                return Ok(diagnosis);
+            }
+            Err(err) => {
+                steps.push(DiagnosticStep::failed("submit", format!("{:#}", err)));
+                return Ok(diagnosis);
+            }
+        };
+        steps.push(DiagnosticStep::ok("submit", format!("status {}, {} bytes", status, text.len())));
+
+        let dom = Html::parse_document(&text);
+
+        #[cfg(feature = "segments")]
+        {
+            steps.push(DiagnosticStep::selector("result", &self.selectors.post_display, &dom));
+
+            let dom_segments = html_utils::get_segments(&self.selectors, self.parse_options, Some(DIAGNOSE_NAME), &dom);
+            steps.push(match &dom_segments {
+                Ok(segments) => DiagnosticStep::ok("segments", format!("dom parser, {} segments", segments.len())),
+                Err(err) => DiagnosticStep::failed("segments", format!("dom parser: {}", err)),
+            });
+
+            #[cfg(feature = "streaming")]
+            {
+                let mut extractor = crate::stream_parse::SegmentExtractor::new(&self.selectors, self.parse_options, Some(DIAGNOSE_NAME));
+                let streamed = extractor.write(text.as_bytes()).and_then(|_| extractor.finish());
+                steps.push(match (streamed, &dom_segments) {
+                    (Ok(streamed), Ok(segments)) if streamed == *segments => {
+                        DiagnosticStep::ok("streaming", "streaming parser agrees with the dom parser")
+                    }
+                    (Ok(streamed), _) => {
+                        DiagnosticStep::failed("streaming", format!("streaming parser found {} segments", streamed.len()))
+                    }
+                    (Err(err), _) => DiagnosticStep::failed("streaming", err.to_string()),
+                });
+            }
+
+            let chart = html_utils::get_chart(&self.selectors, &dom);
+            steps.push(DiagnosticStep::ok(
+                "chart",
+                chart.map_or_else(|| "none".to_string(), |chart| format!("{} chart, {} labels", chart.kind, chart.labels.len())),
+            ));
+        }
+
+        #[cfg(feature = "html")]
+        steps.push(DiagnosticStep::selector("title_and_result", &self.selectors.title_and_result, &dom));
+
+        #[cfg(not(any(feature = "segments", feature = "html")))]
+        let _ = dom;
+
+        Ok(diagnosis)
+    }
+
+    /**
+    Check that a known shindan works end-to-end on a domain.
+
+    Useful in health checks, since form fields and effect markup differ subtly
+    between the mirrors of the site.
+
+    # Arguments
+    - `domain` - The domain to verify.
+
+    # Returns
+    The diagnosis of the known shindan, every step of which succeeded.
+
+    # Errors
+    Returns error listing the failed steps if any step failed.
+
+    # Examples
+    ```
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        for domain in ShindanDomain::ALL {
+            if let Err(err) = ShindanClient::verify_domain(domain.clone()).await {
+                eprintln!("{:?}: {:#}", domain, err);
+            }
+        }
+    }
+    ```
+    */
+    pub async fn verify_domain(domain: ShindanDomain) -> Result<Diagnosis> {
+        let diagnosis = ShindanClient::new(domain.clone())?.diagnose(KNOWN_SHINDAN_ID).await?;
+
+        if !diagnosis.is_healthy() {
+            return Err(anyhow!("Domain {:?} failed verification:\n{}", domain, diagnosis));
+        }
+
+        Ok(diagnosis)
+    }
+}
@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::segment::Segment;
+use crate::result::ShindanResult;
+
+/// How a segment differs between two results.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum SegmentChange {
+    /// The segment is the same in both, but for the name; holds the segment of the second result.
+    Unchanged { segment: Segment },
+    /// The segment is only in the second result.
+    Added { segment: Segment },
+    /// The segment is only in the first result.
+    Removed { segment: Segment },
+    /// A segment of the first result was replaced by one of the same type.
+    Changed { before: Segment, after: Segment },
+}
+
+/// The differences between two results, see [`diff_results`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultDiff {
+    /// The titles of the shindan before and after, if it was retitled.
+    pub title: Option<(String, String)>,
+    /// The segments of both results, in order.
+    pub changes: Vec<SegmentChange>,
+}
+
+impl ResultDiff {
+    /// Whether the results read the same but for their names.
+    pub fn is_unchanged(&self) -> bool {
+        self.title.is_none() && self.changes.iter().all(|change| matches!(change, SegmentChange::Unchanged { .. }))
+    }
+
+    /// The changes other than unchanged segments.
+    pub fn changed(&self) -> impl Iterator<Item = &SegmentChange> {
+        self.changes.iter().filter(|change| !matches!(change, SegmentChange::Unchanged { .. }))
+    }
+
+    /**
+    Render the changes as lines of text, prefixed with `-` for the first result and `+` for the second.
+
+    Unchanged segments are left out; segments without text, e.g. charts, are shown by type.
+
+    # Examples
+    ```
+    use shindan_maker::{diff_results, Segment, Segments, ShindanDomain, ShindanResult};
+
+    let result = |name: &str, luck: &str| {
+        let segments = Segments(vec![Segment::text(format!("{}'s luck today: ", name)), Segment::text(luck)]);
+        ShindanResult::new("1", name, ShindanDomain::En, "Daily luck", segments)
+    };
+
+    let diff = diff_results(&result("Alice", "Great"), &result("Bob", "Bad"));
+    assert_eq!("- Great\n+ Bad", diff.to_plain_text());
+    assert!(diff_results(&result("Alice", "Great"), &result("Bob", "Great")).is_unchanged());
+    ```
+    */
+    pub fn to_plain_text(&self) -> String {
+        let mut lines = Vec::new();
+        if let Some((before, after)) = &self.title {
+            lines.push(format!("- Title: {}", before));
+            lines.push(format!("+ Title: {}", after));
+        }
+        for change in self.changed() {
+            match change {
+                SegmentChange::Added { segment } => lines.push(format!("+ {}", describe(segment))),
+                SegmentChange::Removed { segment } => lines.push(format!("- {}", describe(segment))),
+                SegmentChange::Changed { before, after } => {
+                    lines.push(format!("- {}", describe(before)));
+                    lines.push(format!("+ {}", describe(after)));
+                }
+                SegmentChange::Unchanged { .. } => {}
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/**
+Compare two results segment by segment, e.g. today's fortune with yesterday's, or
+a result before and after its creator edited the shindan.
+
+Segments are matched in order, and differences in the submitted names are ignored,
+so results of different names can be compared. A run of removed segments followed
+by added ones of the same types is reported as changed segments.
+
+# Arguments
+- `a` - The first result, e.g. the older one.
+- `b` - The second result.
+
+# Returns
+The differences from `a` to `b`.
+*/
+pub fn diff_results(a: &ShindanResult, b: &ShindanResult) -> ResultDiff {
+    let before: Vec<Segment> = a.segments.iter().map(|segment| without_name(segment, &a.name)).collect();
+    let after: Vec<Segment> = b.segments.iter().map(|segment| without_name(segment, &b.name)).collect();
+
+    // The longest common subsequence of the segments, by dynamic programming from the end.
+    let mut lengths = vec![vec![0usize; after.len() + 1]; before.len() + 1];
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            lengths[i][j] = if before[i] == after[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::with_capacity(before.len().max(after.len()));
+    let mut removed: Vec<Segment> = Vec::new();
+    let mut added: Vec<Segment> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < before.len() || j < after.len() {
+        if i < before.len() && j < after.len() && before[i] == after[j] {
+            pair_changes(&mut changes, &mut removed, &mut added);
+            changes.push(SegmentChange::Unchanged { segment: b.segments[j].clone() });
+            i += 1;
+            j += 1;
+        } else if j < after.len() && (i == before.len() || lengths[i][j + 1] >= lengths[i + 1][j]) {
+            added.push(b.segments[j].clone());
+            j += 1;
+        } else {
+            removed.push(a.segments[i].clone());
+            i += 1;
+        }
+    }
+    pair_changes(&mut changes, &mut removed, &mut added);
+
+    let title = (a.title != b.title).then(|| (a.title.clone(), b.title.clone()));
+    ResultDiff { title, changes }
+}
+
+/// Report runs of removed and added segments, pairing those of the same types as changed.
+fn pair_changes(changes: &mut Vec<SegmentChange>, removed: &mut Vec<Segment>, added: &mut Vec<Segment>) {
+    let paired = removed.len() == added.len()
+        && removed.iter().zip(added.iter()).all(|(before, after)| before.type_ == after.type_);
+
+    if paired {
+        changes.extend(removed.drain(..).zip(added.drain(..)).map(|(before, after)| SegmentChange::Changed { before, after }));
+    } else {
+        changes.extend(removed.drain(..).map(|segment| SegmentChange::Removed { segment }));
+        changes.extend(added.drain(..).map(|segment| SegmentChange::Added { segment }));
+    }
+}
+
+/// A segment with the name replaced by a placeholder in its text, to compare results of different names.
+fn without_name(segment: &Segment, name: &str) -> Segment {
+    let mut segment = segment.clone();
+    if !name.is_empty() && segment.type_ == "text" {
+        if let Some(Value::String(text)) = segment.data.get_mut("text") {
+            *text = text.replace(name, "{name}");
+        }
+    }
+    segment
+}
+
+fn describe(segment: &Segment) -> String {
+    match segment.as_str() {
+        Some(text) => text.trim_end().replace('\n', " "),
+        None => format!("[{}]", segment.type_),
+    }
+}
@@ -0,0 +1,236 @@
+use anyhow::{anyhow, Result};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::render::escape_markdown;
+use crate::html_utils::escape_html;
+use crate::client::ShindanClient;
+use crate::segment::{Segment, Segments};
+use crate::shindan_id::{self, ShindanId};
+
+/// The submissions of a digest in flight at once.
+const DIGEST_CONCURRENCY: usize = 4;
+
+/// The result of a member of a digest.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DigestEntry {
+    pub name: String,
+    pub segments: Segments,
+}
+
+/// The members of a digest who got the same outcome.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DigestGroup {
+    /// The text of the result with the name of the member replaced by `{name}`.
+    pub outcome: String,
+    pub entries: Vec<DigestEntry>,
+}
+
+impl DigestGroup {
+    /// The names of the members, joined with commas.
+    pub fn names(&self) -> String {
+        self.entries.iter().map(|entry| entry.name.as_str()).collect::<Vec<_>>().join(", ")
+    }
+
+    /// The result of the first member, naming every member of the group instead, unless the first name is empty.
+    pub fn segments(&self) -> Segments {
+        let Some(first) = self.entries.first() else {
+            return Segments(Vec::new());
+        };
+        if self.entries.len() == 1 {
+            return first.segments.clone();
+        }
+
+        // An empty name would match between every character.
+        if first.name.is_empty() {
+            return first.segments.clone();
+        }
+
+        let names = self.names();
+        let segments = first.segments.iter().map(|segment| {
+            let mut segment = segment.clone();
+            if segment.type_ == "text" {
+                if let Some(Value::String(text)) = segment.data.get_mut("text") {
+                    *text = text.replace(&first.name, &names);
+                }
+            }
+            segment
+        });
+        Segments(segments.collect())
+    }
+}
+
+/// The results of a shindan for a roster, grouped by outcome.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Digest {
+    pub id: String,
+    pub title: String,
+    pub url: String,
+    /// The groups, largest first; members keep the order of the roster.
+    pub groups: Vec<DigestGroup>,
+    /// The names whose submission failed.
+    pub failed: Vec<String>,
+}
+
+impl ShindanClient {
+    /**
+    Run a shindan for a roster and combine the results in a single digest, e.g. for one morning message.
+
+    Members whose results read the same but for their name share a group.
+
+    # Arguments
+    - `id` - The ID of the shindan.
+    - `names` - The names of the members.
+
+    # Returns
+    The digest of the results.
+
+    # Errors
+    Returns error if the submissions for every name fail.
+
+    # Examples
+    ```
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::En).unwrap();
+
+        let digest = client.build_digest("1222992", &["Alice", "Bob"]).await.unwrap();
+
+        assert_eq!("Fantasy Stats", digest.title);
+        println!("{}", digest.to_markdown());
+    }
+    ```
+    */
+    pub async fn build_digest(&self, id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>, names: &[&str]) -> Result<Digest> {
+        let id = shindan_id::convert(id)?;
+        let jobs = names.iter().map(|name| (id.as_str().to_string(), name.to_string()));
+        let mut results: Vec<_> = self.submit_stream(jobs, DIGEST_CONCURRENCY).collect().await;
+        results.sort_by_key(|((_, name), _)| names.iter().position(|roster| roster == name));
+
+        let mut title = None;
+        let mut groups: Vec<DigestGroup> = Vec::new();
+        let mut failed = Vec::new();
+        let mut last_error = None;
+        for ((_, name), result) in results {
+            let result = match result {
+                Ok(result) => result,
+                Err(err) => {
+                    log::warn!(id = id.as_str(); "Left {} out of the digest: {:#}", name, err);
+                    failed.push(name);
+                    last_error = Some(err);
+                    continue;
+                }
+            };
+
+            title.get_or_insert(result.title);
+            let outcome = outcome(&result.segments, &name);
+            let entry = DigestEntry { name, segments: result.segments };
+            match groups.iter_mut().find(|group| group.outcome == outcome) {
+                Some(group) => group.entries.push(entry),
+                None => groups.push(DigestGroup { outcome, entries: vec![entry] }),
+            }
+        }
+
+        let Some(title) = title else {
+            return Err(last_error.unwrap_or_else(|| anyhow!("No names to run the shindan for")));
+        };
+        groups.sort_by_key(|group| std::cmp::Reverse(group.entries.len()));
+
+        Ok(Digest { url: self.url(id.as_str()), id: id.as_str().to_string(), title, groups, failed })
+    }
+}
+
+impl Digest {
+    /**
+    Render the digest as CommonMark, a section per group.
+
+    # Examples
+    ```
+    use shindan_maker::{Digest, DigestEntry, DigestGroup, Segment, Segments};
+
+    let entry = |name: &str| DigestEntry {
+        name: name.to_string(),
+        segments: Segments(vec![Segment::text(format!("{} is lucky", name))]),
+    };
+    let digest = Digest {
+        id: "1222992".to_string(),
+        title: "Daily luck".to_string(),
+        url: "https://shindanmaker.com/1222992".to_string(),
+        groups: vec![DigestGroup { outcome: "{name} is lucky".to_string(), entries: vec![entry("Alice"), entry("Bob")] }],
+        failed: vec![],
+    };
+
+    assert_eq!(
+        "# Daily luck\n\n## Alice, Bob\n\nAlice, Bob is lucky\n\n<https://shindanmaker.com/1222992>\n",
+        digest.to_markdown()
+    );
+    ```
+    */
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("# {}\n\n", escape_markdown(&self.title));
+        for group in &self.groups {
+            markdown.push_str(&format!("## {}\n\n", escape_markdown(&group.names())));
+            markdown.push_str(group.segments().to_markdown().trim_end());
+            markdown.push_str("\n\n");
+        }
+        if !self.failed.is_empty() {
+            markdown.push_str(&format!("Not available for: {}\n\n", escape_markdown(&self.failed.join(", "))));
+        }
+        markdown.push_str(&format!("<{}>\n", self.url));
+        markdown
+    }
+
+    /**
+    Render the digest as a standalone HTML document, a section per group.
+
+    The content is in `#digest`, which `to_image` captures as a JPEG image with the `image` feature.
+    */
+    pub fn to_html(&self) -> String {
+        let mut body = format!("<h1>{}</h1>", escape_html(&self.title));
+        for group in &self.groups {
+            body.push_str(&format!(
+                r#"<section><h2>{}</h2><p>{}</p></section>"#,
+                escape_html(&group.names()),
+                segments_html(&group.segments())
+            ));
+        }
+        if !self.failed.is_empty() {
+            body.push_str(&format!(r#"<p class="failed">Not available for: {}</p>"#, escape_html(&self.failed.join(", "))));
+        }
+
+        format!(
+            concat!(
+                r#"<!DOCTYPE html><html><head><meta charset="utf-8"><title>{}</title><style>"#,
+                "body{{margin:0;background:#f3f4f6;font-family:-apple-system,'Segoe UI',Roboto,Helvetica,Arial,sans-serif;color:#1f2937}}",
+                "#digest{{max-width:640px;margin:0 auto;padding:24px;background:#fff}}",
+                "h1{{font-size:24px;margin:0 0 16px}}h2{{font-size:16px;margin:16px 0 4px;color:#6b7280}}",
+                "p{{margin:0;line-height:1.5}}img{{display:block;max-width:100%;margin:8px 0}}.failed{{margin-top:16px;color:#6b7280}}",
+                r#"</style></head><body><div id="digest">{}</div></body></html>"#,
+            ),
+            escape_html(&self.title),
+            body
+        )
+    }
+}
+
+/// The text of a result with the name replaced by `{name}`, so that results differing only by name compare equal.
+fn outcome(segments: &Segments, name: &str) -> String {
+    let text: String = segments.iter().filter(|segment| segment.type_ == "text").filter_map(Segment::as_str).collect();
+    let text = if name.is_empty() { text } else { text.replace(name, "{name}") };
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn segments_html(segments: &Segments) -> String {
+    let mut html = String::new();
+    for segment in segments.iter() {
+        match (segment.type_.as_ref(), segment.as_str()) {
+            ("text", Some(text)) => html.push_str(&escape_html(text).replace('\n', "<br>")),
+            ("image", Some(file)) => html.push_str(&format!(r#"<img src="{}" alt="">"#, escape_html(file))),
+            _ => {}
+        }
+    }
+    html
+}
@@ -0,0 +1,112 @@
+use anyhow::Result;
+
+use crate::http_utils;
+use crate::client::ShindanClient;
+use crate::shindan_id::ShindanId;
+use crate::shindan_domain::ShindanDomain;
+
+impl ShindanClient {
+    /**
+    Get a client for another domain, sharing the configuration, caches and connections of this one.
+
+    Cheap enough to call per request, e.g. for the few shindans only published on
+    the JP domain. A base URL set with [`base_url`](crate::ShindanClientBuilder::base_url)
+    is replaced by the domain's.
+
+    # Arguments
+    - `domain` - The domain of ShindanMaker to use.
+
+    # Returns
+    The client for the domain.
+
+    # Examples
+    ```
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::Cn).unwrap();
+
+        let title = client
+            .with_domain(ShindanDomain::Jp)
+            .get_title("1222992")
+            .await
+            .unwrap();
+
+        println!("{}", title);
+    }
+    ```
+    */
+    pub fn with_domain(&self, domain: ShindanDomain) -> Self {
+        let mut client = self.clone();
+        client.base_url = domain.to_string();
+        client.domain = domain;
+        client.redirected_domains = Default::default();
+        client
+    }
+
+    /// The domain of ShindanMaker the client talks to.
+    pub fn domain(&self) -> &ShindanDomain {
+        &self.domain
+    }
+
+    /**
+    The domain the site last redirected the page of a shindan to, away from the domain it was requested from.
+
+    With [`follow_domain_redirects`](crate::ShindanClientBuilder::follow_domain_redirects)
+    enabled, this is the domain requests for the shindan are sent to.
+
+    # Arguments
+    - `id` - The ID of the shindan.
+
+    # Returns
+    The domain, or `None` if the page of the shindan was not redirected to another domain.
+    */
+    pub fn redirected_domain(&self, id: &str) -> Option<ShindanDomain> {
+        self.redirected_domains.lock().unwrap().get(id).cloned()
+    }
+
+    /// The client for the fallback domain serving a shindan the client's own domain does not.
+    ///
+    /// Domains are probed in order by fetching the page of the shindan, and the domain found
+    /// is remembered for the ID. `None` means the client's own domain is to be used.
+    pub(crate) async fn fallback_for(&self, id: &ShindanId) -> Result<Option<ShindanClient>> {
+        if self.fallback_domains.is_empty() {
+            return Ok(None);
+        }
+
+        let resolved = self.resolved_domains.lock().unwrap().get(id).cloned();
+        let domain = match resolved {
+            Some(domain) => domain,
+            None => self.probe_domains(id).await?,
+        };
+
+        Ok((domain != self.domain).then(|| self.on_domain(domain)))
+    }
+
+    /// Find the first domain serving a shindan, trying the client's own domain first.
+    async fn probe_domains(&self, id: &ShindanId) -> Result<ShindanDomain> {
+        let fallbacks = self.fallback_domains.iter().map(|domain| self.on_domain(domain.clone()));
+
+        let mut not_found = None;
+        for client in std::iter::once(self.clone()).chain(fallbacks) {
+            match client.page(&client.url(id.as_str())).await {
+                Ok(_) => {
+                    self.resolved_domains.lock().unwrap().insert(id.clone(), client.domain.clone());
+                    return Ok(client.domain);
+                }
+                Err(err) if http_utils::is_not_found(&err) => not_found = Some(err),
+                Err(err) => return Err(err),
+            }
+        }
+
+        Err(not_found.expect("The client's own domain is always probed"))
+    }
+
+    /// Like [`with_domain`](Self::with_domain), without falling back to other domains.
+    fn on_domain(&self, domain: ShindanDomain) -> Self {
+        let mut client = self.with_domain(domain);
+        client.fallback_domains = Default::default();
+        client
+    }
+}
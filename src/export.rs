@@ -0,0 +1,163 @@
+use std::io::Write;
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::search::SearchHit;
+use crate::result::ShindanResult;
+
+/**
+A record exported as a row by [`write_csv`] and, with the `parquet` feature, `write_parquet`.
+
+The columns of each type are stable: new columns are only ever appended. Every
+value is text, or missing; lists and maps are JSON encoded.
+*/
+pub trait ExportRow {
+    /// The names of the columns.
+    fn columns() -> &'static [&'static str];
+
+    /// The values of the row, one per column.
+    fn values(&self) -> Vec<Option<String>>;
+}
+
+/// Listings, e.g. search results: `id`, `title`, `url`, with the URL on the domain they were found on.
+impl ExportRow for SearchHit {
+    fn columns() -> &'static [&'static str] {
+        &["id", "title", "url"]
+    }
+
+    fn values(&self) -> Vec<Option<String>> {
+        vec![
+            Some(self.id.clone()),
+            Some(self.title.clone()),
+            Some(self.url()),
+        ]
+    }
+}
+
+/**
+Results: `id`, `name`, `domain`, `url`, `title`, `description`, `text` (the text
+segments), `images` (a JSON array of URLs), `hashtags` (a JSON array), `language`,
+`scores` (a JSON object of the extracted scores) and `fetched_at` (RFC 3339).
+*/
+impl ExportRow for ShindanResult {
+    fn columns() -> &'static [&'static str] {
+        &["id", "name", "domain", "url", "title", "description", "text", "images", "hashtags", "language", "scores", "fetched_at"]
+    }
+
+    fn values(&self) -> Vec<Option<String>> {
+        let text: String = self.segments.iter().filter(|segment| segment.type_ == "text").filter_map(|segment| segment.as_str()).collect();
+        let images: Vec<&str> = self
+            .segments
+            .iter()
+            .filter(|segment| segment.type_ == "image")
+            .filter_map(|segment| segment.data.get("file").and_then(Value::as_str))
+            .collect();
+
+        vec![
+            Some(self.id.clone()),
+            Some(self.name.clone()),
+            Some(self.domain.to_string()),
+            Some(self.url()),
+            Some(self.title.clone()),
+            self.description.clone(),
+            Some(text),
+            Some(Value::from(images).to_string()),
+            Some(Value::from(self.hashtags.clone()).to_string()),
+            self.language.clone(),
+            Some(serde_json::to_string(&self.extracted_scores).unwrap_or_default()),
+            Some(self.fetched_at.to_rfc3339()),
+        ]
+    }
+}
+
+/**
+Write records as CSV, with a header row; missing values are empty.
+
+# Arguments
+- `rows` - The records.
+- `writer` - Where to write, e.g. a file.
+
+# Errors
+Returns error if writing fails.
+
+# Examples
+```
+use shindan_maker::{write_csv, SearchHit, ShindanDomain};
+
+let hits = [SearchHit { id: "1222992".to_string(), title: "Stats, \"fantasy\"".to_string(), domain: ShindanDomain::Jp }];
+let mut csv = Vec::new();
+write_csv(&hits, &mut csv).unwrap();
+
+assert_eq!(
+    "id,title,url\r\n1222992,\"Stats, \"\"fantasy\"\"\",https://shindanmaker.com/1222992\r\n",
+    String::from_utf8(csv).unwrap()
+);
+```
+*/
+pub fn write_csv<T: ExportRow>(rows: &[T], mut writer: impl Write) -> Result<()> {
+    write_csv_record(&mut writer, T::columns().iter().copied())?;
+    for row in rows {
+        let values = row.values();
+        write_csv_record(&mut writer, values.iter().map(|value| value.as_deref().unwrap_or_default()))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write a record per RFC 4180, quoting fields holding separators, quotes or line breaks.
+fn write_csv_record<'a>(writer: &mut impl Write, fields: impl Iterator<Item = &'a str>) -> Result<()> {
+    for (index, field) in fields.enumerate() {
+        if index > 0 {
+            writer.write_all(b",")?;
+        }
+        if field.contains([',', '"', '\r', '\n']) {
+            write!(writer, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            writer.write_all(field.as_bytes())?;
+        }
+    }
+    writer.write_all(b"\r\n")?;
+    Ok(())
+}
+
+/**
+Write records as a Parquet file, with the `parquet` feature.
+
+Every column is an optional UTF-8 string, in a single row group.
+
+# Arguments
+- `rows` - The records.
+- `writer` - Where to write, e.g. a file.
+
+# Errors
+Returns error if writing fails.
+*/
+#[cfg(feature = "parquet")]
+pub fn write_parquet<T: ExportRow>(rows: &[T], writer: impl Write + Send) -> Result<()> {
+    use std::sync::Arc;
+    use parquet::data_type::{ByteArray, ByteArrayType};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+
+    let fields: String = T::columns().iter().map(|column| format!("OPTIONAL BYTE_ARRAY {} (UTF8); ", column)).collect();
+    let schema = Arc::new(parse_message_type(&format!("message record {{ {}}}", fields))?);
+    let mut writer = SerializedFileWriter::new(writer, schema, Arc::new(WriterProperties::builder().build()))?;
+
+    let values: Vec<Vec<Option<String>>> = rows.iter().map(ExportRow::values).collect();
+    let mut row_group = writer.next_row_group()?;
+    let mut index = 0;
+    while let Some(mut column) = row_group.next_column()? {
+        let cells = values.iter().map(|row| row.get(index).cloned().flatten());
+        let definitions: Vec<i16> = cells.clone().map(|cell| i16::from(cell.is_some())).collect();
+        let present: Vec<ByteArray> = cells.flatten().map(|cell| ByteArray::from(cell.into_bytes())).collect();
+
+        column.typed::<ByteArrayType>().write_batch(&present, Some(&definitions), None)?;
+        column.close()?;
+        index += 1;
+    }
+    row_group.close()?;
+    writer.close()?;
+
+    Ok(())
+}
@@ -0,0 +1,228 @@
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::collections::{HashMap, VecDeque};
+use anyhow::Result;
+use tokio::sync::oneshot;
+
+use crate::client::ShindanClient;
+use crate::result::ShindanResult;
+use crate::shindan_id::ShindanId;
+
+/// The error of a job refused because its user already has as many jobs waiting as a [`FairQueue`] allows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueFull {
+    /// The user whose jobs are waiting.
+    pub user: String,
+}
+
+impl fmt::Display for QueueFull {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Too many submissions waiting for {}", self.user)
+    }
+}
+
+impl std::error::Error for QueueFull {}
+
+/**
+Runs the submissions of a shared client fairly across the users of a bot.
+
+Each user has at most one submission in flight, and users take turns in the order
+they started waiting, so a user sending many commands only delays their own.
+A user with as many submissions waiting as the backlog allows gets [`QueueFull`]
+errors, to answer with e.g. "slow down" instead of queueing forever.
+
+The queue is cheap to clone; clones share their turns.
+
+# Examples
+```
+use shindan_maker::{FairQueue, QueueFull, ShindanClient, ShindanDomain};
+
+#[tokio::main]
+async fn main() {
+    let client = ShindanClient::new(ShindanDomain::En).unwrap();
+    let queue = FairQueue::new(client).concurrency(2).backlog(3);
+
+    match queue.submit("telegram:42", "1222992", "Alice").await {
+        Ok(result) => println!("{}", result.segments),
+        Err(err) if err.is::<QueueFull>() => println!("Please wait for your previous shindans"),
+        Err(err) => println!("{:#}", err),
+    }
+}
+```
+*/
+#[derive(Debug, Clone)]
+pub struct FairQueue {
+    client: ShindanClient,
+    turns: Arc<Turns>,
+}
+
+#[derive(Debug)]
+struct Turns {
+    state: Mutex<TurnState>,
+}
+
+#[derive(Debug)]
+struct TurnState {
+    concurrency: usize,
+    backlog: usize,
+    in_flight: usize,
+    users: HashMap<String, UserTurns>,
+    /// The users waiting for a turn and without a job in flight, in the order they became eligible.
+    rotation: VecDeque<String>,
+}
+
+#[derive(Debug, Default)]
+struct UserTurns {
+    running: bool,
+    waiting: VecDeque<oneshot::Sender<Turn>>,
+}
+
+/// The turn of a user, passed to their next waiting job when dropped.
+#[derive(Debug)]
+struct Turn {
+    turns: Option<Arc<Turns>>,
+    user: String,
+}
+
+impl Drop for Turn {
+    fn drop(&mut self) {
+        if let Some(turns) = self.turns.take() {
+            turns.release(&self.user);
+        }
+    }
+}
+
+impl Turns {
+    async fn acquire(self: &Arc<Self>, user: &str) -> Result<Turn> {
+        let receiver = {
+            let mut guard = self.state.lock().unwrap();
+            let state = &mut *guard;
+            let has_slot = state.in_flight < state.concurrency && state.rotation.is_empty();
+            let backlog = state.backlog;
+            let turns = state.users.entry(user.to_string()).or_default();
+
+            if !turns.running && turns.waiting.is_empty() && has_slot {
+                turns.running = true;
+                state.in_flight += 1;
+                return Ok(Turn { turns: Some(self.clone()), user: user.to_string() });
+            }
+            if turns.waiting.len() >= backlog {
+                return Err(QueueFull { user: user.to_string() }.into());
+            }
+
+            let (sender, receiver) = oneshot::channel();
+            turns.waiting.push_back(sender);
+            let eligible = !turns.running && turns.waiting.len() == 1;
+            if eligible {
+                state.rotation.push_back(user.to_string());
+            }
+            receiver
+        };
+
+        // The sender is only dropped along with the queue, which this future borrows.
+        Ok(receiver.await.expect("The fair queue dropped a waiting job"))
+    }
+
+    fn release(self: &Arc<Self>, user: &str) {
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        state.in_flight -= 1;
+        if let Some(turns) = state.users.get_mut(user) {
+            turns.running = false;
+            if turns.waiting.is_empty() {
+                state.users.remove(user);
+            } else {
+                state.rotation.push_back(user.to_string());
+            }
+        }
+
+        while state.in_flight < state.concurrency {
+            let Some(next) = state.rotation.pop_front() else {
+                break;
+            };
+            let Some(turns) = state.users.get_mut(&next) else {
+                continue;
+            };
+
+            while let Some(sender) = turns.waiting.pop_front() {
+                match sender.send(Turn { turns: Some(self.clone()), user: next.clone() }) {
+                    Ok(()) => {
+                        turns.running = true;
+                        break;
+                    }
+                    // The job was cancelled; disarm its turn, which would release it again.
+                    Err(mut turn) => turn.turns = None,
+                }
+            }
+
+            if turns.running {
+                state.in_flight += 1;
+            } else {
+                state.users.remove(&next);
+            }
+        }
+    }
+}
+
+impl FairQueue {
+    /// Create a queue running one submission at a time, with a backlog of 5 per user.
+    pub fn new(client: ShindanClient) -> Self {
+        Self {
+            client,
+            turns: Arc::new(Turns {
+                state: Mutex::new(TurnState {
+                    concurrency: 1,
+                    backlog: 5,
+                    in_flight: 0,
+                    users: HashMap::new(),
+                    rotation: VecDeque::new(),
+                }),
+            }),
+        }
+    }
+
+    /// Set how many submissions of different users are in flight at once, at least 1.
+    pub fn concurrency(self, concurrency: usize) -> Self {
+        self.turns.state.lock().unwrap().concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Set how many submissions a user may have waiting besides the one in flight.
+    pub fn backlog(self, backlog: usize) -> Self {
+        self.turns.state.lock().unwrap().backlog = backlog;
+        self
+    }
+
+    /**
+    Run a job for a user once it is their turn.
+
+    # Arguments
+    - `user` - The user the job is for, e.g. a chat user ID.
+    - `job` - The job, given a clone of the client.
+
+    # Returns
+    The output of the job.
+
+    # Errors
+    Returns [`QueueFull`] if the user has as many jobs waiting as the backlog allows.
+    */
+    pub async fn run<F, Fut, T>(&self, user: &str, job: F) -> Result<T>
+    where
+        F: FnOnce(ShindanClient) -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let _turn = self.turns.acquire(user).await?;
+        Ok(job(self.client.clone()).await)
+    }
+
+    /// Get the result of a shindan for a user once it is their turn, see [`ShindanClient::get_result`].
+    pub async fn submit(
+        &self,
+        user: &str,
+        id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>,
+        name: &str,
+    ) -> Result<ShindanResult> {
+        self.run(user, |client| async move { client.get_result(id, name).await }).await?
+    }
+}
@@ -0,0 +1,226 @@
+use anyhow::Result;
+use std::hash::Hasher;
+use std::collections::HashMap;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::fnv::Fnv1a;
+use crate::client::ShindanClient;
+use crate::metadata::ShindanMetadata;
+
+/// The shindans a feed follows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeedSource {
+    /// The given shindans, by ID.
+    Shindans(Vec<String>),
+    /// Every shindan listed on the page of an author, by their screen name.
+    Author(String),
+}
+
+/**
+What a feed has seen of its shindans, to date entries by when they were first seen or last changed.
+
+Persist it between runs, e.g. as JSON; a new state dates every entry to the current run.
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeedState {
+    entries: HashMap<String, SeenEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SeenEntry {
+    /// A digest of the title, description and tags.
+    digest: u64,
+    updated: DateTime<Utc>,
+}
+
+/// A shindan in a feed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeedEntry {
+    /// The ID of the shindan.
+    pub id: String,
+    /// The page of the shindan, also used as the ID of the entry.
+    pub url: String,
+    /// The title of the shindan.
+    pub title: String,
+    /// The description of the shindan, if it has one.
+    pub description: Option<String>,
+    /// The tags of the shindan, shown as the categories of the entry.
+    pub tags: Vec<String>,
+    /// When the shindan was first seen or last changed.
+    pub updated: DateTime<Utc>,
+}
+
+/// An Atom feed of shindans, newest first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AtomFeed {
+    /// The title of the feed, naming the author it follows if any.
+    pub title: String,
+    /// The page the feed follows, also used as the ID of the feed.
+    pub url: String,
+    /// The shindans, newest first.
+    pub entries: Vec<FeedEntry>,
+}
+
+impl ShindanClient {
+    /**
+    Build an Atom feed of the shindans of a source, from their metadata.
+
+    Entries are dated by when they first appeared in the feed or their title,
+    description or tags last changed, as recorded in `state`. Shindans whose
+    metadata cannot be fetched, e.g. deleted ones, are left out with a warning.
+
+    # Arguments
+    - `source` - The shindans to follow.
+    - `state` - What previous runs have seen; updated with this run.
+
+    # Returns
+    The feed, newest entries first.
+
+    # Errors
+    Returns error if the page of the author cannot be fetched.
+
+    # Examples
+    ```
+    use shindan_maker::{FeedSource, FeedState, ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::En).unwrap();
+        let mut state = FeedState::default();
+
+        let feed = client
+            .atom_feed(&FeedSource::Shindans(vec!["1222992".to_string()]), &mut state)
+            .await
+            .unwrap();
+
+        assert_eq!("Fantasy Stats", feed.entries[0].title);
+        println!("{}", feed.to_xml());
+    }
+    ```
+    */
+    pub async fn atom_feed(&self, source: &FeedSource, state: &mut FeedState) -> Result<AtomFeed> {
+        let (title, url, ids) = match source {
+            FeedSource::Shindans(ids) => ("ShindanMaker".to_string(), self.url(""), ids.clone()),
+            FeedSource::Author(author) => {
+                let hits = self.author_shindans(author).await?;
+                let ids = hits.into_iter().map(|hit| hit.id).collect();
+                (format!("ShindanMaker - {}", author), self.author_url(author), ids)
+            }
+        };
+
+        let now = Utc::now();
+        let mut entries = Vec::with_capacity(ids.len());
+        for id in ids {
+            match self.get_metadata(id.as_str()).await {
+                Ok(metadata) => entries.push(state.entry(self.url(&id), id, metadata, now)),
+                Err(err) => log::warn!(id = id.as_str(); "Left a shindan out of the feed: {:#}", err),
+            }
+        }
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.updated));
+
+        Ok(AtomFeed { title, url, entries })
+    }
+}
+
+impl FeedState {
+    /// Date an entry, recording it as updated now if it is new or changed.
+    fn entry(&mut self, url: String, id: String, metadata: ShindanMetadata, now: DateTime<Utc>) -> FeedEntry {
+        let digest = digest(&metadata);
+        let seen = self.entries.entry(id.clone()).or_insert(SeenEntry { digest, updated: now });
+        if seen.digest != digest {
+            *seen = SeenEntry { digest, updated: now };
+        }
+
+        FeedEntry {
+            id,
+            url,
+            title: metadata.title,
+            description: metadata.description,
+            tags: metadata.tags,
+            updated: seen.updated,
+        }
+    }
+}
+
+impl AtomFeed {
+    /**
+    Serialize the feed as Atom XML.
+
+    # Examples
+    ```
+    use chrono::{TimeZone, Utc};
+    use shindan_maker::{AtomFeed, FeedEntry};
+
+    let updated = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+    let feed = AtomFeed {
+        title: "ShindanMaker".to_string(),
+        url: "https://shindanmaker.com/".to_string(),
+        entries: vec![FeedEntry {
+            id: "1222992".to_string(),
+            url: "https://shindanmaker.com/1222992".to_string(),
+            title: "Stats & Skills".to_string(),
+            description: None,
+            tags: vec!["fantasy".to_string()],
+            updated,
+        }],
+    };
+
+    let xml = feed.to_xml();
+    assert!(xml.contains("<updated>2024-05-01T00:00:00Z</updated>"));
+    assert!(xml.contains("<title>Stats &amp; Skills</title>"));
+    assert!(xml.contains(r#"<category term="fantasy"/>"#));
+    ```
+    */
+    pub fn to_xml(&self) -> String {
+        let updated = self.entries.iter().map(|entry| entry.updated).max().unwrap_or_else(Utc::now);
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+        xml.push_str(&format!("  <title>{}</title>\n", escape_xml(&self.title)));
+        xml.push_str(&format!("  <id>{}</id>\n", escape_xml(&self.url)));
+        xml.push_str(&format!("  <link href=\"{}\"/>\n", escape_xml(&self.url)));
+        xml.push_str(&format!("  <updated>{}</updated>\n", timestamp(updated)));
+
+        for entry in &self.entries {
+            xml.push_str("  <entry>\n");
+            xml.push_str(&format!("    <title>{}</title>\n", escape_xml(&entry.title)));
+            xml.push_str(&format!("    <id>{}</id>\n", escape_xml(&entry.url)));
+            xml.push_str(&format!("    <link href=\"{}\"/>\n", escape_xml(&entry.url)));
+            xml.push_str(&format!("    <updated>{}</updated>\n", timestamp(entry.updated)));
+            if let Some(description) = &entry.description {
+                xml.push_str(&format!("    <summary>{}</summary>\n", escape_xml(description)));
+            }
+            for tag in &entry.tags {
+                xml.push_str(&format!("    <category term=\"{}\"/>\n", escape_xml(tag)));
+            }
+            xml.push_str("  </entry>\n");
+        }
+
+        xml.push_str("</feed>\n");
+        xml
+    }
+}
+
+/// A digest of the title, description and tags, stable across Rust versions since it is persisted.
+fn digest(metadata: &ShindanMetadata) -> u64 {
+    let fields = [metadata.title.as_str(), metadata.description.as_deref().unwrap_or_default()];
+
+    let mut hasher = Fnv1a::default();
+    for field in fields.into_iter().chain(metadata.tags.iter().map(String::as_str)) {
+        hasher.write_field(field);
+    }
+    hasher.finish()
+}
+
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Escape text for XML, dropping the control characters XML 1.0 forbids.
+fn escape_xml(text: &str) -> String {
+    text.replace(|c: char| c.is_control() && !matches!(c, '\t' | '\n' | '\r'), "")
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,219 @@
+/*!
+A minimal C ABI, for game mods and desktop applications not written in Rust.
+
+Build the shared library with `cargo rustc --release --lib --features ffi --crate-type cdylib`
+and include `include/shindan.h`. `shindan_get_image` is only exported with the `png`
+feature too, and declared by the header when `SHINDAN_IMAGE` is defined.
+
+Every function returning a pointer returns null on failure; [`shindan_last_error`]
+then describes the failure of the last call on the same thread. Strings returned by
+the library are freed with [`shindan_string_free`], buffers with [`shindan_bytes_free`]
+and clients with [`shindan_client_free`]. The calls block the calling thread on a
+runtime shared by every client.
+*/
+
+use std::ptr;
+use std::cell::RefCell;
+use once_cell::sync::Lazy;
+use anyhow::{anyhow, Result};
+use tokio::runtime::Runtime;
+use std::panic::{self, AssertUnwindSafe};
+use std::ffi::{c_char, CStr, CString};
+
+use crate::client::ShindanClient;
+
+/// The runtime the blocking calls run on.
+static RUNTIME: Lazy<Runtime> = Lazy::new(|| Runtime::new().expect("Failed to start the runtime"));
+
+thread_local! {
+    /// The error of the last failed call on this thread.
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Run a call, recording its error or panic for [`shindan_last_error`].
+fn call<T>(f: impl FnOnce() -> Result<T>) -> Option<T> {
+    let outcome = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| Err(anyhow!("The call panicked")));
+
+    match outcome {
+        Ok(value) => {
+            LAST_ERROR.with(|last| *last.borrow_mut() = None);
+            Some(value)
+        }
+        Err(err) => {
+            let message = CString::new(format!("{:#}", err).replace('\0', " ")).expect("Nul bytes were replaced");
+            LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+            None
+        }
+    }
+}
+
+/// Borrow a C string argument as UTF-8.
+unsafe fn arg<'a>(name: &str, s: *const c_char) -> Result<&'a str> {
+    if s.is_null() {
+        return Err(anyhow!("The {} must not be null", name));
+    }
+
+    CStr::from_ptr(s).to_str().map_err(|_| anyhow!("The {} must be UTF-8", name))
+}
+
+/// Borrow a client argument.
+unsafe fn client<'a>(client: *const ShindanClient) -> Result<&'a ShindanClient> {
+    client.as_ref().ok_or_else(|| anyhow!("The client must not be null"))
+}
+
+fn into_c_string(s: String) -> Result<*mut c_char> {
+    Ok(CString::new(s)?.into_raw())
+}
+
+/**
+Create a client for a domain.
+
+# Arguments
+- `domain` - The domain of ShindanMaker: `jp`, `en`, `cn`, `kr` or `th`.
+
+# Returns
+The client, to free with [`shindan_client_free`], or null on failure.
+
+# Safety
+`domain` must be null or a valid nul-terminated string.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn shindan_client_new(domain: *const c_char) -> *mut ShindanClient {
+    call(|| {
+        let client = ShindanClient::new(arg("domain", domain)?.parse()?)?;
+        Ok(Box::into_raw(Box::new(client)))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/**
+Free a client created by [`shindan_client_new`].
+
+# Safety
+`client` must be null or a client returned by [`shindan_client_new`], not freed yet.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn shindan_client_free(client: *mut ShindanClient) {
+    if !client.is_null() {
+        drop(Box::from_raw(client));
+    }
+}
+
+/**
+Run a shindan for a name.
+
+# Arguments
+- `client` - The client.
+- `id` - The ID of the shindan.
+- `name` - The name to run the shindan for.
+
+# Returns
+The result as the JSON document of [`ShindanResult::to_json`](crate::ShindanResult::to_json),
+to free with [`shindan_string_free`], or null on failure.
+
+# Safety
+`client` must be a live client; `id` and `name` must be null or valid nul-terminated strings.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn shindan_run(client: *const ShindanClient, id: *const c_char, name: *const c_char) -> *mut c_char {
+    call(|| {
+        let (client, id, name) = (self::client(client)?, arg("id", id)?, arg("name", name)?);
+        let result = RUNTIME.block_on(client.get_result(id, name))?;
+        into_c_string(result.to_json()?)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/**
+Get the title of a shindan.
+
+# Arguments
+- `client` - The client.
+- `id` - The ID of the shindan.
+
+# Returns
+The title, to free with [`shindan_string_free`], or null on failure.
+
+# Safety
+`client` must be a live client; `id` must be null or a valid nul-terminated string.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn shindan_get_title(client: *const ShindanClient, id: *const c_char) -> *mut c_char {
+    call(|| {
+        let (client, id) = (self::client(client)?, arg("id", id)?);
+        into_c_string(RUNTIME.block_on(client.get_title(id))?)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/**
+Capture the result of a shindan as a PNG image, with the `png` feature.
+
+# Arguments
+- `client` - The client.
+- `id` - The ID of the shindan.
+- `name` - The name to run the shindan for.
+- `len` - Receives the length of the image.
+
+# Returns
+The PNG image, to free with [`shindan_bytes_free`], or null on failure.
+
+# Safety
+`client` must be a live client; `id` and `name` must be null or valid nul-terminated
+strings; `len` must be a valid pointer.
+*/
+#[cfg(feature = "png")]
+#[no_mangle]
+pub unsafe extern "C" fn shindan_get_image(
+    client: *const ShindanClient,
+    id: *const c_char,
+    name: *const c_char,
+    len: *mut usize,
+) -> *mut u8 {
+    call(|| {
+        let (client, id, name) = (self::client(client)?, arg("id", id)?, arg("name", name)?);
+        let image = RUNTIME.block_on(client.get_png_image(id, name))?.into_boxed_slice();
+
+        *len = image.len();
+        Ok(Box::into_raw(image).cast::<u8>())
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/**
+Describe the failure of the last call on this thread.
+
+# Returns
+The message, owned by the library and valid until the next call on this thread,
+or null if the last call succeeded.
+*/
+#[no_mangle]
+pub extern "C" fn shindan_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+/**
+Free a string returned by the library.
+
+# Safety
+`s` must be null or a string returned by the library, not freed yet.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn shindan_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/**
+Free a buffer returned by the library.
+
+# Safety
+`bytes` must be null or a buffer returned by the library along with `len`, not freed yet.
+*/
+#[no_mangle]
+pub unsafe extern "C" fn shindan_bytes_free(bytes: *mut u8, len: usize) {
+    if !bytes.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(bytes, len)));
+    }
+}
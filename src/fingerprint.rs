@@ -0,0 +1,106 @@
+use std::fmt;
+use anyhow::Result;
+use std::hash::Hasher;
+use serde::{Deserialize, Serialize};
+
+use crate::fnv::Fnv1a;
+use crate::page::ShindanPage;
+use crate::client::ShindanClient;
+use crate::shindan_id::{self, ShindanId};
+
+/**
+A digest of what the creator of a shindan controls on its page: the title,
+description, tags, sample result and form.
+
+Fingerprints of the same content are equal across runs and crate versions, so they
+can be stored next to cached results or screenshots to tell when to invalidate them.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ContentFingerprint {
+    /// The ID of the shindan.
+    pub id: String,
+    /// The digest, as 16 hexadecimal digits.
+    pub digest: String,
+}
+
+impl fmt::Display for ContentFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.id, self.digest)
+    }
+}
+
+impl ShindanClient {
+    /**
+    Fingerprint the content of a shindan, as edited by its creator.
+
+    The page is always fetched anew, and refreshes the form cache if one is configured.
+
+    # Arguments
+    - `id` - The ID of the shindan.
+
+    # Returns
+    The fingerprint of the shindan.
+
+    # Errors
+    Returns error if the page cannot be fetched or parsed.
+
+    # Examples
+    ```
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::En).unwrap();
+
+        let fingerprint = client.content_fingerprint("1222992").await.unwrap();
+        assert!(!client.has_changed_since(&fingerprint).await.unwrap());
+    }
+    ```
+    */
+    pub async fn content_fingerprint(&self, id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>) -> Result<ContentFingerprint> {
+        let id = shindan_id::convert(id)?;
+        let page = self.fetch_page_uncached(&self.url(id.as_str())).await?;
+
+        Ok(ContentFingerprint {
+            id: id.as_str().to_string(),
+            digest: format!("{:016x}", digest(&page)),
+        })
+    }
+
+    /**
+    Check whether the content of a shindan changed since it was fingerprinted.
+
+    # Arguments
+    - `fingerprint` - A fingerprint from [`content_fingerprint`](Self::content_fingerprint).
+
+    # Returns
+    `true` if the fingerprint of the shindan is different now.
+
+    # Errors
+    Returns error if the page cannot be fetched or parsed.
+    */
+    pub async fn has_changed_since(&self, fingerprint: &ContentFingerprint) -> Result<bool> {
+        Ok(self.content_fingerprint(fingerprint.id.as_str()).await?.digest != fingerprint.digest)
+    }
+}
+
+/// A digest of the content of a page, stable across Rust versions since it is persisted.
+fn digest(page: &ShindanPage) -> u64 {
+    let preview = page.preview();
+    // The CSRF token and random name of the form change with every fetch; its type does not.
+    let form_type = page.form_data.iter().find(|(field, _)| *field == "type").map(|(_, value)| value.as_str());
+
+    let fields = [
+        page.title(),
+        page.description(),
+        preview.and_then(|preview| preview.text.as_deref()),
+        preview.and_then(|preview| preview.image.as_deref()),
+        form_type,
+    ];
+
+    let mut hasher = Fnv1a::default();
+    for field in fields.into_iter().map(Option::unwrap_or_default).chain(page.tags().iter().map(String::as_str)) {
+        hasher.write_field(field);
+    }
+    hasher.finish()
+}
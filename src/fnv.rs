@@ -0,0 +1,32 @@
+use std::hash::Hasher;
+
+/// FNV-1a, a hash stable across Rust versions, unlike the standard hasher, for digests that are persisted.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Fnv1a {
+    /// Hash a field followed by a separator, so that consecutive fields cannot run into each other.
+    pub(crate) fn write_field(&mut self, field: &str) {
+        self.write(field.as_bytes());
+        self.write(&[0]);
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
@@ -0,0 +1,115 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use base64::Engine;
+
+/// The font stack the result is rendered with by default.
+const DEFAULT_STACK: &str = r#"-apple-system, BlinkMacSystemFont, "Segoe UI", Roboto, Helvetica, Arial, sans-serif"#;
+
+/// The monospace font stack of results with preserved whitespace.
+const MONOSPACE_STACK: &str = "ui-monospace, SFMono-Regular, Menlo, Consolas";
+
+/// A font file to embed into HTML, under the family it is referred to by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontFile {
+    /// The `font-family` name the file is declared under, e.g. `Noto Sans JP`.
+    pub family: String,
+    /// A TrueType, OpenType, WOFF or WOFF2 file.
+    pub path: PathBuf,
+}
+
+/**
+The fonts of the HTML built by the client and so of its images, see
+[`ShindanClientBuilder::fonts`](crate::ShindanClientBuilder::fonts).
+
+Headless Chrome on minimal containers often has no CJK fonts, so Japanese, Chinese
+and Korean text is drawn as boxes; embedding a font file covering them fixes it
+wherever the HTML is rendered.
+
+# Examples
+```
+use shindan_maker::FontStack;
+
+let fonts = FontStack::default()
+    .embed("Noto Sans CJK", "/usr/share/fonts/NotoSansCJK-Regular.ttc")
+    .family("Noto Sans JP");
+
+assert_eq!(1, fonts.files.len());
+```
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FontStack {
+    /// Font files embedded with `@font-face`; their families go first in the stack.
+    pub files: Vec<FontFile>,
+    /// Families installed where the HTML is rendered, tried in order after the embedded ones.
+    pub families: Vec<String>,
+}
+
+impl FontStack {
+    /// Embed a font file under a family.
+    pub fn embed(mut self, family: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        self.files.push(FontFile { family: family.into(), path: path.into() });
+        self
+    }
+
+    /// Try an installed family, after the embedded ones and the families added before.
+    pub fn family(mut self, family: impl Into<String>) -> Self {
+        self.families.push(family.into());
+        self
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.families.is_empty()
+    }
+
+    /**
+    The style embedding the files and putting the stack before the default fonts.
+
+    # Errors
+    Returns error if a font file cannot be read.
+    */
+    pub(crate) fn style(&self, preserve_whitespace: bool) -> Result<String> {
+        let mut style = String::from("<style>\n");
+        for file in &self.files {
+            let bytes = fs::read(&file.path).with_context(|| format!("Failed to read the font {}", file.path.display()))?;
+            let (mime, format) = font_type(&file.path);
+            style.push_str(&format!(
+                "        @font-face {{ font-family: {}; src: url(data:{};base64,{}){}; }}\n",
+                css_string(&file.family),
+                mime,
+                base64::prelude::BASE64_STANDARD.encode(bytes),
+                format.map(|format| format!(" format(\"{}\")", format)).unwrap_or_default(),
+            ));
+        }
+
+        let stack: Vec<String> = self.files.iter().map(|file| &file.family).chain(&self.families).map(|family| css_string(family)).collect();
+        let stack = stack.join(", ");
+        style.push_str(&format!(
+            "        body, #shindanResult, #title_and_result > .shindanTitleImageContainer {{ font-family: {}, {}; }}\n",
+            stack, DEFAULT_STACK
+        ));
+        if preserve_whitespace {
+            style.push_str(&format!("        #shindanResult {{ font-family: {}, {}, monospace; }}\n", MONOSPACE_STACK, stack));
+        }
+        style.push_str("    </style>");
+
+        Ok(style)
+    }
+}
+
+/// The MIME type and `@font-face` format of a font file, by its extension.
+fn font_type(path: &Path) -> (&'static str, Option<&'static str>) {
+    match path.extension().and_then(|extension| extension.to_str()).map(str::to_ascii_lowercase).as_deref() {
+        Some("otf") => ("font/otf", Some("opentype")),
+        Some("woff") => ("font/woff", Some("woff")),
+        Some("woff2") => ("font/woff2", Some("woff2")),
+        Some("ttc") => ("font/collection", Some("collection")),
+        _ => ("font/ttf", Some("truetype")),
+    }
+}
+
+/// Quote a family name for CSS, dropping what could break out of the string or the style element.
+fn css_string(value: &str) -> String {
+    let value: String = value.chars().filter(|c| !matches!(c, '"' | '\\' | '<' | '>' | '\n' | '\r')).collect();
+    format!("\"{}\"", value)
+}
@@ -0,0 +1,43 @@
+use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::page::ShindanPage;
+
+/// Form pages keyed by shindan URL, reused until their TTL expires.
+#[derive(Debug, Default)]
+pub(crate) struct FormCache {
+    ttl: Duration,
+    pages: Mutex<HashMap<String, Arc<ShindanPage>>>,
+}
+
+impl FormCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            pages: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn get(&self, url: &str) -> Option<Arc<ShindanPage>> {
+        let mut pages = self.pages.lock().unwrap();
+
+        match pages.get(url) {
+            Some(page) if page.fetched_at.elapsed() < self.ttl => Some(page.clone()),
+            Some(_) => {
+                pages.remove(url);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub(crate) fn insert(&self, url: &str, page: Arc<ShindanPage>) {
+        self.pages.lock().unwrap().insert(url.to_string(), page);
+    }
+
+    #[cfg(any(feature = "segments", feature = "html"))]
+    pub(crate) fn invalidate(&self, url: &str) {
+        self.pages.lock().unwrap().remove(url);
+    }
+}
@@ -0,0 +1,295 @@
+/*!
+A gRPC service exposing a client, for bot infrastructure written in other languages.
+
+The contract is `proto/shindan.proto`, shipped with the crate; generate clients from
+it in any language. Failed submissions answer [`Code::Unavailable`], invalid IDs and empty
+names [`Code::InvalidArgument`], and `RenderImage` answers [`Code::Unimplemented`] without
+the `image` feature. `RunBatch` streams the results in the order they complete, an
+item holding the error of a failed submission instead of ending the stream.
+
+# Examples
+```no_run
+use anyhow::Result;
+use shindan_maker::grpc;
+use shindan_maker::{ShindanClient, ShindanDomain};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let client = ShindanClient::new(ShindanDomain::En)?;
+    grpc::serve(client, "127.0.0.1:50051".parse()?).await
+}
+```
+*/
+
+use std::pin::Pin;
+use std::net::SocketAddr;
+use anyhow::Result;
+use futures::{stream, Stream, StreamExt};
+use tonic::{Code, Request, Response, Status};
+
+use crate::client::ShindanClient;
+use crate::shindan_id::ShindanId;
+use crate::render::PlainTextOptions;
+
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/shindan.ShindanService.rs"));
+}
+
+pub use generated::shindan_service_server::{ShindanService, ShindanServiceServer};
+
+/// The batch concurrency used when a [`RunBatchRequest`] leaves it at 0.
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// A request running a shindan for a name.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RunShindanRequest {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    #[prost(string, tag = "2")]
+    pub name: String,
+}
+
+/// A result, as plain text and as the JSON document of [`to_json`](crate::ShindanResult::to_json).
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ShindanResult {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    #[prost(string, tag = "2")]
+    pub name: String,
+    #[prost(string, tag = "3")]
+    pub title: String,
+    #[prost(string, optional, tag = "4")]
+    pub description: Option<String>,
+    #[prost(string, tag = "5")]
+    pub text: String,
+    #[prost(string, tag = "6")]
+    pub json: String,
+}
+
+/// A request running a shindan for many names.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RunBatchRequest {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    #[prost(string, repeated, tag = "2")]
+    pub names: Vec<String>,
+    /// The maximum number of submissions in flight; 0 means 4.
+    #[prost(uint32, tag = "3")]
+    pub concurrency: u32,
+}
+
+/// The outcome of one submission of a batch.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct BatchItem {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(oneof = "Outcome", tags = "2, 3")]
+    pub outcome: Option<Outcome>,
+}
+
+/// Either the result or the error of a submission.
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum Outcome {
+    #[prost(message, tag = "2")]
+    Result(ShindanResult),
+    #[prost(string, tag = "3")]
+    Error(String),
+}
+
+/// A request for the metadata of a shindan.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GetMetadataRequest {
+    #[prost(string, tag = "1")]
+    pub id: String,
+}
+
+/// The title and description of a shindan.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Metadata {
+    #[prost(string, tag = "1")]
+    pub title: String,
+    #[prost(string, optional, tag = "2")]
+    pub description: Option<String>,
+}
+
+/// A request searching shindans by keyword.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SearchRequest {
+    #[prost(string, tag = "1")]
+    pub keyword: String,
+}
+
+/// A shindan found by a search.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SearchHit {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    #[prost(string, tag = "2")]
+    pub title: String,
+}
+
+/// The shindans found by a search.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SearchResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub hits: Vec<SearchHit>,
+}
+
+/// A JPEG image of a result.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Image {
+    #[prost(bytes = "vec", tag = "1")]
+    pub jpeg: Vec<u8>,
+}
+
+impl TryFrom<&crate::ShindanResult> for ShindanResult {
+    type Error = anyhow::Error;
+
+    fn try_from(result: &crate::ShindanResult) -> Result<Self> {
+        Ok(Self {
+            id: result.id.clone(),
+            name: result.name.clone(),
+            title: result.title.clone(),
+            description: result.description.clone(),
+            text: result.segments.to_plain_text(&PlainTextOptions::default()),
+            json: result.to_json()?,
+        })
+    }
+}
+
+/// The service answering the requests with a [`ShindanClient`].
+#[derive(Clone)]
+pub struct GrpcService {
+    client: ShindanClient,
+}
+
+impl GrpcService {
+    /**
+    Create a service answering with a client.
+
+    # Arguments
+    - `client` - The client answering the requests; configure its rate limit and caches on its builder.
+    */
+    pub fn new(client: ShindanClient) -> Self {
+        Self { client }
+    }
+
+    /// Wrap the service for a [`tonic::transport::Server`].
+    pub fn into_server(self) -> ShindanServiceServer<Self> {
+        ShindanServiceServer::new(self)
+    }
+}
+
+/**
+Serve the service until the process stops.
+
+# Arguments
+- `client` - The client answering the requests.
+- `addr` - The address to listen on, e.g. `127.0.0.1:50051`.
+
+# Errors
+Returns error if the address cannot be bound.
+*/
+pub async fn serve(client: ShindanClient, addr: SocketAddr) -> Result<()> {
+    tonic::transport::Server::builder()
+        .add_service(GrpcService::new(client).into_server())
+        .serve(addr)
+        .await?;
+    Ok(())
+}
+
+#[tonic::async_trait]
+impl ShindanService for GrpcService {
+    type RunBatchStream = Pin<Box<dyn Stream<Item = Result<BatchItem, Status>> + Send>>;
+
+    async fn run_shindan(&self, request: Request<RunShindanRequest>) -> Result<Response<ShindanResult>, Status> {
+        let request = request.into_inner();
+        let (id, name) = (validate_id(&request.id)?, validate_name(&request.name)?);
+
+        let result = self.client.get_result(id, name).await.map_err(upstream)?;
+        ShindanResult::try_from(&result).map(Response::new).map_err(internal)
+    }
+
+    async fn run_batch(&self, request: Request<RunBatchRequest>) -> Result<Response<Self::RunBatchStream>, Status> {
+        let request = request.into_inner();
+        let concurrency = match request.concurrency {
+            0 => DEFAULT_CONCURRENCY,
+            concurrency => concurrency as usize,
+        };
+        let client = self.client.clone();
+        let id = validate_id(&request.id)?;
+
+        let items = stream::iter(request.names)
+            .map(move |name| {
+                let client = client.clone();
+                let id = id.clone();
+                async move {
+                    let outcome = match validate_name(&name) {
+                        Ok(valid) => client
+                            .get_result(&id, valid)
+                            .await
+                            .and_then(|result| ShindanResult::try_from(&result))
+                            .map_or_else(|err| Outcome::Error(format!("{:#}", err)), Outcome::Result),
+                        Err(status) => Outcome::Error(status.message().to_string()),
+                    };
+                    Ok(BatchItem { name, outcome: Some(outcome) })
+                }
+            })
+            .buffer_unordered(concurrency);
+
+        Ok(Response::new(Box::pin(items)))
+    }
+
+    async fn get_metadata(&self, request: Request<GetMetadataRequest>) -> Result<Response<Metadata>, Status> {
+        let id = validate_id(&request.into_inner().id)?;
+        let metadata = self.client.get_metadata(id).await.map_err(upstream)?;
+
+        Ok(Response::new(Metadata {
+            title: metadata.title,
+            description: metadata.description,
+        }))
+    }
+
+    async fn search(&self, request: Request<SearchRequest>) -> Result<Response<SearchResponse>, Status> {
+        let hits = self.client.search(&request.into_inner().keyword).await.map_err(upstream)?;
+
+        Ok(Response::new(SearchResponse {
+            hits: hits.into_iter().map(|hit| SearchHit { id: hit.id, title: hit.title }).collect(),
+        }))
+    }
+
+    #[cfg(feature = "image")]
+    async fn render_image(&self, request: Request<RunShindanRequest>) -> Result<Response<Image>, Status> {
+        let request = request.into_inner();
+        let (id, name) = (validate_id(&request.id)?, validate_name(&request.name)?);
+
+        let jpeg = self.client.get_image(id, name).await.map_err(upstream)?;
+        Ok(Response::new(Image { jpeg }))
+    }
+
+    #[cfg(not(feature = "image"))]
+    async fn render_image(&self, _request: Request<RunShindanRequest>) -> Result<Response<Image>, Status> {
+        Err(Status::unimplemented("RenderImage needs the \"image\" feature"))
+    }
+}
+
+fn upstream(err: anyhow::Error) -> Status {
+    Status::new(Code::Unavailable, format!("{:#}", err))
+}
+
+fn internal(err: anyhow::Error) -> Status {
+    Status::internal(format!("{:#}", err))
+}
+
+fn validate_id(id: &str) -> Result<ShindanId, Status> {
+    ShindanId::new(id).map_err(|err| Status::invalid_argument(err.to_string()))
+}
+
+fn validate_name(name: &str) -> Result<&str, Status> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(Status::invalid_argument("The name must not be empty"));
+    }
+
+    Ok(name)
+}
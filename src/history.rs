@@ -0,0 +1,295 @@
+use std::fmt;
+use std::sync::Mutex;
+use async_trait::async_trait;
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::site_day;
+use crate::client::ShindanClient;
+use crate::result::ShindanResult;
+use crate::render::PlainTextOptions;
+
+/// A submission recorded in a [`HistoryStore`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// The site day of the submission.
+    pub day: NaiveDate,
+    /// The result as plain text.
+    pub text: String,
+    /// The permalink of the shindan.
+    pub url: String,
+    /// The full result.
+    pub result: ShindanResult,
+}
+
+impl HistoryEntry {
+    /**
+    Create the history entry of a result.
+
+    # Arguments
+    - `result` - The result of a submission.
+
+    # Returns
+    A new history entry.
+    */
+    pub fn new(result: ShindanResult) -> Self {
+        Self {
+            day: site_day::site_day(result.fetched_at),
+            text: result.segments.to_plain_text(&PlainTextOptions::default()),
+            url: result.url(),
+            result,
+        }
+    }
+
+    /// When the submission was made.
+    pub fn fetched_at(&self) -> DateTime<Utc> {
+        self.result.fetched_at
+    }
+}
+
+/**
+A store recording every submission of a client, e.g. for "your shindan history" commands.
+
+# Examples
+```
+use anyhow::Result;
+use shindan_maker::{MemoryHistory, ShindanClient, ShindanDomain};
+
+fn main() -> Result<()> {
+    let client = ShindanClient::builder(ShindanDomain::En)
+        .history(MemoryHistory::default())
+        .build()?;
+    Ok(())
+}
+```
+*/
+#[async_trait]
+pub trait HistoryStore: fmt::Debug + Send + Sync {
+    /// Record a submission.
+    async fn record(&self, entry: HistoryEntry) -> Result<()>;
+
+    /// Get the submissions made with a name, newest first.
+    async fn for_user(&self, name: &str) -> Result<Vec<HistoryEntry>>;
+
+    /// Get the latest submission of a shindan with a name on a site day.
+    async fn for_day(&self, id: &str, name: &str, day: NaiveDate) -> Result<Option<HistoryEntry>>;
+}
+
+/// A [`HistoryStore`] keeping submissions in memory for the lifetime of the process.
+#[derive(Debug, Default)]
+pub struct MemoryHistory {
+    entries: Mutex<Vec<HistoryEntry>>,
+}
+
+#[async_trait]
+impl HistoryStore for MemoryHistory {
+    async fn record(&self, entry: HistoryEntry) -> Result<()> {
+        self.entries.lock().unwrap().push(entry);
+        Ok(())
+    }
+
+    async fn for_user(&self, name: &str) -> Result<Vec<HistoryEntry>> {
+        Ok(self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|entry| entry.result.name == name)
+            .cloned()
+            .collect())
+    }
+
+    async fn for_day(&self, id: &str, name: &str, day: NaiveDate) -> Result<Option<HistoryEntry>> {
+        Ok(self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|entry| entry.result.id == id && entry.result.name == name && entry.day == day)
+            .cloned())
+    }
+}
+
+#[cfg(feature = "history-sqlite")]
+pub use sqlite::SqliteHistory;
+
+#[cfg(feature = "history-sqlite")]
+mod sqlite {
+    use std::path::Path;
+    use chrono::NaiveDate;
+    use std::sync::{Arc, Mutex};
+    use async_trait::async_trait;
+    use anyhow::{Context, Result};
+    use rusqlite::{params, Connection, OptionalExtension};
+
+    use super::{HistoryEntry, HistoryStore};
+    use crate::result::ShindanResult;
+
+    /// A [`HistoryStore`] backed by a SQLite database.
+    #[derive(Debug, Clone)]
+    pub struct SqliteHistory {
+        connection: Arc<Mutex<Connection>>,
+    }
+
+    impl SqliteHistory {
+        /**
+        Open or create a history database.
+
+        # Arguments
+        - `path` - The path of the database file.
+
+        # Errors
+        Returns error if the database cannot be opened or migrated.
+        */
+        pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+            Self::from_connection(Connection::open(path)?)
+        }
+
+        /// Create a history database that only lives in memory.
+        pub fn open_in_memory() -> Result<Self> {
+            Self::from_connection(Connection::open_in_memory()?)
+        }
+
+        fn from_connection(connection: Connection) -> Result<Self> {
+            connection.execute_batch(
+                "CREATE TABLE IF NOT EXISTS history (
+                    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                    id TEXT NOT NULL,
+                    name TEXT NOT NULL,
+                    day TEXT NOT NULL,
+                    title TEXT NOT NULL,
+                    text TEXT NOT NULL,
+                    url TEXT NOT NULL,
+                    result TEXT NOT NULL
+                );
+                CREATE INDEX IF NOT EXISTS history_name ON history (name);
+                CREATE INDEX IF NOT EXISTS history_day ON history (id, name, day);",
+            )?;
+
+            Ok(Self {
+                connection: Arc::new(Mutex::new(connection)),
+            })
+        }
+
+        async fn with_connection<T, F>(&self, f: F) -> Result<T>
+        where
+            T: Send + 'static,
+            F: FnOnce(&Connection) -> Result<T> + Send + 'static,
+        {
+            let connection = self.connection.clone();
+            crate::rt::spawn_blocking(move || f(&connection.lock().unwrap())).await?
+        }
+    }
+
+    fn entry_from_json(json: String) -> Result<HistoryEntry> {
+        Ok(HistoryEntry::new(ShindanResult::from_json(&json)?))
+    }
+
+    #[async_trait]
+    impl HistoryStore for SqliteHistory {
+        async fn record(&self, entry: HistoryEntry) -> Result<()> {
+            let json = entry.result.to_json()?;
+
+            self.with_connection(move |connection| {
+                connection.execute(
+                    "INSERT INTO history (id, name, day, title, text, url, result) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        entry.result.id,
+                        entry.result.name,
+                        entry.day.to_string(),
+                        entry.result.title,
+                        entry.text,
+                        entry.url,
+                        json,
+                    ],
+                )?;
+                Ok(())
+            })
+            .await
+            .context("Failed to record the history")
+        }
+
+        async fn for_user(&self, name: &str) -> Result<Vec<HistoryEntry>> {
+            let name = name.to_string();
+
+            self.with_connection(move |connection| {
+                let mut statement = connection.prepare("SELECT result FROM history WHERE name = ?1 ORDER BY seq DESC")?;
+                let rows = statement.query_map(params![name], |row| row.get::<_, String>(0))?;
+
+                rows.map(|json| entry_from_json(json?)).collect()
+            })
+            .await
+        }
+
+        async fn for_day(&self, id: &str, name: &str, day: NaiveDate) -> Result<Option<HistoryEntry>> {
+            let (id, name) = (id.to_string(), name.to_string());
+
+            self.with_connection(move |connection| {
+                connection
+                    .query_row(
+                        "SELECT result FROM history WHERE id = ?1 AND name = ?2 AND day = ?3 ORDER BY seq DESC LIMIT 1",
+                        params![id, name, day.to_string()],
+                        |row| row.get::<_, String>(0),
+                    )
+                    .optional()?
+                    .map(entry_from_json)
+                    .transpose()
+            })
+            .await
+        }
+    }
+}
+
+impl ShindanClient {
+    /**
+    Get the submissions made with a name, newest first.
+
+    # Arguments
+    - `name` - The name the shindans were run with.
+
+    # Returns
+    The recorded submissions.
+
+    # Errors
+    Returns error if no history store is configured or the store fails.
+
+    # Examples
+    ```
+    use shindan_maker::{MemoryHistory, ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::builder(ShindanDomain::En)
+            .history(MemoryHistory::default())
+            .build()
+            .unwrap();
+
+        client.get_result("1222992", "test_user").await.unwrap();
+
+        for entry in client.history_for_user("test_user").await.unwrap() {
+            println!("{} {}: {}", entry.day, entry.result.title, entry.text);
+        }
+    }
+    ```
+    */
+    pub async fn history_for_user(&self, name: &str) -> Result<Vec<HistoryEntry>> {
+        self.history
+            .as_ref()
+            .context("No history store configured")?
+            .for_user(name)
+            .await
+    }
+
+    /// Record a fresh result in the history store, if one is configured.
+    pub(crate) async fn record_history(&self, result: &ShindanResult) -> Result<()> {
+        if let Some(history) = &self.history {
+            history
+                .record(HistoryEntry::new(result.clone()))
+                .await
+                .context("Failed to record the history")?;
+        }
+
+        Ok(())
+    }
+}
@@ -89,6 +89,7 @@ pub const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
             color: #fff;
             line-height: 1.1em;
             font-size: 0.9em;
+            margin: 0;
         }
 
         #shindanResultContainer {
@@ -129,6 +130,7 @@ pub const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
             font-family: -apple-system, BlinkMacSystemFont, "Helvetica Neue", Arial, "Hiragino Kaku Gothic ProN", "Hiragino Sans", Meiryo, sans-serif;
             color: var(--text-body);
             font-size: 1.9rem;
+            margin: 0;
             aspect-ratio: 40/21;
             width: 100%;
         }
@@ -145,6 +147,24 @@ pub const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
             display: block;
             max-width: 960px;
         }
+
+        .shindanResultDescription {
+            padding: 0 1.5rem 1.5rem;
+            font-size: 0.5em;
+            line-height: 1.5;
+            white-space: pre-line;
+        }
+
+        .shindanResultAttribution {
+            padding: 0.5rem 1.5rem;
+            border-top: 1px solid var(--main-blue);
+            font-size: 0.45em;
+            opacity: 0.7;
+        }
+
+        .shindanResultAttribution a {
+            color: inherit;
+        }
     </style>
     <!-- SCRIPTS -->
     <title>ShindanMaker</title>
@@ -152,9 +172,20 @@ pub const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
 
 <body>
 <div id="main-container">
-    <div id="main">
+    <main id="main">
         <!-- TITLE_AND_RESULT -->
-    </div>
+    </main>
 </div>
 </body>
-</html>"#;
\ No newline at end of file
+</html>"#;
+
+/// Overrides the result font with a monospace one that keeps every space, for ASCII art.
+pub const PRESERVE_WHITESPACE_STYLE: &str = r#"<style>
+        #shindanResult {
+            font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, "Noto Sans Mono CJK JP", monospace;
+            white-space: pre;
+        }
+    </style>"#;
+
+/// Where the description and attribution of a result go, at the end of the result card.
+pub const RESULT_EXTRAS: &str = "<!-- RESULT_EXTRAS -->";
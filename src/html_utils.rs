@@ -1,100 +1,378 @@
-use serde_json::json;
-use scraper::{Html, Node};
-use anyhow::{Context, Result};
+use scraper::{ElementRef, Html, Node};
+use anyhow::{anyhow, Context, Result};
 
-use crate::selectors::SELECTORS;
+use crate::selectors::Selectors;
+use crate::page::ShindanPage;
+use crate::image_source::ImageSources;
+use crate::metadata::{ShindanMetadata, ShindanPreview};
 
 #[cfg(feature = "segments")]
-use crate::segment::{Segment, Segments};
+use {
+    serde_json::Value,
+    crate::chart::{Chart, ChartDataset},
+    crate::media_source::MediaSources,
+    crate::table::{TableBuilder, TableSegment},
+    crate::segment::{Segment, Segments},
+    crate::parse_options::ParseOptions,
+};
 
 #[cfg(feature = "html")]
 use {
-    anyhow::anyhow,
+    chrono::NaiveDate,
     scraper::Element,
-    crate::html_template::HTML_TEMPLATE,
+    crate::sanitize::sanitize_fragment,
+    crate::html_template::{HTML_TEMPLATE, PRESERVE_WHITESPACE_STYLE, RESULT_EXTRAS},
 };
 
+pub(crate) type FormData = Vec<(&'static str, String)>;
+
 #[cfg(feature = "segments")]
-pub(crate) fn get_segments(response_text: &str) -> Result<Segments> {
-    let result_document = Html::parse_document(response_text);
+pub(crate) fn get_segments(selectors: &Selectors, options: ParseOptions, name: Option<&str>, result_document: &Html) -> Result<Segments> {
+    let display = selectors.post_display
+        .first(result_document)
+        .context("Failed to get the next element")?;
+
+    Ok(parse_segments(display, options, name, &mut Vec::new()))
+}
+
+/// Like [`get_segments`], but never fails: problems are reported as warnings instead.
+#[cfg(feature = "segments")]
+pub(crate) fn get_segments_lenient(selectors: &Selectors, result_document: &Html) -> (Segments, Vec<String>) {
+    let mut warnings = Vec::new();
 
+    let segments = match selectors.post_display.first(result_document) {
+        Some(display) => parse_segments(display, ParseOptions::default(), None, &mut warnings),
+        None => {
+            warnings.push("Failed to find the result element".to_string());
+            Segments(Vec::new())
+        }
+    };
+
+    (segments, warnings)
+}
+
+/// Parse the children of the result element, leaving the submitted name as it is, if given.
+#[cfg(feature = "segments")]
+fn parse_segments(display: ElementRef, options: ParseOptions, name: Option<&str>, warnings: &mut Vec<String>) -> Segments {
+    finish_segments(options, segments_of(display, name, warnings))
+}
+
+/// Apply the options to the segments parsed from a result element.
+///
+/// A result without segments is logged as a warning: it usually means the site changed
+/// its markup and the parser no longer recognizes the result.
+#[cfg(feature = "segments")]
+pub(crate) fn finish_segments(options: ParseOptions, mut segments: Segments) -> Segments {
+    if segments.is_empty() {
+        log::warn!(segments = 0; "Parsed a result without any segments");
+    }
+
+    // Non-breaking spaces only keep browsers from collapsing runs of spaces.
+    if options.preserve_whitespace {
+        for segment in segments.0.iter_mut().filter(|segment| segment.type_ == "text") {
+            if let Some(text) = segment.as_str().filter(|text| text.contains('\u{a0}')) {
+                segment.data["text"] = Value::from(text.replace('\u{a0}', " "));
+            }
+        }
+    }
+    segments
+}
+
+#[cfg(feature = "segments")]
+fn segments_of(display: ElementRef, name: Option<&str>, warnings: &mut Vec<String>) -> Segments {
     let mut segments = Vec::new();
 
-    result_document.select(&SELECTORS.post_display)
-        .next()
-        .context("Failed to get the next element")?
+    display
         .children()
         .for_each(|child| {
             let node = child.value();
             match node {
                 Node::Text(text) => {
-                    let text = text.replace("&nbsp;", " ");
-                    segments.push(Segment::new("text", json!({
-                            "text": text
-                        })));
+                    segments.push(Segment::text(decode_text(text, name)));
                 }
                 Node::Element(element) => {
                     if element.name() == "br" {
-                        let text = "\n".to_string();
-                        segments.push(Segment::new("text", json!({
-                                "text": text
-                            })));
-                    } else if element.name() == "img" {
-                        let image_url = element.attr("data-src").expect("Failed to get 'data-src' attribute").to_string();
-                        segments.push(Segment::new("image", json!({
-                                "file": image_url
-                            })));
+                        segments.push(Segment::text("\n"));
+                    } else if let Some(sources) = ElementRef::wrap(child).and_then(image_sources) {
+                        match sources.segment() {
+                            Some(image) => segments.push(image),
+                            None => warnings.push("Skipped an image without a source".to_string()),
+                        }
+                    } else if matches!(element.name(), "ul" | "ol") {
+                        if segments.last().and_then(Segment::as_str).is_some_and(|text| !text.ends_with('\n')) {
+                            segments.push(Segment::text("\n"));
+                        }
+                        if let Some(list) = ElementRef::wrap(child) {
+                            list_segments(list, 0, name, &mut segments);
+                        }
+                    } else if element.name() == "table" {
+                        match ElementRef::wrap(child).and_then(|table| table_of(table, name)) {
+                            Some(table) => segments.push(Segment::table(table)),
+                            None => warnings.push("Skipped an empty table".to_string()),
+                        }
+                    } else if let Some(media) = ElementRef::wrap(child).and_then(media_sources) {
+                        match media.segment() {
+                            Some(media) => segments.push(media),
+                            None => warnings.push(format!("Skipped a {} without a source", element.name())),
+                        }
+                    } else if STYLED_TAGS.contains(&element.name()) {
+                        let text = child.descendants().filter_map(|node| node.value().as_text().map(|text| &**text)).collect::<String>();
+                        segments.extend(styled_text(element.name(), element.attr("style"), &text, name));
                     }
                 }
                 _ => {}
             }
         });
 
-    Ok(Segments(segments))
+    Segments(segments)
 }
 
-#[cfg(feature = "html")]
-pub(crate) fn get_html_str(id: &str, response_text: &str) -> Result<String> {
-    let result_document = Html::parse_document(response_text);
+/// Extract the Chart.js configuration from the inline scripts of a result page.
+///
+/// The site builds charts from object literals rather than JSON, so the labels
+/// and datasets are located by key and their arrays parsed individually.
+#[cfg(feature = "segments")]
+pub(crate) fn get_chart(selectors: &Selectors, result_document: &Html) -> Option<Chart> {
+    result_document.select(&selectors.script).find_map(|element| {
+        let script = element.inner_html();
+        let labels: Vec<String> = serde_json::from_value(value_after(&script, "labels")?).ok()?;
+        let datasets_script = &script[script.find("datasets")? + "datasets".len()..];
 
-    let mut title_and_result = result_document
-        .select(&SELECTORS.title_and_result)
-        .next()
-        .context("Failed to get the next element")?
-        .html();
+        let mut datasets = Vec::new();
+        let mut previous_end = 0;
+        for (index, _) in datasets_script.match_indices("data") {
+            let Some(data) = value_after(&datasets_script[index..], "data")
+                .and_then(|data| serde_json::from_value::<Vec<f64>>(data).ok()) else {
+                continue;
+            };
 
-    for effects_selector in &SELECTORS.effects {
-        let effects = result_document.select(effects_selector);
-        for effect in effects {
-            if let Some(next_el) = effect.next_sibling_element() {
-                if next_el.value().name() == "noscript" {
-                    let content = next_el.inner_html();
+            let label = value_after(&datasets_script[previous_end..index], "label")
+                .and_then(|label| label.as_str().map(String::from));
+            datasets.push(ChartDataset { label, data });
+            previous_end = index;
+        }
 
-                    title_and_result = title_and_result.replace(&effect.html(), "")
-                        .replace(&next_el.html(), &content);
-                }
-            }
+        if datasets.is_empty() {
+            return None;
         }
+
+        let kind = ["radar", "bar", "line", "pie", "doughnut", "polarArea"]
+            .into_iter()
+            .find(|kind| script.contains(&format!("'{}'", kind)) || script.contains(&format!("\"{}\"", kind)))
+            .unwrap_or("bar")
+            .to_string();
+
+        Some(Chart { kind, labels, datasets })
+    })
+}
+
+/// Parse the array or string literal following `key:` in a script fragment.
+#[cfg(feature = "segments")]
+pub(crate) fn value_after(script: &str, key: &str) -> Option<Value> {
+    let rest = &script[script.find(key)? + key.len()..];
+    let rest = rest.trim_start_matches(['"', '\'']).trim_start().strip_prefix(':')?.trim_start();
+
+    let end = match rest.chars().next()? {
+        '[' => rest.find(']')?,
+        quote @ ('"' | '\'') => rest[1..].find(quote)? + 1,
+        _ => return None,
+    };
+
+    serde_json::from_str(&rest[..=end].replace('\'', "\"")).ok()
+}
+
+#[cfg(feature = "html")]
+pub(crate) fn get_html_str(selectors: &Selectors, id: &str, response_text: &str, preserve_whitespace: bool) -> Result<String> {
+    get_html_frame(selectors, id, response_text, preserve_whitespace, &mut |_, content| content.to_string())
+}
+
+/**
+Build the HTML of a result page with its typing and shuffling effects in a given state.
+
+`effect` gets the mode of each effect, e.g. `ef_typing`, and its final content, and returns the content to show.
+*/
+#[cfg(feature = "html")]
+pub(crate) fn get_html_frame(
+    selectors: &Selectors,
+    id: &str,
+    response_text: &str,
+    preserve_whitespace: bool,
+    effect: &mut dyn FnMut(&str, &str) -> String,
+) -> Result<String> {
+    let result_document = Html::parse_document(response_text);
+
+    let title_and_result = selectors.title_and_result
+        .first(&result_document)
+        .context("Failed to get the next element")?;
+    let title = extract_title(selectors, &result_document).ok();
+    let (html, title) = with_accessibility(selectors, title_and_result, title_and_result.html(), title);
+    let mut title_and_result = replace_effects(selectors, &result_document, html, effect);
+    if let Some(close) = title_and_result.rfind("</") {
+        title_and_result.insert_str(close, RESULT_EXTRAS);
     }
 
     let mut html = HTML_TEMPLATE
         .replace("<!-- TITLE_AND_RESULT -->", &title_and_result);
+    if let Some(title) = title {
+        html = html.replacen("<title>ShindanMaker</title>", &format!("<title>{} | ShindanMaker</title>", escape_html(&title)), 1);
+    }
+
+    if preserve_whitespace {
+        html = html.replacen("</head>", &format!("    {}\n</head>", PRESERVE_WHITESPACE_STYLE), 1);
+    }
 
     if response_text.contains("chart.js") {
         let mut scripts = vec![
             r#"<script src="https://cn.shindanmaker.com/js/app.js?id=163959a7e23bfa7264a0ddefb3c36f13" defer=""></script>"#,
             r#"<script src="https://cn.shindanmaker.com/js/chart.js?id=391e335afc72362acd6bf1ea1ba6b74c" defer=""></script>"#];
 
-        let shindan_script = get_first_script(&result_document, id)?;
+        let shindan_script = get_first_script(selectors, &result_document, id)?;
         scripts.push(&shindan_script);
         html = html.replace("<!-- SCRIPTS -->", &scripts.join("\n"));
     }
     Ok(html)
 }
 
+/// The sanitized inner HTML of the result element, with the final text of its typing and shuffling effects.
 #[cfg(feature = "html")]
-pub(crate) fn get_first_script(result_document: &Html, id: &str) -> Result<String> {
-    for element in result_document.select(&SELECTORS.script) {
+pub(crate) fn get_result_html_fragment(selectors: &Selectors, response_text: &str) -> Result<String> {
+    let result_document = Html::parse_document(response_text);
+
+    let display = selectors.post_display
+        .first(&result_document)
+        .context("Failed to get the next element")?;
+
+    Ok(sanitize_fragment(&without_effects(selectors, &result_document, display.inner_html())))
+}
+
+/// Replace the animated effects in the HTML of a part of a result page by their `noscript` content.
+#[cfg(feature = "html")]
+fn without_effects(selectors: &Selectors, result_document: &Html, html: String) -> String {
+    replace_effects(selectors, result_document, html, &mut |_, content| content.to_string())
+}
+
+/// Replace the animated effects in the HTML of a part of a result page by what `effect` makes of their mode and `noscript` content.
+#[cfg(feature = "html")]
+fn replace_effects(selectors: &Selectors, result_document: &Html, mut html: String, effect_content: &mut dyn FnMut(&str, &str) -> String) -> String {
+    for effects_selector in &selectors.effects {
+        let effects = result_document.select(effects_selector);
+        for effect in effects {
+            if let Some(next_el) = effect.next_sibling_element() {
+                if next_el.value().name() == "noscript" {
+                    // With scripting enabled, the markup in a `noscript` is parsed as its raw text.
+                    let content = next_el.text().collect::<String>();
+                    let content = effect_content(effect.value().attr("data-mode").unwrap_or_default(), &content);
+
+                    html = html.replace(&effect.html(), "")
+                        .replace(&next_el.html(), &content);
+                }
+            }
+        }
+    }
+    html
+}
+
+/**
+Make the title of the HTML of a result its `h1` heading and give its images alt text,
+the shindan's title for the title image and none for the others.
+
+`html` is the HTML of `element`, as serialized from the same document so that its
+parts are found as they are. Returns the HTML and the title, from `title` or else
+the text of the heading.
+*/
+#[cfg(feature = "html")]
+fn with_accessibility(selectors: &Selectors, element: ElementRef, mut html: String, title: Option<String>) -> (String, Option<String>) {
+    let heading = element.select(&selectors.result_heading).next();
+    let title = title.or_else(|| {
+        let text = heading?.text().collect::<String>().trim().to_string();
+        (!text.is_empty()).then_some(text)
+    });
+    if let Some(heading) = heading.filter(|heading| heading.value().name() != "h1") {
+        let name = heading.value().name();
+        let heading = heading.html();
+        if let Some(inner) = heading.strip_prefix(&format!("<{}", name)).and_then(|rest| rest.strip_suffix(&format!("</{}>", name))) {
+            html = html.replacen(&heading, &format!("<h1{}</h1>", inner), 1);
+        }
+    }
+
+    // Only the tags of the heading changed, so the images in it are found as they are.
+    for image in element.select(&selectors.image_without_alt) {
+        let in_heading = image.ancestors()
+            .filter_map(ElementRef::wrap)
+            .any(|element| element.value().classes().any(|class| class == "shindanTitleImageContainer"));
+        let alt = if in_heading { title.as_deref().unwrap_or_default() } else { "" };
+
+        let image = image.html();
+        html = html.replacen(&image, &with_attributes(&image, &format!(r#"alt="{}""#, escape_html(alt))), 1);
+    }
+
+    (html, title)
+}
+
+/**
+The description and attribution footer ending the card of a result, each if given.
+
+The attribution is the URL of the shindan, its author if known, and the day of the result.
+*/
+#[cfg(feature = "html")]
+pub(crate) fn get_result_extras(description: Option<&str>, attribution: Option<(&str, Option<&str>, NaiveDate)>) -> String {
+    let mut extras = String::new();
+    if let Some(description) = description.map(str::trim).filter(|description| !description.is_empty()) {
+        extras.push_str(&format!(r#"<div class="shindanResultDescription">{}</div>"#, escape_html(description)));
+    }
+    if let Some((url, author, day)) = attribution {
+        let mut parts = vec![format!(r#"<a href="{}">{}</a>"#, escape_html(url), escape_html(url))];
+        parts.extend(author.map(escape_html));
+        parts.push(day.format("%Y-%m-%d").to_string());
+        extras.push_str(&format!(r#"<footer class="shindanResultAttribution">{}</footer>"#, parts.join(" · ")));
+    }
+    extras
+}
+
+/// Add attributes to the opening tag of the HTML of an element.
+#[cfg(feature = "html")]
+fn with_attributes(element: &str, attributes: &str) -> String {
+    let name_end = element[1..]
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .map_or(element.len(), |index| index + 1);
+    format!("{} {}{}", &element[..name_end], attributes, &element[name_end..])
+}
+
+/// Escape text for HTML content and double-quoted attribute values.
+#[cfg(any(feature = "segments", feature = "html"))]
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The language of a page, from the `lang` attribute of its root element, if it is a well-formed tag.
+#[cfg(feature = "html")]
+pub(crate) fn get_lang(response_text: &str) -> Option<String> {
+    let document = Html::parse_document(response_text);
+    let lang = document.root_element().value().attr("lang")?;
+    (!lang.is_empty() && lang.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')).then(|| lang.to_string())
+}
+
+/// Replace the tag from `open` to `close` around the first reference to `url`.
+#[cfg(feature = "html")]
+pub(crate) fn replace_tag(html: &str, url: &str, open: &str, close: &str, replacement: &str) -> String {
+    let escaped = url.replace('&', "&amp;");
+    let Some(position) = html.find(&format!("\"{}\"", escaped)).or_else(|| html.find(&format!("\"{}\"", url))) else {
+        return html.to_string();
+    };
+    let (Some(start), Some(end)) = (html[..position].rfind(open), html[position..].find(close)) else {
+        return html.to_string();
+    };
+
+    let end = position + end + close.len();
+    format!("{}{}{}", &html[..start], replacement, &html[end..])
+}
+
+#[cfg(feature = "html")]
+pub(crate) fn get_first_script(selectors: &Selectors, result_document: &Html, id: &str) -> Result<String> {
+    for element in result_document.select(&selectors.script) {
         let html = element.html();
         if html.contains(id) {
             return Ok(html);
@@ -104,30 +382,347 @@ pub(crate) fn get_first_script(result_document: &Html, id: &str) -> Result<Strin
     Err(anyhow!("Failed to find script with id {}", id))
 }
 
-pub(crate) fn extract_title_and_form_data(html_content: &str, name: &str) -> Result<(String, Vec<(&'static str, String)>)> {
+pub(crate) fn extract_page(selectors: &Selectors, url: &str, html_content: &str, session_cookie: String) -> Result<ShindanPage> {
     let document = Html::parse_document(html_content);
-    let title = extract_title(&document)?;
-    let form_data = extract_form_data(&document, name)?;
 
-    Ok((title, form_data))
+    let page = ShindanPage::new(
+        url,
+        session_cookie,
+        extract_title(selectors, &document).ok(),
+        extract_description(selectors, &document).ok(),
+        extract_form_data(selectors, &document)?,
+    )
+    .with_tags(extract_tags(selectors, &document))
+    .with_author(extract_author(selectors, &document))
+    .with_preview(extract_preview(selectors, &document));
+
+    #[cfg(feature = "segments")]
+    let page = page.with_description_segments(extract_description_segments(selectors, &document).ok());
+
+    Ok(page)
 }
 
-pub(crate) fn extract_title(dom: &Html) -> Result<String> {
-    Ok(dom
-        .select(&SELECTORS.shindan_title)
-        .next()
-        .context("Failed to get the next element")?
-        .value().attr("data-shindan_title")
-        .context("Failed to get 'data-shindan_title' attribute")?
-        .to_string())
+pub(crate) fn extract_metadata(selectors: &Selectors, dom: &Html) -> Result<ShindanMetadata> {
+    Ok(ShindanMetadata {
+        title: extract_title(selectors, dom)?,
+        description: extract_description(selectors, dom).ok(),
+        #[cfg(feature = "segments")]
+        description_segments: extract_description_segments(selectors, dom).ok(),
+        tags: extract_tags(selectors, dom),
+        preview: extract_preview(selectors, dom),
+    })
 }
 
-pub(crate) fn extract_description(dom: &Html) -> Result<String> {
+/// Extract the tags the creator assigned to a shindan.
+pub(crate) fn extract_tags(selectors: &Selectors, dom: &Html) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+
+    for element in selectors.tags.all(dom) {
+        let tag = element.text().collect::<String>();
+        let tag = tag.trim().trim_start_matches('#').trim();
+        if !tag.is_empty() && !tags.iter().any(|known| known == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+
+    tags
+}
+
+/// Extract the name of the author of a shindan.
+pub(crate) fn extract_author(selectors: &Selectors, dom: &Html) -> Option<String> {
+    let author = selectors.author.first(dom)?.text().collect::<String>();
+    let author = author.trim().trim_start_matches('@').trim();
+    (!author.is_empty()).then(|| author.to_string())
+}
+
+/// Extract the hashtags the share links of a result page post with, from their `hashtags` parameter and text.
+#[cfg(feature = "segments")]
+pub(crate) fn get_hashtags(selectors: &Selectors, result_document: &Html) -> Vec<String> {
+    let mut hashtags: Vec<String> = Vec::new();
+
+    for link in selectors.share.all(result_document) {
+        let Some(url) = link.value().attr("href").and_then(|href| url::Url::parse(href).ok()) else {
+            continue;
+        };
+
+        for (key, value) in url.query_pairs() {
+            let found: Vec<&str> = match key.as_ref() {
+                "hashtags" => value.split(',').collect(),
+                "text" => value.split_whitespace().filter_map(|word| word.strip_prefix('#')).collect(),
+                _ => continue,
+            };
+
+            for hashtag in found.into_iter().map(str::trim).filter(|hashtag| !hashtag.is_empty()) {
+                if !hashtags.iter().any(|known| known == hashtag) {
+                    hashtags.push(hashtag.to_string());
+                }
+            }
+        }
+    }
+
+    hashtags
+}
+
+/// Extract the sample result of a shindan and the share image of the page.
+pub(crate) fn extract_preview(selectors: &Selectors, dom: &Html) -> Option<ShindanPreview> {
+    let example = selectors.preview.first(dom);
+
+    let text = example
+        .map(|example| example.text().collect::<String>().trim().to_string())
+        .filter(|text| !text.is_empty());
+    let image = example
+        .and_then(|example| example.select(&selectors.image).next())
+        .and_then(|image| {
+            let picture = image.parent().and_then(ElementRef::wrap).filter(|parent| parent.value().name() == "picture");
+            image_sources(picture.unwrap_or(image))?.best().map(String::from)
+        });
+    let share_image = dom.select(&selectors.og_image).next().and_then(|meta| meta.value().attr("content")).map(String::from);
+
+    (text.is_some() || image.is_some() || share_image.is_some()).then_some(ShindanPreview { text, image, share_image })
+}
+
+/// The sources of an `img`, or of the `source`s and `img` of a `picture`.
+fn image_sources(element: ElementRef) -> Option<ImageSources> {
+    match element.value().name() {
+        "img" => Some(ImageSources::of(element.value().attrs())),
+        "picture" => {
+            let mut sources = ImageSources::default();
+            for descendant in element.descendants().filter_map(ElementRef::wrap) {
+                if matches!(descendant.value().name(), "source" | "img") {
+                    sources.add_element(descendant.value().attrs());
+                }
+            }
+            Some(sources)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(feature = "segments")]
+/// Decode the character references of raw HTML text, as a DOM parser would.
+pub(crate) fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        // No named reference is longer than 32 bytes, so the search for its end stops there.
+        let reference = rest[1..].char_indices().take(33).find(|&(_, c)| c == ';').map(|(end, _)| &rest[1..=end]);
+        let characters = reference.and_then(|reference| match reference.strip_prefix('#') {
+            Some(code) => {
+                let code = match code {
+                    hex if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16).ok()?,
+                    decimal => decimal.parse().ok()?,
+                };
+                Some((char::from_u32(code)?, None))
+            }
+            None => {
+                let &(first, second) = html5ever::data::NAMED_ENTITIES.get(&rest[1..reference.len() + 2])?;
+                Some((char::from_u32(first)?, char::from_u32(second).filter(|&c| c != '\0')))
+            }
+        });
+
+        match (reference, characters) {
+            (Some(reference), Some((first, second))) => {
+                decoded.push(first);
+                decoded.extend(second);
+                rest = &rest[reference.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+/// The text of a text node with the character references it still holds decoded.
+///
+/// The site escapes some results twice, so references such as `&amp;` or `&#9733;`
+/// survive the parse of the page; `&nbsp;` among them becomes a plain space.
+///
+/// The submitted name is only escaped once, so where it appears it is kept as it is:
+/// a name that is literally `R&amp;D` must not become `R&D`.
+#[cfg(feature = "segments")]
+pub(crate) fn decode_text(text: &str, name: Option<&str>) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    match name.filter(|name| name.contains('&') && text.contains(*name)) {
+        Some(name) => text.split(name).map(|part| decode_text(part, None)).collect::<Vec<_>>().join(name),
+        None => decode_entities(&text.replace("&nbsp;", " ")),
+    }
+}
+
+/// The inline elements of a result whose text is kept, with its style.
+#[cfg(feature = "segments")]
+pub(crate) const STYLED_TAGS: [&str; 3] = ["b", "strong", "span"];
+
+/// A text segment for the text of a styled inline element, with `bold` and `color` data
+/// from its tag and `style` attribute. Empty text gives no segment.
+#[cfg(feature = "segments")]
+pub(crate) fn styled_text(tag: &str, style: Option<&str>, text: &str, name: Option<&str>) -> Option<Segment> {
+    let text = decode_text(text, name);
+    if text.is_empty() {
+        return None;
+    }
+
+    let mut segment = Segment::text(text);
+    let mut bold = matches!(tag, "b" | "strong");
+    let declarations = style
+        .into_iter()
+        .flat_map(|style| style.split(';'))
+        .filter_map(|declaration| declaration.split_once(':'))
+        .map(|(property, value)| (property.trim().to_ascii_lowercase(), value.trim()));
+
+    for (property, value) in declarations {
+        match property.as_str() {
+            "font-weight" => bold = matches!(value, "bold" | "bolder") || value.parse::<u16>().is_ok_and(|weight| weight >= 600),
+            "color" if !value.is_empty() => segment.data["color"] = Value::from(value),
+            _ => {}
+        }
+    }
+    if bold {
+        segment.data["bold"] = Value::Bool(true);
+    }
+
+    Some(segment)
+}
+
+/// The numbering of a `ul` or `ol` being read.
+#[cfg(feature = "segments")]
+#[derive(Debug)]
+pub(crate) struct ListLevel {
+    ordered: bool,
+    next: i64,
+}
+
+#[cfg(feature = "segments")]
+impl ListLevel {
+    /// Start a list of the given element name, numbered from its `start` attribute.
+    pub(crate) fn of(name: &str, start: Option<&str>) -> Self {
+        let next = start.and_then(|start| start.trim().parse().ok()).unwrap_or(1);
+        Self { ordered: name == "ol", next }
+    }
+
+    /// The marker of the next item: a bullet, or its number.
+    pub(crate) fn next_marker(&mut self) -> String {
+        if !self.ordered {
+            return "• ".to_string();
+        }
+        self.next += 1;
+        format!("{}. ", self.next - 1)
+    }
+}
+
+/// A line of a list item, indented by its nesting level, with whitespace collapsed.
+#[cfg(feature = "segments")]
+pub(crate) fn list_item(level: usize, marker: &str, text: &str, name: Option<&str>) -> Segment {
+    let text = decode_text(text, name).split_whitespace().collect::<Vec<_>>().join(" ");
+    Segment::text(format!("{}{}{}\n", "  ".repeat(level), marker, text))
+}
+
+/// The items of a `ul` or `ol` as lines of text, followed by those of the lists nested in each.
+#[cfg(feature = "segments")]
+fn list_segments(list: ElementRef, level: usize, name: Option<&str>, segments: &mut Vec<Segment>) {
+    let mut numbering = ListLevel::of(list.value().name(), list.value().attr("start"));
+
+    for item in list.children().filter_map(ElementRef::wrap).filter(|child| child.value().name() == "li") {
+        let mut text = String::new();
+        let mut nested = Vec::new();
+        item_contents(item, &mut text, &mut nested);
+
+        segments.push(list_item(level, &numbering.next_marker(), &text, name));
+        for list in nested {
+            list_segments(list, level + 1, name, segments);
+        }
+    }
+}
+
+/// The text of a list item, leaving out that of the lists nested in it, which are collected instead.
+#[cfg(feature = "segments")]
+fn item_contents<'a>(element: ElementRef<'a>, text: &mut String, nested: &mut Vec<ElementRef<'a>>) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(content) => text.push_str(content),
+            Node::Element(node) => match (node.name(), ElementRef::wrap(child)) {
+                ("ul" | "ol", Some(list)) => nested.push(list),
+                (_, Some(child)) => item_contents(child, text, nested),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+}
+
+/// The headers and rows of a `table`.
+#[cfg(feature = "segments")]
+fn table_of(table: ElementRef, name: Option<&str>) -> Option<TableSegment> {
+    let mut builder = TableBuilder::default();
+    table_rows(table, false, &mut builder);
+    builder.finish(name)
+}
+
+#[cfg(feature = "segments")]
+fn table_rows(element: ElementRef, head: bool, builder: &mut TableBuilder) {
+    for child in element.children().filter_map(ElementRef::wrap) {
+        match child.value().name() {
+            "thead" => table_rows(child, true, builder),
+            "tbody" | "tfoot" => table_rows(child, head, builder),
+            "tr" => {
+                builder.start_row(head);
+                for cell in child.children().filter_map(ElementRef::wrap) {
+                    let name = cell.value().name();
+                    if matches!(name, "th" | "td") {
+                        builder.start_cell(name == "th");
+                        builder.push_text(&cell.text().collect::<String>());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The sources and poster of a `video` or `audio`, including its `source` children.
+#[cfg(feature = "segments")]
+fn media_sources(element: ElementRef) -> Option<MediaSources> {
+    let mut media = MediaSources::of(element.value().name(), element.value().attrs())?;
+    for source in element.children().filter_map(ElementRef::wrap) {
+        if source.value().name() == "source" {
+            media.add_source_element(source.value().attrs());
+        }
+    }
+    Some(media)
+}
+
+pub(crate) fn extract_title(selectors: &Selectors, dom: &Html) -> Result<String> {
+    let title = selectors.shindan_title
+        .first(dom)
+        .context("Failed to get the next element")?;
+
+    match title.value().attr("data-shindan_title") {
+        Some(title) => Ok(title.to_string()),
+        None => {
+            let text = title.text().collect::<String>().trim().to_string();
+            if text.is_empty() {
+                return Err(anyhow!("Failed to get 'data-shindan_title' attribute"));
+            }
+            Ok(text)
+        }
+    }
+}
+
+pub(crate) fn extract_description(selectors: &Selectors, dom: &Html) -> Result<String> {
     let mut desc = Vec::new();
 
-    dom
-        .select(&SELECTORS.shindan_description_display)
-        .next()
+    selectors.shindan_description_display
+        .first(dom)
         .context("Failed to get the next element")?
         .children()
         .for_each(|child| {
@@ -152,17 +747,81 @@ pub(crate) fn extract_description(dom: &Html) -> Result<String> {
     Ok(desc.join(""))
 }
 
-pub(crate) fn extract_form_data(
-    dom: &Html,
-    name: &str,
-) -> Result<Vec<(&'static str, String)>> {
+/// Extract the description as segments, keeping the links, images and list items flattened by [`extract_description`].
+#[cfg(feature = "segments")]
+pub(crate) fn extract_description_segments(selectors: &Selectors, dom: &Html) -> Result<Segments> {
+    let display = selectors.shindan_description_display
+        .first(dom)
+        .context("Failed to get the next element")?;
+
+    let mut segments = Vec::new();
+    rich_segments_of(display, &mut segments);
+
+    Ok(Segments(segments))
+}
+
+#[cfg(feature = "segments")]
+fn rich_segments_of(element: ElementRef, segments: &mut Vec<Segment>) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => segments.push(Segment::text(decode_text(text, None))),
+            Node::Element(node) => {
+                let Some(child) = ElementRef::wrap(child) else {
+                    continue;
+                };
+
+                match node.name() {
+                    "br" => segments.push(Segment::text("\n")),
+                    "img" | "picture" => segments.extend(image_sources(child).and_then(|sources| sources.segment())),
+                    "video" | "audio" => segments.extend(media_sources(child).and_then(|media| media.segment())),
+                    "table" => segments.extend(table_of(child, None).map(Segment::table)),
+                    "a" => match node.attr("href") {
+                        Some(url) => {
+                            let text = child.text().collect::<String>().trim().to_string();
+                            segments.push(Segment::link(if text.is_empty() { url.to_string() } else { text }, url));
+                        }
+                        None => rich_segments_of(child, segments),
+                    },
+                    "ul" | "ol" => {
+                        if segments.last().and_then(Segment::as_str).is_some_and(|text| !text.ends_with('\n')) {
+                            segments.push(Segment::text("\n"));
+                        }
+                        // Items are marked as those of result lists are, indented by the lists around them.
+                        let level = child.ancestors().filter_map(ElementRef::wrap).filter(|list| matches!(list.value().name(), "ul" | "ol")).count();
+                        let mut numbering = ListLevel::of(node.name(), node.attr("start"));
+                        for item in child.children().filter_map(ElementRef::wrap).filter(|item| item.value().name() == "li") {
+                            segments.push(Segment::text(format!("{}{}", "  ".repeat(level), numbering.next_marker())));
+                            rich_segments_of(item, segments);
+                            if segments.last().and_then(Segment::as_str).is_some_and(|text| !text.ends_with('\n')) {
+                                segments.push(Segment::text("\n"));
+                            }
+                        }
+                    }
+                    _ => rich_segments_of(child, segments),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub(crate) fn extract_token(selectors: &Selectors, dom: &Html) -> Result<String> {
+    Ok(selectors.form[0]
+        .first(dom)
+        .context("Failed to get the next element")?
+        .value()
+        .attr("value")
+        .context("Failed to get value attribute")?
+        .to_string())
+}
+
+pub(crate) fn extract_form_data(selectors: &Selectors, dom: &Html) -> Result<FormData> {
     const FIELDS: &[&str] = &["_token", "randname", "type"];
     let mut form_data = Vec::with_capacity(FIELDS.len() + 1);
 
     for (index, &field) in FIELDS.iter().enumerate() {
-        let value = dom
-            .select(&SELECTORS.form[index])
-            .next()
+        let value = selectors.form[index]
+            .first(dom)
             .context("Failed to get the next element")?
             .value()
             .attr("value")
@@ -171,7 +830,5 @@ pub(crate) fn extract_form_data(
         form_data.push((field, value.to_string()));
     }
 
-    form_data.push(("user_input_value_1", name.to_string()));
-
     Ok(form_data)
 }
\ No newline at end of file
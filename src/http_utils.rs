@@ -1,6 +1,28 @@
-use reqwest::header;
-use reqwest::header::{HeaderMap, HeaderValue};
+use std::fmt;
 use anyhow::{Context, Result};
+use reqwest::{header, StatusCode, Url};
+use reqwest::header::{HeaderMap, HeaderValue};
+
+use crate::singleflight::SharedError;
+
+/// The error of a page the site answered 404 for.
+#[derive(Debug)]
+pub(crate) struct NotFound(pub(crate) String);
+
+impl fmt::Display for NotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "No shindan found at {}", self.0)
+    }
+}
+
+impl std::error::Error for NotFound {}
+
+/// Whether an error, or one of its causes, is a [`NotFound`], even if shared by a coalesced call.
+pub(crate) fn is_not_found(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause.is::<NotFound>() || cause.downcast_ref::<SharedError>().is_some_and(|shared| is_not_found(shared.inner()))
+    })
+}
 
 pub(crate) fn extract_session_cookie(response: &reqwest::Response) -> Result<String> {
     response.cookies()
@@ -23,4 +45,37 @@ pub(crate) fn prepare_headers(session_cookie: &str) -> Result<HeaderMap> {
     );
 
     Ok(headers)
-}
\ No newline at end of file
+}
+
+/// Whether a response ended on the login page, i.e. the session is missing or has expired.
+pub(crate) fn is_login_redirect(url: &Url) -> bool {
+    url.path().trim_end_matches('/') == "/login"
+}
+
+/// Whether a request failed in a way worth retrying: a connection error, a timeout, 429 or a 5xx status.
+pub(crate) fn is_transient(outcome: &reqwest::Result<reqwest::Response>) -> bool {
+    match outcome {
+        Ok(response) => response.status() == StatusCode::TOO_MANY_REQUESTS || response.status().is_server_error(),
+        Err(err) => err.is_connect() || err.is_timeout(),
+    }
+}
+
+/// Percent-encode text as a single segment of a URL path.
+pub(crate) fn encode_path_segment(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(char::from(byte)),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Whether the site rejected a form because its CSRF token has expired.
+#[cfg(any(feature = "segments", feature = "html"))]
+pub(crate) fn is_csrf_token_mismatch(status: StatusCode) -> bool {
+    const PAGE_EXPIRED: u16 = 419;
+
+    status.as_u16() == PAGE_EXPIRED
+}
@@ -0,0 +1,325 @@
+use anyhow::Result;
+use base64::Engine;
+#[cfg(any(feature = "animated", feature = "png"))]
+use anyhow::{bail, Context};
+#[cfg(any(feature = "animated", feature = "png"))]
+use jpeg_decoder::PixelFormat;
+use cdp_html_shot::Browser;
+use std::future::Future;
+use futures::future::join_all;
+use tokio::sync::{OnceCell, Semaphore};
+
+use crate::jitter::Spacing;
+use crate::client::ShindanClient;
+#[cfg(feature = "segments")]
+use crate::digest::Digest;
+#[cfg(feature = "segments")]
+use crate::segment::Segments;
+#[cfg(feature = "segments")]
+use crate::result::ShindanResult;
+#[cfg(feature = "segments")]
+use crate::render::CardTheme;
+use crate::shindan_id::ShindanId;
+
+/// The headless browser shared by every capture, launched on first use.
+static BROWSER: OnceCell<Browser> = OnceCell::const_new();
+
+/// How [`ShindanClient::get_image_with`] sizes the image of a result.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScreenshotOptions {
+    /// The space around the result card, in CSS pixels. Defaults to none.
+    pub padding: u32,
+    /// The width of the result card, in CSS pixels. Defaults to the width of the site's layout, 750.
+    pub width: Option<u32>,
+}
+
+impl ShindanClient {
+    /**
+    Capture the result of a shindan as a JPEG image.
+
+    The result is rendered by a headless Chrome, launched once per process.
+
+    # Arguments
+    - `id` - The ID of the shindan.
+    - `name` - The name to run the shindan for.
+
+    # Returns
+    The JPEG image of the result.
+
+    # Errors
+    Returns error if the submission fails or the browser cannot capture the result.
+
+    # Examples
+    ```no_run
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::En).unwrap();
+
+        let image = client.get_image("1222992", "test_user").await.unwrap();
+        std::fs::write("result.jpeg", image).unwrap();
+    }
+    ```
+    */
+    pub async fn get_image(&self, id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>, name: &str) -> Result<Vec<u8>> {
+        let html = self.get_html_str(id, name).await?;
+        capture(&html, "#title_and_result").await
+    }
+
+    /**
+    Capture the result of a shindan as a JPEG image cropped to the result card, e.g. for chat embeds.
+
+    The image holds the card and the padding around it, whatever the size of the
+    browser window, and nothing else. The card is wrapped in a frame of its own that
+    is captured as an element, so the browser measures the frame's box and clips the
+    screenshot to it; only the frame's width and padding are set by a stylesheet.
+
+    # Arguments
+    - `id` - The ID of the shindan.
+    - `name` - The name to run the shindan for.
+    - `options` - The padding around the card and its width.
+
+    # Returns
+    The JPEG image of the result.
+
+    # Errors
+    Returns error if the submission fails or the browser cannot capture the result.
+
+    # Examples
+    ```no_run
+    use shindan_maker::{ScreenshotOptions, ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::En).unwrap();
+
+        let options = ScreenshotOptions { padding: 8, width: Some(480) };
+        let image = client.get_image_with("1222992", "test_user", &options).await.unwrap();
+        std::fs::write("result.jpeg", image).unwrap();
+    }
+    ```
+    */
+    pub async fn get_image_with(
+        &self,
+        id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>,
+        name: &str,
+        options: &ScreenshotOptions,
+    ) -> Result<Vec<u8>> {
+        let html = self.get_html_str(id, name).await?;
+        capture(&fit_to_card(&html, options), "#card_frame").await
+    }
+
+    /**
+    Get the result of a shindan and capture it as a JPEG image, from a single submission.
+
+    # Arguments
+    - `id` - The ID of the shindan.
+    - `name` - The name to run the shindan for.
+
+    # Returns
+    The result and the JPEG image of it.
+
+    # Errors
+    Returns error if the submission fails or the browser cannot capture the result.
+
+    # Examples
+    ```no_run
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::En).unwrap();
+
+        let (result, image) = client.get_result_with_image("1222992", "test_user").await.unwrap();
+        std::fs::write(format!("{}.jpeg", result.name), image).unwrap();
+    }
+    ```
+    */
+    #[cfg(feature = "segments")]
+    pub async fn get_result_with_image(
+        &self,
+        id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>,
+        name: &str,
+    ) -> Result<(ShindanResult, Vec<u8>)> {
+        let (result, html) = self.get_result_with_html(id, name).await?;
+        Ok((result, capture(&html, "#title_and_result").await?))
+    }
+
+    /**
+    Capture the results of many submissions as JPEG images, e.g. for every member of a guild.
+
+    The results are rendered in tabs of the shared headless Chrome. At most
+    `parallel_tabs` jobs run at a time, each submitting its form and rendering
+    its result before the next one starts, under the client's rate limit and
+    batch spacing.
+
+    # Arguments
+    - `jobs` - The `(id, name)` pairs to submit; an invalid ID fails its job only.
+    - `parallel_tabs` - The maximum number of jobs submitting or rendering at once.
+
+    # Returns
+    The JPEG image of each result, or why it failed, in the order of `jobs`.
+
+    # Examples
+    ```no_run
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::En).unwrap();
+
+        let jobs = ["Alice", "Bob", "Carol"].map(|name| ("1222992", name));
+        let images = client.render_images_batch(jobs, 4).await;
+
+        for (index, image) in images.into_iter().enumerate() {
+            std::fs::write(format!("result-{}.jpeg", index), image.unwrap()).unwrap();
+        }
+    }
+    ```
+    */
+    pub async fn render_images_batch<I, Id, N>(&self, jobs: I, parallel_tabs: usize) -> Vec<Result<Vec<u8>>>
+    where
+        I: IntoIterator<Item = (Id, N)>,
+        Id: TryInto<ShindanId, Error: Into<anyhow::Error>>,
+        N: AsRef<str>,
+    {
+        self.render_batch_with(jobs, parallel_tabs, |html| async move { capture(&html, "#title_and_result").await }).await
+    }
+
+    /// [`render_images_batch`](Self::render_images_batch), rendering each page with `render` instead of the browser.
+    pub(crate) async fn render_batch_with<I, Id, N, F, Fut>(&self, jobs: I, parallel_tabs: usize, render: F) -> Vec<Result<Vec<u8>>>
+    where
+        I: IntoIterator<Item = (Id, N)>,
+        Id: TryInto<ShindanId, Error: Into<anyhow::Error>>,
+        N: AsRef<str>,
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<Vec<u8>>>,
+    {
+        let tabs = Semaphore::new(parallel_tabs.max(1));
+        let spacing = self.batch_spacing.map(Spacing::new);
+
+        let renders = jobs.into_iter().map(|(id, name)| {
+            let tabs = &tabs;
+            let spacing = &spacing;
+            let render = &render;
+
+            async move {
+                // The tab is taken before submitting, so no more results are fetched than can be rendered.
+                let _tab = tabs.acquire().await?;
+                if let Some(spacing) = spacing {
+                    spacing.wait().await;
+                }
+
+                let html = self.get_html_str(id, name.as_ref()).await?;
+                render(html).await
+            }
+        });
+
+        join_all(renders).await
+    }
+}
+
+#[cfg(feature = "segments")]
+impl Digest {
+    /**
+    Capture the digest as a JPEG image, a collage of the results to post as one picture.
+
+    # Returns
+    The JPEG image of the digest, as [`Digest::to_html`] renders it.
+
+    # Errors
+    Returns error if the browser cannot capture the digest.
+    */
+    pub async fn to_image(&self) -> Result<Vec<u8>> {
+        capture(&self.to_html(), "#digest").await
+    }
+}
+
+/**
+Capture segments as the JPEG image of a result card, e.g. for archived, cached or translated results.
+
+The card is the one [`Segments::to_card_html`] renders, captured like the live results of
+[`ShindanClient::get_image`].
+
+# Arguments
+- `segments` - The result to render.
+- `theme` - The title and colors of the card.
+
+# Returns
+The JPEG image of the card.
+
+# Errors
+Returns error if the browser cannot capture the card.
+
+# Examples
+```no_run
+use shindan_maker::{render_segments_to_image, CardTheme, Segment, Segments};
+
+#[tokio::main]
+async fn main() {
+    let segments = Segments(vec![Segment::text("Alice is lucky")]);
+
+    let image = render_segments_to_image(&segments, &CardTheme::default()).await.unwrap();
+    std::fs::write("result.jpeg", image).unwrap();
+}
+```
+*/
+#[cfg(feature = "segments")]
+pub async fn render_segments_to_image(segments: &Segments, theme: &CardTheme) -> Result<Vec<u8>> {
+    capture(&segments.to_card_html(theme), "#title_and_result").await
+}
+
+/**
+Wrap the card of a result page in a `#card_frame` holding the card and its padding only.
+
+The frame is ours rather than an element of the site's layout, so a change to the
+site's stylesheet does not move the crop: the browser measures the frame when it is
+captured. Only the width limit of the page's own template is lifted, for wide cards.
+*/
+pub(crate) fn fit_to_card(html: &str, options: &ScreenshotOptions) -> String {
+    const LAYOUT_WIDTH: u32 = 750;
+
+    let style = format!(
+        "<style>\n        html, body {{ max-width: none; }}\n        #card_frame {{ box-sizing: content-box; width: {}px; padding: {}px; }}\n        #card_frame > #title_and_result {{ margin: 0; }}\n    </style>",
+        options.width.unwrap_or(LAYOUT_WIDTH),
+        options.padding,
+    );
+    let mut html = html.replacen("</head>", &format!("    {}\n</head>", style), 1)
+        .replacen(r#"<main id="main">"#, r#"<main id="main"><div id="card_frame">"#, 1);
+    if let Some(close) = html.rfind("</main>") {
+        html.insert_str(close, "</div>");
+    }
+    html
+}
+
+/// A decoded image, as RGB pixels.
+#[cfg(any(feature = "animated", feature = "png"))]
+pub(crate) struct Frame {
+    pub(crate) width: u16,
+    pub(crate) height: u16,
+    pub(crate) pixels: Vec<u8>,
+}
+
+/// Decode a JPEG capture into RGB pixels.
+#[cfg(any(feature = "animated", feature = "png"))]
+pub(crate) fn decode_jpeg(jpeg: &[u8]) -> Result<Frame> {
+    let mut decoder = jpeg_decoder::Decoder::new(jpeg);
+    let pixels = decoder.decode().context("Failed to decode a capture")?;
+    let info = decoder.info().context("Failed to decode a capture")?;
+
+    let pixels = match info.pixel_format {
+        PixelFormat::RGB24 => pixels,
+        PixelFormat::L8 => pixels.iter().flat_map(|&luma| [luma; 3]).collect(),
+        format => bail!("Unsupported capture pixel format: {:?}", format),
+    };
+    Ok(Frame { width: info.width, height: info.height, pixels })
+}
+
+/// Capture the element of a page matching a CSS selector as a JPEG image.
+pub(crate) async fn capture(html: &str, selector: &str) -> Result<Vec<u8>> {
+    let browser = BROWSER.get_or_try_init(Browser::new).await?;
+    let image = browser.capture_html(html, selector).await?;
+
+    Ok(base64::prelude::BASE64_STANDARD.decode(image)?)
+}
@@ -0,0 +1,146 @@
+use std::cmp::Ordering;
+
+#[cfg(feature = "segments")]
+use {serde_json::Value, crate::segment::Segment};
+
+/// The resolution a source of an image is declared with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Resolution {
+    /// A `srcset` width descriptor, e.g. `640w`.
+    Width(f64),
+    /// A `srcset` pixel density descriptor, e.g. `2x`.
+    Density(f64),
+    /// A plain attribute, ranked by how likely it holds the real image rather than a placeholder.
+    Attribute(f64),
+}
+
+impl Resolution {
+    /// Widths beat densities, which beat plain attributes; then the larger value wins.
+    fn rank(self) -> (u8, f64) {
+        match self {
+            Resolution::Width(width) => (2, width),
+            Resolution::Density(density) => (1, density),
+            Resolution::Attribute(priority) => (0, priority),
+        }
+    }
+}
+
+/**
+The candidate sources of an image, read from the attributes of an `img` and of the
+`source` elements of its `picture`.
+
+Lazy-loading scripts and CDNs spread the real image over `src`, `data-src`,
+`data-lazy-src` and `srcset`, leaving a placeholder in `src`; the candidate with the
+highest declared resolution is taken, and `data:` placeholders only as a last resort.
+*/
+#[derive(Debug, Default)]
+pub(crate) struct ImageSources {
+    candidates: Vec<(String, Resolution)>,
+    alt: Option<String>,
+    title: Option<String>,
+}
+
+impl ImageSources {
+    /// Read the sources of an element from its attributes.
+    pub(crate) fn of<'a>(attributes: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let mut sources = Self::default();
+        sources.add_element(attributes);
+        sources
+    }
+
+    /// Add the sources of another element, e.g. a `source` of the same `picture`.
+    pub(crate) fn add_element<'a>(&mut self, attributes: impl IntoIterator<Item = (&'a str, &'a str)>) {
+        for (name, value) in attributes {
+            match name {
+                "srcset" | "data-srcset" => self.add_srcset(value),
+                "data-lazy-src" => self.add(value, Resolution::Attribute(3.0)),
+                "data-src" => self.add(value, Resolution::Attribute(2.0)),
+                "src" => self.add(value, Resolution::Attribute(1.0)),
+                "alt" => describe(&mut self.alt, value),
+                "title" => describe(&mut self.title, value),
+                _ => {}
+            }
+        }
+    }
+
+    fn add_srcset(&mut self, srcset: &str) {
+        for candidate in srcset.split(',') {
+            let mut parts = candidate.split_whitespace();
+            let Some(url) = parts.next() else {
+                continue;
+            };
+
+            let descriptor = parts.next().unwrap_or("1x");
+            let resolution = match descriptor.split_at(descriptor.len().saturating_sub(1)) {
+                (width, "w") => width.parse().ok().map(Resolution::Width),
+                (density, "x") => density.parse().ok().map(Resolution::Density),
+                _ => None,
+            };
+            self.add(url, resolution.unwrap_or(Resolution::Density(1.0)));
+        }
+    }
+
+    fn add(&mut self, url: &str, resolution: Resolution) {
+        let url = url.trim();
+        if !url.is_empty() {
+            self.candidates.push((url.to_string(), resolution));
+        }
+    }
+
+    /// The source with the highest declared resolution, preferring real images over `data:` placeholders.
+    pub(crate) fn best(&self) -> Option<&str> {
+        self.candidates
+            .iter()
+            .max_by(|(a, a_resolution), (b, b_resolution)| {
+                let (a_rank, a_value) = a_resolution.rank();
+                let (b_rank, b_value) = b_resolution.rank();
+
+                (!is_placeholder(a), a_rank)
+                    .cmp(&(!is_placeholder(b), b_rank))
+                    .then(a_value.partial_cmp(&b_value).unwrap_or(Ordering::Equal))
+            })
+            .map(|(url, _)| url.as_str())
+    }
+
+    /// Every distinct source but `data:` placeholders, in document order.
+    #[cfg(feature = "segments")]
+    pub(crate) fn urls(&self) -> Vec<&str> {
+        let mut urls: Vec<&str> = Vec::new();
+        for (url, _) in &self.candidates {
+            if !is_placeholder(url) && !urls.contains(&url.as_str()) {
+                urls.push(url);
+            }
+        }
+        urls
+    }
+
+    /// An image segment of the best source, with its `alt` and `title` text, listing
+    /// every source under `candidates` if there are several.
+    #[cfg(feature = "segments")]
+    pub(crate) fn segment(&self) -> Option<Segment> {
+        let mut segment = Segment::image(self.best()?);
+
+        let urls = self.urls();
+        if urls.len() > 1 {
+            segment.data["candidates"] = Value::from(urls);
+        }
+        for (key, text) in [("alt", &self.alt), ("title", &self.title)] {
+            if let Some(text) = text {
+                segment.data[key] = Value::from(text.as_str());
+            }
+        }
+        Some(segment)
+    }
+}
+
+/// Keep the first non-blank description of an image.
+fn describe(description: &mut Option<String>, text: &str) {
+    let text = text.trim();
+    if description.is_none() && !text.is_empty() {
+        *description = Some(text.to_string());
+    }
+}
+
+fn is_placeholder(url: &str) -> bool {
+    url.starts_with("data:")
+}
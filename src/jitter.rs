@@ -0,0 +1,59 @@
+use std::time::{Duration, Instant};
+
+use crate::rt;
+
+#[cfg(any(feature = "segments", feature = "image"))]
+use tokio::sync::Mutex;
+
+/// A random delay between two bounds, so requests are not spaced like clockwork.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Jitter {
+    min: Duration,
+    max: Duration,
+}
+
+impl Jitter {
+    pub(crate) fn new(min: Duration, max: Duration) -> Self {
+        Self { min: min.min(max), max: min.max(max) }
+    }
+
+    /// Draw a delay, uniformly between the bounds.
+    pub(crate) fn sample(&self) -> Duration {
+        self.min + (self.max - self.min).mul_f64(fastrand::f64())
+    }
+
+    /// Wait for a freshly drawn delay.
+    pub(crate) async fn sleep(&self) {
+        rt::sleep_until(Instant::now() + self.sample()).await;
+    }
+}
+
+/// Spaces the starts of the submissions of a batch by random gaps.
+#[cfg(any(feature = "segments", feature = "image"))]
+#[derive(Debug)]
+pub(crate) struct Spacing {
+    jitter: Jitter,
+    next: Mutex<Instant>,
+}
+
+#[cfg(any(feature = "segments", feature = "image"))]
+impl Spacing {
+    pub(crate) fn new(jitter: Jitter) -> Self {
+        Self {
+            jitter,
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Wait until the next submission may start.
+    pub(crate) async fn wait(&self) {
+        let slot = {
+            let mut next = self.next.lock().await;
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.jitter.sample();
+            slot
+        };
+
+        rt::sleep_until(slot).await;
+    }
+}
@@ -0,0 +1,162 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::builder;
+use crate::client::ShindanClient;
+use crate::shindan_domain::ShindanDomain;
+
+/**
+How fast a domain answered [`ShindanClient::ping`].
+
+The breakdown comes from timing a DNS lookup, a request on a new connection and a
+request on the same connection once open: `connect` is what the new connection cost
+on top of the second request, i.e. the TCP and TLS handshakes.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DomainLatency {
+    pub domain: ShindanDomain,
+    /// Resolving the host of the domain.
+    pub dns: Option<Duration>,
+    /// Opening a connection: the TCP and, for HTTPS, TLS handshakes.
+    pub connect: Option<Duration>,
+    /// From sending a request on an open connection to receiving the headers of the response.
+    pub ttfb: Option<Duration>,
+    /// Why the domain could not be measured, if it could not.
+    pub error: Option<String>,
+}
+
+impl DomainLatency {
+    /// The time a first request to the domain takes until its first byte, if the domain was measured.
+    pub fn total(&self) -> Option<Duration> {
+        Some(self.dns? + self.connect? + self.ttfb?)
+    }
+}
+
+/// The latency of each domain measured by [`ShindanClient::ping`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyReport {
+    /// The domains, in the order they were measured.
+    pub domains: Vec<DomainLatency>,
+}
+
+impl LatencyReport {
+    /// The domain answering a first request the fastest, if any could be measured.
+    pub fn fastest(&self) -> Option<&DomainLatency> {
+        self.domains.iter().filter(|latency| latency.error.is_none()).min_by_key(|latency| latency.total())
+    }
+}
+
+impl fmt::Display for LatencyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for latency in &self.domains {
+            match (&latency.error, latency.dns, latency.connect, latency.ttfb) {
+                (None, Some(dns), Some(connect), Some(ttfb)) => writeln!(
+                    f,
+                    "{}: dns {:?}, connect {:?}, ttfb {:?}",
+                    latency.domain, dns, connect, ttfb
+                )?,
+                (error, ..) => writeln!(f, "{}: FAIL {}", latency.domain, error.as_deref().unwrap_or("not measured"))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ShindanClient {
+    /**
+    Measure the latency of the client's domain, its fallback domains and the other regional domains.
+
+    Each domain gets a DNS lookup and two `HEAD` requests to its home page, on a
+    connection of their own, counting towards the crawl budget like any request.
+    The client's domain is measured at its [`base_url`](crate::ShindanClientBuilder::base_url), if set.
+
+    # Returns
+    The latency of each domain, including the domains that failed.
+
+    # Examples
+    ```
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::En).unwrap();
+
+        let report = client.ping().await;
+        print!("{}", report);
+        if let Some(fastest) = report.fastest() {
+            println!("Fastest: {}", fastest.domain);
+        }
+    }
+    ```
+    */
+    pub async fn ping(&self) -> LatencyReport {
+        let mut domains = vec![self.domain.clone()];
+        for domain in self.fallback_domains.iter().chain(&ShindanDomain::ALL) {
+            if !domains.contains(domain) {
+                domains.push(domain.clone());
+            }
+        }
+
+        self.ping_domains(domains).await
+    }
+
+    /**
+    Measure the latency of some domains, see [`ping`](Self::ping).
+
+    # Arguments
+    - `domains` - The domains to measure, e.g. mirrors.
+
+    # Returns
+    The latency of each domain, in the same order.
+    */
+    pub async fn ping_domains(&self, domains: impl IntoIterator<Item = ShindanDomain>) -> LatencyReport {
+        let mut report = LatencyReport { domains: Vec::new() };
+        for domain in domains {
+            let mut latency = DomainLatency { domain, dns: None, connect: None, ttfb: None, error: None };
+            if let Err(err) = self.measure(&mut latency).await {
+                log::debug!("Failed to measure the latency of {}: {:#}", latency.domain, err);
+                latency.error = Some(format!("{:#}", err));
+            }
+            report.domains.push(latency);
+        }
+        report
+    }
+
+    async fn measure(&self, latency: &mut DomainLatency) -> Result<()> {
+        let url = match latency.domain == self.domain {
+            true => url::Url::parse(&self.base_url)?,
+            false => url::Url::parse(&latency.domain.to_string())?,
+        };
+        let host = url.host_str().context("The domain has no host")?;
+        let port = url.port_or_known_default().context("The domain has no port")?;
+        latency.dns = Some(resolve(host, port).await?);
+
+        // A client of its own, so that the first request opens a connection.
+        let client = builder::http_client(self.timeout, self.cookie_jar.clone())?;
+        let cold = self.time_head(&client, &url).await?;
+        let warm = self.time_head(&client, &url).await?;
+
+        latency.connect = Some(cold.saturating_sub(warm).saturating_sub(latency.dns.unwrap_or_default()));
+        latency.ttfb = Some(warm);
+        Ok(())
+    }
+
+    /// Time a `HEAD` request until the headers of its response.
+    async fn time_head(&self, client: &reqwest::Client, url: &url::Url) -> Result<Duration> {
+        let request = client.head(url.clone()).build()?;
+        self.wait_turn(&request).await;
+
+        let started = Instant::now();
+        client.execute(request).await?.error_for_status()?;
+        Ok(started.elapsed())
+    }
+}
+
+/// Time resolving a host.
+async fn resolve(host: &str, port: u16) -> Result<Duration> {
+    let started = Instant::now();
+    let _ = tokio::net::lookup_host((host, port)).await.with_context(|| format!("Failed to resolve {}", host))?;
+    Ok(started.elapsed())
+}
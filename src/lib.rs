@@ -11,7 +11,9 @@ A Rust library for interacting with [ShindanMaker].
 
 This library provides functionality to interact with various ShindanMaker domains, submit shindans, and parse results.
 
-- Asynchronous API (Tokio)
+- Asynchronous API (Tokio; the "timer-agnostic" feature frees the crate's own timers
+  and blocking work from it, but Tokio stays a dependency and the HTTP connections of
+  reqwest still need its reactor)
 - Multi-domain support (JP, EN, CN, KR, TH)
 - Easy shindan submission and result parsing
 
@@ -87,25 +89,200 @@ async fn main() {
 ```
 */
 
+mod auth;
 mod client;
+mod builder;
+mod config;
 mod selectors;
+mod form_cache;
+mod page;
+mod metadata;
+mod metrics;
+mod response_info;
+mod rate_limit;
+mod jitter;
+mod crawl_budget;
+mod circuit_breaker;
+mod rt;
+mod compression;
+#[cfg(any(feature = "html", feature = "archive"))]
+mod asset_cache;
+mod singleflight;
+mod site_day;
+#[cfg(feature = "segments")]
+mod batch;
+#[cfg(feature = "segments")]
+mod digest;
+#[cfg(feature = "segments")]
+mod fair_queue;
+#[cfg(feature = "segments")]
+mod diff;
+#[cfg(feature = "scores")]
+mod scores;
+#[cfg(feature = "archive")]
+mod archive;
+#[cfg(feature = "export")]
+mod export;
+#[cfg(feature = "segments")]
+mod result_cache;
+#[cfg(feature = "segments")]
+mod stats;
+#[cfg(feature = "streaming")]
+mod stream_parse;
+#[cfg(feature = "history")]
+mod history;
+mod metadata_cache;
+mod diagnose;
+mod latency;
+mod domain_fallback;
+mod search;
+mod fingerprint;
+mod fnv;
+mod task_pool;
 mod html_utils;
+mod image_source;
+#[cfg(feature = "segments")]
+mod media_source;
 mod http_utils;
 mod shindan_domain;
+mod shindan_id;
 #[cfg(feature = "segments")]
 mod segment;
+#[cfg(feature = "segments")]
+mod chart;
+#[cfg(feature = "segments")]
+mod table;
+#[cfg(feature = "segments")]
+mod result;
+#[cfg(feature = "segments")]
+mod translate;
+#[cfg(feature = "segments")]
+mod parse_options;
 #[cfg(feature = "html")]
 mod html_template;
+#[cfg(feature = "html")]
+mod site_assets;
+#[cfg(feature = "html")]
+mod fonts;
+#[cfg(feature = "html")]
+mod sanitize;
+#[cfg(feature = "feeds")]
+mod feed;
+#[cfg(feature = "notifier")]
+mod notifier;
+#[cfg(feature = "scheduler")]
+mod scheduler;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+#[cfg(feature = "cassette")]
+mod cassette;
+#[cfg(feature = "image")]
+mod image;
+#[cfg(feature = "animated")]
+mod animation;
+#[cfg(feature = "png")]
+mod png;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "segments")]
+mod render;
 
 pub use client::ShindanClient;
+pub use builder::ShindanClientBuilder;
+pub use config::{RateLimitConfig, ShindanConfig};
+pub use auth::{Credentials, RefreshFuture, SessionRefresher};
+pub use page::ShindanPage;
+pub use metadata::{ShindanMetadata, ShindanPreview};
+pub use selectors::SelectorConfig;
+pub use diagnose::{Diagnosis, DiagnosticStep};
+pub use latency::{DomainLatency, LatencyReport};
+pub use search::SearchHit;
+pub use circuit_breaker::CircuitOpen;
+pub use fingerprint::ContentFingerprint;
+pub use task_pool::{Priority, ShindanTaskPool, TaskKind};
+pub use metrics::{MetricsHook, ResponseMetrics};
+pub use response_info::ResponseInfo;
 pub use shindan_domain::ShindanDomain;
+pub use shindan_id::ShindanId;
+pub use site_day::{site_day, site_today};
 #[cfg(feature = "segments")]
-pub use segment::{Segment, Segments};
+pub use segment::{LenientSegments, Segment, Segments};
+#[cfg(feature = "segments")]
+pub use chart::{Chart, ChartDataset, ChartOverrides};
+#[cfg(feature = "segments")]
+pub use table::TableSegment;
+#[cfg(feature = "segments")]
+pub use result::{ShindanResult, RESULT_SCHEMA_VERSION};
+#[cfg(feature = "segments")]
+pub use digest::{Digest, DigestEntry, DigestGroup};
+#[cfg(feature = "segments")]
+pub use fair_queue::{FairQueue, QueueFull};
+#[cfg(feature = "segments")]
+pub use diff::{diff_results, ResultDiff, SegmentChange};
+#[cfg(feature = "scores")]
+pub use scores::ScoreRule;
+#[cfg(feature = "archive")]
+pub use archive::Archive;
+#[cfg(feature = "export")]
+pub use export::{write_csv, ExportRow};
+#[cfg(feature = "parquet")]
+pub use export::write_parquet;
+#[cfg(feature = "html")]
+pub use site_assets::{AssetVersions, SiteAssets};
+#[cfg(feature = "html")]
+pub use fonts::{FontFile, FontStack};
+#[cfg(feature = "image")]
+pub use image::ScreenshotOptions;
+#[cfg(all(feature = "image", feature = "segments"))]
+pub use image::render_segments_to_image;
+#[cfg(feature = "animated")]
+pub use animation::AnimationOptions;
+#[cfg(feature = "segments")]
+pub use translate::Translator;
+#[cfg(feature = "segments")]
+pub use parse_options::ParseOptions;
+#[cfg(feature = "segments")]
+pub use result_cache::{CacheKey, MemoryCache, ResultCache};
+#[cfg(feature = "segments")]
+pub use stats::{ChartStat, StatsReport, TextVariant};
+#[cfg(feature = "segments")]
+pub use render::{format_result, ImagePlaceholder, PlainTextOptions, ShareOptions, SharePayload};
+#[cfg(feature = "segments")]
+pub use render::{ChunkStrategy, EmojiStyle, ProblemChars, UnicodeOptions};
+#[cfg(feature = "history")]
+pub use history::{HistoryEntry, HistoryStore, MemoryHistory};
+#[cfg(feature = "history-sqlite")]
+pub use history::SqliteHistory;
+#[cfg(feature = "cassette")]
+pub use cassette::{Cassette, CassetteMode, Interaction};
+#[cfg(feature = "satori")]
+pub use render::SatoriElement;
+#[cfg(feature = "telegram")]
+pub use render::{TelegramMessage, TelegramPhoto, TELEGRAM_MESSAGE_LIMIT};
+#[cfg(feature = "matrix")]
+pub use render::MatrixMessage;
+#[cfg(feature = "tui")]
+pub use render::{ImageProtocol, TerminalOptions};
+#[cfg(feature = "email")]
+pub use render::{render_email, EmailOptions, EmailSection};
+#[cfg(all(feature = "segments", feature = "html"))]
+pub use render::CardTheme;
+#[cfg(feature = "feeds")]
+pub use feed::{AtomFeed, FeedEntry, FeedSource, FeedState};
+#[cfg(feature = "notifier")]
+pub use notifier::{Alert, Notifier, WatchTarget, Webhook, WebhookFormat};
+#[cfg(feature = "scheduler")]
+pub use scheduler::{DailyScheduler, DeliveryFuture};
 
 #[cfg(test)]
 mod tests {
-    use crate::{ShindanClient, ShindanDomain};
-    use tokio;
+    use crate::{ShindanClient, ShindanDomain, ShindanId};
 
     #[tokio::test]
     async fn test_get_title() {
@@ -144,4 +321,2762 @@ mod tests {
 
         assert_eq!("Fantasy Stats", title);
     }
-}
\ No newline at end of file
+
+    /// Generate one end-to-end test per domain, since the mirrors differ subtly.
+    ///
+    /// They hit the live site, so they only run with `cargo test -- --ignored`.
+    macro_rules! domain_parity_tests {
+        ($($name:ident: $domain:ident,)*) => {
+            $(
+                #[tokio::test]
+                #[ignore = "requires network access"]
+                async fn $name() {
+                    ShindanClient::verify_domain(ShindanDomain::$domain).await.unwrap();
+                }
+            )*
+        };
+    }
+
+    #[test]
+    fn test_domain_round_trip() {
+        for domain in ShindanDomain::ALL {
+            assert_eq!(domain, domain.as_str().to_uppercase().parse().unwrap());
+            assert_eq!(format!("https://{}/", domain.host()), domain.to_string());
+            assert_eq!(domain, serde_json::from_value(serde_json::to_value(&domain).unwrap()).unwrap());
+        }
+
+        let domains: std::collections::BTreeSet<_> = ShindanDomain::ALL.into_iter().collect();
+        assert_eq!(ShindanDomain::ALL.len(), domains.len());
+    }
+
+    domain_parity_tests! {
+        test_domain_parity_jp: Jp,
+        test_domain_parity_en: En,
+        test_domain_parity_cn: Cn,
+        test_domain_parity_kr: Kr,
+        test_domain_parity_th: Th,
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_domain_parity_offline() {
+        use crate::test_util::{MockShindan, FIXTURES};
+
+        let server = MockShindan::start().await;
+        for domain in ShindanDomain::ALL {
+            let client = ShindanClient::builder(domain.clone())
+                .base_url(&format!("{}/", server.uri()))
+                .polite(false)
+                .build()
+                .unwrap();
+
+            for fixture in FIXTURES {
+                let diagnosis = client.diagnose(fixture.id).await.unwrap();
+                assert!(diagnosis.is_healthy(), "{:?} {}: {}", domain, fixture.id, diagnosis);
+            }
+        }
+    }
+
+    #[cfg(feature = "segments")]
+    #[test]
+    fn test_truncate() {
+        use crate::{Segment, Segments};
+
+        let segments = Segments(vec![
+            Segment::text("Hi "),
+            Segment::link("link", "https://example.com"),
+            Segment::text(" é👍🏽 tail"),
+        ]);
+
+        assert_eq!("", segments.truncate_chars(0).to_string());
+        assert_eq!("", segments.truncate_graphemes(0).to_string());
+        assert!(Segments(vec![Segment::image("https://example.com/a.png")]).truncate_chars(0).is_empty());
+        assert_eq!("…", segments.truncate_chars(1).to_string());
+        assert_eq!(segments, segments.truncate_chars(100));
+
+        // A link that does not fit is dropped with everything after it.
+        assert_eq!(vec![Segment::text("Hi…")], segments.truncate_chars(6).0);
+        assert_eq!(vec![segments[0].clone(), segments[1].clone(), Segment::text("…")], segments.truncate_chars(8).0);
+
+        // The thumbs up and its skin tone are two chars but one grapheme, never split.
+        assert_eq!(Some(" é…"), segments.truncate_chars(11)[2].as_str());
+        assert_eq!(Some(" é👍🏽…"), segments.truncate_graphemes(12)[2].as_str());
+    }
+
+    #[cfg(feature = "segments")]
+    #[test]
+    fn test_unicode_options() {
+        use crate::{EmojiStyle, ProblemChars, UnicodeOptions};
+
+        let family = "👨\u{200D}👩\u{200D}👧";
+        let heart = "❤\u{FE0F}";
+        let variant = "葛\u{E0100}";
+        let text = format!("\u{FEFF}{} {} {}\u{200B}!\u{202E}\u{3000}end", family, heart, variant);
+
+        let defaults = UnicodeOptions::default();
+        assert_eq!(format!("{} {} {}! end", family, heart, variant), defaults.apply(&text));
+
+        let keep = UnicodeOptions { problem_chars: ProblemChars::Keep, fullwidth_spaces: false, ..Default::default() };
+        assert_eq!(text, keep.apply(&text));
+
+        let replace = UnicodeOptions { problem_chars: ProblemChars::Replace("?".to_string()), ..Default::default() };
+        assert_eq!("?a?b c", replace.apply("\u{200B}a\u{2066}b c"));
+
+        let shortcodes = UnicodeOptions { emoji: EmojiStyle::Shortcode, ..Default::default() };
+        assert_eq!(":1f468::1f469::1f467: :heart: ☆", shortcodes.apply(&format!("{} {} ☆", family, heart)));
+
+        let strip = UnicodeOptions { emoji: EmojiStyle::Strip, ..Default::default() };
+        assert_eq!(" (＾▽＾)☆ ", strip.apply(&format!("{} (＾▽＾)☆ {}", family, heart)));
+    }
+
+    #[cfg(feature = "onebot")]
+    #[tokio::test]
+    async fn test_onebot_v12_uploads_images() {
+        use serde_json::json;
+        use crate::{Segment, Segments};
+
+        let segments = Segments(vec![
+            Segment::text("Lucky"),
+            Segment::image("https://example.com/a.png"),
+            Segment::link("more", "https://example.com"),
+        ]);
+
+        let mut uploads = Vec::new();
+        let message = segments.to_onebot_v12(|url| {
+            uploads.push(url);
+            let file_id = format!("file-{}", uploads.len());
+            async move { Ok(file_id) }
+        }).await.unwrap();
+
+        assert_eq!(vec!["https://example.com/a.png"], uploads);
+        assert_eq!(json!([
+            {"type": "text", "data": {"text": "Lucky"}},
+            {"type": "image", "data": {"file_id": "file-1"}},
+            {"type": "text", "data": {"text": "more (https://example.com)"}},
+        ]), message);
+
+        let failed = segments.to_onebot_v12(|_| async { Err(anyhow::anyhow!("upload_file failed")) }).await;
+        assert!(format!("{:#}", failed.unwrap_err()).contains("upload_file failed"));
+        assert_eq!(json!([]), Segments(Vec::new()).to_onebot_v12(|_| async { Ok(String::new()) }).await.unwrap());
+    }
+
+    #[cfg(feature = "telegram")]
+    #[test]
+    fn test_telegram_chunks() {
+        use serde_json::json;
+        use crate::{Segment, Segments, TELEGRAM_MESSAGE_LIMIT};
+
+        let line = format!("{}\n", "a".repeat(3000));
+        let segments = Segments(vec![Segment::new("text", json!({ "text": line.repeat(3) }))]);
+
+        let message = segments.to_telegram_html();
+
+        assert_eq!(3, message.chunks.len());
+        assert!(message.chunks.iter().all(|chunk| chunk.chars().count() <= TELEGRAM_MESSAGE_LIMIT));
+
+        // Each die is two UTF-16 code units, as Telegram counts them.
+        let dice = "🎲".repeat(3000);
+        let message = Segments(vec![Segment::text(dice.as_str())]).to_telegram_html();
+
+        assert_eq!(2, message.chunks.len());
+        assert_eq!(TELEGRAM_MESSAGE_LIMIT, message.chunks[0].encode_utf16().count());
+        assert_eq!(dice, message.chunks.concat());
+    }
+
+    #[cfg(feature = "slack")]
+    #[test]
+    fn test_slack_blocks() {
+        use serde_json::json;
+        use crate::{Segment, Segments, ShindanDomain, ShindanResult};
+
+        let segments = Segments(vec![
+            Segment::text("<!channel> R&D "),
+            Segment::link("<more>", "https://example.com/?a=1&b=<2>|3"),
+            Segment::image("https://example.com/a.png"),
+            Segment::text("a".repeat(3001)),
+        ]);
+        let blocks = ShindanResult::new("1222992", "test_user", ShindanDomain::En, "Fantasy Stats", segments).to_slack_blocks();
+
+        assert_eq!(json!({"type": "header", "text": {"type": "plain_text", "text": "Fantasy Stats", "emoji": true}}), blocks[0]);
+        assert_eq!("&lt;!channel&gt; R&amp;D <https://example.com/?a=1&amp;b=&lt;2&gt;%7C3|&lt;more&gt;>", blocks[1]["text"]["text"]);
+        assert_eq!(json!({"type": "image", "image_url": "https://example.com/a.png", "alt_text": "Fantasy Stats"}), blocks[2]);
+        assert_eq!(3000, blocks[3]["text"]["text"].as_str().unwrap().len());
+        assert_eq!("a", blocks[4]["text"]["text"]);
+        assert_eq!(5, blocks.as_array().unwrap().len());
+
+        let empty = ShindanResult::new("1222992", "test_user", ShindanDomain::En, "Fantasy Stats", Segments(Vec::new()));
+        assert_eq!(1, empty.to_slack_blocks().as_array().unwrap().len());
+    }
+
+    #[cfg(feature = "matrix")]
+    #[test]
+    fn test_matrix_message() {
+        use crate::{Segment, Segments};
+
+        let segments = Segments(vec![
+            Segment::text("<b>A&B</b>\n"),
+            Segment::link("more <3", r#"https://example.com/?a=1&b="2""#),
+            Segment::image("https://example.com/a.png"),
+            Segment::image("https://example.com/b.png"),
+        ]);
+        let message = segments.to_matrix_message();
+
+        assert_eq!("<b>A&B</b>\nhttps://example.com/?a=1&b=\"2\"", message.body);
+        assert_eq!(
+            r#"&lt;b&gt;A&amp;B&lt;/b&gt;<br/><a href="https://example.com/?a=1&amp;b=&quot;2&quot;">more &lt;3</a>"#,
+            message.formatted_body
+        );
+        assert_eq!(vec!["https://example.com/a.png", "https://example.com/b.png"], message.images);
+
+        let empty = Segments(Vec::new()).to_matrix_message();
+        assert_eq!(("", "", 0), (empty.body.as_str(), empty.formatted_body.as_str(), empty.images.len()));
+        assert_eq!("m.text", empty.msgtype);
+    }
+
+    #[cfg(feature = "segments")]
+    #[test]
+    fn test_markdown() {
+        use crate::{Segment, Segments, ShindanDomain, ShindanResult};
+
+        let segments = Segments(vec![
+            Segment::text("*A* <b>#1</b>\n"),
+            Segment::link("[x]_y", "https://example.com/a"),
+            Segment::image("https://example.com/a.png"),
+        ]);
+        assert_eq!(
+            "\\*A\\* \\<b\\>\\#1\\</b\\>\\\n[\\[x\\]\\_y](https://example.com/a)![image](https://example.com/a.png)",
+            segments.to_markdown()
+        );
+        assert_eq!("", Segments(Vec::new()).to_markdown());
+
+        let mut image = Segment::image("https://example.com/a (1).png");
+        image.data["alt"] = "a [fox]".into();
+        let segments = Segments(vec![Segment::link("wiki", "https://example.com/Fox_(animal)"), image]);
+        assert_eq!(
+            "[wiki](https://example.com/Fox_\\(animal\\))![a \\[fox\\]](https://example.com/a%20\\(1\\).png)",
+            segments.to_markdown()
+        );
+
+        let result = ShindanResult::new("1222992", "test_user", ShindanDomain::En, "*Stats*", Segments(vec![Segment::text("STR: 10\n\n")]));
+        assert_eq!("# \\*Stats\\*\n\nSTR: 10\n", result.to_markdown(false));
+        assert!(result.to_markdown(true).starts_with("---\ntitle: \"*Stats*\"\nid: \"1222992\"\n"));
+    }
+
+    #[cfg(all(feature = "onebot", feature = "satori", feature = "telegram", feature = "slack", feature = "matrix", feature = "bbcode", feature = "tui"))]
+    #[test]
+    fn test_tables_in_chat_output() {
+        use serde_json::json;
+        use crate::{Segment, Segments, ShindanDomain, ShindanResult, TableSegment, TerminalOptions};
+
+        let table = TableSegment { headers: vec!["Stat".to_string()], rows: vec![vec!["STR".to_string()]] };
+        let segments = Segments(vec![Segment::text("Stats"), Segment::table(table)]);
+        let text = "Stats\nStat\n----\nSTR\n";
+
+        assert_eq!(text, segments.to_string());
+        assert_eq!(json!([{"type": "text", "data": {"text": "Stats"}}, {"type": "text", "data": {"text": "\nStat\n----\nSTR\n"}}]), segments.to_onebot_v11());
+        assert_eq!(text, segments.to_satori_xhtml());
+        assert_eq!(vec![text], segments.to_telegram_html().chunks);
+        assert_eq!("Stats\n```\nStat\n----\nSTR\n```\n", segments.to_markdown());
+        assert_eq!("Stats\n[code]Stat\n----\nSTR[/code]\n", segments.to_bbcode());
+
+        let message = segments.to_matrix_message();
+        assert_eq!(text, message.body);
+        assert_eq!("Stats<table><thead><tr><th>Stat</th></tr></thead><tbody><tr><td>STR</td></tr></tbody></table>", message.formatted_body);
+
+        let result = ShindanResult::new("1222992", "test_user", ShindanDomain::En, "Fantasy Stats", segments);
+        assert_eq!("Stats\n```Stat\n----\nSTR```\n", result.to_slack_blocks()[1]["text"]["text"]);
+        let options = TerminalOptions { color: false, image_protocol: None, ..Default::default() };
+        assert_eq!(format!("Fantasy Stats\n\n{}", text), result.to_ansi(&options));
+    }
+
+    #[cfg(all(feature = "onebot", feature = "satori", feature = "telegram", feature = "slack", feature = "matrix", feature = "bbcode", feature = "tui"))]
+    #[tokio::test]
+    async fn test_media_in_chat_output() {
+        use serde_json::json;
+        use crate::{PlainTextOptions, Segment, Segments, ShindanDomain, ShindanResult, TerminalOptions};
+
+        let video = "https://example.com/a.mp4";
+        let audio = "https://example.com/a.mp3";
+        let segments = Segments(vec![Segment::video(video), Segment::audio(audio)]);
+
+        assert_eq!(format!("{}{}", video, audio), segments.to_string());
+        assert_eq!(format!("{}{}", video, audio), segments.to_plain_text(&PlainTextOptions::default()));
+        assert_eq!(json!([
+            {"type": "video", "data": {"file": video}},
+            {"type": "record", "data": {"file": audio}},
+        ]), segments.to_onebot_v11());
+        assert_eq!(format!("[CQ:video,file={}][CQ:record,file={}]", video, audio), segments.to_cq_string());
+        let message = segments.to_onebot_v12(|url| async move { Ok(format!("uploaded:{}", url)) }).await.unwrap();
+        assert_eq!(json!([
+            {"type": "video", "data": {"file_id": format!("uploaded:{}", video)}},
+            {"type": "audio", "data": {"file_id": format!("uploaded:{}", audio)}},
+        ]), message);
+        assert_eq!(format!("<video src=\"{}\"/><audio src=\"{}\"/>", video, audio), segments.to_satori_xhtml());
+        assert_eq!(vec![format!("<a href=\"{0}\">{0}</a><a href=\"{1}\">{1}</a>", video, audio)], segments.to_telegram_html().chunks);
+        assert_eq!(format!("[{0}]({0})[{1}]({1})", video, audio), segments.to_markdown());
+        assert_eq!(format!("[url]{}[/url][url]{}[/url]", video, audio), segments.to_bbcode());
+        assert_eq!(format!("{}{}", video, audio), segments.to_matrix_message().body);
+
+        let result = ShindanResult::new("1222992", "test_user", ShindanDomain::En, "Fantasy Stats", segments);
+        assert_eq!(format!("<{0}|{0}><{1}|{1}>", video, audio), result.to_slack_blocks()[1]["text"]["text"]);
+        let options = TerminalOptions { color: false, image_protocol: None, ..Default::default() };
+        assert!(result.to_ansi(&options).ends_with(&format!("\x1b]8;;{}\x1b\\[audio]\x1b]8;;\x1b\\", audio)));
+    }
+
+    #[cfg(feature = "segments")]
+    #[test]
+    fn test_plain_text() {
+        use crate::{ImagePlaceholder, PlainTextOptions, Segment, Segments};
+
+        let segments = Segments(vec![
+            Segment::text("<b>A&amp;B</b> "),
+            Segment::link("more", "https://example.com/a"),
+            Segment::text("\n"),
+            Segment::image("https://example.com/a.png"),
+        ]);
+        let text = |image| segments.to_plain_text(&PlainTextOptions { image, ..Default::default() });
+
+        // Plain text is sent as it is, so markup in results is left alone.
+        assert_eq!("<b>A&amp;B</b> https://example.com/a\n[image]", text(ImagePlaceholder::Text("[image]".to_string())));
+        assert_eq!("<b>A&amp;B</b> https://example.com/a\nhttps://example.com/a.png", text(ImagePlaceholder::Url));
+        assert_eq!("<b>A&amp;B</b> https://example.com/a", text(ImagePlaceholder::Omit));
+        assert_eq!("", Segments(Vec::new()).to_plain_text(&PlainTextOptions::default()));
+    }
+
+    #[cfg(feature = "segments")]
+    #[test]
+    fn test_plain_text_wraps_nested_lists() {
+        use crate::{PlainTextOptions, Segment, Segments};
+
+        let segments = Segments(vec![
+            Segment::text("- Stats\n"),
+            Segment::text("  - Strength is very high\n"),
+            Segment::text("Luck: excellent in every way"),
+        ]);
+        let text = segments.to_plain_text(&PlainTextOptions { max_width: Some(12), ..Default::default() });
+
+        assert_eq!("- Stats\n  - Strength\n  is very\n  high\nLuck:\nexcellent in\nevery way", text);
+    }
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn test_ansi() {
+        use serde_json::json;
+        use crate::{Chart, ChartDataset, Segment, Segments, ShindanDomain, ShindanResult, TerminalOptions};
+
+        let segments = Segments(vec![
+            Segment::text("\x1b[2JLucky\x07\n"),
+            Segment::new("text", json!({"text": "SSR", "bold": true, "color": "#f00"})),
+            Segment::link("more\x1b]8;;", "https://example.com/\x1b"),
+            Segment::image("https://example.com/a.png"),
+        ]);
+        let result = ShindanResult::new("1222992", "test_user", ShindanDomain::En, "Stats\x1b[0m", segments);
+        let options = |color| TerminalOptions { color, image_protocol: None, ..Default::default() };
+
+        assert_eq!(
+            concat!(
+                "Stats[0m\n\n[2JLucky\nSSR",
+                "\x1b]8;;https://example.com/\x1b\\more]8;;\x1b]8;;\x1b\\",
+                "\x1b]8;;https://example.com/a.png\x1b\\[image]\x1b]8;;\x1b\\",
+            ),
+            result.to_ansi(&options(false))
+        );
+        assert!(result.to_ansi(&options(true)).contains("\x1b[1;38;2;255;0;0mSSR\x1b[0m"));
+
+        let empty = ShindanResult::new("1222992", "test_user", ShindanDomain::En, "Stats", Segments(Vec::new()));
+        assert_eq!("\x1b[1mStats\x1b[0m\n\n", empty.to_ansi(&options(true)));
+
+        let parsed = Segments::from_html(r#"<span id="post_display"><strong style="color: #00ff00">SSR</strong></span>"#).unwrap();
+        let styled = ShindanResult::new("1222992", "test_user", ShindanDomain::En, "Stats", parsed);
+        assert!(styled.to_ansi(&options(true)).contains("\x1b[1;38;2;0;255;0mSSR\x1b[0m"));
+
+        let mut charted = empty.clone();
+        charted.chart = Some(Chart {
+            kind: "bar".to_string(),
+            labels: vec!["STR\x1b[2J".to_string()],
+            datasets: vec![ChartDataset { label: Some("\x1b]0;title\x07Stats".to_string()), data: vec![1.0] }],
+        });
+        let narrow = TerminalOptions { chart_width: 1, ..options(false) };
+        assert_eq!("Stats\n\n\n\n]0;titleStats\nSTR[2J █ 1\n", charted.to_ansi(&narrow));
+    }
+
+    #[cfg(all(feature = "tui", feature = "test-util"))]
+    #[tokio::test]
+    async fn test_render_terminal_links_images_kitty_cannot_display() {
+        use wiremock::{Mock, ResponseTemplate};
+        use wiremock::matchers::{method, path};
+        use crate::{ImageProtocol, Segment, Segments, ShindanDomain, ShindanResult, TerminalOptions};
+        use crate::test_util::MockShindan;
+
+        let server = MockShindan::start().await;
+        for (route, body) in [("/a.png", &b"\x89PNG\r\n\x1a\npng"[..]), ("/a.jpeg", &b"\xff\xd8\xffjpeg"[..])] {
+            Mock::given(method("GET"))
+                .and(path(route))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(body))
+                .mount(server.server())
+                .await;
+        }
+
+        let png = format!("{}/a.png", server.uri());
+        let jpeg = format!("{}/a.jpeg", server.uri());
+        let segments = Segments(vec![Segment::image(png.as_str()), Segment::image(jpeg.as_str())]);
+        let result = ShindanResult::new("1222992", "test_user", ShindanDomain::En, "Stats", segments);
+        let options = TerminalOptions { color: false, image_protocol: Some(ImageProtocol::Kitty), ..Default::default() };
+
+        let output = server.client().unwrap().render_terminal(&result, &options).await.unwrap();
+        assert!(output.contains(&format!("\n{}\n", ImageProtocol::Kitty.encode(b"\x89PNG\r\n\x1a\npng"))));
+        assert!(output.contains(&format!("\x1b]8;;{}\x1b\\[image]", jpeg)));
+    }
+
+    #[cfg(feature = "bbcode")]
+    #[test]
+    fn test_bbcode() {
+        use serde_json::json;
+        use crate::{Segment, Segments};
+
+        let segments = Segments(vec![
+            Segment::new("text", json!({"text": "SSR", "color": "red]x[/color"})),
+            Segment::new("text", json!({"text": " <b>&</b> ", "bold": true, "color": "#0f0"})),
+            Segment::link("more", "https://example.com/?a[]=1]"),
+            Segment::image("https://example.com/a[1].png"),
+        ]);
+
+        assert_eq!(
+            concat!(
+                "SSR[b][color=#0f0] <b>&</b> [/color][/b]",
+                "[url=https://example.com/?a%5B%5D=1%5D]more[/url][img]https://example.com/a%5B1%5D.png[/img]",
+            ),
+            segments.to_bbcode()
+        );
+        assert_eq!("", Segments(Vec::new()).to_bbcode());
+
+        let parsed = Segments::from_html(r#"<span id="post_display">Rank: <b style="color: #f00">SSR</b><span>!</span></span>"#).unwrap();
+        assert_eq!("Rank: [b][color=#f00]SSR[/color][/b]!", parsed.to_bbcode());
+    }
+
+    #[cfg(feature = "segments")]
+    #[test]
+    fn test_result_json() {
+        use crate::{Segment, Segments, ShindanDomain, ShindanResult};
+
+        let segments = Segments(vec![
+            Segment::text("\"Quoted\" \\ <b>\n☆🎲"),
+            Segment::link("more", "https://example.com/?a=1&b=2"),
+            Segment::image("https://example.com/a.png"),
+        ]);
+        let result = ShindanResult::new("1222992", "test_\"user\"", ShindanDomain::En, "Fantasy Stats", segments);
+
+        let json = result.to_json().unwrap();
+        assert!(json.contains("\"version\": 1"));
+        assert_eq!(result, ShindanResult::from_json(&json).unwrap());
+
+        let empty = ShindanResult::new("1222992", "test_user", ShindanDomain::En, "", Segments(Vec::new()));
+        assert_eq!(empty, ShindanResult::from_json(&empty.to_json().unwrap()).unwrap());
+
+        let newer = json.replacen("\"version\": 1", "\"version\": 2", 1);
+        assert!(ShindanResult::from_json(&newer).unwrap_err().to_string().contains("version 2"));
+        assert!(ShindanResult::from_json("{}").is_err());
+    }
+
+    #[cfg(feature = "segments")]
+    #[test]
+    fn test_format_result() {
+        use crate::{format_result, Segment, Segments, ShindanDomain, ShindanResult};
+
+        let segments = Segments(vec![
+            Segment::text("<b>Lucky</b> "),
+            Segment::link("more", "https://example.com/more"),
+            Segment::image("https://example.com/a.png"),
+            Segment::image("https://example.com/b.png"),
+        ]);
+        let result = ShindanResult::new("1222992", "{{title}}", ShindanDomain::En, "Fantasy Stats", segments);
+
+        // Values are inserted as they are, without being expanded again.
+        assert_eq!("{{title}}: <b>Lucky</b> https://example.com/more", format_result("{{name}}: {{ text }}", &result).unwrap());
+        assert_eq!("https://example.com/a.png\nhttps://example.com/b.png", format_result("{{images}}", &result).unwrap());
+        assert_eq!("https://example.com/a.png", format_result("{{first_image}}", &result).unwrap());
+
+        let empty = ShindanResult::new("1222992", "test_user", ShindanDomain::En, "Fantasy Stats", Segments(Vec::new()));
+        assert_eq!("[||]", format_result("[{{text}}|{{first_image}}|{{images}}]", &empty).unwrap());
+        assert_eq!("", format_result("", &empty).unwrap());
+
+        assert!(format_result("{{text", &empty).unwrap_err().to_string().contains("Unterminated"));
+        assert!(format_result("{{score}}", &empty).unwrap_err().to_string().contains("`score`"));
+        assert!(format_result("{{text | shout}}", &empty).unwrap_err().to_string().contains("`shout`"));
+    }
+
+    #[cfg(feature = "segments")]
+    #[test]
+    fn test_chunk_bytes() {
+        use crate::{ChunkStrategy, Segment, Segments};
+
+        let segments = Segments(vec![Segment::text("運勢は大吉です。".repeat(50)), Segment::image("https://example.com/a.png")]);
+
+        let chunks = segments.chunk(100, ChunkStrategy::Bytes);
+
+        assert!(chunks.iter().all(|chunk| chunk.to_string().len() <= 100));
+        assert_eq!(segments.to_string(), chunks.iter().map(|chunk| chunk.to_string()).collect::<String>());
+        assert_eq!(Some(&Segment::image("https://example.com/a.png")), chunks.last().unwrap().last());
+    }
+
+    #[cfg(feature = "segments")]
+    #[test]
+    fn test_share_payload_fits_post_limit() {
+        use serde_json::json;
+        use crate::{Segment, Segments, ShindanResult};
+
+        let result = ShindanResult::new(
+            "1222992",
+            "test_user",
+            ShindanDomain::Jp,
+            "診断",
+            Segments(vec![Segment::new("text", json!({ "text": "あ".repeat(200) }))]),
+        );
+
+        let payload = result.share_payload();
+
+        assert!(payload.text.ends_with('…'));
+        assert!(payload.text.chars().count() * 2 + "\n#shindanmaker\n".len() + 23 <= 280);
+    }
+
+    #[cfg(feature = "segments")]
+    #[tokio::test]
+    async fn test_memory_cache_evicts_least_recently_used() {
+        use chrono::{Duration, Utc};
+        use crate::{CacheKey, MemoryCache, ResultCache, Segments, ShindanResult};
+
+        let cache = MemoryCache::new(2);
+        let now = Utc::now();
+        let expires_at = now + Duration::hours(1);
+        let key = |name| CacheKey::new(ShindanDomain::En, "1222992", name, now);
+        let result = |name| ShindanResult::new("1222992", name, ShindanDomain::En, "Fantasy Stats", Segments(vec![]));
+
+        cache.put(key("a"), result("a"), expires_at).await.unwrap();
+        cache.put(key("b"), result("b"), expires_at).await.unwrap();
+        cache.get(&key("a")).await.unwrap();
+        cache.put(key("c"), result("c"), expires_at).await.unwrap();
+
+        assert!(cache.get(&key("a")).await.unwrap().is_some());
+        assert!(cache.get(&key("b")).await.unwrap().is_none());
+        assert!(cache.get(&key("c")).await.unwrap().is_some());
+
+        cache.put(key("c"), result("c"), now - Duration::seconds(1)).await.unwrap();
+        assert!(cache.get(&key("c")).await.unwrap().is_none());
+        assert_eq!(1, cache.len());
+
+        cache.put(key("d"), result("d"), expires_at).await.unwrap();
+        cache.put(key("e"), result("e"), expires_at).await.unwrap();
+        assert!(cache.get(&key("a")).await.unwrap().is_none());
+        assert_eq!(2, cache.len());
+    }
+
+    #[cfg(all(feature = "test-util", feature = "html"))]
+    #[tokio::test]
+    async fn test_result_with_html_submits_once() {
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let (result, html) = server.client().unwrap().get_result_with_html(PLAIN_TEXT.id, "test_user").await.unwrap();
+
+        assert_eq!(PLAIN_TEXT.title, result.title);
+        assert!(html.contains("test_user"));
+        let requests = server.server().received_requests().await.unwrap();
+        assert_eq!(1, requests.iter().filter(|request| request.method.as_str() == "POST").count());
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_search() {
+        use wiremock::matchers::{method, path, query_param};
+        use wiremock::{Mock, ResponseTemplate};
+        use crate::SearchHit;
+        use crate::test_util::MockShindan;
+
+        let server = MockShindan::start().await;
+        Mock::given(method("GET"))
+            .and(path("/list/search"))
+            .and(query_param("q", "fantasy stats"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(include_str!("test_util/fixtures/search.html"), "text/html; charset=UTF-8"))
+            .mount(server.server())
+            .await;
+
+        let client = server.client().unwrap();
+        let hits = client.search("fantasy stats").await.unwrap();
+        let hit = |id: &str, title: &str| SearchHit { id: id.to_string(), title: title.to_string(), domain: client.domain().clone() };
+        assert_eq!(vec![hit("1222992", "Fantasy Stats"), hit("1000001", "Your Spirit Animal"), hit("1000002", "Battle Stats")], hits);
+
+        let requests = server.server().received_requests().await.unwrap();
+        assert_eq!(Some("q=fantasy+stats"), requests[0].url.query());
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_batch_uses_result_cache() {
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let posts = || async {
+            server.server().received_requests().await.unwrap().iter().filter(|request| request.method.as_str() == "POST").count()
+        };
+
+        let client = server.builder().result_cache().build().unwrap();
+        let cached = client.get_result(PLAIN_TEXT.id, "Alice").await.unwrap();
+        assert_eq!(1, posts().await);
+
+        let results = client.get_segments_batch(PLAIN_TEXT.id, &["Alice", "Bob"], 2).await.unwrap();
+        let results: Vec<_> = results.into_iter().map(Result::unwrap).collect();
+        assert_eq!(cached.segments, results[0]);
+        assert_eq!(2, posts().await);
+
+        let stored = client.already_run_today(PLAIN_TEXT.id, "Bob").await.unwrap().unwrap();
+        assert_eq!(results[1], stored.segments);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_already_run_today() {
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let requests = || async { server.server().received_requests().await.unwrap().len() };
+
+        let client = server.client().unwrap();
+        assert!(client.already_run_today(PLAIN_TEXT.id, "test_user").await.unwrap().is_none());
+
+        let client = server.builder().result_cache().build().unwrap();
+        assert!(client.already_run_today(PLAIN_TEXT.id, "test_user").await.unwrap().is_none());
+        let result = client.get_result(PLAIN_TEXT.id, "test_user").await.unwrap();
+        let sent = requests().await;
+
+        let replayed = client.already_run_today(PLAIN_TEXT.id, "test_user").await.unwrap().unwrap();
+        assert_eq!(result.segments, replayed.segments);
+        assert!(client.already_run_today(PLAIN_TEXT.id, "other_user").await.unwrap().is_none());
+        assert_eq!(sent, requests().await);
+
+        #[cfg(feature = "history")]
+        {
+            use crate::MemoryHistory;
+
+            let client = server.builder().history(MemoryHistory::default()).build().unwrap();
+            assert!(client.already_run_today(PLAIN_TEXT.id, "test_user").await.unwrap().is_none());
+            let result = client.get_result(PLAIN_TEXT.id, "test_user").await.unwrap();
+            let replayed = client.already_run_today(PLAIN_TEXT.id, "test_user").await.unwrap().unwrap();
+            assert_eq!(result.segments, replayed.segments);
+        }
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_metadata_revalidation() {
+        use std::time::Duration;
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, ResponseTemplate};
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let route = format!("/{}", PLAIN_TEXT.id);
+        Mock::given(method("GET"))
+            .and(path(route.as_str()))
+            .and(header("if-none-match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .with_priority(1)
+            .mount(server.server())
+            .await;
+        Mock::given(method("GET"))
+            .and(path(route.as_str()))
+            .respond_with(ResponseTemplate::new(200).insert_header("ETag", "\"v1\"").set_body_raw(PLAIN_TEXT.page(), "text/html; charset=UTF-8"))
+            .with_priority(2)
+            .mount(server.server())
+            .await;
+
+        let client = server.builder().metadata_cache_ttl(Duration::ZERO).build().unwrap();
+        assert_eq!(PLAIN_TEXT.title, client.get_metadata(PLAIN_TEXT.id).await.unwrap().title);
+        assert_eq!(PLAIN_TEXT.title, client.get_metadata(PLAIN_TEXT.id).await.unwrap().title);
+
+        let requests = server.server().received_requests().await.unwrap();
+        assert_eq!(2, requests.len());
+        assert!(!requests[0].headers.contains_key("if-none-match"));
+        assert_eq!("\"v1\"", requests[1].headers["if-none-match"]);
+
+        let client = server.builder().metadata_cache_ttl(Duration::from_secs(3600)).build().unwrap();
+        client.get_metadata(PLAIN_TEXT.id).await.unwrap();
+        client.get_metadata(PLAIN_TEXT.id).await.unwrap();
+        assert_eq!(3, server.server().received_requests().await.unwrap().len());
+    }
+
+    #[cfg(feature = "segments")]
+    #[tokio::test]
+    async fn test_result_cache_rolls_over_at_jst_midnight() {
+        use chrono::{Duration, NaiveDate, TimeZone, Utc};
+        use crate::site_day::{site_day, site_day_end};
+        use crate::{CacheKey, MemoryCache, ResultCache, Segments, ShindanResult};
+
+        let before = Utc.with_ymd_and_hms(2024, 1, 1, 14, 59, 59).unwrap();
+        let after = before + Duration::seconds(1);
+        assert_eq!(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(), site_day(before));
+        assert_eq!(NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(), site_day(after));
+        assert_eq!(after, site_day_end(before));
+        assert_eq!(after + Duration::days(1), site_day_end(after));
+
+        let key = |at| CacheKey::new(ShindanDomain::En, "1222992", "a", at);
+        assert_eq!(key(before - Duration::hours(23)), key(before));
+        assert_ne!(key(before), key(after));
+
+        let today_end = site_day_end(Utc::now());
+        let cache = MemoryCache::new(8);
+        let result = ShindanResult::new("1222992", "a", ShindanDomain::En, "Fantasy Stats", Segments(vec![]));
+        cache.put(key(today_end - Duration::seconds(1)), result, today_end).await.unwrap();
+        assert!(cache.get(&key(Utc::now())).await.unwrap().is_some());
+        assert!(cache.get(&key(today_end)).await.unwrap().is_none());
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_form_cache_ttl() {
+        use std::time::Duration;
+        use wiremock::matchers::method;
+        use wiremock::{Mock, ResponseTemplate};
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let client = server.builder().form_cache_ttl(Duration::from_millis(200)).build().unwrap();
+        let page_fetches = || async {
+            let requests = server.server().received_requests().await.unwrap();
+            requests.iter().filter(|request| request.method.as_str() == "GET").count()
+        };
+
+        client.get_segments(PLAIN_TEXT.id, "a").await.unwrap();
+        client.get_segments(PLAIN_TEXT.id, "b").await.unwrap();
+        assert_eq!(1, page_fetches().await);
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        client.get_segments(PLAIN_TEXT.id, "c").await.unwrap();
+        assert_eq!(2, page_fetches().await);
+
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(419))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(server.server())
+            .await;
+        assert!(client.get_segments(PLAIN_TEXT.id, "d").await.unwrap().to_string().contains('d'));
+        assert_eq!(3, page_fetches().await);
+    }
+
+    #[cfg(feature = "streaming")]
+    #[test]
+    fn test_streaming_segments_match_dom() {
+        use scraper::Html;
+        use crate::html_utils;
+        use crate::selectors::SELECTORS;
+        use crate::stream_parse::SegmentExtractor;
+
+        let html = r#"<html><body><span id="post_display">Tom &amp; Jerry<br><span style="color: #f00; font-weight: 700">R&amp;<i>D</i></span><b></b><strong>SSR</strong>&#x263A;&nbsp;<img data-src="https://example.com/a.png"></span></body></html>"#;
+
+        let mut extractor = SegmentExtractor::new(&SELECTORS, Default::default(), None);
+        for chunk in html.as_bytes().chunks(7) {
+            extractor.write(chunk).unwrap();
+        }
+
+        let expected = html_utils::get_segments(&SELECTORS, Default::default(), None, &Html::parse_document(html)).unwrap();
+
+        assert_eq!(expected, extractor.finish().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_singleflight_coalesces_concurrent_calls() {
+        use std::sync::Arc;
+        use std::time::Duration;
+        use futures::future::join_all;
+        use crate::singleflight::SingleFlight;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let flights = SingleFlight::<&str, usize>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let results = join_all((0..20).map(|_| {
+            let calls = calls.clone();
+            flights.run("1222992", async move {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                Ok(calls.fetch_add(1, Ordering::SeqCst))
+            })
+        }))
+        .await;
+
+        assert_eq!(1, calls.load(Ordering::SeqCst));
+        assert!(results.into_iter().all(|result| result.unwrap() == 0));
+    }
+
+    #[cfg(feature = "timer-agnostic")]
+    #[test]
+    fn test_rate_limiter_without_tokio() {
+        use std::time::{Duration, Instant};
+        use crate::rate_limit::RateLimiter;
+
+        let limiter = RateLimiter::new(2, Duration::from_millis(100));
+        let start = Instant::now();
+
+        futures::executor::block_on(async {
+            limiter.acquire().await;
+            limiter.acquire().await;
+        });
+
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[cfg(all(feature = "timer-agnostic", feature = "test-util"))]
+    #[test]
+    fn test_client_on_another_executor() {
+        use std::time::Duration;
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let server = runtime.block_on(MockShindan::start());
+        let client = server.builder().rate_limit(1, Duration::from_millis(50)).build().unwrap();
+
+        // Only the connections of reqwest need the reactor of a Tokio runtime; the rate limiter's timers do not.
+        let _reactor = runtime.enter();
+        futures::executor::block_on(async {
+            assert_eq!(PLAIN_TEXT.title, client.get_title(PLAIN_TEXT.id).await.unwrap());
+            assert_eq!(PLAIN_TEXT.title, client.get_title(PLAIN_TEXT.id).await.unwrap());
+        });
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip_body_decodes_in_chunks() {
+        use std::io::Write;
+        use flate2::Compression;
+        use flate2::write::GzEncoder;
+        use crate::compression::BodyDecoder;
+        use reqwest::header::{HeaderMap, HeaderValue, CONTENT_ENCODING};
+
+        let body = "<span id=\"post_display\">STR: 10</span>".repeat(100);
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(body.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+        let mut decoder = BodyDecoder::from_headers(&headers).unwrap();
+
+        let mut decoded = Vec::new();
+        for chunk in compressed.chunks(16) {
+            decoded.extend(decoder.write(chunk).unwrap());
+        }
+        decoded.extend(decoder.finish().unwrap());
+
+        assert!(compressed.len() < body.len());
+        assert_eq!(body.as_bytes(), decoded);
+    }
+
+    #[cfg(feature = "history-sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_history_round_trip() {
+        use crate::{HistoryEntry, HistoryStore, Segment, Segments, ShindanResult, SqliteHistory};
+
+        let history = SqliteHistory::open_in_memory().unwrap();
+        let result = ShindanResult::new(
+            "1222992",
+            "test_user",
+            ShindanDomain::En,
+            "Fantasy Stats",
+            Segments(vec![Segment::text("STR: 10")]),
+        );
+        let entry = HistoryEntry::new(result);
+
+        history.record(entry.clone()).await.unwrap();
+
+        assert_eq!(vec![entry.clone()], history.for_user("test_user").await.unwrap());
+        assert_eq!(Some(entry.clone()), history.for_day("1222992", "test_user", entry.day).await.unwrap());
+        assert!(history.for_user("someone_else").await.unwrap().is_empty());
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_mock_server_serves_fixtures() {
+        use crate::test_util::{MockShindan, CHART, IMAGES, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let client = server.client().unwrap();
+
+        assert_eq!(PLAIN_TEXT.title, client.get_title(PLAIN_TEXT.id).await.unwrap());
+
+        let (segments, title) = client.get_segments_with_title(PLAIN_TEXT.id, "test_user").await.unwrap();
+        assert_eq!(PLAIN_TEXT.title, title);
+        assert_eq!("test_user's stats:\nSTR: 10\nDEX: 8\nINT: 12", segments.to_string());
+
+        let segments = client.get_segments(IMAGES.id, "test_user").await.unwrap();
+        assert_eq!(crate::Segment::image("https://example.com/images/fox.png"), segments.0[2]);
+
+        let result = client.get_result(CHART.id, "test_user").await.unwrap();
+        assert_eq!(vec![80.0, 65.0, 90.0], result.chart.unwrap().datasets[0].data);
+    }
+
+    #[test]
+    fn test_parse_and_validate_shindan_ids() {
+        let (domain, id) = ShindanId::parse("https://shindanmaker.com/1222992/").unwrap();
+        assert_eq!((ShindanDomain::Jp, "1222992"), (domain, id.as_str()));
+
+        let (domain, id) = ShindanId::parse("http://mirror.example/shindan/1222992#result").unwrap();
+        assert_eq!(ShindanDomain::custom("http://mirror.example/shindan/").unwrap(), domain);
+        assert_eq!("1222992", id.as_str());
+
+        assert!(ShindanId::parse("https://en.shindanmaker.com/list/ranking").is_err());
+        assert!(ShindanId::parse("1222992").is_err());
+
+        assert!(ShindanId::new(" 1222992").is_err());
+        assert!(ShindanId::new("1222992123456").is_err());
+        assert_eq!("\"1222992\"", serde_json::to_string(&ShindanId::new("1222992").unwrap()).unwrap());
+        assert!(serde_json::from_str::<ShindanId>("\"12a\"").is_err());
+    }
+
+    #[cfg(all(feature = "feeds", feature = "test-util"))]
+    #[tokio::test]
+    async fn test_author_feed() {
+        use wiremock::{Mock, ResponseTemplate};
+        use wiremock::matchers::{method, path};
+        use crate::{FeedSource, FeedState};
+        use crate::test_util::{MockShindan, IMAGES, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let author = r#"<a href="/1222992">Fantasy Stats</a><a href="/1000001">Spirit Animal</a><a href="/9999999">Deleted</a>"#;
+        Mock::given(method("GET"))
+            .and(path("/author/al%20ice%2F%3Fx"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(author, "text/html; charset=UTF-8"))
+            .mount(server.server())
+            .await;
+
+        let client = server.client().unwrap();
+        let source = FeedSource::Author("al ice/?x".to_string());
+        let mut state = FeedState::default();
+
+        let feed = client.atom_feed(&source, &mut state).await.unwrap();
+        let mut titles: Vec<&str> = feed.entries.iter().map(|entry| entry.title.as_str()).collect();
+        titles.sort();
+        assert_eq!(vec![PLAIN_TEXT.title, IMAGES.title], titles);
+        assert!(feed.to_xml().contains(&format!("<id>{}/author/al%20ice%2F%3Fx</id>", server.uri())));
+
+        let again = client.atom_feed(&source, &mut state).await.unwrap();
+        assert_eq!(feed.entries, again.entries);
+    }
+
+    #[cfg(all(feature = "notifier", feature = "test-util"))]
+    #[tokio::test]
+    async fn test_notifier() {
+        use wiremock::{Mock, ResponseTemplate};
+        use wiremock::matchers::{method, path};
+        use crate::test_util::MockShindan;
+        use crate::{Notifier, WatchTarget, Webhook, WebhookFormat};
+
+        let server = MockShindan::start().await;
+        let latest = |html: &str| ResponseTemplate::new(200).set_body_raw(html.to_string(), "text/html; charset=UTF-8");
+        Mock::given(method("GET"))
+            .and(path("/list/latest"))
+            .respond_with(latest(r#"<a href="/1222992">Fantasy Stats</a>"#))
+            .up_to_n_times(1)
+            .mount(server.server())
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/list/latest"))
+            .respond_with(latest(r#"<a href="/1000001">Spirit Animal</a><a href="/1222992">Fantasy Stats</a>"#))
+            .mount(server.server())
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(server.server())
+            .await;
+
+        let mut notifier = Notifier::new(server.client().unwrap())
+            .watch(WatchTarget::NewArrivals)
+            .webhook(Webhook { url: format!("{}/hook", server.uri()), format: WebhookFormat::Generic });
+
+        assert!(notifier.check().await.unwrap().is_empty());
+        let alerts = notifier.check().await.unwrap();
+        assert_eq!(1, alerts.len());
+        assert_eq!(vec!["1000001"], alerts[0].shindans.iter().map(|hit| hit.id.as_str()).collect::<Vec<_>>());
+
+        let posts = server.server().received_requests().await.unwrap();
+        let hook = posts.iter().find(|request| request.url.path() == "/hook").unwrap();
+        let payload: serde_json::Value = serde_json::from_slice(&hook.body).unwrap();
+        assert_eq!("new arrivals", payload["source"]);
+        assert_eq!(format!("{}/1000001", server.uri()), payload["shindans"][0]["url"]);
+    }
+
+    #[cfg(all(feature = "notifier", feature = "test-util"))]
+    #[tokio::test]
+    async fn test_notifier_retries_failed_posts() {
+        use wiremock::{Mock, ResponseTemplate};
+        use wiremock::matchers::{method, path};
+        use crate::test_util::MockShindan;
+        use crate::{Notifier, WatchTarget, Webhook, WebhookFormat};
+
+        let server = MockShindan::start().await;
+        let latest = |html: &str| ResponseTemplate::new(200).set_body_raw(html.to_string(), "text/html; charset=UTF-8");
+        Mock::given(method("GET"))
+            .and(path("/list/latest"))
+            .respond_with(latest(r#"<a href="/1222992">Fantasy Stats</a>"#))
+            .up_to_n_times(1)
+            .mount(server.server())
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/list/latest"))
+            .respond_with(latest(r#"<a href="/1000001">Spirit Animal</a><a href="/1222992">Fantasy Stats</a>"#))
+            .mount(server.server())
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .respond_with(ResponseTemplate::new(500))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(server.server())
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(server.server())
+            .await;
+
+        let mut notifier = Notifier::new(server.client().unwrap())
+            .watch(WatchTarget::NewArrivals)
+            .webhook(Webhook { url: format!("{}/hook", server.uri()), format: WebhookFormat::Generic });
+
+        assert!(notifier.check().await.unwrap().is_empty());
+        assert!(notifier.check().await.is_err());
+
+        let alerts = notifier.check().await.unwrap();
+        assert_eq!(vec!["1000001"], alerts[0].shindans.iter().map(|hit| hit.id.as_str()).collect::<Vec<_>>());
+        assert!(notifier.check().await.unwrap().is_empty());
+
+        let posts = server.server().received_requests().await.unwrap();
+        assert_eq!(2, posts.iter().filter(|request| request.url.path() == "/hook").count());
+    }
+
+    #[cfg(feature = "segments")]
+    #[test]
+    fn test_stats_keep_text_of_empty_names() {
+        use crate::{Segment, Segments, ShindanResult, StatsReport};
+
+        let result = |name: &str| {
+            let text = format!("{}is a knight", name);
+            ShindanResult::new("1222992", name, ShindanDomain::En, "Fantasy Stats", Segments(vec![Segment::text(text)]))
+        };
+
+        let report = StatsReport::aggregate(&[result(""), result("Alice ")]);
+        let mut texts: Vec<_> = report.text_variants.iter().map(|variant| variant.text.as_str()).collect();
+        texts.sort();
+        assert_eq!(vec!["is a knight", "{name}is a knight"], texts);
+    }
+
+    #[cfg(feature = "segments")]
+    #[test]
+    fn test_digest_group_with_empty_name() {
+        use crate::{DigestEntry, DigestGroup, Segment, Segments};
+
+        let entry = |name: &str| DigestEntry {
+            name: name.to_string(),
+            segments: Segments(vec![Segment::text(format!("{} is lucky", name))]),
+        };
+        let group = |names: &[&str]| DigestGroup {
+            outcome: "{name} is lucky".to_string(),
+            entries: names.iter().map(|name| entry(name)).collect(),
+        };
+
+        assert_eq!(" is lucky", group(&["", "Bob"]).segments().to_string());
+        assert_eq!("Alice, Bob is lucky", group(&["Alice", "Bob"]).segments().to_string());
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_build_digest() {
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let client = server.client().unwrap();
+
+        let digest = client.build_digest(PLAIN_TEXT.id, &["alice", "bob", "carol"]).await.unwrap();
+        assert_eq!(PLAIN_TEXT.title, digest.title);
+        assert!(digest.failed.is_empty());
+
+        let names: Vec<&str> = digest.groups.iter().flat_map(|group| &group.entries).map(|entry| entry.name.as_str()).collect();
+        assert_eq!(3, names.len());
+        for group in &digest.groups {
+            assert!(!group.outcome.contains(&group.entries[0].name));
+            assert!(digest.to_markdown().contains(&group.names()));
+        }
+        assert!(digest.to_html().contains(r#"<div id="digest"><h1>Fantasy Stats</h1>"#));
+
+        assert!(client.build_digest("9999999", &["alice"]).await.is_err());
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_fair_queue() {
+        use std::sync::Mutex;
+        use crate::{FairQueue, QueueFull};
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let queue = FairQueue::new(server.client().unwrap()).backlog(2);
+        let order = Mutex::new(Vec::new());
+
+        let job = |label: &'static str| {
+            let order = &order;
+            move |_| async move {
+                order.lock().unwrap().push(label);
+                tokio::task::yield_now().await;
+            }
+        };
+        // Alice sends four commands before Bob sends one: Bob goes second, and Alice's last is refused.
+        let (a1, a2, a3, a4, b1) = futures::join!(
+            queue.run("alice", job("a1")),
+            queue.run("alice", job("a2")),
+            queue.run("alice", job("a3")),
+            queue.run("alice", job("a4")),
+            queue.run("bob", job("b1")),
+        );
+        assert!(a1.is_ok() && a2.is_ok() && a3.is_ok() && b1.is_ok());
+        assert_eq!(Some(&QueueFull { user: "alice".to_string() }), a4.unwrap_err().downcast_ref());
+        assert_eq!(vec!["a1", "b1", "a2", "a3"], *order.lock().unwrap());
+
+        let result = queue.submit("alice", PLAIN_TEXT.id, "test_user").await.unwrap();
+        assert_eq!(PLAIN_TEXT.title, result.title);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_task_pool_priorities() {
+        use std::sync::Mutex;
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+        use crate::{Priority, ShindanTaskPool, TaskKind};
+
+        let server = MockShindan::start().await;
+        let pool = ShindanTaskPool::new(server.client().unwrap()).limit(TaskKind::Submission, 1);
+        let order = Mutex::new(Vec::new());
+
+        let job = |label: &'static str| {
+            let order = &order;
+            move |_| async move {
+                order.lock().unwrap().push(label);
+                tokio::task::yield_now().await;
+            }
+        };
+        // The first job holds the only slot while the others queue, batch first.
+        futures::join!(
+            pool.run(TaskKind::Submission, Priority::Batch, job("running")),
+            pool.run(TaskKind::Submission, Priority::Batch, job("batch")),
+            pool.run(TaskKind::Submission, Priority::Interactive, job("interactive")),
+            pool.run(TaskKind::Metadata, Priority::Batch, job("metadata")),
+        );
+        assert_eq!(vec!["running", "metadata", "interactive", "batch"], *order.lock().unwrap());
+
+        let result = pool.get_result(PLAIN_TEXT.id, "test_user", Priority::Interactive).await.unwrap();
+        assert_eq!(PLAIN_TEXT.title, result.title);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_content_fingerprint() {
+        use crate::test_util::{MockShindan, IMAGES, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let client = server.client().unwrap();
+
+        let fingerprint = client.content_fingerprint(PLAIN_TEXT.id).await.unwrap();
+        assert_eq!(PLAIN_TEXT.id, fingerprint.id);
+        assert_eq!(16, fingerprint.digest.len());
+        assert_eq!(fingerprint, client.content_fingerprint(PLAIN_TEXT.id).await.unwrap());
+        assert!(!client.has_changed_since(&fingerprint).await.unwrap());
+
+        let other = client.content_fingerprint(IMAGES.id).await.unwrap();
+        assert_ne!(fingerprint.digest, other.digest);
+        let edited = crate::ContentFingerprint { digest: other.digest, ..fingerprint };
+        assert!(client.has_changed_since(&edited).await.unwrap());
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_diff_results() {
+        use crate::{diff_results, Segment, SegmentChange};
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let client = server.client().unwrap();
+
+        let before = client.get_result(PLAIN_TEXT.id, "alice").await.unwrap();
+        let after = client.get_result(PLAIN_TEXT.id, "bob").await.unwrap();
+        assert!(diff_results(&before, &after).is_unchanged());
+
+        let mut edited = after.clone();
+        edited.title = "Fantasy Stats 2".to_string();
+        edited.segments.0.push(Segment::image("https://example.com/new.png"));
+        let diff = diff_results(&before, &edited);
+
+        assert_eq!(Some((before.title.clone(), edited.title.clone())), diff.title);
+        let changed: Vec<&SegmentChange> = diff.changed().collect();
+        assert_eq!(vec![&SegmentChange::Added { segment: Segment::image("https://example.com/new.png") }], changed);
+        assert!(diff.to_plain_text().ends_with("+ https://example.com/new.png"));
+    }
+
+    #[cfg(feature = "export")]
+    #[test]
+    fn test_export_csv() {
+        use crate::{write_csv, ExportRow, SearchHit, Segment, Segments, ShindanResult};
+
+        let mut with_description = ShindanResult::new("1", "alice", ShindanDomain::En, "Luck", Segments(vec![Segment::text("Great\nluck")]));
+        with_description.description = Some("Daily".to_string());
+        let without = ShindanResult::new("2", "bob", ShindanDomain::Jp, "Stats", Segments(vec![Segment::image("https://example.com/a.png")]));
+
+        let mut csv = Vec::new();
+        write_csv(&[with_description, without], &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.starts_with(&format!("{}\r\n", ShindanResult::columns().join(","))));
+        assert!(csv.contains(r#","Great
+luck","#));
+        assert!(csv.contains(r#","[""https://example.com/a.png""]","#));
+
+        let hits = [
+            SearchHit { id: "1222992".to_string(), title: "Fantasy Stats".to_string(), domain: ShindanDomain::En },
+            SearchHit { id: "1000001".to_string(), title: "Spirit Animal".to_string(), domain: ShindanDomain::Kr },
+        ];
+        let mut csv = Vec::new();
+        write_csv(&hits, &mut csv).unwrap();
+        assert_eq!(
+            concat!(
+                "id,title,url\r\n",
+                "1222992,Fantasy Stats,https://en.shindanmaker.com/1222992\r\n",
+                "1000001,Spirit Animal,https://kr.shindanmaker.com/1000001\r\n",
+            ),
+            String::from_utf8(csv).unwrap()
+        );
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_export_results() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+        use crate::{write_parquet, ExportRow, Segment, Segments, ShindanResult};
+
+        let mut with_description = ShindanResult::new("1", "alice", ShindanDomain::En, "Luck", Segments(vec![Segment::text("Great\nluck")]));
+        with_description.description = Some("Daily".to_string());
+        let without = ShindanResult::new("2", "bob", ShindanDomain::Jp, "Stats", Segments(vec![Segment::image("https://example.com/a.png")]));
+        let results = [with_description, without];
+
+        let path = std::env::temp_dir().join(format!("shindan-maker-export-{}.parquet", std::process::id()));
+        write_parquet(&results, std::fs::File::create(&path).unwrap()).unwrap();
+        let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(2, metadata.num_rows());
+        assert_eq!(ShindanResult::columns().len(), metadata.schema_descr().num_columns());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "archive")]
+    #[test]
+    fn test_result_from_archive() {
+        use std::collections::BTreeMap;
+        use crate::{Archive, Segment, Segments, ShindanResult};
+
+        let dir = std::env::temp_dir().join(format!("shindan-maker-import-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("images")).unwrap();
+        std::fs::write(dir.join("images/0.png"), b"png").unwrap();
+
+        let segments = Segments(vec![
+            Segment::text("A fox"),
+            Segment::image("https://example.com/fox.png"),
+            Segment::image("https://example.com/gone.png"),
+        ]);
+        let result = ShindanResult::new("1000001", "test_user", ShindanDomain::En, "Spirit Animal", segments);
+        let archive = Archive {
+            result: result.clone(),
+            images: BTreeMap::from([("https://example.com/fox.png".to_string(), "images/0.png".to_string())]),
+            html: None,
+            screenshot: None,
+            archived_at: result.fetched_at,
+        };
+        std::fs::write(dir.join("archive.json"), serde_json::to_string(&archive).unwrap()).unwrap();
+
+        let imported = ShindanResult::from_archive(&dir).unwrap();
+        let local = url::Url::from_file_path(dir.canonicalize().unwrap().join("images/0.png")).unwrap();
+        assert_eq!(Segment::image(local.as_str()), imported.segments[1]);
+        assert_eq!(result.segments[2], imported.segments[2]);
+        assert_eq!(result.title, imported.title);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(ShindanResult::from_archive(&dir).is_err());
+    }
+
+    #[cfg(all(feature = "archive", feature = "test-util"))]
+    #[tokio::test]
+    async fn test_archive_result() {
+        use crate::Archive;
+        use crate::test_util::{MockShindan, IMAGES};
+
+        let server = MockShindan::start().await;
+        let client = server.client().unwrap();
+        let dir = std::env::temp_dir().join(format!("shindan-maker-archive-{}", std::process::id()));
+
+        let archive = client.archive_result(IMAGES.id, "test_user", &dir).await.unwrap();
+        assert_eq!(IMAGES.title, archive.result.title);
+
+        let requests = server.server().received_requests().await.unwrap();
+        assert_eq!(1, requests.iter().filter(|request| request.method.as_str() == "POST").count());
+        assert_eq!(archive, Archive::load(&dir).unwrap());
+        for file in archive.images.values() {
+            assert!(dir.join(file).is_file());
+        }
+
+        #[cfg(feature = "html")]
+        {
+            let html = std::fs::read_to_string(dir.join(archive.html.as_deref().unwrap())).unwrap();
+            assert!(html.contains("spirit animal is a fox"));
+        }
+
+        // Without a browser the archive is still written, only without a screenshot.
+        if let Some(file) = &archive.screenshot {
+            assert!(dir.join(file).is_file());
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(all(feature = "scores", feature = "test-util"))]
+    #[tokio::test]
+    async fn test_extracted_scores() {
+        use crate::ScoreRule;
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let client = server
+            .builder()
+            .score_rules([ScoreRule::pattern("str", r"STR: (\d+)").unwrap(), ScoreRule::rarity("rarity")])
+            .build()
+            .unwrap();
+
+        let result = client.get_result(PLAIN_TEXT.id, "test_user").await.unwrap();
+        assert_eq!(Some(&10.0), result.extracted_scores.get("str"));
+        assert!(!result.extracted_scores.contains_key("rarity"));
+
+        let json = result.to_json().unwrap();
+        assert_eq!(result.extracted_scores, crate::ShindanResult::from_json(&json).unwrap().extracted_scores);
+    }
+
+    #[cfg(all(feature = "scheduler", feature = "test-util"))]
+    #[tokio::test]
+    async fn test_daily_scheduler() {
+        use std::sync::{Arc, Mutex};
+        use crate::DailyScheduler;
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let delivered = Arc::new(Mutex::new(Vec::new()));
+        let channel = |name: &'static str| {
+            let delivered = delivered.clone();
+            move |result: crate::ShindanResult| {
+                delivered.lock().unwrap().push((name, result.title));
+                async { Ok(()) }
+            }
+        };
+
+        let mut scheduler = DailyScheduler::new(server.client().unwrap())
+            .job(PLAIN_TEXT.id, "alice", channel("general"))
+            .job(PLAIN_TEXT.id, "alice", channel("fortunes"))
+            .job("9999999", "alice", channel("missing"))
+            .retries(1)
+            .retry_delay(std::time::Duration::ZERO);
+
+        assert_eq!(2, scheduler.run_due().await);
+        assert_eq!(
+            vec![("general", PLAIN_TEXT.title.to_string()), ("fortunes", PLAIN_TEXT.title.to_string())],
+            *delivered.lock().unwrap()
+        );
+
+        let submissions = server.server().received_requests().await.unwrap();
+        let posts = submissions.iter().filter(|request| request.method.as_str() == "POST").count();
+        assert_eq!(1, posts);
+
+        assert_eq!(0, scheduler.run_due().await);
+    }
+
+    #[cfg(all(feature = "scheduler", feature = "test-util"))]
+    #[tokio::test]
+    async fn test_daily_scheduler_retries_deliveries() {
+        use std::time::Duration;
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+        use tokio::sync::Notify;
+        use crate::DailyScheduler;
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let bob_delivered = Arc::new(Notify::new());
+        let down = Arc::new(AtomicBool::new(true));
+        let attempts = Arc::new(AtomicUsize::new(0));
+
+        let waiting = bob_delivered.clone();
+        let (failing, attempted) = (down.clone(), attempts.clone());
+        let mut scheduler = DailyScheduler::new(server.client().unwrap())
+            .job(PLAIN_TEXT.id, "alice", move |_| {
+                let waiting = waiting.clone();
+                async move {
+                    waiting.notified().await;
+                    Ok(())
+                }
+            })
+            .job(PLAIN_TEXT.id, "bob", move |_| {
+                bob_delivered.notify_one();
+                async { Ok(()) }
+            })
+            .job(PLAIN_TEXT.id, "carol", move |_| {
+                attempted.fetch_add(1, Ordering::SeqCst);
+                let down = failing.load(Ordering::SeqCst);
+                async move { if down { Err(anyhow::anyhow!("The channel is down")) } else { Ok(()) } }
+            })
+            .retries(1)
+            .retry_delay(Duration::ZERO);
+
+        // Alice is only delivered once Bob is, whatever order the jobs start in.
+        let delivered = tokio::time::timeout(Duration::from_secs(5), scheduler.run_due()).await.unwrap();
+        assert_eq!(2, delivered);
+        assert_eq!(2, attempts.load(Ordering::SeqCst));
+
+        down.store(false, Ordering::SeqCst);
+        assert_eq!(1, scheduler.run_due().await);
+        assert_eq!(0, scheduler.run_due().await);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_custom_domain() {
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let domain: ShindanDomain = server.uri().parse().unwrap();
+        let client = ShindanClient::new(domain.clone()).unwrap();
+
+        let result = client.get_result(PLAIN_TEXT.id, "test_user").await.unwrap();
+        assert_eq!(domain, result.domain);
+        assert_eq!(format!("{}/{}", server.uri(), PLAIN_TEXT.id), result.url());
+
+        assert!(ShindanDomain::custom("ftp://mirror.example/").is_err());
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_domain_fallback() {
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let empty = MockShindan::with_fixtures(&[]).await;
+        let mirror = MockShindan::start().await;
+        let mirror_domain: ShindanDomain = mirror.uri().parse().unwrap();
+
+        let client = ShindanClient::new(empty.uri().parse().unwrap()).unwrap();
+        let error = client.get_title(PLAIN_TEXT.id).await.unwrap_err();
+        assert!(error.to_string().starts_with("No shindan found"));
+        assert_eq!(PLAIN_TEXT.title, client.with_domain(mirror_domain.clone()).get_title(PLAIN_TEXT.id).await.unwrap());
+
+        let client = ShindanClient::builder(empty.uri().parse().unwrap())
+            .fallback_domains([mirror_domain.clone()])
+            .build()
+            .unwrap();
+        let result = client.get_result(PLAIN_TEXT.id, "test_user").await.unwrap();
+        assert_eq!(mirror_domain, result.domain);
+        assert_eq!(PLAIN_TEXT.title, client.get_title(PLAIN_TEXT.id).await.unwrap());
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_domain_redirect() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, ResponseTemplate};
+        use crate::test_util::{MockShindan, CHART, PLAIN_TEXT};
+
+        let moved = MockShindan::with_fixtures(&[]).await;
+        let mirror = MockShindan::start().await;
+        let mirror_domain: ShindanDomain = mirror.uri().parse().unwrap();
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(302).insert_header("Location", format!("{}/{}", mirror.uri(), PLAIN_TEXT.id)))
+            .mount(moved.server())
+            .await;
+
+        let client = ShindanClient::builder(moved.uri().parse().unwrap())
+            .follow_domain_redirects(true)
+            .build()
+            .unwrap();
+        let result = client.get_result(PLAIN_TEXT.id, "test_user").await.unwrap();
+        assert_eq!(mirror_domain, result.domain);
+        assert_eq!(Some(mirror_domain), client.redirected_domain(PLAIN_TEXT.id));
+        assert_eq!(None, client.redirected_domain(CHART.id));
+
+        let response = result.response.unwrap();
+        assert_eq!((format!("{}/{}", mirror.uri(), PLAIN_TEXT.id), 200), (response.url, response.status));
+
+        client.get_title(PLAIN_TEXT.id).await.unwrap();
+        assert_eq!(1, moved.server().received_requests().await.unwrap().len());
+
+        client.get_title(CHART.id).await.unwrap();
+        assert_eq!(2, moved.server().received_requests().await.unwrap().len());
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_description_segments() {
+        use crate::Segment;
+        use crate::test_util::{Fixture, MockShindan, PLAIN_TEXT};
+
+        let fixture = Fixture {
+            description: r#"Read the <a href="https://example.com/faq">FAQ</a>:<ul><li><b>one</b><ol start="2"><li>half</li></ol></li><li>two</li></ul>"#,
+            ..PLAIN_TEXT
+        };
+        let server = MockShindan::with_fixtures(&[fixture]).await;
+        let metadata = server.client().unwrap().get_metadata(fixture.id).await.unwrap();
+
+        let expected = [
+            Segment::text("Read the "),
+            Segment::link("FAQ", "https://example.com/faq"),
+            Segment::text(":"),
+            Segment::text("\n"),
+            Segment::text("• "),
+            Segment::text("one"),
+            Segment::text("\n"),
+            Segment::text("  2. "),
+            Segment::text("half"),
+            Segment::text("\n"),
+            Segment::text("• "),
+            Segment::text("two"),
+            Segment::text("\n"),
+        ];
+        assert_eq!(expected.as_slice(), metadata.description_segments.unwrap().as_slice());
+        assert_eq!(Some("Read the FAQ:"), metadata.description.as_deref());
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_preview() {
+        use crate::ShindanPreview;
+        use crate::test_util::{Fixture, MockShindan, PLAIN_TEXT};
+
+        let fixture = Fixture {
+            description: r#"Try it!<div id="shindanResultExample">test_user is a <b>dragon</b><img src="https://example.com/dragon.png"></div>"#,
+            ..PLAIN_TEXT
+        };
+        let server = MockShindan::with_fixtures(&[fixture]).await;
+        let page = server.client().unwrap().fetch_page(fixture.id).await.unwrap();
+
+        let expected = ShindanPreview {
+            text: Some("test_user is a dragon".to_string()),
+            image: Some("https://example.com/dragon.png".to_string()),
+            share_image: None,
+        };
+        assert_eq!(Some(&expected), page.preview());
+        assert_eq!(Some(expected), page.metadata().unwrap().preview);
+
+        let share_only = Fixture {
+            description: r#"<meta property="og:image" content="https://example.com/ogp/1.png">Try it!"#,
+            ..PLAIN_TEXT
+        };
+        let server = MockShindan::with_fixtures(&[share_only]).await;
+        let preview = server.client().unwrap().get_metadata(share_only.id).await.unwrap().preview.unwrap();
+        assert_eq!((None, Some("https://example.com/ogp/1.png")), (preview.image, preview.share_image.as_deref()));
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_tags_and_hashtags() {
+        use crate::test_util::{Fixture, MockShindan, PLAIN_TEXT};
+
+        let fixture = Fixture {
+            description: r#"Stats!<div id="shindanTags"><a href="/list?tag=rpg">#rpg</a><a href="/list?tag=stats">stats</a></div>"#,
+            result: r#"<span id="post_display">{name}: STR 10</span><a href="https://x.com/intent/post?text=%23FantasyStats%20STR%2010&hashtags=shindanmaker">Post</a>"#,
+            ..PLAIN_TEXT
+        };
+        let server = MockShindan::with_fixtures(&[fixture]).await;
+        let client = server.client().unwrap();
+
+        assert_eq!(vec!["rpg", "stats"], client.get_metadata(fixture.id).await.unwrap().tags);
+
+        let result = client.get_result(fixture.id, "test_user").await.unwrap();
+        assert_eq!(vec!["FantasyStats", "shindanmaker"], result.hashtags);
+        assert!(result.share_payload().post_text().contains("#FantasyStats #shindanmaker"));
+    }
+
+    #[cfg(all(feature = "language", feature = "test-util"))]
+    #[tokio::test]
+    async fn test_result_language() {
+        use crate::test_util::{Fixture, MockShindan, PLAIN_TEXT};
+
+        let fixture = Fixture {
+            result: r#"<span id="post_display">{name}、今日のあなたは古い橋の下に隠された宝物を見つけるでしょう。</span>"#,
+            ..PLAIN_TEXT
+        };
+        let server = MockShindan::with_fixtures(&[fixture]).await;
+
+        let result = server.client().unwrap().get_result(fixture.id, "test_user").await.unwrap();
+        assert_eq!(Some("jpn"), result.language.as_deref());
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_seeded_form() {
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let client = server.builder().seed("snapshot").build().unwrap();
+
+        let page = client.page(&client.url(PLAIN_TEXT.id)).await.unwrap();
+        assert_eq!(Some("名無しのR"), page.randname());
+
+        client.get_segments(PLAIN_TEXT.id, "test_user").await.unwrap();
+
+        let requests = server.server().received_requests().await.unwrap();
+        let body = String::from_utf8(requests.last().unwrap().body.clone()).unwrap();
+        assert!(body.contains("randname=snapshot"));
+    }
+
+    #[cfg(all(feature = "test-util", feature = "toml"))]
+    #[tokio::test]
+    async fn test_config_retries() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, ResponseTemplate};
+        use crate::ShindanConfig;
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let fail_once = |verb| Mock::given(method(verb))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .with_priority(1);
+
+        let server = MockShindan::start().await;
+        let toml = format!(r#"
+            domain = "en"
+            base_url = "{}/"
+            retries = 1
+            polite = false
+        "#, server.uri());
+        let client = ShindanClient::from_config(&ShindanConfig::from_toml(&toml).unwrap()).unwrap();
+
+        fail_once("GET").mount(server.server()).await;
+        assert!(client.get_segments(PLAIN_TEXT.id, "test_user").await.is_ok());
+        assert_eq!(3, server.server().received_requests().await.unwrap().len());
+
+        fail_once("POST").mount(server.server()).await;
+        assert!(client.get_segments(PLAIN_TEXT.id, "test_user").await.is_err());
+        assert_eq!(5, server.server().received_requests().await.unwrap().len());
+
+        fail_once("POST").mount(server.server()).await;
+        let config = ShindanConfig::from_toml(&format!("{toml}retry_submissions = true")).unwrap();
+        let client = ShindanClient::from_config(&config).unwrap();
+        assert!(client.get_segments(PLAIN_TEXT.id, "test_user").await.is_ok());
+        assert_eq!(8, server.server().received_requests().await.unwrap().len());
+
+        assert!(ShindanConfig::from_toml("timeout_secs = -1.0").unwrap().builder().is_err());
+    }
+
+    #[cfg(all(feature = "test-util", feature = "html"))]
+    #[tokio::test]
+    async fn test_font_stack() {
+        use crate::FontStack;
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let path = std::env::temp_dir().join(format!("shindan-maker-font-{}.woff2", std::process::id()));
+        std::fs::write(&path, "font").unwrap();
+        let fonts = FontStack::default().embed("Test CJK", &path).family("Noto Sans JP");
+
+        let client = server.builder().fonts(fonts.clone()).build().unwrap();
+        let html = client.get_html_str(PLAIN_TEXT.id, "test_user").await.unwrap();
+        assert!(html.contains(r#"@font-face { font-family: "Test CJK"; src: url(data:font/woff2;base64,Zm9udA==) format("woff2"); }"#));
+        assert!(html.contains(r#"font-family: "Test CJK", "Noto Sans JP", -apple-system"#));
+        assert!(html.find("@font-face").unwrap() < html.find("</head>").unwrap());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(server.builder().fonts(fonts).build().is_err());
+    }
+
+    #[cfg(all(feature = "test-util", feature = "html", feature = "tui"))]
+    #[tokio::test]
+    async fn test_chart_overrides() {
+        use crate::{ChartOverrides, TerminalOptions};
+        use crate::test_util::{MockShindan, CHART};
+
+        let server = MockShindan::start().await;
+        let overrides = ChartOverrides::default().label("ATK", "Attack").label("stats", "</script>").color("#e91e63");
+
+        let html = server.client().unwrap().get_html_str(CHART.id, "test_user").await.unwrap();
+        assert!(!html.contains("Chart.instances"));
+        let client = server.builder().chart_overrides(overrides.clone()).build().unwrap();
+        let html = client.get_html_str(CHART.id, "test_user").await.unwrap();
+        assert!(html.contains("Chart.instances") && html.contains(r##"colors = ["#e91e63"]"##));
+        assert!(html.contains(r#""ATK":"Attack""#) && html.contains(r#""<\/script>""#));
+
+        let result = client.get_result(CHART.id, "test_user").await.unwrap();
+        let options = TerminalOptions { color: true, chart_overrides: overrides, ..Default::default() };
+        let ansi = result.to_ansi(&options);
+        assert!(ansi.contains("Attack ") && ansi.contains("\x1b[38;2;233;30;99m"));
+        assert_eq!(vec!["ATK", "DEF", "SPD"], result.chart.unwrap().labels);
+    }
+
+    #[cfg(all(feature = "test-util", feature = "html"))]
+    #[tokio::test]
+    async fn test_site_assets() {
+        use wiremock::matchers::{method, path, query_param};
+        use wiremock::{Mock, ResponseTemplate};
+        use crate::{AssetVersions, SiteAssets};
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let page = r#"<link rel="stylesheet" href="https://en.shindanmaker.com/css/app.css?id=0123abcd"><script src="https://en.shindanmaker.com/js/chart.js?id=89ef" defer></script>"#;
+        let live = AssetVersions::from_page(page);
+        assert_eq!("https://en.shindanmaker.com/", live.base_url);
+        assert_eq!(("0123abcd", "89ef"), (live.app_css.as_str(), live.chart_js.as_str()));
+        assert_eq!(AssetVersions::builtin().app_js, live.app_js);
+
+        let server = MockShindan::start().await;
+        Mock::given(method("GET"))
+            .and(path("/css/app.css"))
+            .and(query_param("id", "v2"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(".live { }", "text/css"))
+            .mount(server.server())
+            .await;
+        let pinned = |app_css: &str| {
+            let versions = AssetVersions { base_url: format!("{}/", server.uri()), app_css: app_css.to_string(), ..AssetVersions::builtin() };
+            server.builder().site_assets(SiteAssets::Pinned(versions)).build().unwrap()
+        };
+        let builtin_css = "https://cn.shindanmaker.com/css/app.css?id=";
+
+        let html = pinned("v2").get_html_str(PLAIN_TEXT.id, "test_user").await.unwrap();
+        assert!(html.contains("<style>.live { }</style>") && !html.contains(builtin_css));
+
+        let html = pinned("gone").get_html_str(PLAIN_TEXT.id, "test_user").await.unwrap();
+        assert!(!html.contains(".live") && html.contains(builtin_css));
+        assert!(html.contains(&format!(r#"content="css/app.css={}""#, AssetVersions::builtin().app_css)));
+
+        let home = format!(r#"<link rel="stylesheet" href="{0}/css/app.css?id=v2"><script src="{0}/js/app.js?id=a2"></script>"#, server.uri());
+        Mock::given(method("GET")).and(path("/")).respond_with(ResponseTemplate::new(200).set_body_raw(home, "text/html")).mount(server.server()).await;
+        Mock::given(method("GET")).and(path("/js/app.js")).respond_with(ResponseTemplate::new(200).set_body_string("app()")).mount(server.server()).await;
+        Mock::given(method("GET")).and(path("/js/chart.js")).respond_with(ResponseTemplate::new(200).set_body_string("chart()")).mount(server.server()).await;
+
+        let dir = std::env::temp_dir().join(format!("shindan-maker-static-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let versions = server.client().unwrap().refresh_site_assets(&dir).await.unwrap();
+        assert_eq!(("v2", "a2"), (versions.app_css.as_str(), versions.app_js.as_str()));
+        assert_eq!(AssetVersions::builtin().chart_js, versions.chart_js);
+        assert_eq!(versions, AssetVersions::load(&dir).unwrap());
+        assert_eq!("chart()", std::fs::read_to_string(dir.join("js/chart.js")).unwrap());
+
+        let client = server.builder().site_assets(SiteAssets::Directory(dir.clone())).build().unwrap();
+        let html = client.get_html_str(PLAIN_TEXT.id, "test_user").await.unwrap();
+        assert!(html.contains("<style>.live { }</style>") && html.contains(r#"content="css/app.css=v2""#));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(all(feature = "test-util", feature = "archive"))]
+    #[tokio::test]
+    async fn test_asset_cache() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, ResponseTemplate};
+        use crate::test_util::MockShindan;
+
+        let server = MockShindan::start().await;
+        let asset = |route: &str, cache_control: &str| {
+            Mock::given(method("GET"))
+                .and(path(route.to_string()))
+                .respond_with(ResponseTemplate::new(200).insert_header("Cache-Control", cache_control).insert_header("ETag", "\"v1\"").set_body_raw("body { }", "text/css"))
+        };
+        asset("/app.css", "public, max-age=3600").mount(server.server()).await;
+        asset("/live.css", "no-store").mount(server.server()).await;
+        asset("/stale.css", "no-cache").mount(server.server()).await;
+        Mock::given(method("GET"))
+            .and(path("/stale.css"))
+            .and(header("If-None-Match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304).insert_header("Cache-Control", "no-cache"))
+            .with_priority(1)
+            .mount(server.server())
+            .await;
+
+        let dir = std::env::temp_dir().join(format!("shindan-maker-assets-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for _ in 0..2 {
+            // A new client each time, so that only the directory is shared.
+            let client = server.builder().asset_cache(&dir).build().unwrap();
+            for route in ["/app.css", "/live.css", "/stale.css"] {
+                let asset = client.fetch_asset(&format!("{}{}", server.uri(), route)).await.unwrap();
+                assert_eq!(b"body { }".as_slice(), asset.bytes);
+                assert_eq!(Some("text/css"), asset.content_type.as_deref());
+            }
+        }
+
+        let requests = server.server().received_requests().await.unwrap();
+        let count = |route: &str| requests.iter().filter(|request| request.url.path() == route).count();
+        assert_eq!((1, 2, 2), (count("/app.css"), count("/live.css"), count("/stale.css")));
+        assert!(requests.last().unwrap().headers.contains_key("if-none-match"));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        // Room for a single asset: storing another one evicts the first.
+        let client = server.builder().asset_cache(&dir).asset_cache_size(10).build().unwrap();
+        for route in ["/app.css", "/stale.css", "/app.css"] {
+            client.fetch_asset(&format!("{}{}", server.uri(), route)).await.unwrap();
+        }
+        let files = std::fs::read_dir(&dir).unwrap().map(|file| file.unwrap().file_name().into_string().unwrap()).collect::<Vec<_>>();
+        assert_eq!(2, files.len(), "{:?}", files);
+        let requests = server.server().received_requests().await.unwrap();
+        assert_eq!(3, requests.iter().filter(|request| request.url.path() == "/app.css").count());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_ping_domains() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, ResponseTemplate};
+        use crate::test_util::MockShindan;
+
+        let server = MockShindan::start().await;
+        Mock::given(method("HEAD")).respond_with(ResponseTemplate::new(200)).mount(server.server()).await;
+        let mirror: ShindanDomain = server.uri().parse().unwrap();
+        let unreachable = ShindanDomain::custom("http://127.0.0.1:1/").unwrap();
+
+        let report = server.client().unwrap().ping_domains([unreachable.clone(), mirror.clone(), ShindanDomain::En]).await;
+        assert_eq!(unreachable, report.domains[0].domain);
+        assert!(report.domains[0].error.is_some() && report.domains[0].total().is_none());
+        assert!(report.domains[1].error.is_none() && report.domains[1].total().is_some());
+        assert!(report.fastest().unwrap().error.is_none());
+        // The client's own domain is measured at its base URL, the mock server.
+        assert!(report.domains[2].error.is_none() && report.domains[2].total().is_some());
+        assert_eq!(4, server.server().received_requests().await.unwrap().len());
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_circuit_breaker() {
+        use std::time::Duration;
+        use wiremock::matchers::method;
+        use wiremock::{Mock, ResponseTemplate};
+        use crate::CircuitOpen;
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .with_priority(1)
+            .mount(server.server())
+            .await;
+        let client = server.builder().circuit_breaker(2, Duration::from_millis(100)).build().unwrap();
+
+        assert!(client.get_metadata(PLAIN_TEXT.id).await.is_err());
+        assert!(client.get_metadata(PLAIN_TEXT.id).await.is_err());
+        let err = client.get_metadata(PLAIN_TEXT.id).await.unwrap_err();
+        assert!(err.chain().any(|cause| cause.is::<CircuitOpen>()));
+        assert_eq!(2, server.server().received_requests().await.unwrap().len());
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(PLAIN_TEXT.title, client.get_metadata(PLAIN_TEXT.id).await.unwrap().title);
+        assert!(client.get_metadata(PLAIN_TEXT.id).await.is_ok());
+    }
+
+    #[test]
+    fn test_is_login_redirect() {
+        use reqwest::Url;
+        use crate::http_utils::is_login_redirect;
+
+        assert!(is_login_redirect(&Url::parse("https://shindanmaker.com/login").unwrap()));
+        assert!(is_login_redirect(&Url::parse("https://shindanmaker.com/login/?next=mypage").unwrap()));
+        assert!(!is_login_redirect(&Url::parse("https://shindanmaker.com/mypage").unwrap()));
+        assert!(!is_login_redirect(&Url::parse("https://shindanmaker.com/login_help").unwrap()));
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_relogin_on_login_redirect() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, ResponseTemplate};
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        Mock::given(method("GET"))
+            .and(path(format!("/{}", PLAIN_TEXT.id)))
+            .respond_with(ResponseTemplate::new(302).insert_header("Location", "/login"))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(server.server())
+            .await;
+
+        let logins = Arc::new(AtomicUsize::new(0));
+        let counter = logins.clone();
+        let client = server
+            .builder()
+            .session_refresher(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async { Ok("fresh".to_string()) }
+            })
+            .build()
+            .unwrap();
+
+        assert_eq!(PLAIN_TEXT.title, client.get_metadata(PLAIN_TEXT.id).await.unwrap().title);
+        assert_eq!(1, logins.load(Ordering::SeqCst));
+
+        assert!(client.get_metadata(PLAIN_TEXT.id).await.is_ok());
+        assert_eq!(1, logins.load(Ordering::SeqCst));
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_translator() {
+        use anyhow::Result;
+        use async_trait::async_trait;
+        use crate::Translator;
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        #[derive(Debug)]
+        struct Shout;
+
+        #[async_trait]
+        impl Translator for Shout {
+            async fn translate(&self, texts: &[String]) -> Result<Vec<String>> {
+                Ok(texts.iter().map(|text| text.to_uppercase()).collect())
+            }
+        }
+
+        let server = MockShindan::start().await;
+        let client = server.builder().translator(Shout).build().unwrap();
+
+        let result = client.get_result(PLAIN_TEXT.id, "test_user").await.unwrap();
+        let translation = result.translation.unwrap();
+        assert_eq!(result.segments.len(), translation.len());
+        assert_eq!(result.segments.to_string().to_uppercase(), translation.to_string());
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_humanized_delays() {
+        use std::time::{Duration, Instant};
+        use futures::StreamExt;
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let delay = Duration::from_millis(50);
+        let client = server
+            .builder()
+            .submit_delay(delay, delay * 2)
+            .batch_spacing(delay, delay)
+            .build()
+            .unwrap();
+
+        let start = Instant::now();
+        client.get_result(PLAIN_TEXT.id, "test_user").await.unwrap();
+        assert!(start.elapsed() >= delay);
+
+        let start = Instant::now();
+        let jobs = ["a", "b", "c"].map(|name| (PLAIN_TEXT.id.to_string(), name.to_string()));
+        let results: Vec<_> = client.submit_stream(jobs, 3).collect().await;
+        assert!(results.iter().all(|(_, result)| result.is_ok()));
+        assert!(start.elapsed() >= delay * 3);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_crawl_budget() {
+        use std::time::{Duration, Instant};
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let period = Duration::from_millis(200);
+        assert!(ShindanClient::new(ShindanDomain::En).unwrap().crawl_budget.is_none());
+        let client = server.builder().crawl_budget(2, period).polite(true).build().unwrap();
+
+        let start = Instant::now();
+        client.get_title(PLAIN_TEXT.id).await.unwrap();
+        client.clone().get_title(PLAIN_TEXT.id).await.unwrap();
+        assert!(start.elapsed() < period);
+
+        client.get_title(PLAIN_TEXT.id).await.unwrap();
+        assert!(start.elapsed() >= period);
+    }
+
+    #[cfg(all(feature = "cassette", feature = "test-util"))]
+    #[tokio::test]
+    async fn test_cassette_replays_without_network() {
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+        use crate::{Cassette, CassetteMode};
+
+        let path = std::env::temp_dir().join(format!("shindan-maker-cassette-{}.json", std::process::id()));
+
+        let server = MockShindan::start().await;
+        let recorded = server
+            .builder()
+            .cassette(Cassette::record(&path))
+            .build()
+            .unwrap()
+            .get_segments(PLAIN_TEXT.id, "test_user")
+            .await
+            .unwrap();
+        let base_url = format!("{}/", server.uri());
+        drop(server);
+
+        let cassette = Cassette::replay(&path).unwrap();
+        assert_eq!(CassetteMode::Replay, cassette.mode());
+        assert_eq!(2, cassette.interactions().len());
+
+        let client = ShindanClient::builder(ShindanDomain::En)
+            .base_url(&base_url)
+            .cassette(cassette)
+            .build()
+            .unwrap();
+        assert_eq!(recorded, client.get_segments(PLAIN_TEXT.id, "test_user").await.unwrap());
+        assert!(client.get_segments(PLAIN_TEXT.id, "someone_else").await.is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_selector_config_falls_back_in_order() {
+        use scraper::Html;
+        use crate::html_utils;
+        use crate::SelectorConfig;
+        use crate::selectors::Selectors;
+
+        let config = SelectorConfig::from_toml(r##"
+            title = ["#shindanTitle", "h1.title"]
+            result = ["#post_display", "div.result"]
+        "##).unwrap();
+        assert_eq!(SelectorConfig::default().token, config.token);
+
+        let selectors = Selectors::compile(&config).unwrap();
+        let html = Html::parse_document(r#"<h1 class="title">Fantasy Stats</h1><div class="result">STR: 10</div>"#);
+
+        assert_eq!("Fantasy Stats", html_utils::extract_title(&selectors, &html).unwrap());
+        assert_eq!("STR: 10", html_utils::get_segments(&selectors, Default::default(), None, &html).unwrap().to_string());
+
+        let invalid = SelectorConfig { result: vec!["div[".into()], ..SelectorConfig::default() };
+        assert!(Selectors::compile(&invalid).is_err());
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_diagnose_reports_failing_steps() {
+        use crate::SelectorConfig;
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+
+        let diagnosis = server.client().unwrap().diagnose(PLAIN_TEXT.id).await.unwrap();
+        assert!(diagnosis.is_healthy(), "{}", diagnosis);
+
+        let selectors = SelectorConfig { title: vec!["h2.title".into()], ..SelectorConfig::default() };
+        let client = server.builder().selectors(selectors).build().unwrap();
+        let diagnosis = client.diagnose(PLAIN_TEXT.id).await.unwrap();
+
+        let failures: Vec<_> = diagnosis.failures().map(|step| step.name.as_str()).collect();
+        assert_eq!(vec!["title"], failures);
+    }
+
+    #[cfg(feature = "segments")]
+    #[test]
+    fn test_chart_scripts() {
+        use scraper::Html;
+        use crate::selectors::SELECTORS;
+        use crate::html_utils::{get_chart, value_after};
+
+        let chart = |script: &str| get_chart(&SELECTORS, &Html::parse_document(&format!("<script>{}</script>", script)));
+
+        let radar = chart(include_str!("../tests/fixtures/charts/radar.js")).unwrap();
+        assert_eq!("radar", radar.kind);
+        assert_eq!(vec!["STR", "DEX", "INT", "LUK"], radar.labels);
+        assert_eq!(1, radar.datasets.len());
+        assert_eq!(Some("status"), radar.datasets[0].label.as_deref());
+        assert_eq!(vec![72.0, 48.5, 100.0, 0.0], radar.datasets[0].data);
+
+        let bar = chart(include_str!("../tests/fixtures/charts/bar_datasets.js")).unwrap();
+        assert_eq!("bar", bar.kind);
+        assert_eq!(vec!["Mon", "Tue", "Wed"], bar.labels);
+        let labels: Vec<_> = bar.datasets.iter().map(|dataset| dataset.label.as_deref()).collect();
+        assert_eq!(vec![Some("today"), None, Some("tomorrow")], labels);
+        assert_eq!(vec![1.0, 5.0, 9.0], bar.datasets[1].data);
+
+        assert_eq!(None, chart(include_str!("../tests/fixtures/charts/no_chart.js")));
+
+        assert_eq!(Some(serde_json::json!(["a", "b"])), value_after("labels: ['a', 'b']", "labels"));
+        assert_eq!(Some(serde_json::json!("x")), value_after(r#""label" : "x", data"#, "label"));
+        assert_eq!(None, value_after("labels: getLabels()", "labels"));
+        assert_eq!(None, value_after("data: [1, 2", "data"));
+        assert_eq!(None, value_after("no key here", "labels"));
+    }
+
+    #[cfg(feature = "segments")]
+    #[test]
+    fn test_lazy_images() {
+        use serde_json::json;
+        use crate::{Segment, Segments};
+
+        let html = concat!(
+            r#"<span id="post_display">A"#,
+            r#"<img src="data:image/gif;base64,R0" data-src="small.png" srcset="small.png 320w, large.png 1280w">"#,
+            r#"<picture><source srcset="a.webp 1x, a@2x.webp 2x" type="image/webp"><img src="a.png"></picture>"#,
+            "</span>",
+        );
+
+        let expected = Segments(vec![
+            Segment::text("A"),
+            Segment::new("image", json!({"file": "large.png", "candidates": ["small.png", "large.png"]})),
+            Segment::new("image", json!({"file": "a@2x.webp", "candidates": ["a.webp", "a@2x.webp", "a.png"]})),
+        ]);
+        assert_eq!(expected, Segments::from_html(html).unwrap());
+
+        #[cfg(feature = "streaming")]
+        {
+            use crate::selectors::SELECTORS;
+            use crate::stream_parse::SegmentExtractor;
+
+            let mut extractor = SegmentExtractor::new(&SELECTORS, Default::default(), None);
+            extractor.write(html.as_bytes()).unwrap();
+            assert_eq!(expected, extractor.finish().unwrap());
+        }
+    }
+
+    #[cfg(feature = "segments")]
+    #[test]
+    fn test_image_alt() {
+        use crate::Segments;
+
+        let html = r#"<span id="post_display">Your familiar:<br><img src="fox.png" alt=" A red fox " title="Fox"><img src="owl.png" alt=" "></span>"#;
+        let segments = Segments::from_html(html).unwrap();
+
+        assert_eq!((Some("A red fox"), Some("Fox")), (segments[2].data["alt"].as_str(), segments[2].data["title"].as_str()));
+        assert_eq!((None, None), (segments[3].data.get("alt"), segments[3].data.get("title")));
+    }
+
+    #[cfg(feature = "segments")]
+    #[test]
+    fn test_media_segments() {
+        use serde_json::json;
+        use crate::{Segment, Segments};
+
+        let html = concat!(
+            r#"<span id="post_display">Theme song:<br>"#,
+            r#"<video poster="cover.jpg"><source src="song.webm" type="video/webm"><source src="song.mp4"></video>"#,
+            r#"<audio src="jingle.mp3"></audio><audio></audio>"#,
+            "</span>",
+        );
+
+        let expected = Segments(vec![
+            Segment::text("Theme song:"),
+            Segment::text("\n"),
+            Segment::new("video", json!({"file": "song.webm", "sources": ["song.webm", "song.mp4"], "poster": "cover.jpg"})),
+            Segment::audio("jingle.mp3"),
+        ]);
+        assert_eq!(expected, Segments::from_html(html).unwrap());
+        assert_eq!(1, Segments::from_html_lenient(html).warnings.len());
+
+        #[cfg(feature = "streaming")]
+        {
+            use crate::selectors::SELECTORS;
+            use crate::stream_parse::SegmentExtractor;
+
+            let mut extractor = SegmentExtractor::new(&SELECTORS, Default::default(), None);
+            extractor.write(html.as_bytes()).unwrap();
+            assert_eq!(expected, extractor.finish().unwrap());
+        }
+    }
+
+    #[cfg(feature = "segments")]
+    #[test]
+    fn test_table_segments() {
+        use crate::{PlainTextOptions, Segment, Segments, TableSegment};
+
+        let html = concat!(
+            r#"<span id="post_display">Stats:<table>"#,
+            "<thead><tr><th>Stat</th><th>Rank</th></tr></thead>",
+            "<tbody><tr><td>STR</td><td> S &amp; <b>up</b> </td></tr><tr><td>INT</td><td>E</td></tr></tbody>",
+            "</table>Good luck</span>",
+        );
+
+        let table = TableSegment {
+            headers: vec!["Stat".to_string(), "Rank".to_string()],
+            rows: vec![vec!["STR".to_string(), "S & up".to_string()], vec!["INT".to_string(), "E".to_string()]],
+        };
+        let expected = Segments(vec![Segment::text("Stats:"), Segment::table(table), Segment::text("Good luck")]);
+        assert_eq!(expected, Segments::from_html(html).unwrap());
+        assert_eq!(
+            "Stats:\nStat | Rank\n-----+-------\nSTR  | S & up\nINT  | E\nGood luck",
+            expected.to_plain_text(&PlainTextOptions::default())
+        );
+
+        #[cfg(feature = "streaming")]
+        {
+            use crate::selectors::SELECTORS;
+            use crate::stream_parse::SegmentExtractor;
+
+            let mut extractor = SegmentExtractor::new(&SELECTORS, Default::default(), None);
+            extractor.write(html.as_bytes()).unwrap();
+            assert_eq!(expected, extractor.finish().unwrap());
+        }
+    }
+
+    #[cfg(feature = "segments")]
+    #[test]
+    fn test_lists() {
+        use crate::{PlainTextOptions, Segments};
+
+        let html = concat!(
+            r#"<span id="post_display">Your quests:<ul>"#,
+            "<li>Slay the <b>dragon</b></li>",
+            r#"<li>Find &amp; return the sword<ol start="3"><li>Ask the smith</li><li>Pay him</li></ol></li>"#,
+            "</ul>Good luck</span>",
+        );
+
+        let expected = "Your quests:\n• Slay the dragon\n• Find & return the sword\n  3. Ask the smith\n  4. Pay him\nGood luck";
+        let segments = Segments::from_html(html).unwrap();
+        assert_eq!(expected, segments.to_plain_text(&PlainTextOptions::default()));
+
+        #[cfg(feature = "streaming")]
+        {
+            use crate::selectors::SELECTORS;
+            use crate::stream_parse::SegmentExtractor;
+
+            let mut extractor = SegmentExtractor::new(&SELECTORS, Default::default(), None);
+            extractor.write(html.as_bytes()).unwrap();
+            assert_eq!(segments, extractor.finish().unwrap());
+        }
+    }
+
+    #[cfg(feature = "segments")]
+    #[test]
+    fn test_entity_decoding() {
+        use crate::{Segment, Segments};
+
+        let html = r#"<span id="post_display">Tom &amp;amp; Jerry &amp;lt;3 &amp;#9733;&amp;#x2606; &amp;hearts;&amp;nbsp;R&amp;D &amp;bogus;</span>"#;
+        let expected = Segments(vec![Segment::text("Tom & Jerry <3 ★☆ ♥ R&D &bogus;")]);
+        assert_eq!(expected, Segments::from_html(html).unwrap());
+
+        #[cfg(feature = "streaming")]
+        {
+            use crate::selectors::SELECTORS;
+            use crate::stream_parse::SegmentExtractor;
+
+            let mut extractor = SegmentExtractor::new(&SELECTORS, Default::default(), None);
+            extractor.write(html.as_bytes()).unwrap();
+            assert_eq!(expected, extractor.finish().unwrap());
+        }
+    }
+
+    #[cfg(all(feature = "segments", feature = "test-util"))]
+    #[tokio::test]
+    async fn test_entity_decoding_keeps_name() {
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let client = server.client().unwrap();
+
+        for name in ["R&amp;D", "&#9733; &amp;amp;"] {
+            let segments = client.get_segments(PLAIN_TEXT.id, name).await.unwrap();
+            assert_eq!(Some(format!("{}'s stats:", name).as_str()), segments[0].as_str());
+
+            let result = client.get_result(PLAIN_TEXT.id, name).await.unwrap();
+            assert!(result.segments.to_string().starts_with(&format!("{}'s stats:", name)));
+        }
+    }
+
+    #[cfg(feature = "segments")]
+    #[test]
+    fn test_preserve_whitespace() {
+        use crate::{ParseOptions, PlainTextOptions, Segments};
+
+        let html = "<span id=\"post_display\">&nbsp;&nbsp;/\\_/\\  <br>&nbsp;( o.o )<br><br>&nbsp;&nbsp;&gt; ^ &lt;</span>";
+        let options = ParseOptions { preserve_whitespace: true };
+        let segments = Segments::from_html_with(html, options).unwrap();
+
+        let text = segments.to_plain_text(&PlainTextOptions { preserve_whitespace: true, max_width: Some(4), ..Default::default() });
+        assert_eq!("  /\\_/\\  \n ( o.o )\n\n  > ^ <", text);
+    }
+
+    #[cfg(feature = "html")]
+    #[test]
+    fn test_result_html_fragment() {
+        use crate::html_utils;
+        use crate::selectors::SELECTORS;
+
+        let page = concat!(
+            r#"<html><body><div id="title_and_result"><span id="post_display">"#,
+            r#"<b class="c" onclick="steal()">Alice</b> &amp; co<br>"#,
+            r#"<span class="shindanEffects" data-mode="ef_typing">...</span><noscript>is <i>lucky</i></noscript>"#,
+            r#"<script>steal()</script><a href=" java&#9;script:steal()">x</a><img src="a.png">"#,
+            "</span></div></body></html>",
+        );
+
+        assert_eq!(
+            r#"<b class="c">Alice</b> &amp; co<br>is <i>lucky</i><a>x</a><img src="a.png">"#,
+            html_utils::get_result_html_fragment(&SELECTORS, page).unwrap()
+        );
+    }
+
+    #[cfg(feature = "html")]
+    #[test]
+    fn test_sanitize_fragment() {
+        use crate::sanitize::sanitize_fragment;
+
+        let cases = [
+            (r#"<a href="data:text/html,<script>steal()</script>">x</a>"#, "<a>x</a>"),
+            (r#"<img src="data:image/svg+xml;base64,PHN2Zz4=">"#, "<img>"),
+            (r#"<a href="VBScript:steal()">x</a>"#, "<a>x</a>"),
+            (r#"<img srcset="a.png 1x, vbscript:steal() 2x">"#, "<img>"),
+            (r#"<img src="mailto:a@example.com">"#, "<img>"),
+            (r#"<span style="background: url(javascript:steal())">x</span>"#, "<span>x</span>"),
+            (r#"<b style="position: fixed; inset: 0">x</b>"#, "<b>x</b>"),
+            (r#"<svg><a><animate attributeName="href" values="javascript:steal()"/><text>x</text></a></svg>"#, ""),
+            (r#"<svg><set attributeName="onmouseover" to="steal()"/></svg>y"#, "y"),
+            (r#"<font color="red" onclick="steal()">x</font><marquee>y</marquee>"#, "xy"),
+            (r#"<a href="https://example.com/a?b=c:d">x</a>"#, r#"<a href="https://example.com/a?b=c:d">x</a>"#),
+            (r#"<a href="mailto:a@example.com">x</a>"#, r#"<a href="mailto:a@example.com">x</a>"#),
+            (r#"<img src="/images/a:b.png">"#, r#"<img src="/images/a:b.png">"#),
+        ];
+        for (html, expected) in cases {
+            assert_eq!(expected, sanitize_fragment(html), "{}", html);
+        }
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_fit_to_card() {
+        use crate::image::fit_to_card;
+        use crate::ScreenshotOptions;
+
+        let page = r#"<html><head><title>x</title></head><body><main id="main"><div id="title_and_result"></div></main></body></html>"#;
+        let fitted = fit_to_card(page, &ScreenshotOptions { padding: 16, width: Some(600) });
+        assert!(fitted.contains("#card_frame { box-sizing: content-box; width: 600px; padding: 16px; }"));
+        assert!(fitted.contains(r#"<main id="main"><div id="card_frame"><div id="title_and_result"></div></div></main>"#));
+        let style = fitted.find("<style>").unwrap();
+        assert!(fitted.find("<title>").unwrap() < style && style < fitted.find("</head>").unwrap());
+
+        let fitted = fit_to_card(page, &ScreenshotOptions::default());
+        assert!(fitted.contains("width: 750px; padding: 0px;"));
+        assert_eq!(1, fitted.matches("<style>").count());
+    }
+
+    #[cfg(all(feature = "image", feature = "test-util"))]
+    #[tokio::test]
+    async fn test_render_images_batch() {
+        use crate::test_util::{MockShindan, IMAGES, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let client = server.client().unwrap();
+        let jobs = [(PLAIN_TEXT.id, "Alice"), ("9999999", "Bob"), ("not an id", "Carol"), (IMAGES.id, "Dave")];
+        let images = client.render_batch_with(jobs, 2, |html| async move { Ok(html.into_bytes()) }).await;
+
+        assert_eq!(4, images.len());
+        let page = |index: usize| String::from_utf8(images[index].as_ref().unwrap().clone()).unwrap();
+        assert!(page(0).contains("Alice") && !page(0).contains("Dave"));
+        assert!(format!("{:#}", images[1].as_ref().unwrap_err()).contains("No shindan found"));
+        assert!(images[2].is_err());
+        assert!(page(3).contains("Dave") && !page(3).contains("Alice"));
+    }
+
+    #[cfg(all(feature = "image", feature = "test-util"))]
+    #[tokio::test]
+    async fn test_render_images_batch_limits_submissions() {
+        use std::sync::Arc;
+        use std::time::Duration;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use wiremock::{Mock, Request, Respond, ResponseTemplate};
+        use wiremock::matchers::{method, path};
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        /// Counts the jobs between their submission and the end of their render.
+        struct Counting {
+            active: Arc<AtomicUsize>,
+            max: Arc<AtomicUsize>,
+        }
+
+        impl Respond for Counting {
+            fn respond(&self, _: &Request) -> ResponseTemplate {
+                let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max.fetch_max(active, Ordering::SeqCst);
+                ResponseTemplate::new(200)
+                    .set_delay(Duration::from_millis(20))
+                    .set_body_raw(PLAIN_TEXT.result_for("test_user"), "text/html; charset=UTF-8")
+            }
+        }
+
+        let server = MockShindan::start().await;
+        let (active, max) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        Mock::given(method("POST"))
+            .and(path(format!("/{}", PLAIN_TEXT.id)))
+            .respond_with(Counting { active: active.clone(), max: max.clone() })
+            .with_priority(1)
+            .mount(server.server())
+            .await;
+
+        let client = server.client().unwrap();
+        let jobs = (0..6).map(|index| (PLAIN_TEXT.id, format!("user{}", index)));
+        let images = client.render_batch_with(jobs, 2, |html| {
+            let active = active.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                active.fetch_sub(1, Ordering::SeqCst);
+                Ok(html.into_bytes())
+            }
+        }).await;
+
+        assert!(images.iter().all(Result::is_ok));
+        assert_eq!(2, max.load(Ordering::SeqCst));
+    }
+
+    #[cfg(feature = "animated")]
+    #[test]
+    fn test_effect_frame() {
+        use crate::animation::effect_frame;
+
+        let mut rng = fastrand::Rng::with_seed(1);
+        assert_eq!(r#"lu<span style="visibility: hidden">cky</span>"#, effect_frame("ef_typing", "lucky", 0.4, &mut rng));
+        assert_eq!("lucky", effect_frame("ef_typing", "lucky", 1.0, &mut rng));
+
+        let shuffled = effect_frame("ef_shuffle", "lucky day", 0.2, &mut rng);
+        assert_eq!(9, shuffled.chars().count());
+        assert!(shuffled.starts_with("lu"));
+        assert_eq!(Some(5), shuffled.find(' '));
+        assert!(shuffled.chars().filter(|c| !c.is_whitespace()).all(|c| "luckyda".contains(c)));
+        assert_eq!("lucky day", effect_frame("ef_shuffle", "lucky day", 1.0, &mut rng));
+
+        // Markup is shown finished throughout, and so are unknown effects.
+        assert_eq!("<b>lucky</b>", effect_frame("ef_typing", "<b>lucky</b>", 0.2, &mut rng));
+        assert_eq!("a &amp; b", effect_frame("ef_shuffle", "a &amp; b", 0.2, &mut rng));
+        assert_eq!("lucky", effect_frame("ef_unknown", "lucky", 0.2, &mut rng));
+    }
+
+    #[cfg(feature = "animated")]
+    #[test]
+    fn test_encode_gif() {
+        use std::time::Duration;
+        use crate::image::Frame;
+        use crate::animation::encode_gif;
+
+        let wide = Frame { width: 2, height: 1, pixels: vec![255, 0, 0, 255, 0, 0] };
+        let tall = Frame { width: 1, height: 2, pixels: vec![0, 0, 255, 0, 0, 255] };
+        let gif = encode_gif(&[(wide, Duration::from_millis(80)), (tall, Duration::from_secs(2))]).unwrap();
+
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(gif.as_slice()).unwrap();
+        assert_eq!((2, 2), (decoder.width(), decoder.height()));
+
+        // Each frame takes the size of the largest, padded with white.
+        let pixel = |buffer: &[u8], index: usize| buffer[index * 4..index * 4 + 3].to_vec();
+        let close = |pixel: Vec<u8>, expected: [u8; 3]| pixel.iter().zip(expected).all(|(&a, b)| a.abs_diff(b) < 16);
+        let first = decoder.read_next_frame().unwrap().unwrap();
+        assert_eq!(8, first.delay);
+        assert!(close(pixel(&first.buffer, 1), [255, 0, 0]));
+        assert!(close(pixel(&first.buffer, 2), [255, 255, 255]) && close(pixel(&first.buffer, 3), [255, 255, 255]));
+        let second = decoder.read_next_frame().unwrap().unwrap();
+        assert_eq!(200, second.delay);
+        assert!(close(pixel(&second.buffer, 2), [0, 0, 255]));
+        assert!(close(pixel(&second.buffer, 1), [255, 255, 255]) && close(pixel(&second.buffer, 3), [255, 255, 255]));
+        assert!(decoder.read_next_frame().unwrap().is_none());
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn test_encode_png() {
+        use std::io::Read;
+        use flate2::read::ZlibDecoder;
+        use crate::image::Frame;
+        use crate::png::encode_png;
+
+        let frame = Frame { width: 2, height: 2, pixels: vec![255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255] };
+        let png = encode_png(&frame).unwrap();
+        assert_eq!(b"\x89PNG\r\n\x1a\n", &png[..8]);
+
+        // Every chunk is its length, type, data and the CRC of its type and data.
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let (kind, data) = (&rest[4..8], &rest[8..8 + len]);
+            let crc = u32::from_be_bytes(rest[8 + len..12 + len].try_into().unwrap());
+            assert_eq!(crc32fast::hash(&rest[4..8 + len]), crc);
+            chunks.push((kind.to_vec(), data.to_vec()));
+            rest = &rest[12 + len..];
+        }
+
+        let kinds: Vec<&[u8]> = chunks.iter().map(|(kind, _)| kind.as_slice()).collect();
+        assert_eq!(vec![&b"IHDR"[..], b"IDAT", b"IEND"], kinds);
+        assert_eq!(vec![0, 0, 0, 2, 0, 0, 0, 2, 8, 2, 0, 0, 0], chunks[0].1);
+
+        let mut rows = Vec::new();
+        ZlibDecoder::new(chunks[1].1.as_slice()).read_to_end(&mut rows).unwrap();
+        assert_eq!(vec![0, 255, 0, 0, 0, 255, 0, 0, 0, 0, 255, 255, 255, 255], rows);
+        assert!(chunks[2].1.is_empty());
+    }
+
+    #[cfg(all(feature = "test-util", feature = "html"))]
+    #[tokio::test]
+    async fn test_result_extras() {
+        use crate::site_day::site_today;
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let server = MockShindan::start().await;
+        let plain = server.client().unwrap().get_html_str(PLAIN_TEXT.id, "test_user").await.unwrap();
+        assert!(!plain.contains("shindanResultDescription\""));
+        assert!(!plain.contains("<footer"));
+        assert!(!plain.contains("RESULT_EXTRAS"));
+
+        let client = server.builder().include_description(true).include_attribution(true).build().unwrap();
+        let html = client.get_html_str(PLAIN_TEXT.id, "test_user").await.unwrap();
+        let url = format!("{}/{}", server.uri(), PLAIN_TEXT.id);
+
+        assert!(html.contains(r#"<div class="shindanResultDescription">Find out your fantasy stats!"#));
+        assert!(html.contains(&format!(
+            r#"<footer class="shindanResultAttribution"><a href="{}">{}</a> · fixture_author · {}</footer></div>"#,
+            url, url, site_today().format("%Y-%m-%d")
+        )));
+        assert_eq!(Some("fixture_author"), client.fetch_page(PLAIN_TEXT.id).await.unwrap().author());
+    }
+
+    #[cfg(feature = "html")]
+    #[test]
+    fn test_html_accessibility() {
+        use crate::html_utils;
+        use crate::selectors::SELECTORS;
+
+        let page = concat!(
+            r#"<html lang="ko"><body><div id="title_and_result">"#,
+            r#"<div class="shindanTitleImageContainer"><a href="/1"><img src="title.png"></a></div>"#,
+            r#"<span id="post_display">Alice<img src="a.png"><img src="b.png" alt="B"></span>"#,
+            r#"</div><h1 id="shindanTitle" data-shindan_title="Cat &quot;Luck&quot;">x</h1></body></html>"#,
+        );
+        let html = html_utils::get_html_str(&SELECTORS, "1", page, false).unwrap();
+
+        assert!(html.contains(r#"<h1 class="shindanTitleImageContainer"><a href="/1">"#));
+        assert!(html.contains("</a></h1>"));
+        assert!(html.contains(r#"<img alt="Cat &quot;Luck&quot;" src="title.png">"#));
+        assert!(html.contains(r#"Alice<img alt="" src="a.png"><img "#));
+        assert_eq!(3, html.matches(" alt=").count());
+        assert!(html.contains("<title>Cat &quot;Luck&quot; | ShindanMaker</title>"));
+        assert!(html.contains(r#"<main id="main">"#));
+
+        let titled = r#"<div id="title_and_result"><div id="shindanResultTitle">Cat luck</div><span id="post_display">Alice</span></div>"#;
+        let html = html_utils::get_html_str(&SELECTORS, "1", titled, false).unwrap();
+        assert!(html.contains(r#"<h1 id="shindanResultTitle">Cat luck</h1>"#));
+        assert_eq!(Some("ko".to_string()), html_utils::get_lang(page));
+        assert_eq!(None, html_utils::get_lang(r#"<html lang='en"><script>'><body></body></html>"#));
+    }
+
+    #[cfg(all(feature = "segments", feature = "html"))]
+    #[test]
+    fn test_card_html() {
+        use crate::{CardTheme, Segment, Segments, TableSegment};
+
+        let segments = Segments(vec![
+            Segment::text("Rank\n"),
+            Segment::table(TableSegment { headers: vec!["ATK".to_string()], rows: vec![vec!["<9>".to_string()]] }),
+        ]);
+        let theme = CardTheme {
+            title: Some("Stats & more".to_string()),
+            accent_color: "red;}</style><script>".to_string(),
+            preserve_whitespace: true,
+            ..Default::default()
+        };
+        let html = segments.to_card_html(&theme);
+
+        assert!(html.contains("<title>Stats &amp; more</title>"));
+        assert!(html.contains("--main-blue: redstylescript; }"));
+        assert!(html.contains("white-space: pre;"));
+        assert!(html.contains(r#"<span id="shindanResult">Rank<br><table"#));
+        assert!(html.contains(">&lt;9&gt;</td></tr></table></span>"));
+        assert!(!html.contains("<!-- TITLE_AND_RESULT -->"));
+    }
+
+    #[cfg(feature = "email")]
+    #[test]
+    fn test_email_html() {
+        use serde_json::json;
+        use crate::{render_email, EmailOptions, EmailSection, Segment, Segments};
+
+        let fortune = Segments(vec![
+            Segment::text("Your familiar:\n"),
+            Segment::new("image", json!({"file": "https://example.com/fox.png?a=1&b=\"2\"", "alt": "A <red> fox"})),
+            Segment::link("More foxes", "https://example.com/foxes?page=2&sort=new"),
+            Segment::new("video", json!({"file": "https://example.com/fox.mp4", "poster": "https://example.com/fox.jpg"})),
+            Segment::audio("https://example.com/yip.mp3"),
+        ]);
+        let options = EmailOptions { width: 400, link_color: "#0000ff\"><script>".to_string(), ..Default::default() };
+        let html = render_email(&[EmailSection { heading: Some("Fox & friends"), segments: &fortune }], &options);
+
+        assert!(html.contains(">Fox &amp; friends</td></tr>"));
+        assert!(html.contains(concat!(
+            r#"Your familiar:<br><img src="https://example.com/fox.png?a=1&amp;b=&quot;2&quot;" alt="A &lt;red&gt; fox" width="352" "#,
+            r#"style="display:block;width:100%;max-width:352px;height:auto;border:0;margin:8px 0;">"#,
+        )));
+        assert!(html.contains(concat!(
+            r#"<a href="https://example.com/foxes?page=2&amp;sort=new" style="color:#0000ffscript;text-decoration:underline;">"#,
+            "More foxes</a>",
+        )));
+        assert!(html.contains(r#"<a href="https://example.com/fox.mp4" style="color:#0000ffscript;text-decoration:underline;"><img src="https://example.com/fox.jpg" alt="video" width="352""#));
+        assert!(html.contains(r#"text-decoration:underline;">▶ https://example.com/yip.mp3</a>"#));
+        assert!(!html.contains("<script>"));
+    }
+
+    #[cfg(feature = "segments")]
+    mod parser_properties {
+        use proptest::prelude::*;
+        use crate::Segments;
+
+        /// Fragments of result pages, valid or not, to be concatenated in any order.
+        const FRAGMENTS: &[&str] = &[
+            "STR: 10", "<br>", "<br/>", "<img>", "<img data-src=\"a.png\">", "<img src='b.png'>", "<img data-src",
+            "<span>", "</span>", "<b>", "</b>", "<div>", "</div>", "&amp;", "&nbsp;", "&#x263A;", "&#99999999;",
+            "&bogus;", "&#x", "&", ";", "<", ">", "<!--", "-->", "<script>", "</script>", "<span id=\"post_display\">",
+        ];
+
+        fn result_page() -> impl Strategy<Value = String> {
+            prop::collection::vec(prop::sample::select(FRAGMENTS), 0..40)
+                .prop_map(|fragments| format!("<span id=\"post_display\">{}", fragments.concat()))
+        }
+
+        proptest! {
+            #[test]
+            fn lenient_parse_never_panics(html in ".*") {
+                let parsed = Segments::from_html_lenient(&html);
+                if Segments::from_html(&html).is_err() {
+                    prop_assert!(!parsed.warnings.is_empty());
+                }
+            }
+
+            #[test]
+            fn strict_and_lenient_parses_agree(html in result_page(), cut in 0usize..400) {
+                let truncated: String = html.chars().take(cut).collect();
+
+                let lenient = Segments::from_html_lenient(&truncated);
+                match Segments::from_html(&truncated) {
+                    Ok(segments) => prop_assert_eq!(segments, lenient.segments),
+                    Err(_) => prop_assert!(!lenient.warnings.is_empty()),
+                }
+            }
+
+            #[cfg(feature = "streaming")]
+            #[test]
+            fn streaming_parse_never_panics(html in result_page(), chunk_size in 1usize..16) {
+                use crate::selectors::SELECTORS;
+                use crate::stream_parse::SegmentExtractor;
+
+                let mut extractor = SegmentExtractor::new(&SELECTORS, Default::default(), None);
+                for chunk in html.as_bytes().chunks(chunk_size) {
+                    extractor.write(chunk).unwrap();
+                }
+                extractor.finish().unwrap();
+            }
+        }
+    }
+
+    #[cfg(feature = "segments")]
+    #[test]
+    fn test_fixture_snapshots() {
+        use std::fs;
+        use scraper::Html;
+        use serde_json::json;
+        use crate::{html_utils, Segments};
+        use crate::selectors::SELECTORS;
+
+        insta::glob!("../tests/fixtures", "*.page.html", |path| {
+            let page = Html::parse_document(&fs::read_to_string(path).unwrap());
+            let result_path = path.to_string_lossy().replace(".page.html", ".result.html");
+            let result = fs::read_to_string(result_path).unwrap();
+
+            insta::assert_json_snapshot!(json!({
+                "title": html_utils::extract_title(&SELECTORS, &page).ok(),
+                "description": html_utils::extract_description(&SELECTORS, &page).ok(),
+                "segments": Segments::from_html(&result).ok(),
+            }));
+        });
+    }
+
+    #[cfg(all(feature = "server", feature = "test-util"))]
+    #[tokio::test]
+    async fn test_server_routes() {
+        use serde_json::{json, Value};
+        use tokio::net::TcpListener;
+        use crate::server;
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let mock = MockShindan::start().await;
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}/shindan/{}", listener.local_addr().unwrap(), PLAIN_TEXT.id);
+        let router = server::router(mock.client().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let http = reqwest::Client::new();
+        let run = |name: &str| {
+            http.post(format!("{}/run", base))
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(json!({ "name": name }).to_string())
+                .send()
+        };
+
+        let title = http.get(format!("{}/title", base)).send().await.unwrap().bytes().await.unwrap();
+        assert_eq!(PLAIN_TEXT.title, serde_json::from_slice::<Value>(&title).unwrap()["title"]);
+
+        let result = run("test_user").await.unwrap().bytes().await.unwrap();
+        assert_eq!("test_user", serde_json::from_slice::<Value>(&result).unwrap()["name"]);
+
+        let response = run(" ").await.unwrap();
+        assert_eq!(reqwest::StatusCode::BAD_REQUEST, response.status());
+    }
+
+    #[cfg(feature = "ffi")]
+    #[test]
+    fn test_ffi_reports_errors() {
+        use std::ffi::{CStr, CString};
+        use crate::ffi::*;
+
+        let domain = CString::new("xx").unwrap();
+        let client = unsafe { shindan_client_new(domain.as_ptr()) };
+
+        assert!(client.is_null());
+        let error = unsafe { CStr::from_ptr(shindan_last_error()) };
+        assert_eq!("Invalid domain", error.to_str().unwrap());
+
+        let domain = CString::new("en").unwrap();
+        let client = unsafe { shindan_client_new(domain.as_ptr()) };
+
+        assert!(!client.is_null());
+        assert!(shindan_last_error().is_null());
+        assert!(unsafe { shindan_get_title(client, std::ptr::null()) }.is_null());
+        unsafe { shindan_client_free(client) };
+    }
+
+    #[cfg(all(feature = "grpc", feature = "test-util"))]
+    #[tokio::test]
+    async fn test_grpc_service() {
+        use futures::StreamExt;
+        use tonic::{Code, Request};
+        use crate::grpc::{GrpcService, Outcome, RunBatchRequest, RunShindanRequest, ShindanService};
+        use crate::test_util::{MockShindan, PLAIN_TEXT};
+
+        let mock = MockShindan::start().await;
+        let service = GrpcService::new(mock.client().unwrap());
+        let run = |name: &str| Request::new(RunShindanRequest { id: PLAIN_TEXT.id.to_string(), name: name.to_string() });
+
+        let result = service.run_shindan(run("test_user")).await.unwrap().into_inner();
+        assert_eq!(PLAIN_TEXT.title, result.title);
+        assert_eq!("test_user", result.name);
+
+        let status = service.run_shindan(run(" ")).await.unwrap_err();
+        assert_eq!(Code::InvalidArgument, status.code());
+
+        let batch = RunBatchRequest {
+            id: PLAIN_TEXT.id.to_string(),
+            names: vec!["alice".to_string(), " ".to_string(), "bob".to_string()],
+            concurrency: 2,
+        };
+        let items: Vec<_> = service.run_batch(Request::new(batch)).await.unwrap().into_inner().collect().await;
+
+        assert_eq!(3, items.len());
+        let errors = items
+            .iter()
+            .filter(|item| matches!(item.as_ref().unwrap().outcome, Some(Outcome::Error(_))))
+            .count();
+        assert_eq!(1, errors);
+    }
+}
@@ -0,0 +1,278 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use futures::{stream, StreamExt};
+use std::hash::Hasher;
+use clap::{Parser, Subcommand};
+use anyhow::{anyhow, Context, Result};
+use std::io::{self, BufRead, BufReader};
+use shindan_maker::{format_result, PlainTextOptions, ShindanClient, ShindanDomain, ShindanResult};
+
+#[path = "fnv.rs"]
+mod fnv;
+
+use fnv::Fnv1a;
+
+/// Run ShindanMaker shindans from the command line.
+#[derive(Debug, Parser)]
+#[command(name = "shindan", version)]
+struct Cli {
+    /// The domain of ShindanMaker: jp, en, cn, kr, th, or the base URL of a mirror.
+    #[arg(short, long, global = true, default_value = "en")]
+    domain: ShindanDomain,
+
+    /// Print JSON instead of text.
+    #[arg(long, global = true, conflicts_with = "format")]
+    json: bool,
+
+    /// Format results with a template, e.g. "{{title}}: {{text | oneline}}".
+    #[arg(short, long, global = true)]
+    format: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run a shindan for a name, or for many names printed as JSON lines.
+    Run {
+        /// The ID of the shindan.
+        id: String,
+        /// The name to run the shindan for; without it, names are read one per line.
+        name: Option<String>,
+        /// Read the names from a file instead of stdin.
+        #[arg(long, conflicts_with = "name")]
+        names_file: Option<PathBuf>,
+        /// The maximum number of submissions in flight.
+        #[arg(short, long, default_value_t = 4)]
+        concurrency: usize,
+        /// Also write every result to this directory, as JSON and, with "cli-image", as an image.
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+    /// Print the title and description of a shindan.
+    Title {
+        /// The ID of the shindan.
+        id: String,
+    },
+    /// Search shindans by keyword.
+    Search {
+        /// The keyword to search for.
+        keyword: String,
+    },
+    /// Capture the result of a shindan as an image.
+    #[cfg(feature = "cli-image")]
+    Img {
+        /// The ID of the shindan.
+        id: String,
+        /// The name to run the shindan for.
+        name: String,
+        /// The image file to write: .jpeg, or .png with the "png" feature.
+        #[arg(short, long, default_value = "shindan.jpeg")]
+        output: PathBuf,
+    },
+    /// Download the current CSS and JS of the site, to build HTML from with `SiteAssets::Directory`.
+    #[cfg(feature = "html")]
+    RefreshAssets {
+        /// The directory to write the assets to.
+        #[arg(default_value = "static")]
+        dir: PathBuf,
+    },
+    /// Serve the client over HTTP, for bots written in other languages.
+    #[cfg(feature = "server")]
+    Serve {
+        /// The address to listen on.
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let client = ShindanClient::new(cli.domain.clone())?;
+
+    match cli.command {
+        Command::Run { id, name: Some(name), out_dir, .. } => {
+            let (result, image) = fetch_result(&client, &id, &name, out_dir.is_some()).await?;
+
+            if let Some(out_dir) = &out_dir {
+                fs::create_dir_all(out_dir)?;
+                save_result(out_dir, &result, image.as_deref())?;
+            }
+
+            if cli.json {
+                println!("{}", result.to_json()?);
+            } else if let Some(template) = &cli.format {
+                println!("{}", format_result(template, &result)?);
+            } else {
+                println!("{}\n\n{}", result.title, result.segments.to_plain_text(&PlainTextOptions::default()));
+            }
+        }
+        Command::Run { id, name: None, names_file, concurrency, out_dir } => {
+            let names = read_names(names_file.as_deref())?;
+            if let Some(out_dir) = &out_dir {
+                fs::create_dir_all(out_dir)?;
+            }
+
+            let mut results = stream::iter(names)
+                .map(|name| async {
+                    let outcome = fetch_result(&client, &id, &name, out_dir.is_some()).await;
+                    (name, outcome)
+                })
+                .buffer_unordered(concurrency.max(1));
+            let mut failures = 0;
+
+            while let Some((name, outcome)) = results.next().await {
+                let (result, image) = match outcome {
+                    Ok(result) => result,
+                    Err(err) => {
+                        failures += 1;
+                        eprintln!("{}: {:#}", name, err);
+                        println!("{}", serde_json::json!({ "name": name, "error": format!("{:#}", err) }));
+                        continue;
+                    }
+                };
+
+                if let Some(out_dir) = &out_dir {
+                    if let Err(err) = save_result(out_dir, &result, image.as_deref()) {
+                        eprintln!("{}: {:#}", name, err);
+                    }
+                }
+
+                match &cli.format {
+                    Some(template) => println!("{}", format_result(template, &result)?.replace('\n', " ")),
+                    None => println!("{}", serde_json::to_string(&result)?),
+                }
+            }
+
+            if failures > 0 {
+                return Err(anyhow!("{} submissions failed", failures));
+            }
+        }
+        Command::Title { id } => {
+            let metadata = client.get_metadata(&id).await?;
+
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&metadata)?);
+            } else {
+                println!("{}", metadata.title);
+                if let Some(description) = metadata.description {
+                    println!("\n{}", description);
+                }
+            }
+        }
+        Command::Search { keyword } => {
+            let hits = client.search(&keyword).await?;
+
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&hits)?);
+            } else {
+                for hit in hits {
+                    println!("{}\t{}", hit.id, hit.title);
+                }
+            }
+        }
+        #[cfg(feature = "cli-image")]
+        Command::Img { id, name, output } => {
+            fs::write(&output, capture_image(&client, &id, &name, &output).await?)?;
+            println!("{}", output.display());
+        }
+        #[cfg(feature = "html")]
+        Command::RefreshAssets { dir } => {
+            let versions = client.refresh_site_assets(&dir).await?;
+
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&versions)?);
+            } else {
+                println!("css/app.css={} js/app.js={} js/chart.js={}", versions.app_css, versions.app_js, versions.chart_js);
+                println!("{}", dir.display());
+            }
+        }
+        #[cfg(feature = "server")]
+        Command::Serve { addr } => {
+            println!("Listening on {}", addr);
+            shindan_maker::server::serve(shindan_maker::server::default_client(cli.domain)?, addr).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Read names one per line, from a file or stdin, skipping blank lines.
+fn read_names(names_file: Option<&Path>) -> Result<Vec<String>> {
+    let reader: Box<dyn BufRead> = match names_file {
+        Some(path) if path != Path::new("-") => Box::new(BufReader::new(
+            fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?,
+        )),
+        _ => Box::new(io::stdin().lock()),
+    };
+
+    let mut names = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            names.push(line.trim().to_string());
+        }
+    }
+
+    Ok(names)
+}
+
+/// Run a shindan and, with "cli-image" and `capture` set, capture the same result as an image.
+async fn fetch_result(client: &ShindanClient, id: &str, name: &str, capture: bool) -> Result<(ShindanResult, Option<Vec<u8>>)> {
+    #[cfg(feature = "cli-image")]
+    if capture {
+        let (result, image) = client.get_result_with_image(id, name).await?;
+        return Ok((result, Some(image)));
+    }
+    #[cfg(not(feature = "cli-image"))]
+    let _ = capture;
+
+    Ok((client.get_result(id, name).await?, None))
+}
+
+/// Capture the result of a shindan in the image format the extension of the output file names.
+#[cfg(feature = "cli-image")]
+async fn capture_image(client: &ShindanClient, id: &str, name: &str, output: &Path) -> Result<Vec<u8>> {
+    let extension = output.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+
+    match extension.to_ascii_lowercase().as_str() {
+        "jpg" | "jpeg" => client.get_image(id, name).await,
+        #[cfg(feature = "png")]
+        "png" => client.get_png_image(id, name).await,
+        #[cfg(not(feature = "png"))]
+        "png" => Err(anyhow!("Writing PNG images needs the \"png\" feature; write a .jpeg file instead")),
+        _ => Err(anyhow!("Unsupported image file {}; write a .jpeg or .png file", output.display())),
+    }
+}
+
+/// Write a result to a directory as `<stem>.json` and its image, if any, as `<stem>.jpeg`.
+fn save_result(out_dir: &Path, result: &ShindanResult, image: Option<&[u8]>) -> Result<()> {
+    let stem = file_stem(&result.name);
+
+    fs::write(out_dir.join(format!("{}.json", stem)), result.to_json()?)?;
+    if let Some(image) = image {
+        fs::write(out_dir.join(format!("{}.jpeg", stem)), image)?;
+    }
+
+    Ok(())
+}
+
+/// A file name stem for a name, with a hash suffix when characters had to be replaced,
+/// so that e.g. "a b" and "a_b" do not overwrite each other.
+fn file_stem(name: &str) -> String {
+    let stem: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+
+    if stem == name {
+        return stem;
+    }
+
+    // FNV rather than the standard hasher, so that names keep their files across Rust versions.
+    let mut hasher = Fnv1a::default();
+    hasher.write_field(name);
+    format!("{}-{:08x}", stem, hasher.finish() as u32)
+}
@@ -0,0 +1,66 @@
+use serde_json::Value;
+
+use crate::segment::Segment;
+
+/// The sources of a `video` or `audio` element: its own `src` and those of its `source` children.
+#[derive(Debug)]
+pub(crate) struct MediaSources {
+    kind: &'static str,
+    sources: Vec<String>,
+    poster: Option<String>,
+}
+
+impl MediaSources {
+    /// Start reading a media element from its attributes, if it is a `video` or `audio`.
+    pub(crate) fn of<'a>(name: &str, attributes: impl IntoIterator<Item = (&'a str, &'a str)>) -> Option<Self> {
+        let kind = match name {
+            "video" => "video",
+            "audio" => "audio",
+            _ => return None,
+        };
+
+        let mut media = Self { kind, sources: Vec::new(), poster: None };
+        for (name, value) in attributes {
+            match name {
+                "src" | "data-src" => media.add_source(value),
+                "poster" | "data-poster" if media.poster.is_none() && !value.trim().is_empty() => {
+                    media.poster = Some(value.trim().to_string())
+                }
+                _ => {}
+            }
+        }
+        Some(media)
+    }
+
+    /// Add the source of a `source` child from its attributes.
+    pub(crate) fn add_source_element<'a>(&mut self, attributes: impl IntoIterator<Item = (&'a str, &'a str)>) {
+        for (name, value) in attributes {
+            if matches!(name, "src" | "data-src") {
+                self.add_source(value);
+            }
+        }
+    }
+
+    fn add_source(&mut self, url: &str) {
+        let url = url.trim();
+        if !url.is_empty() && !self.sources.iter().any(|source| source == url) {
+            self.sources.push(url.to_string());
+        }
+    }
+
+    /// A `video` or `audio` segment of the first source, listing every source under `sources` if there are several.
+    pub(crate) fn segment(&self) -> Option<Segment> {
+        let mut segment = match self.kind {
+            "video" => Segment::video(self.sources.first()?),
+            _ => Segment::audio(self.sources.first()?),
+        };
+
+        if self.sources.len() > 1 {
+            segment.data["sources"] = Value::from(self.sources.clone());
+        }
+        if let Some(poster) = &self.poster {
+            segment.data["poster"] = Value::from(poster.as_str());
+        }
+        Some(segment)
+    }
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "segments")]
+use crate::segment::Segments;
+
+/// The title, description, tags and preview of a shindan.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShindanMetadata {
+    pub title: String,
+    /// The description flattened to text.
+    pub description: Option<String>,
+    /// The description with its links, images and list items, as `text`, `link` and `image` segments.
+    #[cfg(feature = "segments")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description_segments: Option<Segments>,
+    /// The tags the creator assigned to the shindan, without `#`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// What the results of the shindan look like, if the page shows it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<ShindanPreview>,
+}
+
+/// A sample result or preview image shown on a shindan page before it is run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShindanPreview {
+    /// The text of the sample result.
+    pub text: Option<String>,
+    /// The URL of the image of the sample result.
+    pub image: Option<String>,
+    /// The URL of the share image of the page (`og:image`), which does not necessarily show a result.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub share_image: Option<String>,
+}
@@ -0,0 +1,84 @@
+use std::sync::Mutex;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use reqwest::RequestBuilder;
+
+use crate::metadata::ShindanMetadata;
+
+/// The `ETag` and `Last-Modified` headers of a response, used to revalidate it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Validators {
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+}
+
+impl Validators {
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Self {
+        Self {
+            etag: headers.get(header::ETAG).cloned(),
+            last_modified: headers.get(header::LAST_MODIFIED).cloned(),
+        }
+    }
+
+    /// Turn a request into a conditional request.
+    pub(crate) fn apply(&self, mut request: RequestBuilder) -> RequestBuilder {
+        if let Some(etag) = &self.etag {
+            request = request.header(header::IF_NONE_MATCH, etag.clone());
+        }
+        if let Some(last_modified) = &self.last_modified {
+            request = request.header(header::IF_MODIFIED_SINCE, last_modified.clone());
+        }
+        request
+    }
+}
+
+/// Metadata of a shindan page, along with what is needed to revalidate it.
+#[derive(Debug, Clone)]
+pub(crate) struct MetadataEntry {
+    pub(crate) metadata: ShindanMetadata,
+    pub(crate) validators: Validators,
+    fetched_at: Instant,
+}
+
+/// Metadata keyed by shindan URL, reused until their TTL expires and revalidated afterwards.
+#[derive(Debug, Default)]
+pub(crate) struct MetadataCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, MetadataEntry>>,
+}
+
+impl MetadataCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get the metadata of a page, and whether it is still fresh.
+    pub(crate) fn get(&self, url: &str) -> Option<(MetadataEntry, bool)> {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(url)
+            .map(|entry| (entry.clone(), entry.fetched_at.elapsed() < self.ttl))
+    }
+
+    pub(crate) fn insert(&self, url: &str, metadata: ShindanMetadata, validators: Validators) {
+        let entry = MetadataEntry {
+            metadata,
+            validators,
+            fetched_at: Instant::now(),
+        };
+
+        self.entries.lock().unwrap().insert(url.to_string(), entry);
+    }
+
+    /// Mark the metadata of a page as fresh again after the site confirmed it is unchanged.
+    pub(crate) fn revalidate(&self, url: &str) {
+        if let Some(entry) = self.entries.lock().unwrap().get_mut(url) {
+            entry.fetched_at = Instant::now();
+        }
+    }
+}
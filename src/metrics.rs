@@ -0,0 +1,30 @@
+use std::fmt;
+use std::sync::Arc;
+
+/// A callback receiving the [`ResponseMetrics`] of every response the client reads.
+pub type MetricsHook = Arc<dyn Fn(&ResponseMetrics) + Send + Sync>;
+
+/// Measurements of a response, reported once its body has been read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseMetrics {
+    /// The URL of the response.
+    pub url: String,
+    /// The HTTP status code.
+    pub status: u16,
+    /// The `Content-Encoding` of the body, if it was compressed.
+    pub content_encoding: Option<String>,
+    /// The size of the body as received.
+    pub wire_bytes: u64,
+    /// The size of the body after decompression.
+    pub decoded_bytes: u64,
+}
+
+/// The metrics hook of a client.
+#[derive(Clone)]
+pub(crate) struct Metrics(pub(crate) MetricsHook);
+
+impl fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MetricsHook")
+    }
+}
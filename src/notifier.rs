@@ -0,0 +1,248 @@
+use std::time::{Duration, Instant};
+use std::collections::{HashMap, HashSet};
+use anyhow::{Context, Result};
+use reqwest::header::CONTENT_TYPE;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::rt;
+use crate::search::SearchHit;
+use crate::client::ShindanClient;
+
+/// A page of ShindanMaker listing shindans, watched by a [`Notifier`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatchTarget {
+    /// The ranking of popular shindans.
+    Ranking,
+    /// The newest shindans.
+    NewArrivals,
+    /// The shindans of an author, by their screen name.
+    Author(String),
+    /// Any other page listing shindans, by its path, e.g. `list/theme`.
+    Page(String),
+}
+
+impl WatchTarget {
+    fn path(&self) -> String {
+        match self {
+            WatchTarget::Ranking => "list/ranking".to_string(),
+            WatchTarget::NewArrivals => "list/latest".to_string(),
+            WatchTarget::Author(author) => format!("author/{}", author),
+            WatchTarget::Page(path) => path.trim_start_matches('/').to_string(),
+        }
+    }
+
+    /// How the target is named in notifications.
+    pub fn label(&self) -> String {
+        match self {
+            WatchTarget::Ranking => "ranking".to_string(),
+            WatchTarget::NewArrivals => "new arrivals".to_string(),
+            WatchTarget::Author(author) => format!("author {}", author),
+            WatchTarget::Page(path) => path.clone(),
+        }
+    }
+}
+
+/// The shape of the JSON posted to a webhook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookFormat {
+    /// A Discord message with an embed per shindan, at most 10 per message.
+    Discord,
+    /// `{"source": ..., "shindans": [{"id", "title", "url"}]}`.
+    Generic,
+}
+
+/// A URL notified of new shindans.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    pub format: WebhookFormat,
+}
+
+/// New shindans found on a watched page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    pub target: WatchTarget,
+    pub shindans: Vec<SearchHit>,
+}
+
+/**
+Watches pages listing shindans and posts the shindans new to them to webhooks.
+
+The first check of a page only records what it lists; later checks notify the
+shindans it did not list before. Failures are logged and retried at the next check,
+so [`run`](Self::run) keeps going until it is dropped. Until every webhook has accepted
+a shindan, it is posted again at each check, including to the webhooks that did.
+
+# Examples
+```no_run
+use std::time::Duration;
+use shindan_maker::{Notifier, ShindanClient, ShindanDomain, WatchTarget, Webhook, WebhookFormat};
+
+#[tokio::main]
+async fn main() {
+    let client = ShindanClient::new(ShindanDomain::Jp).unwrap();
+
+    Notifier::new(client)
+        .watch(WatchTarget::NewArrivals)
+        .watch(WatchTarget::Author("example".to_string()))
+        .webhook(Webhook {
+            url: "https://discord.com/api/webhooks/123/abc".to_string(),
+            format: WebhookFormat::Discord,
+        })
+        .interval(Duration::from_secs(600))
+        .run()
+        .await;
+}
+```
+*/
+#[derive(Debug)]
+pub struct Notifier {
+    client: ShindanClient,
+    targets: Vec<WatchTarget>,
+    webhooks: Vec<Webhook>,
+    interval: Duration,
+    seen: HashMap<WatchTarget, HashSet<String>>,
+}
+
+impl Notifier {
+    /// Create a notifier fetching pages with a client, checking every 15 minutes.
+    pub fn new(client: ShindanClient) -> Self {
+        Self {
+            client,
+            targets: Vec::new(),
+            webhooks: Vec::new(),
+            interval: Duration::from_secs(15 * 60),
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Watch a page.
+    pub fn watch(mut self, target: WatchTarget) -> Self {
+        self.targets.push(target);
+        self
+    }
+
+    /// Post new shindans to a webhook.
+    pub fn webhook(mut self, webhook: Webhook) -> Self {
+        self.webhooks.push(webhook);
+        self
+    }
+
+    /// Set the time between checks.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /**
+    Check every watched page once and post what is new to the webhooks.
+
+    # Returns
+    The new shindans of each page with any.
+
+    # Errors
+    Returns error if a page cannot be fetched or a webhook rejects a notification;
+    the other pages and webhooks are still handled, and the rejected shindans are
+    notified again at the next check.
+    */
+    pub async fn check(&mut self) -> Result<Vec<Alert>> {
+        let mut alerts = Vec::new();
+        let mut failure = None;
+
+        for target in self.targets.clone() {
+            let hits = match self.client.fetch_hits(self.client.client.get(self.client.url(&target.path()))).await {
+                Ok(hits) => hits,
+                Err(err) => {
+                    failure = Some(err.context(format!("Failed to check the {}", target.label())));
+                    continue;
+                }
+            };
+
+            let first_check = !self.seen.contains_key(&target);
+            let seen = self.seen.entry(target.clone()).or_default();
+            let new: Vec<SearchHit> = hits.into_iter().filter(|hit| !seen.contains(&hit.id)).collect();
+            if first_check {
+                seen.extend(new.into_iter().map(|hit| hit.id));
+            } else if !new.is_empty() {
+                alerts.push(Alert { target, shindans: new });
+            }
+        }
+
+        for alert in &alerts {
+            let mut delivered = true;
+            for webhook in &self.webhooks {
+                if let Err(err) = self.post(webhook, alert).await {
+                    failure = Some(err);
+                    delivered = false;
+                }
+            }
+            // Shindans are only seen once every webhook has them, so a failed post is retried at the next check.
+            if delivered {
+                let seen = self.seen.entry(alert.target.clone()).or_default();
+                seen.extend(alert.shindans.iter().map(|hit| hit.id.clone()));
+            }
+        }
+
+        match failure {
+            Some(err) => Err(err),
+            None => Ok(alerts),
+        }
+    }
+
+    /// Check the watched pages at every interval, forever.
+    pub async fn run(mut self) {
+        loop {
+            let next = Instant::now() + self.interval;
+            match self.check().await {
+                Ok(alerts) => log::debug!(alerts = alerts.len(); "Checked the watched pages"),
+                Err(err) => log::warn!("Failed to notify new shindans: {:#}", err),
+            }
+            rt::sleep_until(next).await;
+        }
+    }
+
+    async fn post(&self, webhook: &Webhook, alert: &Alert) -> Result<()> {
+        for payload in payloads(webhook.format, alert, |id| self.client.url(id)) {
+            self.client
+                .client
+                .post(&webhook.url)
+                .header(CONTENT_TYPE, "application/json")
+                .body(payload.to_string())
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .with_context(|| format!("Failed to post to the webhook {}", webhook.url))?;
+        }
+        Ok(())
+    }
+}
+
+/// The JSON bodies notifying an alert in a format.
+fn payloads(format: WebhookFormat, alert: &Alert, url_of: impl Fn(&str) -> String) -> Vec<Value> {
+    match format {
+        WebhookFormat::Generic => vec![json!({
+            "source": alert.target.label(),
+            "shindans": alert.shindans
+                .iter()
+                .map(|hit| json!({"id": hit.id, "title": hit.title, "url": url_of(&hit.id)}))
+                .collect::<Vec<_>>(),
+        })],
+        // Discord rejects messages with more than 10 embeds.
+        WebhookFormat::Discord => alert
+            .shindans
+            .chunks(10)
+            .map(|hits| {
+                json!({
+                    "content": format!("New shindans in the {}", alert.target.label()),
+                    "embeds": hits
+                        .iter()
+                        .map(|hit| json!({"title": hit.title, "url": url_of(&hit.id)}))
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect(),
+    }
+}
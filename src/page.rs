@@ -0,0 +1,163 @@
+use std::time::Instant;
+use anyhow::{Context, Result};
+
+use crate::html_utils::FormData;
+use crate::shindan_id::ShindanId;
+use crate::metadata::{ShindanMetadata, ShindanPreview};
+use crate::shindan_domain::ShindanDomain;
+#[cfg(feature = "segments")]
+use crate::segment::Segments;
+
+/**
+A shindan page fetched and parsed once.
+
+Its title, description and form are extracted up front, so reading them is cheap
+and the page can be submitted with any number of names.
+*/
+#[derive(Debug)]
+pub struct ShindanPage {
+    url: String,
+    pub(crate) final_url: String,
+    title: Option<String>,
+    description: Option<String>,
+    tags: Vec<String>,
+    author: Option<String>,
+    preview: Option<ShindanPreview>,
+    #[cfg(feature = "segments")]
+    description_segments: Option<Segments>,
+    pub(crate) session_cookie: String,
+    pub(crate) form_data: FormData,
+    pub(crate) fetched_at: Instant,
+}
+
+impl ShindanPage {
+    pub(crate) fn new(
+        url: &str,
+        session_cookie: String,
+        title: Option<String>,
+        description: Option<String>,
+        form_data: FormData,
+    ) -> Self {
+        Self {
+            url: url.to_string(),
+            final_url: url.to_string(),
+            title,
+            description,
+            tags: Vec::new(),
+            author: None,
+            preview: None,
+            #[cfg(feature = "segments")]
+            description_segments: None,
+            session_cookie,
+            form_data,
+            fetched_at: Instant::now(),
+        }
+    }
+
+    pub(crate) fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    pub(crate) fn with_author(mut self, author: Option<String>) -> Self {
+        self.author = author;
+        self
+    }
+
+    pub(crate) fn with_preview(mut self, preview: Option<ShindanPreview>) -> Self {
+        self.preview = preview;
+        self
+    }
+
+    #[cfg(feature = "segments")]
+    pub(crate) fn with_description_segments(mut self, description_segments: Option<Segments>) -> Self {
+        self.description_segments = description_segments;
+        self
+    }
+
+    /// The URL of the page.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The URL that served the page, after following redirects. Forms are submitted to it.
+    pub fn final_url(&self) -> &str {
+        &self.final_url
+    }
+
+    /**
+    The domain the site redirected the page to, e.g. when a shindan moved to another regional domain.
+
+    # Returns
+    The domain serving the page, or `None` if the page was served by the domain it was requested from.
+    */
+    pub fn redirected_domain(&self) -> Option<ShindanDomain> {
+        let (requested, _) = ShindanId::parse(&self.url).ok()?;
+        let (served, _) = ShindanId::parse(&self.final_url).ok()?;
+
+        (served != requested).then_some(served)
+    }
+
+    /// The title of the shindan, if the page has one.
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// The description of the shindan flattened to text, if the page has one.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// The name of the author of the shindan, if the page links to them.
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    /// The tags the creator assigned to the shindan, without `#`.
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// The sample result or preview image of the shindan, if the page shows one.
+    pub fn preview(&self) -> Option<&ShindanPreview> {
+        self.preview.as_ref()
+    }
+
+    /// The description of the shindan with its links, images and list items, if the page has one.
+    #[cfg(feature = "segments")]
+    pub fn description_segments(&self) -> Option<&Segments> {
+        self.description_segments.as_ref()
+    }
+
+    /**
+    Get the title and description of the shindan.
+
+    # Errors
+    Returns error if the page has no title.
+    */
+    pub fn metadata(&self) -> Result<ShindanMetadata> {
+        Ok(ShindanMetadata {
+            title: self.title.clone().context("Failed to get the title of the shindan")?,
+            description: self.description.clone(),
+            #[cfg(feature = "segments")]
+            description_segments: self.description_segments.clone(),
+            tags: self.tags.clone(),
+            preview: self.preview.clone(),
+        })
+    }
+
+    /// The random name the site filled the form with, used by some shindans for an empty name.
+    pub fn randname(&self) -> Option<&str> {
+        self.form_data.iter().find(|(field, _)| *field == "randname").map(|(_, value)| value.as_str())
+    }
+
+    /// Get the form data to submit for a name, with the `randname` pinned to `seed` if given.
+    pub(crate) fn form_data_for(&self, name: &str, seed: Option<&str>) -> FormData {
+        let mut form_data = self.form_data.clone();
+        if let (Some(seed), Some((_, randname))) = (seed, form_data.iter_mut().find(|(field, _)| *field == "randname")) {
+            *randname = seed.to_string();
+        }
+        form_data.push(("user_input_value_1", name.to_string()));
+        form_data
+    }
+}
@@ -0,0 +1,21 @@
+/**
+How the segments of results are parsed.
+
+# Examples
+```
+use shindan_maker::{ParseOptions, Segments};
+
+let html = "<span id=\"post_display\">&nbsp;&nbsp;/\\_/\\</span>";
+let options = ParseOptions { preserve_whitespace: true };
+
+assert_eq!("  /\\_/\\", Segments::from_html_with(html, options).unwrap().to_string());
+```
+*/
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Turn the non-breaking spaces of text into plain spaces, for results such as ASCII
+    /// art whose runs of spaces must survive as they are. Defaults to `false`.
+    ///
+    /// Render such results with [`PlainTextOptions::preserve_whitespace`](crate::PlainTextOptions::preserve_whitespace) too.
+    pub preserve_whitespace: bool,
+}
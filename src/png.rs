@@ -0,0 +1,78 @@
+use std::io::Write;
+use anyhow::Result;
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+
+use crate::client::ShindanClient;
+use crate::shindan_id::ShindanId;
+use crate::image::{decode_jpeg, Frame};
+
+impl ShindanClient {
+    /**
+    Capture the result of a shindan as a PNG image, e.g. for applications that only read PNG.
+
+    The browser captures the result as [`get_image`](Self::get_image) does; the pixels
+    of that capture are then encoded losslessly, so the PNG looks the same as the JPEG.
+
+    # Arguments
+    - `id` - The ID of the shindan.
+    - `name` - The name to run the shindan for.
+
+    # Returns
+    The PNG image of the result.
+
+    # Errors
+    Returns error if the submission fails or the browser cannot capture the result.
+
+    # Examples
+    ```no_run
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::En).unwrap();
+
+        let image = client.get_png_image("1222992", "test_user").await.unwrap();
+        std::fs::write("result.png", image).unwrap();
+    }
+    ```
+    */
+    pub async fn get_png_image(&self, id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>, name: &str) -> Result<Vec<u8>> {
+        let jpeg = self.get_image(id, name).await?;
+        encode_png(&decode_jpeg(&jpeg)?)
+    }
+}
+
+/// Encode RGB pixels as a PNG image, in a single `IDAT` chunk of unfiltered rows.
+pub(crate) fn encode_png(frame: &Frame) -> Result<Vec<u8>> {
+    const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&u32::from(frame.width).to_be_bytes());
+    header.extend_from_slice(&u32::from(frame.height).to_be_bytes());
+    // 8-bit RGB, deflate compression, adaptive filtering, no interlacing.
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut data = ZlibEncoder::new(Vec::new(), Compression::default());
+    for row in frame.pixels.chunks_exact(usize::from(frame.width.max(1)) * 3) {
+        // Each row starts with its filter type, none here.
+        data.write_all(&[0])?;
+        data.write_all(row)?;
+    }
+
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &data.finish()?);
+    write_chunk(&mut png, b"IEND", &[]);
+    Ok(png)
+}
+
+/// Append a chunk: its length, type, data and the CRC of its type and data.
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32fast::hash(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
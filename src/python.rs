@@ -0,0 +1,129 @@
+/*!
+Python bindings, built into a `shindan_maker` extension module with [maturin].
+
+```sh
+maturin develop --features python
+```
+
+```python
+import asyncio
+from shindan_maker import Client
+
+async def main():
+    client = Client("en")
+    print(await client.get_title("1222992"))
+    results = await client.run_batch("1222992", ["alice", "bob"], concurrency=4)
+
+asyncio.run(main())
+```
+
+Every method returns an asyncio awaitable, driven by a Tokio runtime owned by the
+module. Results are returned as the dictionaries of
+[`ShindanResult::to_json`](crate::ShindanResult::to_json).
+
+[maturin]: https://www.maturin.rs/
+*/
+
+use futures::StreamExt;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use pyo3::exceptions::PyValueError;
+use pyo3_async_runtimes::tokio::future_into_py;
+
+use crate::client::ShindanClient;
+use crate::result::ShindanResult;
+
+/// Parse a JSON document into Python objects.
+fn loads(json: &str) -> PyResult<PyObject> {
+    Python::with_gil(|py| Ok(py.import("json")?.call_method1("loads", (json,))?.unbind()))
+}
+
+/// A ShindanMaker client, cheap to share between tasks.
+#[pyclass(name = "Client", module = "shindan_maker", frozen)]
+struct PyShindanClient {
+    client: ShindanClient,
+}
+
+#[pymethods]
+impl PyShindanClient {
+    /// Create a client for a domain: "jp", "en", "cn", "kr" or "th".
+    #[new]
+    fn new(domain: &str) -> PyResult<Self> {
+        let domain = domain.parse().map_err(|err| PyValueError::new_err(format!("{:#}", err)))?;
+        Ok(Self { client: ShindanClient::new(domain)? })
+    }
+
+    /// Get the title of a shindan.
+    fn get_title<'py>(&self, py: Python<'py>, id: String) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.client.clone();
+        future_into_py(py, async move { Ok(client.get_title(&id).await?) })
+    }
+
+    /// Get the title and description of a shindan, as a dictionary.
+    fn get_metadata<'py>(&self, py: Python<'py>, id: String) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.client.clone();
+        future_into_py(py, async move {
+            let metadata = client.get_metadata(&id).await?;
+            loads(&serde_json::to_string(&metadata).map_err(anyhow::Error::from)?)
+        })
+    }
+
+    /// Run a shindan for a name, returning the result as a dictionary.
+    fn get_result<'py>(&self, py: Python<'py>, id: String, name: String) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.client.clone();
+        future_into_py(py, async move { loads(&client.get_result(&id, &name).await?.to_json()?) })
+    }
+
+    /// Search shindans by keyword, returning a list of `{"id", "title"}` dictionaries.
+    fn search<'py>(&self, py: Python<'py>, keyword: String) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.client.clone();
+        future_into_py(py, async move {
+            let hits = client.search(&keyword).await?;
+            loads(&serde_json::to_string(&hits).map_err(anyhow::Error::from)?)
+        })
+    }
+
+    /// Run a shindan for many names, returning the results in the order of the names.
+    ///
+    /// A failed submission is a `{"name", "error"}` dictionary instead of a result.
+    #[pyo3(signature = (id, names, concurrency = 4))]
+    fn run_batch<'py>(&self, py: Python<'py>, id: String, names: Vec<String>, concurrency: usize) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.client.clone();
+        future_into_py(py, async move {
+            let jobs = names.into_iter().map(|name| (id.clone(), name));
+            let outcomes: Vec<_> = futures::stream::iter(jobs)
+                .map(|(id, name)| {
+                    let client = &client;
+                    async move {
+                        let outcome = client.get_result(&id, &name).await;
+                        (name, outcome)
+                    }
+                })
+                .buffered(concurrency.max(1))
+                .collect()
+                .await;
+
+            Python::with_gil(|py| {
+                let json = py.import("json")?;
+                outcomes
+                    .into_iter()
+                    .map(|(name, outcome)| match outcome.and_then(|result: ShindanResult| result.to_json()) {
+                        Ok(json_result) => Ok(json.call_method1("loads", (json_result,))?.unbind()),
+                        Err(err) => {
+                            let failure = PyDict::new(py);
+                            failure.set_item("name", name)?;
+                            failure.set_item("error", format!("{:#}", err))?;
+                            Ok(failure.into_any().unbind())
+                        }
+                    })
+                    .collect::<PyResult<Vec<PyObject>>>()
+            })
+        })
+    }
+}
+
+#[pymodule]
+fn shindan_maker(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyShindanClient>()?;
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use tokio::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::rt;
+
+/// Spaces requests evenly so that at most `requests` are sent per `period`.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(requests: u32, period: Duration) -> Self {
+        Self {
+            interval: period / requests.max(1),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Wait until the next request may be sent.
+    pub(crate) async fn acquire(&self) {
+        let slot = {
+            let mut next = self.next.lock().await;
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.interval;
+            slot
+        };
+
+        rt::sleep_until(slot).await;
+    }
+}
@@ -0,0 +1,75 @@
+use serde_json::Value;
+
+use crate::segment::Segments;
+use crate::table::line_break_before;
+
+impl Segments {
+    /**
+    Convert the segments to BBCode for phpBB-style forums.
+
+    Text segments carrying `bold` or `color` data, as parsed from `<b>`, `<strong>` and
+    styled `<span>` elements, are wrapped in `[b]` and `[color]` tags, and tables, which
+    phpBB lacks, become `[code]` blocks. BBCode has no escapes, so text is written as it is;
+    colors that are not a name or a hex code are dropped, and brackets in URLs are
+    percent-encoded so they stay in their tag.
+
+    # Returns
+    The BBCode string of the segments.
+
+    # Examples
+    ```
+    use serde_json::json;
+    use shindan_maker::{Segment, Segments};
+
+    let segments = Segments(vec![
+        Segment::new("text", json!({"text": "Rank: "})),
+        Segment::new("text", json!({"text": "SSR", "bold": true, "color": "#ff0000"})),
+        Segment::new("image", json!({"file": "https://example.com/a.png"})),
+    ]);
+
+    assert_eq!(
+        segments.to_bbcode(),
+        "Rank: [b][color=#ff0000]SSR[/color][/b][img]https://example.com/a.png[/img]"
+    );
+    ```
+    */
+    pub fn to_bbcode(&self) -> String {
+        let mut bbcode = String::new();
+
+        for segment in self.iter() {
+            let field = |key: &str| segment.data.get(key).and_then(Value::as_str);
+
+            match (segment.type_.as_ref(), field("text"), field("file"), field("url")) {
+                ("text", Some(text), _, _) => {
+                    let mut text = text.to_string();
+                    if let Some(color) = field("color").filter(|color| color.chars().all(|c| c.is_ascii_alphanumeric() || c == '#')) {
+                        text = format!("[color={}]{}[/color]", color, text);
+                    }
+                    if segment.data.get("bold").and_then(Value::as_bool).unwrap_or(false) {
+                        text = format!("[b]{}[/b]", text);
+                    }
+                    bbcode.push_str(&text);
+                }
+                ("table", _, _, _) => {
+                    if let Some(table) = segment.as_table() {
+                        let code = format!("[code]{}[/code]\n", table.to_plain_text());
+                        bbcode.push_str(line_break_before(&bbcode));
+                        bbcode.push_str(&code);
+                    }
+                }
+                ("image", _, Some(file), _) => bbcode.push_str(&format!("[img]{}[/img]", encode_brackets(file))),
+                ("link", text, _, Some(url)) => {
+                    bbcode.push_str(&format!("[url={}]{}[/url]", encode_brackets(url), text.unwrap_or(url)))
+                }
+                ("video" | "audio", _, Some(file), _) => bbcode.push_str(&format!("[url]{}[/url]", encode_brackets(file))),
+                _ => {}
+            }
+        }
+
+        bbcode
+    }
+}
+
+fn encode_brackets(url: &str) -> String {
+    url.replace('[', "%5B").replace(']', "%5D")
+}
@@ -0,0 +1,169 @@
+use serde_json::Value;
+
+use crate::segment::Segments;
+use crate::table::TableSegment;
+use crate::html_utils::escape_html;
+use crate::html_template::{HTML_TEMPLATE, PRESERVE_WHITESPACE_STYLE};
+
+/// The look of the result card rendered by [`Segments::to_card_html`]. Colors are CSS colors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CardTheme {
+    /// The title shown in the band above the result, e.g. the name of the shindan. Defaults to none.
+    pub title: Option<String>,
+    /// The color of the border and of the title band. Defaults to the site's `#00c5ff`.
+    pub accent_color: String,
+    /// Defaults to `#212529`.
+    pub text_color: String,
+    /// The color behind the result. Defaults to `#ffffff`.
+    pub background_color: String,
+    /// Show the result in a monospace font keeping every space, for ASCII art. Defaults to false.
+    pub preserve_whitespace: bool,
+}
+
+impl Default for CardTheme {
+    fn default() -> Self {
+        Self {
+            title: None,
+            accent_color: "#00c5ff".to_string(),
+            text_color: "#212529".to_string(),
+            background_color: "#ffffff".to_string(),
+            preserve_whitespace: false,
+        }
+    }
+}
+
+impl Segments {
+    /**
+    Render the segments as the card of a live result, e.g. for archived, cached or translated results.
+
+    The page uses the same template as [`ShindanClient::get_html_str`](crate::ShindanClient::get_html_str),
+    with the result in `#title_and_result`. Texts keep their color and weight, images are
+    shown at most as wide as the card, videos and audio become links, tables keep their
+    cells; charts are left out.
+
+    # Arguments
+    - `theme` - The title and colors of the card.
+
+    # Returns
+    A complete HTML document.
+
+    # Examples
+    ```
+    use shindan_maker::{CardTheme, Segment, Segments};
+
+    let segments = Segments(vec![Segment::text("Alice is <lucky>")]);
+    let html = segments.to_card_html(&CardTheme { title: Some("Daily luck".to_string()), ..Default::default() });
+
+    assert!(html.contains(r#"<h1 id="shindanResultTitle">Daily luck</h1>"#));
+    assert!(html.contains("Alice is &lt;lucky&gt;"));
+    ```
+    */
+    pub fn to_card_html(&self, theme: &CardTheme) -> String {
+        let mut card = String::from(r#"<div id="title_and_result">"#);
+        if let Some(title) = &theme.title {
+            card.push_str(&format!(r#"<h1 id="shindanResultTitle">{}</h1>"#, escape_html(title)));
+        }
+        card.push_str(&format!(
+            concat!(
+                r#"<div id="shindanResultContainer"><div id="shindanResultHeight"><div id="shindanResultCell">"#,
+                r#"<div id="shindanResultContent"><span id="shindanResult">{}</span></div>"#,
+                "</div></div></div></div>",
+            ),
+            segments_html(self)
+        ));
+
+        let background = css_value(&theme.background_color);
+        let mut style = format!(
+            "<style>\n        :root {{ --body-bg: {}; --text-body: {}; --bg-img-line: {}; --bg-img-fill: {}; --main-blue: {}; }}\n    </style>",
+            background,
+            css_value(&theme.text_color),
+            background,
+            background,
+            css_value(&theme.accent_color),
+        );
+        if theme.preserve_whitespace {
+            style.push_str(&format!("\n    {}", PRESERVE_WHITESPACE_STYLE));
+        }
+
+        let mut html = HTML_TEMPLATE.replace("<!-- TITLE_AND_RESULT -->", &card);
+        if let Some(title) = &theme.title {
+            html = html.replace("<title>ShindanMaker</title>", &format!("<title>{}</title>", escape_html(title)));
+        }
+        html.replacen("</head>", &format!("    {}\n</head>", style), 1)
+    }
+}
+
+fn segments_html(segments: &Segments) -> String {
+    let mut html = String::new();
+
+    for segment in segments.iter() {
+        let field = |key: &str| segment.data.get(key).and_then(Value::as_str);
+
+        match (segment.type_.as_ref(), field("text"), field("file"), field("url")) {
+            ("text", Some(text), _, _) => {
+                let text = escape_html(text).replace('\n', "<br>");
+                let color = field("color").map(|color| format!("color: {};", css_value(color)));
+                let bold = segment.data.get("bold").and_then(Value::as_bool).unwrap_or(false);
+                // Spans in the result are bold, as the submitted name is.
+                match (color, bold) {
+                    (None, false) => html.push_str(&text),
+                    (color, bold) => html.push_str(&format!(
+                        r#"<span style="{}font-weight: {};">{}</span>"#,
+                        color.unwrap_or_default(),
+                        if bold { 700 } else { 400 },
+                        text
+                    )),
+                }
+            }
+            ("link", text, _, Some(url)) => {
+                html.push_str(&format!(r#"<a href="{}">{}</a>"#, escape_html(url), escape_html(text.unwrap_or(url))));
+            }
+            ("image", _, Some(file), _) => html.push_str(&format!(
+                r#"<img src="{}" alt="{}" style="max-width: 100%; height: auto;">"#,
+                escape_html(file),
+                escape_html(field("alt").unwrap_or_default())
+            )),
+            ("video" | "audio", _, Some(file), _) => {
+                html.push_str(&format!(r#"<a href="{}">▶ {}</a>"#, escape_html(file), escape_html(file)));
+            }
+            ("table", _, _, _) => {
+                if let Some(table) = segment.as_table() {
+                    html.push_str(&table_html(&table));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    html
+}
+
+fn table_html(table: &TableSegment) -> String {
+    const CELL: &str = "padding: 0.25em 0.5em; border: 1px solid currentColor;";
+
+    let mut html = String::from(r#"<table style="border-collapse: collapse; white-space: normal;">"#);
+    if !table.headers.is_empty() {
+        html.push_str("<tr>");
+        for header in &table.headers {
+            html.push_str(&format!(r#"<th style="{}">{}</th>"#, CELL, escape_html(header)));
+        }
+        html.push_str("</tr>");
+    }
+    for row in &table.rows {
+        html.push_str("<tr>");
+        for cell in row {
+            html.push_str(&format!(r#"<td style="{}">{}</td>"#, CELL, escape_html(cell)));
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// Keep only the characters of a CSS color, so values cannot break out of the style.
+fn css_value(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '#' | '(' | ')' | ',' | '.' | '%' | ' ' | '-'))
+        .collect()
+}
@@ -0,0 +1,153 @@
+use serde_json::Value;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::segment::{Segment, Segments};
+
+/// How the length of a chunk is measured by [`Segments::chunk`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkStrategy {
+    /// Count Unicode scalar values, as most chat platforms do.
+    Chars,
+    /// Count grapheme clusters, i.e. user-perceived characters.
+    Graphemes,
+    /// Count UTF-8 bytes, for platforms limiting the payload size.
+    Bytes,
+}
+
+impl ChunkStrategy {
+    fn measure(self, text: &str) -> usize {
+        match self {
+            ChunkStrategy::Chars => text.chars().count(),
+            ChunkStrategy::Graphemes => text.graphemes(true).count(),
+            ChunkStrategy::Bytes => text.len(),
+        }
+    }
+}
+
+/// A piece of a segment, and the index of the segment it was cut from.
+struct Piece {
+    source: usize,
+    segment: Segment,
+    len: usize,
+}
+
+impl Segments {
+    /**
+    Split the segments into chunks within a length limit, e.g. to send a long result as several messages.
+
+    Text is split at line breaks where possible, then at spaces, and never inside a
+    grapheme cluster. Only the text of text and link segments counts; images do not,
+    and stay in the chunk of the text before them. Links are never split, so a link
+    longer than the limit makes a chunk of its own.
+
+    # Arguments
+    - `limit` - The maximum length of a chunk.
+    - `strategy` - How the length is measured.
+
+    # Returns
+    The chunks, in order; none if the segments carry nothing.
+
+    # Examples
+    ```
+    use shindan_maker::{ChunkStrategy, Segment, Segments};
+
+    let segments = Segments(vec![
+        Segment::text("Your rank:\n"),
+        Segment::image("https://example.com/a.png"),
+        Segment::text("SSR\nLucky item: a rubber duck"),
+    ]);
+
+    let chunks = segments.chunk(25, ChunkStrategy::Chars);
+
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(
+        chunks[0],
+        Segments(vec![
+            Segment::text("Your rank:\n"),
+            Segment::image("https://example.com/a.png"),
+            Segment::text("SSR\n"),
+        ])
+    );
+    assert_eq!(chunks[1].to_string(), "Lucky item: a rubber duck");
+    ```
+    */
+    pub fn chunk(&self, limit: usize, strategy: ChunkStrategy) -> Vec<Segments> {
+        let mut chunks = Vec::new();
+        let mut chunk: Vec<Segment> = Vec::new();
+        let mut chunk_len = 0;
+        let mut last_source = None;
+
+        for piece in self.pieces(limit, strategy) {
+            if chunk_len + piece.len > limit && chunk_len > 0 {
+                chunks.push(Segments(std::mem::take(&mut chunk)));
+                chunk_len = 0;
+                last_source = None;
+            }
+
+            match chunk.last_mut() {
+                // Pieces of the same text segment are joined back together.
+                Some(last) if last_source == Some(piece.source) && piece.segment.type_ == "text" => {
+                    let text = format!("{}{}", text_of(last), text_of(&piece.segment));
+                    last.data["text"] = Value::String(text);
+                }
+                _ => chunk.push(piece.segment),
+            }
+            chunk_len += piece.len;
+            last_source = Some(piece.source);
+        }
+
+        chunks.push(Segments(chunk));
+        chunks.retain(|chunk| chunk.iter().any(|segment| segment.type_ != "text" || !text_of(segment).trim().is_empty()));
+        chunks
+    }
+
+    /// Cut text segments into pieces within the limit, along line breaks, spaces and grapheme clusters.
+    fn pieces(&self, limit: usize, strategy: ChunkStrategy) -> Vec<Piece> {
+        let mut pieces = Vec::new();
+
+        for (source, segment) in self.iter().enumerate() {
+            let len = match segment.type_.as_ref() {
+                "text" | "link" => strategy.measure(text_of(segment)),
+                _ => 0,
+            };
+            if segment.type_ != "text" || len <= limit {
+                pieces.push(Piece { source, segment: segment.clone(), len });
+                continue;
+            }
+
+            let mut push = |text: &str| {
+                let mut segment = segment.clone();
+                segment.data["text"] = Value::String(text.to_string());
+                pieces.push(Piece { source, segment, len: strategy.measure(text) });
+            };
+
+            for line in text_of(segment).split_inclusive('\n') {
+                if strategy.measure(line) <= limit {
+                    push(line);
+                    continue;
+                }
+                for word in line.split_inclusive(' ') {
+                    if strategy.measure(word) <= limit {
+                        push(word);
+                        continue;
+                    }
+
+                    let mut part = String::new();
+                    for grapheme in word.graphemes(true) {
+                        if !part.is_empty() && strategy.measure(&part) + strategy.measure(grapheme) > limit {
+                            push(&std::mem::take(&mut part));
+                        }
+                        part.push_str(grapheme);
+                    }
+                    push(&part);
+                }
+            }
+        }
+
+        pieces
+    }
+}
+
+fn text_of(segment: &Segment) -> &str {
+    segment.data.get("text").and_then(Value::as_str).unwrap_or_default()
+}
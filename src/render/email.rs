@@ -0,0 +1,240 @@
+use serde_json::Value;
+
+use crate::segment::Segments;
+use crate::table::TableSegment;
+use crate::html_utils::escape_html;
+
+/// The look of the HTML rendered for emails. Colors are CSS colors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmailOptions {
+    /// The title of the email, shown above the results and as the document title.
+    pub title: Option<String>,
+    /// The width of the content in pixels. Defaults to 600, which most clients show in full.
+    pub width: u32,
+    /// Defaults to a system font stack falling back to Arial.
+    pub font_family: String,
+    /// Defaults to `#1f2937`.
+    pub text_color: String,
+    /// The color of links. Defaults to `#2563eb`.
+    pub link_color: String,
+    /// The color around the content. Defaults to `#f3f4f6`.
+    pub background_color: String,
+    /// The color behind the content. Defaults to `#ffffff`.
+    pub content_color: String,
+}
+
+impl Default for EmailOptions {
+    fn default() -> Self {
+        Self {
+            title: None,
+            width: 600,
+            font_family: "-apple-system, 'Segoe UI', Roboto, Helvetica, Arial, sans-serif".to_string(),
+            text_color: "#1f2937".to_string(),
+            link_color: "#2563eb".to_string(),
+            background_color: "#f3f4f6".to_string(),
+            content_color: "#ffffff".to_string(),
+        }
+    }
+}
+
+/// A result of an email rendered by [`render_email`], under an optional heading.
+#[derive(Debug, Clone, Copy)]
+pub struct EmailSection<'a> {
+    /// The heading of the result, e.g. the name of the shindan or of the user.
+    pub heading: Option<&'a str>,
+    /// The result.
+    pub segments: &'a Segments,
+}
+
+/**
+Render results as an HTML email, e.g. a newsletter-style digest of the day's shindans.
+
+Email clients such as Gmail and Outlook ignore `<style>` blocks and most modern
+CSS, so the layout is made of tables and every element carries its own inline
+style. Images are shown full width at most; videos and audio become links, with
+their poster if any; tables keep their cells; charts are left out.
+
+# Arguments
+- `sections` - The results, in order.
+- `options` - The title, width, font and colors of the email.
+
+# Returns
+A complete HTML document.
+
+# Examples
+```
+use shindan_maker::{render_email, EmailOptions, EmailSection, Segment, Segments};
+
+let fortune = Segments(vec![Segment::text("Alice is <lucky>\nToday"), Segment::image("https://example.com/a.png")]);
+let html = render_email(
+    &[EmailSection { heading: Some("Daily fortune"), segments: &fortune }],
+    &EmailOptions { title: Some("Your shindans".to_string()), ..Default::default() },
+);
+
+assert!(html.contains("<title>Your shindans</title>"));
+assert!(html.contains("Alice is &lt;lucky&gt;<br>Today"));
+assert!(html.contains(r#"<img src="https://example.com/a.png" alt="" width="552""#));
+```
+*/
+pub fn render_email(sections: &[EmailSection], options: &EmailOptions) -> String {
+    let font = format!(
+        "font-family:{};color:{};",
+        escape_html(&options.font_family),
+        css_value(&options.text_color)
+    );
+    // The content is padded by 24px on each side.
+    let inner_width = options.width.saturating_sub(48);
+
+    let mut rows = String::new();
+    if let Some(title) = &options.title {
+        rows.push_str(&format!(
+            r#"<tr><td style="padding:24px 24px 0;{}font-size:24px;font-weight:bold;line-height:1.3;">{}</td></tr>"#,
+            font,
+            escape_html(title)
+        ));
+    }
+    for section in sections {
+        if let Some(heading) = section.heading {
+            rows.push_str(&format!(
+                r#"<tr><td style="padding:24px 24px 0;{}font-size:18px;font-weight:bold;line-height:1.3;">{}</td></tr>"#,
+                font,
+                escape_html(heading)
+            ));
+        }
+        rows.push_str(&format!(
+            r#"<tr><td style="padding:12px 24px 24px;{}font-size:16px;line-height:1.5;">{}</td></tr>"#,
+            font,
+            section_html(section.segments, options, inner_width)
+        ));
+    }
+
+    format!(
+        concat!(
+            r#"<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Transitional//EN" "http://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd">"#,
+            "\n",
+            r#"<html xmlns="http://www.w3.org/1999/xhtml"><head>"#,
+            r#"<meta http-equiv="Content-Type" content="text/html; charset=UTF-8">"#,
+            r#"<meta name="viewport" content="width=device-width, initial-scale=1.0">"#,
+            "<title>{title}</title></head>",
+            r#"<body style="margin:0;padding:0;background-color:{background};">"#,
+            r#"<table role="presentation" width="100%" cellpadding="0" cellspacing="0" border="0" style="background-color:{background};">"#,
+            r#"<tr><td align="center" style="padding:24px 12px;">"#,
+            r#"<table role="presentation" width="{width}" cellpadding="0" cellspacing="0" border="0" style="width:{width}px;max-width:100%;background-color:{content};">"#,
+            "{rows}</table></td></tr></table></body></html>",
+        ),
+        title = escape_html(options.title.as_deref().unwrap_or_default()),
+        background = css_value(&options.background_color),
+        content = css_value(&options.content_color),
+        width = options.width,
+        rows = rows,
+    )
+}
+
+impl Segments {
+    /**
+    Render the segments as an HTML email, see [`render_email`].
+
+    # Arguments
+    - `options` - The title, width, font and colors of the email.
+
+    # Returns
+    A complete HTML document.
+    */
+    pub fn to_email_html(&self, options: &EmailOptions) -> String {
+        render_email(&[EmailSection { heading: None, segments: self }], options)
+    }
+}
+
+fn section_html(segments: &Segments, options: &EmailOptions, width: u32) -> String {
+    let mut html = String::new();
+
+    for segment in segments.iter() {
+        let field = |key: &str| segment.data.get(key).and_then(Value::as_str);
+
+        match (segment.type_.as_ref(), field("text"), field("file"), field("url")) {
+            ("text", Some(text), _, _) => {
+                let text = escape_html(text).replace('\n', "<br>");
+                let color = field("color").map(|color| format!("color:{};", css_value(color)));
+                let bold = segment.data.get("bold").and_then(Value::as_bool).unwrap_or(false);
+                match (color, bold) {
+                    (None, false) => html.push_str(&text),
+                    (color, bold) => html.push_str(&format!(
+                        r#"<span style="{}{}">{}</span>"#,
+                        color.unwrap_or_default(),
+                        if bold { "font-weight:bold;" } else { "" },
+                        text
+                    )),
+                }
+            }
+            ("link", text, _, Some(url)) => html.push_str(&link(url, &escape_html(text.unwrap_or(url)), options)),
+            ("image", _, Some(file), _) => html.push_str(&image(file, field("alt").unwrap_or_default(), width)),
+            ("video" | "audio", _, Some(file), _) => {
+                let content = match field("poster") {
+                    Some(poster) => image(poster, segment.type_.as_ref(), width),
+                    None => format!("▶ {}", escape_html(file)),
+                };
+                html.push_str(&link(file, &content, options));
+            }
+            ("table", _, _, _) => {
+                if let Some(table) = segment.as_table() {
+                    html.push_str(&table_html(&table));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    html
+}
+
+fn link(url: &str, content: &str, options: &EmailOptions) -> String {
+    format!(
+        r#"<a href="{}" style="color:{};text-decoration:underline;">{}</a>"#,
+        escape_html(url),
+        css_value(&options.link_color),
+        content
+    )
+}
+
+/// An image as wide as the content at most; the `width` attribute is what Outlook obeys.
+fn image(url: &str, alt: &str, width: u32) -> String {
+    format!(
+        r#"<img src="{}" alt="{}" width="{}" style="display:block;width:100%;max-width:{}px;height:auto;border:0;margin:8px 0;">"#,
+        escape_html(url),
+        escape_html(alt),
+        width,
+        width
+    )
+}
+
+fn table_html(table: &TableSegment) -> String {
+    const CELL: &str = "padding:6px 10px;border:1px solid #d1d5db;text-align:left;";
+
+    let mut html = String::from(
+        r#"<table role="presentation" cellpadding="0" cellspacing="0" border="0" style="border-collapse:collapse;margin:8px 0;">"#,
+    );
+    if !table.headers.is_empty() {
+        html.push_str("<tr>");
+        for header in &table.headers {
+            html.push_str(&format!(r#"<th style="{}font-weight:bold;">{}</th>"#, CELL, escape_html(header)));
+        }
+        html.push_str("</tr>");
+    }
+    for row in &table.rows {
+        html.push_str("<tr>");
+        for cell in row {
+            html.push_str(&format!(r#"<td style="{}">{}</td>"#, CELL, escape_html(cell)));
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// Keep only the characters of a CSS color or font list, so values cannot break out of a `style` attribute.
+fn css_value(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '#' | '(' | ')' | ',' | '.' | '%' | ' ' | '-'))
+        .collect()
+}
@@ -0,0 +1,162 @@
+use serde_json::Value;
+
+use crate::segment::Segments;
+use crate::result::ShindanResult;
+use crate::table::line_break_before;
+
+impl Segments {
+    /**
+    Convert the segments to CommonMark.
+
+    Line breaks inside paragraphs are kept as hard breaks, images become image links,
+    described by their `alt` text when they have one, and tables become code blocks.
+
+    # Returns
+    The Markdown string of the segments.
+
+    # Examples
+    ```
+    use serde_json::json;
+    use shindan_maker::{Segment, Segments};
+
+    let segments = Segments(vec![
+        Segment::new("text", json!({"text": "*STR*: 10\nDEX: 8\n"})),
+        Segment::new("image", json!({"file": "https://example.com/a.png"})),
+    ]);
+
+    assert_eq!(
+        segments.to_markdown(),
+        "\\*STR\\*: 10\\\nDEX: 8\\\n![image](https://example.com/a.png)"
+    );
+    ```
+    */
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        let mut inline = String::new();
+
+        for segment in self.iter() {
+            let field = |key: &str| segment.data.get(key).and_then(Value::as_str);
+
+            match (segment.type_.as_ref(), field("text"), field("file"), field("url")) {
+                ("text", Some(text), _, _) => inline.push_str(&escape_markdown(text)),
+                ("table", _, _, _) => {
+                    if let Some(table) = segment.as_table() {
+                        // Code blocks keep the columns aligned, and hard breaks out of them.
+                        markdown.push_str(&hard_breaks(&std::mem::take(&mut inline)));
+                        let code = format!("```\n{}\n```\n", table.to_plain_text());
+                        markdown.push_str(line_break_before(&markdown));
+                        markdown.push_str(&code);
+                    }
+                }
+                ("image", _, Some(file), _) => {
+                    let alt = field("alt").filter(|alt| !alt.is_empty()).unwrap_or("image");
+                    inline.push_str(&format!("![{}]({})", escape_markdown(alt), escape_destination(file)))
+                }
+                ("link", text, _, Some(url)) => {
+                    inline.push_str(&format!("[{}]({})", escape_markdown(text.unwrap_or(url)), escape_destination(url)))
+                }
+                ("video" | "audio", _, Some(file), _) => {
+                    inline.push_str(&format!("[{}]({})", escape_markdown(file), escape_destination(file)))
+                }
+                _ => {}
+            }
+        }
+
+        markdown.push_str(&hard_breaks(&inline));
+        markdown
+    }
+}
+
+impl ShindanResult {
+    /**
+    Convert the result to a CommonMark document.
+
+    # Arguments
+    - `front_matter` - Whether to prepend YAML front matter with the title, ID, URL and fetch date.
+
+    # Returns
+    The Markdown document, headed by the title of the shindan.
+
+    # Examples
+    ```
+    use serde_json::json;
+    use shindan_maker::{Segment, Segments, ShindanDomain, ShindanResult};
+
+    let result = ShindanResult::new(
+        "1222992",
+        "test_user",
+        ShindanDomain::En,
+        "Fantasy Stats",
+        Segments(vec![Segment::new("text", json!({"text": "STR: 10"}))]),
+    );
+
+    assert_eq!(result.to_markdown(false), "# Fantasy Stats\n\nSTR: 10\n");
+    assert!(result.to_markdown(true).starts_with("---\ntitle: \"Fantasy Stats\"\n"));
+    ```
+    */
+    pub fn to_markdown(&self, front_matter: bool) -> String {
+        let mut markdown = String::new();
+
+        if front_matter {
+            markdown.push_str("---\n");
+            markdown.push_str(&format!("title: {}\n", Value::from(self.title.as_str())));
+            markdown.push_str(&format!("id: {}\n", Value::from(self.id.as_str())));
+            markdown.push_str(&format!("url: {}\n", self.url()));
+            markdown.push_str(&format!("date: {}\n", self.fetched_at.to_rfc3339()));
+            markdown.push_str("---\n\n");
+        }
+
+        markdown.push_str(&format!("# {}\n\n", escape_markdown(&self.title)));
+        markdown.push_str(self.segments.to_markdown().trim_end());
+        markdown.push('\n');
+
+        markdown
+    }
+}
+
+/// Turn single line breaks into hard breaks, leaving blank lines as paragraph breaks.
+fn hard_breaks(markdown: &str) -> String {
+    let lines: Vec<&str> = markdown.split('\n').collect();
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            match lines.get(index + 1) {
+                Some(next) if !line.is_empty() && !next.is_empty() => format!("{}\\", line),
+                _ => line.to_string(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Escape a URL for a link destination, so that parentheses and spaces do not end it.
+fn escape_destination(url: &str) -> String {
+    let mut escaped = String::with_capacity(url.len());
+    for c in url.chars() {
+        match c {
+            '\\' | '(' | ')' | '<' | '>' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' ' => escaped.push_str("%20"),
+            c if c.is_control() => escaped.extend(c.to_string().bytes().map(|byte| format!("%{:02X}", byte))),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub(crate) fn escape_markdown(text: &str) -> String {
+    const SPECIAL: &str = "\\`*_[]<>#|~";
+
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if SPECIAL.contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,106 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::segment::Segments;
+use crate::table::TableSegment;
+use crate::html_utils::escape_html;
+
+/// An `m.room.message` event content for Matrix.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MatrixMessage {
+    pub msgtype: &'static str,
+    /// The plain-text fallback.
+    pub body: String,
+    pub format: &'static str,
+    /// The `org.matrix.custom.html` body.
+    pub formatted_body: String,
+    /// Image URLs to upload to the media repository and send as `m.image` events,
+    /// since Matrix clients only render `mxc://` images inside formatted bodies.
+    #[serde(skip)]
+    pub images: Vec<String>,
+}
+
+impl Segments {
+    /**
+    Convert the segments to a Matrix message.
+
+    # Returns
+    The Matrix message content, with the images listed separately.
+
+    # Examples
+    ```
+    use serde_json::json;
+    use shindan_maker::{Segment, Segments};
+
+    let segments = Segments(vec![
+        Segment::new("text", json!({"text": "A&B\n"})),
+        Segment::new("image", json!({"file": "https://example.com/a.png"})),
+    ]);
+
+    let message = segments.to_matrix_message();
+    assert_eq!(message.body, "A&B\n");
+    assert_eq!(message.formatted_body, "A&amp;B<br/>");
+    assert_eq!(message.images, vec!["https://example.com/a.png"]);
+    ```
+    */
+    pub fn to_matrix_message(&self) -> MatrixMessage {
+        let mut body = String::new();
+        let mut formatted_body = String::new();
+        let mut images = Vec::new();
+
+        for segment in self.iter() {
+            let field = |key: &str| segment.data.get(key).and_then(Value::as_str);
+
+            match (segment.type_.as_ref(), field("text"), field("file"), field("url")) {
+                ("text", Some(text), _, _) => {
+                    body.push_str(text);
+                    formatted_body.push_str(&escape_html(text).replace('\n', "<br/>"));
+                }
+                ("video" | "audio", _, Some(file), _) => {
+                    body.push_str(file);
+                    formatted_body.push_str(&format!("<a href=\"{0}\">{0}</a>", escape_html(file)));
+                }
+                ("link", text, _, Some(url)) => {
+                    body.push_str(url);
+                    formatted_body.push_str(&format!(
+                        "<a href=\"{}\">{}</a>",
+                        escape_html(url),
+                        escape_html(text.unwrap_or(url))
+                    ));
+                }
+                ("table", _, _, _) => {
+                    if let Some(table) = segment.as_table() {
+                        body.push_str(&table.to_text_block(&body));
+                        formatted_body.push_str(&html_table(&table));
+                    }
+                }
+                ("image", _, Some(file), _) => images.push(file.to_string()),
+                _ => {}
+            }
+        }
+
+        MatrixMessage {
+            msgtype: "m.text",
+            body,
+            format: "org.matrix.custom.html",
+            formatted_body,
+            images,
+        }
+    }
+}
+
+/// A table in the HTML subset Matrix clients render, with the plain text of its cells.
+fn html_table(table: &TableSegment) -> String {
+    let row = |cell: &str, cells: &[String]| {
+        let cells: String = cells.iter().map(|text| format!("<{0}>{1}</{0}>", cell, escape_html(text))).collect();
+        format!("<tr>{}</tr>", cells)
+    };
+
+    let mut html = String::from("<table>");
+    if !table.headers.is_empty() {
+        html.push_str(&format!("<thead>{}</thead>", row("th", &table.headers)));
+    }
+    let rows: String = table.rows.iter().map(|cells| row("td", cells)).collect();
+    html.push_str(&format!("<tbody>{}</tbody></table>", rows));
+    html
+}
@@ -0,0 +1,45 @@
+mod markdown;
+mod unicode;
+mod truncate;
+mod chunk;
+mod share;
+mod template;
+mod plain_text;
+
+#[cfg(feature = "onebot")]
+mod onebot;
+#[cfg(feature = "satori")]
+mod satori;
+#[cfg(feature = "telegram")]
+mod telegram;
+#[cfg(feature = "slack")]
+mod slack;
+#[cfg(feature = "matrix")]
+mod matrix;
+#[cfg(feature = "tui")]
+mod tui;
+#[cfg(feature = "bbcode")]
+mod bbcode;
+#[cfg(feature = "email")]
+mod email;
+#[cfg(feature = "html")]
+mod card;
+
+pub use share::{ShareOptions, SharePayload};
+pub(crate) use markdown::escape_markdown;
+pub use template::format_result;
+pub use plain_text::{ImagePlaceholder, PlainTextOptions};
+pub use chunk::ChunkStrategy;
+pub use unicode::{EmojiStyle, ProblemChars, UnicodeOptions};
+#[cfg(feature = "satori")]
+pub use satori::SatoriElement;
+#[cfg(feature = "telegram")]
+pub use telegram::{TelegramMessage, TelegramPhoto, TELEGRAM_MESSAGE_LIMIT};
+#[cfg(feature = "matrix")]
+pub use matrix::MatrixMessage;
+#[cfg(feature = "tui")]
+pub use tui::{ImageProtocol, TerminalOptions};
+#[cfg(feature = "email")]
+pub use email::{render_email, EmailOptions, EmailSection};
+#[cfg(feature = "html")]
+pub use card::CardTheme;
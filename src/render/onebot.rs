@@ -0,0 +1,191 @@
+use std::future::Future;
+use serde_json::{json, Value};
+use anyhow::{Context, Result};
+
+use crate::segment::{Segment, Segments};
+
+impl Segments {
+    /**
+    Convert the segments to a OneBot v11 message array.
+
+    Text is emitted verbatim, as required by the array message format.
+    Use [`to_cq_string`](Self::to_cq_string) for the legacy string format.
+
+    # Returns
+    A JSON array of OneBot v11 message segments.
+
+    # Examples
+    ```
+    use serde_json::json;
+    use shindan_maker::{Segment, Segments};
+
+    let segments = Segments(vec![
+        Segment::new("text", json!({"text": "Hello, world!"})),
+        Segment::new("image", json!({"file": "https://example.com/a.png"})),
+    ]);
+
+    assert_eq!(segments.to_onebot_v11(), json!([
+        {"type": "text", "data": {"text": "Hello, world!"}},
+        {"type": "image", "data": {"file": "https://example.com/a.png"}},
+    ]));
+    ```
+    */
+    pub fn to_onebot_v11(&self) -> Value {
+        Value::Array(self.with_previous().filter_map(|(previous, segment)| onebot_v11_segment(segment, previous)).collect())
+    }
+
+    /**
+    Convert the segments to a OneBot v12 message array.
+
+    OneBot v12 images, videos and audio reference a file by the `file_id` that the
+    `upload_file` action returns, not by URL, so each of their URLs is handed to `upload`,
+    which is expected to upload it, e.g. with `upload_file` and `"type": "url"`, and
+    return its `file_id`.
+
+    # Arguments
+    - `upload` - Uploads the file at a URL and returns its `file_id`.
+
+    # Returns
+    A JSON array of OneBot v12 message segments.
+
+    # Errors
+    Returns error if a file fails to upload.
+
+    # Examples
+    ```
+    use serde_json::json;
+    use shindan_maker::{Segment, Segments};
+
+    #[tokio::main]
+    async fn main() {
+        let segments = Segments(vec![
+            Segment::new("text", json!({"text": "Hello, world!"})),
+            Segment::new("image", json!({"file": "https://example.com/a.png"})),
+        ]);
+
+        // A bot would call the `upload_file` action of its implementation here.
+        let message = segments.to_onebot_v12(|url| async move { Ok(format!("uploaded:{}", url)) }).await.unwrap();
+
+        assert_eq!(message, json!([
+            {"type": "text", "data": {"text": "Hello, world!"}},
+            {"type": "image", "data": {"file_id": "uploaded:https://example.com/a.png"}},
+        ]));
+    }
+    ```
+    */
+    pub async fn to_onebot_v12<F, Fut>(&self, mut upload: F) -> Result<Value>
+    where
+        F: FnMut(String) -> Fut,
+        Fut: Future<Output = Result<String>>,
+    {
+        let mut message = Vec::with_capacity(self.len());
+        for (previous, segment) in self.with_previous() {
+            match (segment.type_.as_ref(), segment.data.get("file").and_then(Value::as_str)) {
+                (type_ @ ("image" | "video" | "audio"), Some(url)) => {
+                    let file_id = upload(url.to_string()).await.with_context(|| format!("Failed to upload the {} {}", type_, url))?;
+                    message.push(json!({"type": type_, "data": {"file_id": file_id}}));
+                }
+                _ => message.extend(onebot_v12_segment(segment, previous)),
+            }
+        }
+        Ok(Value::Array(message))
+    }
+
+    /**
+    Convert the segments to a single CQ code message string.
+
+    Text is escaped so that `&`, `[` and `]` are not read as CQ codes,
+    and CQ code parameters additionally escape `,`.
+
+    # Returns
+    The OneBot v11 string message.
+
+    # Examples
+    ```
+    use serde_json::json;
+    use shindan_maker::{Segment, Segments};
+
+    let segments = Segments(vec![
+        Segment::new("text", json!({"text": "[Rank] A&B\n"})),
+        Segment::new("image", json!({"file": "https://example.com/a.png?x=1,2"})),
+    ]);
+
+    assert_eq!(
+        segments.to_cq_string(),
+        "&#91;Rank&#93; A&amp;B\n[CQ:image,file=https://example.com/a.png?x=1&#44;2]"
+    );
+    ```
+    */
+    pub fn to_cq_string(&self) -> String {
+        self.to_onebot_v11()
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(cq_code)
+            .collect()
+    }
+}
+
+fn cq_code(segment: &Value) -> String {
+    let type_ = segment["type"].as_str().unwrap_or_default();
+
+    match (type_, segment["data"].as_object()) {
+        ("text", Some(data)) => escape_cq_text(data["text"].as_str().unwrap_or_default()),
+        (_, Some(data)) => {
+            let params: String = data
+                .iter()
+                .filter_map(|(key, value)| value.as_str().map(|value| format!(",{}={}", key, escape_cq_param(value))))
+                .collect();
+            format!("[CQ:{}{}]", type_, params)
+        }
+        _ => String::new(),
+    }
+}
+
+fn escape_cq_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('[', "&#91;")
+        .replace(']', "&#93;")
+}
+
+fn escape_cq_param(value: &str) -> String {
+    escape_cq_text(value).replace(',', "&#44;")
+}
+
+fn onebot_v11_segment(segment: &Segment, previous: Option<&Segment>) -> Option<Value> {
+    let text = |key: &str| segment.data.get(key).and_then(Value::as_str);
+
+    match segment.type_.as_ref() {
+        "text" => Some(json!({"type": "text", "data": {"text": text("text")?}})),
+        "table" => Some(json!({"type": "text", "data": {"text": segment.table_text(previous)?}})),
+        "image" => Some(json!({"type": "image", "data": {"file": text("file")?}})),
+        "video" => Some(json!({"type": "video", "data": {"file": text("file")?}})),
+        "audio" => Some(json!({"type": "record", "data": {"file": text("file")?}})),
+        "link" => Some(json!({
+            "type": "share",
+            "data": {
+                "url": text("url")?,
+                "title": text("text").or(text("title")).unwrap_or(text("url")?),
+            }
+        })),
+        _ => None,
+    }
+}
+
+fn onebot_v12_segment(segment: &Segment, previous: Option<&Segment>) -> Option<Value> {
+    let text = |key: &str| segment.data.get(key).and_then(Value::as_str);
+
+    match segment.type_.as_ref() {
+        "text" => Some(json!({"type": "text", "data": {"text": text("text")?}})),
+        "table" => Some(json!({"type": "text", "data": {"text": segment.table_text(previous)?}})),
+        "link" => {
+            let url = text("url")?;
+            let link = match text("text").filter(|text| *text != url) {
+                Some(text) => format!("{} ({})", text, url),
+                None => url.to_string(),
+            };
+            Some(json!({"type": "text", "data": {"text": link}}))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,174 @@
+use serde_json::Value;
+use serde::{Deserialize, Serialize};
+
+use crate::segment::Segments;
+
+/// How images are represented in plain text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImagePlaceholder {
+    /// Replace each image with the given text, e.g. `[image]`.
+    Text(String),
+    /// Replace each image with its URL.
+    Url,
+    /// Leave images out.
+    Omit,
+}
+
+/// Options for [`Segments::to_plain_text`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlainTextOptions {
+    /// How images are represented. Defaults to `[image]`.
+    pub image: ImagePlaceholder,
+    /// Wrap lines longer than this many characters. Defaults to no wrapping.
+    pub max_width: Option<usize>,
+    /// Normalize line endings, strip trailing spaces and collapse runs of blank lines.
+    /// Defaults to `true`.
+    pub normalize_newlines: bool,
+    /// Keep every space and blank line, e.g. of ASCII art meant for a monospace font:
+    /// lines are neither wrapped nor stripped, and non-breaking spaces become spaces.
+    /// Defaults to `false`.
+    pub preserve_whitespace: bool,
+}
+
+impl Default for PlainTextOptions {
+    fn default() -> Self {
+        Self {
+            image: ImagePlaceholder::Text("[image]".to_string()),
+            max_width: None,
+            normalize_newlines: true,
+            preserve_whitespace: false,
+        }
+    }
+}
+
+impl Segments {
+    /**
+    Convert the segments to plain text for text-only channels.
+
+    # Arguments
+    - `options` - How images, long lines and newlines are handled.
+
+    # Returns
+    The plain text of the segments.
+
+    # Examples
+    ```
+    use serde_json::json;
+    use shindan_maker::{ImagePlaceholder, PlainTextOptions, Segment, Segments};
+
+    let segments = Segments(vec![
+        Segment::new("text", json!({"text": "Your power level is over nine thousand\r\n\n\n\n"})),
+        Segment::new("image", json!({"file": "https://example.com/a.png"})),
+    ]);
+
+    let text = segments.to_plain_text(&PlainTextOptions {
+        image: ImagePlaceholder::Text("<image>".to_string()),
+        max_width: Some(20),
+        ..Default::default()
+    });
+
+    assert_eq!(text, "Your power level is\nover nine thousand\n\n<image>");
+    ```
+    */
+    pub fn to_plain_text(&self, options: &PlainTextOptions) -> String {
+        let mut text = String::new();
+
+        for segment in self.iter() {
+            let field = |key: &str| segment.data.get(key).and_then(Value::as_str);
+
+            match (segment.type_.as_ref(), field("text"), field("file"), field("url")) {
+                ("text", Some(content), _, _) => text.push_str(content),
+                ("link", _, _, Some(url)) => text.push_str(url),
+                ("video" | "audio", _, Some(file), _) => text.push_str(file),
+                ("image", _, Some(file), _) => match &options.image {
+                    ImagePlaceholder::Text(placeholder) => text.push_str(placeholder),
+                    ImagePlaceholder::Url => text.push_str(file),
+                    ImagePlaceholder::Omit => {}
+                },
+                ("table", _, _, _) => {
+                    if let Some(table) = segment.as_table() {
+                        text.push_str(&table.to_text_block(&text));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if options.preserve_whitespace {
+            let text = text.replace("\r\n", "\n").replace(['\r', '\u{a0}'], " ");
+            return text.trim_matches('\n').to_string();
+        }
+
+        if options.normalize_newlines {
+            text = normalize_newlines(&text);
+        }
+
+        match options.max_width {
+            Some(width) if width > 0 => text
+                .split('\n')
+                .map(|line| wrap_line(line, width))
+                .collect::<Vec<String>>()
+                .join("\n"),
+            _ => text,
+        }
+    }
+}
+
+fn normalize_newlines(text: &str) -> String {
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    let mut lines: Vec<&str> = Vec::new();
+
+    for line in text.split('\n').map(str::trim_end) {
+        let blank_run = line.is_empty() && lines.last().is_some_and(|last| last.is_empty());
+        if !blank_run {
+            lines.push(line);
+        }
+    }
+
+    lines.join("\n").trim_matches('\n').to_string()
+}
+
+/// Wrap a line at spaces where possible, breaking words longer than the width.
+///
+/// Leading whitespace, such as the indentation of nested list items, is kept
+/// and repeated on continuation lines.
+fn wrap_line(line: &str, width: usize) -> String {
+    let rest = line.trim_start();
+    let indent = &line[..line.len() - rest.len()];
+    let width = width.saturating_sub(indent.chars().count()).max(1);
+
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    let mut current_len = 0;
+
+    for word in rest.split(' ') {
+        let word_len = word.chars().count();
+
+        if current_len > 0 && current_len + 1 + word_len > width {
+            lines.push(std::mem::take(&mut current));
+            current_len = 0;
+        }
+        if current_len > 0 {
+            current.push(' ');
+            current_len += 1;
+        }
+
+        for c in word.chars() {
+            if current_len == width {
+                lines.push(std::mem::take(&mut current));
+                current_len = 0;
+            }
+            current.push(c);
+            current_len += 1;
+        }
+    }
+
+    lines.push(current);
+    lines
+        .iter()
+        .map(|line| format!("{}{}", indent, line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+
+use crate::segment::{Segment, Segments};
+use crate::html_utils::escape_html;
+
+/// A Satori message element.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SatoriElement {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub attrs: BTreeMap<String, String>,
+    pub children: Vec<SatoriElement>,
+}
+
+impl SatoriElement {
+    fn new(type_: &str, attrs: &[(&str, &str)], children: Vec<SatoriElement>) -> Self {
+        Self {
+            type_: type_.to_string(),
+            attrs: attrs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect(),
+            children,
+        }
+    }
+
+    /**
+    Serialize the element to Satori XHTML.
+
+    # Returns
+    The XHTML string of the element.
+    */
+    pub fn to_xhtml(&self) -> String {
+        if self.type_ == "text" {
+            return escape_html(self.attrs.get("content").map(String::as_str).unwrap_or_default());
+        }
+
+        let attrs: String = self.attrs
+            .iter()
+            .map(|(key, value)| format!(" {}=\"{}\"", key, escape_html(value)))
+            .collect();
+
+        if self.children.is_empty() {
+            format!("<{}{}/>", self.type_, attrs)
+        } else {
+            let children: String = self.children.iter().map(SatoriElement::to_xhtml).collect();
+            format!("<{}{}>{}</{}>", self.type_, attrs, children, self.type_)
+        }
+    }
+}
+
+impl Segments {
+    /**
+    Convert the segments to Satori message elements.
+
+    # Returns
+    The Satori elements of the segments.
+
+    # Examples
+    ```
+    use serde_json::json;
+    use shindan_maker::{Segment, Segments};
+
+    let segments = Segments(vec![
+        Segment::new("text", json!({"text": "Hello, world!"})),
+        Segment::new("image", json!({"file": "https://example.com/a.png"})),
+    ]);
+
+    let elements = segments.to_satori_elements();
+    assert_eq!(elements[0].type_, "text");
+    assert_eq!(elements[1].attrs["src"], "https://example.com/a.png");
+    ```
+    */
+    pub fn to_satori_elements(&self) -> Vec<SatoriElement> {
+        self.with_previous().filter_map(|(previous, segment)| satori_element(segment, previous)).collect()
+    }
+
+    /**
+    Convert the segments to a Satori XHTML message string.
+
+    # Returns
+    The Satori XHTML message.
+
+    # Examples
+    ```
+    use serde_json::json;
+    use shindan_maker::{Segment, Segments};
+
+    let segments = Segments(vec![
+        Segment::new("text", json!({"text": "<Rank> A&B"})),
+        Segment::new("image", json!({"file": "https://example.com/a.png"})),
+    ]);
+
+    assert_eq!(
+        segments.to_satori_xhtml(),
+        "&lt;Rank&gt; A&amp;B<img src=\"https://example.com/a.png\"/>"
+    );
+    ```
+    */
+    pub fn to_satori_xhtml(&self) -> String {
+        self.to_satori_elements().iter().map(SatoriElement::to_xhtml).collect()
+    }
+}
+
+fn satori_element(segment: &Segment, previous: Option<&Segment>) -> Option<SatoriElement> {
+    let text = |key: &str| segment.data.get(key).and_then(|value| value.as_str());
+
+    match segment.type_.as_ref() {
+        "text" => Some(SatoriElement::new("text", &[("content", text("text")?)], Vec::new())),
+        "table" => Some(SatoriElement::new("text", &[("content", &segment.table_text(previous)?)], Vec::new())),
+        "image" => Some(SatoriElement::new("img", &[("src", text("file")?)], Vec::new())),
+        "video" | "audio" => Some(SatoriElement::new(&segment.type_, &[("src", text("file")?)], Vec::new())),
+        "link" => {
+            let href = text("url")?;
+            let content = text("text").unwrap_or(href);
+            Some(SatoriElement::new(
+                "a",
+                &[("href", href)],
+                vec![SatoriElement::new("text", &[("content", content)], Vec::new())],
+            ))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,183 @@
+use reqwest::Url;
+
+use crate::result::ShindanResult;
+use crate::render::{ImagePlaceholder, PlainTextOptions};
+
+/// The weighted length limit of a post on X.
+const POST_LIMIT: usize = 280;
+/// The weighted length X counts for any URL.
+const URL_WEIGHT: usize = 23;
+
+/// The text of a post sharing a result on X.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharePayload {
+    /// The excerpt of the result.
+    pub text: String,
+    /// The hashtags, without `#`.
+    pub hashtags: Vec<String>,
+    /// The URL of the shindan.
+    pub url: String,
+}
+
+/// Options of a [`SharePayload`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareOptions {
+    /// The hashtags to post, without `#`, when the result has none. Defaults to `shindanmaker`.
+    pub fallback_hashtags: Vec<String>,
+}
+
+impl Default for ShareOptions {
+    fn default() -> Self {
+        Self { fallback_hashtags: vec!["shindanmaker".to_string()] }
+    }
+}
+
+impl SharePayload {
+    /**
+    Get the full post text: the excerpt, the hashtags and the URL on separate lines.
+
+    # Returns
+    The post text.
+    */
+    pub fn post_text(&self) -> String {
+        let hashtags = self.hashtags
+            .iter()
+            .map(|hashtag| format!("#{}", hashtag))
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        [self.text.as_str(), hashtags.as_str(), self.url.as_str()]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<&str>>()
+            .join("\n")
+    }
+
+    /**
+    Get the web intent URL opening the X post composer with the post text.
+
+    # Returns
+    The intent URL.
+    */
+    pub fn intent_url(&self) -> String {
+        Url::parse_with_params("https://x.com/intent/post", &[("text", self.post_text())])
+            .map(String::from)
+            .unwrap_or_default()
+    }
+}
+
+impl ShindanResult {
+    /**
+    Create the text to share the result on X, like the site's share button.
+
+    The post carries the [`hashtags`](ShindanResult::hashtags) of the result, or
+    `#shindanmaker` if none were found; see [`share_payload_with`](Self::share_payload_with)
+    to post other hashtags instead.
+
+    The result excerpt is trimmed so that the whole post, with hashtags and URL,
+    stays within the 280 weighted characters of a post.
+
+    # Returns
+    The share payload.
+
+    # Examples
+    ```
+    use serde_json::json;
+    use shindan_maker::{Segment, Segments, ShindanDomain, ShindanResult};
+
+    let result = ShindanResult::new(
+        "1222992",
+        "test_user",
+        ShindanDomain::En,
+        "Fantasy Stats",
+        Segments(vec![Segment::new("text", json!({"text": "STR: 10"}))]),
+    );
+
+    let payload = result.share_payload();
+    assert_eq!(payload.post_text(), "STR: 10\n#shindanmaker\nhttps://en.shindanmaker.com/1222992");
+    ```
+    */
+    pub fn share_payload(&self) -> SharePayload {
+        self.share_payload_with(&ShareOptions::default())
+    }
+
+    /**
+    Create the text to share the result on X with custom options.
+
+    # Arguments
+    - `options` - The share options, e.g. the hashtags used when the result has none.
+
+    # Returns
+    The share payload.
+
+    # Examples
+    ```
+    use serde_json::json;
+    use shindan_maker::{Segment, Segments, ShareOptions, ShindanDomain, ShindanResult};
+
+    let result = ShindanResult::new(
+        "1222992",
+        "test_user",
+        ShindanDomain::En,
+        "Fantasy Stats",
+        Segments(vec![Segment::new("text", json!({"text": "STR: 10"}))]),
+    );
+
+    let options = ShareOptions { fallback_hashtags: vec!["mybot".to_string()] };
+    assert_eq!(result.share_payload_with(&options).post_text(), "STR: 10\n#mybot\nhttps://en.shindanmaker.com/1222992");
+
+    let options = ShareOptions { fallback_hashtags: Vec::new() };
+    assert_eq!(result.share_payload_with(&options).post_text(), "STR: 10\nhttps://en.shindanmaker.com/1222992");
+    ```
+    */
+    pub fn share_payload_with(&self, options: &ShareOptions) -> SharePayload {
+        let hashtags = if self.hashtags.is_empty() {
+            options.fallback_hashtags.clone()
+        } else {
+            self.hashtags.clone()
+        };
+        let text = self.segments.to_plain_text(&PlainTextOptions {
+            image: ImagePlaceholder::Omit,
+            ..Default::default()
+        });
+
+        let hashtags_weight: usize = hashtags.iter().map(|hashtag| weighted_len(hashtag) + 2).sum();
+        let budget = POST_LIMIT.saturating_sub(hashtags_weight + URL_WEIGHT + 1);
+
+        SharePayload {
+            text: truncate_weighted(&text, budget),
+            hashtags,
+            url: self.url(),
+        }
+    }
+}
+
+/// The length of text as counted by X, where most non-Latin characters count twice.
+fn weighted_len(text: &str) -> usize {
+    text.chars().map(char_weight).sum()
+}
+
+fn char_weight(c: char) -> usize {
+    match c as u32 {
+        0x0000..=0x10FF | 0x2000..=0x200D | 0x2010..=0x201F | 0x2032..=0x2037 => 1,
+        _ => 2,
+    }
+}
+
+fn truncate_weighted(text: &str, budget: usize) -> String {
+    if weighted_len(text) <= budget {
+        return text.to_string();
+    }
+
+    let mut truncated = String::new();
+    let mut weight = 0;
+    for c in text.chars() {
+        if weight + char_weight(c) + char_weight('…') > budget {
+            break;
+        }
+        weight += char_weight(c);
+        truncated.push(c);
+    }
+
+    format!("{}…", truncated.trim_end())
+}
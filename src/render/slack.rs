@@ -0,0 +1,100 @@
+use serde_json::{json, Value};
+
+use crate::result::ShindanResult;
+use crate::table::line_break_before;
+
+impl ShindanResult {
+    /**
+    Convert the result to Slack Block Kit blocks.
+
+    The title becomes a header block, text becomes section blocks, tables become
+    code blocks in them and images become image blocks, in the order they appear in the result.
+
+    # Returns
+    A JSON array of Slack blocks.
+
+    # Examples
+    ```
+    use serde_json::json;
+    use shindan_maker::{Segment, Segments, ShindanDomain, ShindanResult};
+
+    let result = ShindanResult::new(
+        "1222992",
+        "test_user",
+        ShindanDomain::En,
+        "Fantasy Stats",
+        Segments(vec![
+            Segment::new("text", json!({"text": "STR: 10"})),
+            Segment::new("image", json!({"file": "https://example.com/a.png"})),
+        ]),
+    );
+
+    let blocks = result.to_slack_blocks();
+    assert_eq!(blocks[0]["type"], "header");
+    assert_eq!(blocks[1]["text"]["text"], "STR: 10");
+    assert_eq!(blocks[2]["image_url"], "https://example.com/a.png");
+    ```
+    */
+    pub fn to_slack_blocks(&self) -> Value {
+        const HEADER_LIMIT: usize = 150;
+
+        let mut blocks = vec![json!({
+            "type": "header",
+            "text": {"type": "plain_text", "text": truncate(&self.title, HEADER_LIMIT), "emoji": true},
+        })];
+        let mut text = String::new();
+
+        for segment in self.segments.iter() {
+            let field = |key: &str| segment.data.get(key).and_then(Value::as_str);
+
+            match (segment.type_.as_ref(), field("text"), field("file"), field("url")) {
+                ("text", Some(content), _, _) => text.push_str(&escape_mrkdwn(content)),
+                ("table", _, _, _) => {
+                    if let Some(table) = segment.as_table() {
+                        let code = format!("```{}```\n", escape_mrkdwn(&table.to_plain_text()));
+                        text.push_str(line_break_before(&text));
+                        text.push_str(&code);
+                    }
+                }
+                ("link", content, _, Some(url)) => {
+                    let link = escape_mrkdwn(url).replace('|', "%7C");
+                    text.push_str(&format!("<{}|{}>", link, escape_mrkdwn(content.unwrap_or(url))))
+                }
+                ("video" | "audio", _, Some(file), _) => {
+                    text.push_str(&format!("<{}|{}>", escape_mrkdwn(file).replace('|', "%7C"), escape_mrkdwn(file)))
+                }
+                ("image", _, Some(file), _) => {
+                    push_sections(&mut blocks, &std::mem::take(&mut text));
+                    blocks.push(json!({"type": "image", "image_url": file, "alt_text": self.title}));
+                }
+                _ => {}
+            }
+        }
+
+        push_sections(&mut blocks, &text);
+        Value::Array(blocks)
+    }
+}
+
+/// Push the text as section blocks, splitting it to stay within the section text limit.
+fn push_sections(blocks: &mut Vec<Value>, text: &str) {
+    const SECTION_LIMIT: usize = 3000;
+
+    let chars: Vec<char> = text.chars().collect();
+    for part in chars.chunks(SECTION_LIMIT) {
+        let part: String = part.iter().collect();
+        if !part.trim().is_empty() {
+            blocks.push(json!({"type": "section", "text": {"type": "mrkdwn", "text": part}}));
+        }
+    }
+}
+
+fn truncate(text: &str, limit: usize) -> String {
+    text.chars().take(limit).collect()
+}
+
+fn escape_mrkdwn(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,180 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::segment::Segments;
+use crate::html_utils::escape_html;
+
+/// The maximum length of a Telegram text message, in UTF-16 code units.
+pub const TELEGRAM_MESSAGE_LIMIT: usize = 4096;
+
+/// A shindan result prepared for the Telegram Bot API.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TelegramMessage {
+    /// The `parse_mode` to send the text chunks with.
+    pub parse_mode: &'static str,
+    /// Text chunks, each within [`TELEGRAM_MESSAGE_LIMIT`] UTF-16 code units of visible text, to send with `sendMessage`.
+    pub chunks: Vec<String>,
+    /// Images to send with `sendPhoto`.
+    pub photos: Vec<TelegramPhoto>,
+}
+
+/// A `sendPhoto` payload descriptor.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TelegramPhoto {
+    pub photo: String,
+}
+
+impl Segments {
+    /**
+    Convert the segments to Telegram HTML messages.
+
+    # Returns
+    The escaped text chunks and the images of the segments.
+
+    # Examples
+    ```
+    use serde_json::json;
+    use shindan_maker::{Segment, Segments};
+
+    let segments = Segments(vec![
+        Segment::new("text", json!({"text": "<Rank> A&B"})),
+        Segment::new("image", json!({"file": "https://example.com/a.png"})),
+    ]);
+
+    let message = segments.to_telegram_html();
+    assert_eq!(message.parse_mode, "HTML");
+    assert_eq!(message.chunks, vec!["&lt;Rank&gt; A&amp;B"]);
+    assert_eq!(message.photos[0].photo, "https://example.com/a.png");
+    ```
+    */
+    pub fn to_telegram_html(&self) -> TelegramMessage {
+        self.to_telegram("HTML", escape_html, |text, url| {
+            format!("<a href=\"{}\">{}</a>", escape_html(url), escape_html(text))
+        })
+    }
+
+    /**
+    Convert the segments to Telegram MarkdownV2 messages.
+
+    # Returns
+    The escaped text chunks and the images of the segments.
+
+    # Examples
+    ```
+    use serde_json::json;
+    use shindan_maker::{Segment, Segments};
+
+    let segments = Segments(vec![
+        Segment::new("text", json!({"text": "Score: 9.5 (A+)"})),
+    ]);
+
+    let message = segments.to_telegram_markdown_v2();
+    assert_eq!(message.parse_mode, "MarkdownV2");
+    assert_eq!(message.chunks, vec![r"Score: 9\.5 \(A\+\)"]);
+    ```
+    */
+    pub fn to_telegram_markdown_v2(&self) -> TelegramMessage {
+        self.to_telegram("MarkdownV2", escape_markdown_v2, |text, url| {
+            format!("[{}]({})", escape_markdown_v2(text), url.replace('\\', "\\\\").replace(')', "\\)"))
+        })
+    }
+
+    fn to_telegram(
+        &self,
+        parse_mode: &'static str,
+        escape: fn(&str) -> String,
+        link: fn(&str, &str) -> String,
+    ) -> TelegramMessage {
+        let mut pieces = Vec::new();
+        let mut photos = Vec::new();
+
+        for segment in self.iter() {
+            let field = |key: &str| segment.data.get(key).and_then(Value::as_str);
+
+            match (segment.type_.as_ref(), field("text"), field("file"), field("url")) {
+                ("text", Some(text), _, _) => pieces.extend(split_text(text, escape)),
+                ("table", _, _, _) => {
+                    let previous = pieces.last().map_or("", |(piece, _): &(String, usize)| piece.as_str());
+                    if let Some(table) = segment.as_table() {
+                        let block = table.to_text_block(previous);
+                        pieces.extend(split_text(&block, escape));
+                    }
+                }
+                ("image", _, Some(file), _) => photos.push(TelegramPhoto { photo: file.to_string() }),
+                ("video" | "audio", _, Some(file), _) => pieces.push((link(file, file), file.encode_utf16().count())),
+                ("link", text, _, Some(url)) => {
+                    let text = text.unwrap_or(url);
+                    pieces.push((link(text, url), text.encode_utf16().count()));
+                }
+                _ => {}
+            }
+        }
+
+        TelegramMessage {
+            parse_mode,
+            chunks: pack_chunks(pieces),
+            photos,
+        }
+    }
+}
+
+/// Split text into escaped pieces along line breaks, each within the message limit.
+///
+/// Telegram counts lengths in UTF-16 code units, so characters outside the Basic
+/// Multilingual Plane, such as most emoji, count twice; they are never split.
+fn split_text(text: &str, escape: fn(&str) -> String) -> Vec<(String, usize)> {
+    let mut pieces = Vec::new();
+
+    for line in text.split_inclusive('\n') {
+        let mut part = String::new();
+        let mut len = 0;
+        for c in line.chars() {
+            if len + c.len_utf16() > TELEGRAM_MESSAGE_LIMIT {
+                pieces.push((escape(&std::mem::take(&mut part)), len));
+                len = 0;
+            }
+            part.push(c);
+            len += c.len_utf16();
+        }
+        if !part.is_empty() {
+            pieces.push((escape(&part), len));
+        }
+    }
+
+    pieces
+}
+
+/// Pack escaped pieces into chunks whose visible length stays within the message limit.
+fn pack_chunks(pieces: Vec<(String, usize)>) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut chunk = String::new();
+    let mut len = 0;
+
+    for (piece, piece_len) in pieces {
+        if len + piece_len > TELEGRAM_MESSAGE_LIMIT && !chunk.is_empty() {
+            chunks.push(std::mem::take(&mut chunk));
+            len = 0;
+        }
+        chunk.push_str(&piece);
+        len += piece_len;
+    }
+
+    if !chunk.trim().is_empty() {
+        chunks.push(chunk);
+    }
+
+    chunks
+}
+
+fn escape_markdown_v2(text: &str) -> String {
+    const SPECIAL: &str = "\\_*[]()~`>#+-=|{}.!";
+
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if SPECIAL.contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
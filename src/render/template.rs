@@ -0,0 +1,129 @@
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+
+use crate::result::ShindanResult;
+use crate::shindan_domain::ShindanDomain;
+use crate::render::{ImagePlaceholder, PlainTextOptions};
+
+/**
+Format a result with a template.
+
+Placeholders are written as `{{variable}}` or `{{variable | filter | filter:arg}}`.
+
+Variables:
+- `title`, `description`, `id`, `name`, `url`, `domain`, `date`
+- `text` - The result as plain text, without images.
+- `first_image` - The URL of the first image, or nothing.
+- `images` - The URLs of all images, one per line.
+
+Filters:
+- `truncate:N` - Keep at most `N` characters, ending with `…` when cut.
+- `upper`, `lower`, `trim`
+- `oneline` - Replace line breaks with spaces.
+
+# Arguments
+- `template` - The template, e.g. loaded from a config file.
+- `result` - The result to format.
+
+# Returns
+The formatted message.
+
+# Errors
+Returns error if the template is unterminated or uses an unknown variable or filter.
+
+# Examples
+```
+use serde_json::json;
+use shindan_maker::{format_result, Segment, Segments, ShindanDomain, ShindanResult};
+
+let result = ShindanResult::new(
+    "1222992",
+    "test_user",
+    ShindanDomain::En,
+    "Fantasy Stats",
+    Segments(vec![
+        Segment::new("text", json!({"text": "STR: 10\nDEX: 8"})),
+        Segment::new("image", json!({"file": "https://example.com/a.png"})),
+    ]),
+);
+
+let message = format_result("{{title | upper}} — {{text | oneline | truncate:8}} {{first_image}}", &result).unwrap();
+assert_eq!(message, "FANTASY STATS — STR: 10… https://example.com/a.png");
+```
+*/
+pub fn format_result(template: &str, result: &ShindanResult) -> Result<String> {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+
+        let end = rest[start..]
+            .find("}}")
+            .with_context(|| format!("Unterminated placeholder at `{}`", &rest[start..]))?;
+        let mut parts = rest[start + 2..start + end].split('|').map(str::trim);
+
+        let mut value = variable(parts.next().unwrap_or_default(), result)?;
+        for filter in parts {
+            value = apply_filter(filter, value)?;
+        }
+
+        output.push_str(&value);
+        rest = &rest[start + end + 2..];
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+fn variable(name: &str, result: &ShindanResult) -> Result<String> {
+    let images = || {
+        result.segments
+            .iter()
+            .filter(|segment| segment.type_ == "image")
+            .filter_map(|segment| segment.data.get("file").and_then(Value::as_str))
+    };
+
+    Ok(match name {
+        "title" => result.title.clone(),
+        "description" => result.description.clone().unwrap_or_default(),
+        "id" => result.id.clone(),
+        "name" => result.name.clone(),
+        "url" => result.url(),
+        "domain" => match &result.domain {
+            ShindanDomain::Custom(url) => url.to_string(),
+            domain => serde_json::to_value(domain)?.as_str().unwrap_or_default().to_string(),
+        },
+        "date" => result.fetched_at.format("%Y-%m-%d").to_string(),
+        "text" => result.segments.to_plain_text(&PlainTextOptions {
+            image: ImagePlaceholder::Omit,
+            ..Default::default()
+        }),
+        "first_image" => images().next().unwrap_or_default().to_string(),
+        "images" => images().collect::<Vec<&str>>().join("\n"),
+        _ => return Err(anyhow!("Unknown template variable `{}`", name)),
+    })
+}
+
+fn apply_filter(filter: &str, value: String) -> Result<String> {
+    let (name, arg) = match filter.split_once(':') {
+        Some((name, arg)) => (name.trim(), Some(arg.trim())),
+        None => (filter, None),
+    };
+
+    Ok(match (name, arg) {
+        ("truncate", Some(limit)) => {
+            let limit: usize = limit.parse().with_context(|| format!("Invalid truncate length `{}`", limit))?;
+            if value.chars().count() > limit {
+                format!("{}…", value.chars().take(limit).collect::<String>().trim_end())
+            } else {
+                value
+            }
+        }
+        ("upper", None) => value.to_uppercase(),
+        ("lower", None) => value.to_lowercase(),
+        ("trim", None) => value.trim().to_string(),
+        ("oneline", None) => value.split_whitespace().collect::<Vec<&str>>().join(" "),
+        _ => return Err(anyhow!("Unknown template filter `{}`", filter)),
+    })
+}
@@ -0,0 +1,127 @@
+use serde_json::Value;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::segment::{Segment, Segments};
+
+/// Appended to truncated segments.
+const ELLIPSIS: &str = "…";
+
+impl Segments {
+    /**
+    Truncate the segments to at most `limit` characters, ending with `…` when cut.
+
+    Only the text of text and link segments counts. Text segments are cut between grapheme
+    clusters, so an emoji or accented letter is never split; link and image segments are
+    kept whole or dropped with everything after them.
+
+    # Arguments
+    - `limit` - The maximum number of characters, including the ellipsis; 0 leaves no segments.
+
+    # Returns
+    The truncated segments, or the segments as they are if they fit.
+
+    # Examples
+    ```
+    use shindan_maker::{Segment, Segments};
+
+    let segments = Segments(vec![
+        Segment::text("Your lucky item: "),
+        Segment::image("https://example.com/a.png"),
+        Segment::text("a rubber duck"),
+    ]);
+
+    assert_eq!(segments.truncate_chars(40), segments);
+    assert_eq!(
+        segments.truncate_chars(20),
+        Segments(vec![
+            Segment::text("Your lucky item: "),
+            Segment::image("https://example.com/a.png"),
+            Segment::text("a…"),
+        ])
+    );
+    ```
+    */
+    pub fn truncate_chars(&self, limit: usize) -> Segments {
+        self.truncate_by(limit, |text| text.chars().count())
+    }
+
+    /**
+    Truncate the segments to at most `limit` grapheme clusters, i.e. user-perceived
+    characters, ending with `…` when cut.
+
+    Like [`truncate_chars`](Self::truncate_chars), counting an emoji made of several
+    codepoints, e.g. a family or a flag, as one character.
+
+    # Arguments
+    - `limit` - The maximum number of grapheme clusters, including the ellipsis; 0 leaves no segments.
+
+    # Returns
+    The truncated segments, or the segments as they are if they fit.
+
+    # Examples
+    ```
+    use shindan_maker::{Segment, Segments};
+
+    let segments = Segments(vec![Segment::text("Family: 👨‍👩‍👧👨‍👩‍👧👨‍👩‍👧")]);
+
+    assert_eq!(segments.truncate_graphemes(10).to_string(), "Family: 👨‍👩‍👧…");
+    ```
+    */
+    pub fn truncate_graphemes(&self, limit: usize) -> Segments {
+        self.truncate_by(limit, |text| text.graphemes(true).count())
+    }
+
+    fn truncate_by(&self, limit: usize, measure: fn(&str) -> usize) -> Segments {
+        if limit == 0 {
+            return Segments(Vec::new());
+        }
+
+        let weight = |segment: &Segment| match segment.type_.as_ref() {
+            "text" | "link" => segment.data.get("text").and_then(Value::as_str).map_or(0, measure),
+            _ => 0,
+        };
+
+        if self.iter().map(weight).sum::<usize>() <= limit {
+            return self.clone();
+        }
+
+        let mut budget = limit.saturating_sub(measure(ELLIPSIS));
+        let mut truncated = Vec::new();
+
+        for segment in self.iter() {
+            let segment_weight = weight(segment);
+            if segment_weight <= budget {
+                budget -= segment_weight;
+                truncated.push(segment.clone());
+                continue;
+            }
+
+            if let ("text", Some(text)) = (segment.type_.as_ref(), segment.data.get("text").and_then(Value::as_str)) {
+                let mut cut = String::new();
+                for grapheme in text.graphemes(true) {
+                    let grapheme_weight = measure(grapheme);
+                    if grapheme_weight > budget {
+                        break;
+                    }
+                    budget -= grapheme_weight;
+                    cut.push_str(grapheme);
+                }
+
+                let mut segment = segment.clone();
+                segment.data["text"] = Value::String(cut);
+                truncated.push(segment);
+            }
+            break;
+        }
+
+        match truncated.last_mut().filter(|segment| segment.type_ == "text") {
+            Some(last) => {
+                let text = last.data.get("text").and_then(Value::as_str).unwrap_or_default();
+                last.data["text"] = Value::String(format!("{}{}", text.trim_end(), ELLIPSIS));
+            }
+            None => truncated.push(Segment::text(ELLIPSIS)),
+        }
+
+        Segments(truncated)
+    }
+}
@@ -0,0 +1,312 @@
+use std::env;
+use anyhow::Result;
+use serde_json::Value;
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+
+use crate::chart::{Chart, ChartOverrides};
+use crate::client::ShindanClient;
+use crate::result::ShindanResult;
+
+/// An inline image protocol supported by some terminals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageProtocol {
+    /// The kitty graphics protocol.
+    Kitty,
+    /// The iTerm2 inline images protocol, also understood by WezTerm.
+    ITerm2,
+}
+
+impl ImageProtocol {
+    /**
+    Detect the inline image protocol of the current terminal from the environment.
+
+    # Returns
+    - `Some(ImageProtocol)`: The protocol the terminal supports.
+    - `None`: If the terminal is not known to display inline images.
+    */
+    pub fn detect() -> Option<Self> {
+        let term = env::var("TERM").unwrap_or_default();
+        let term_program = env::var("TERM_PROGRAM").unwrap_or_default();
+
+        if env::var_os("KITTY_WINDOW_ID").is_some() || term.contains("kitty") {
+            Some(Self::Kitty)
+        } else if term_program == "iTerm.app" || term_program == "WezTerm" {
+            Some(Self::ITerm2)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the terminal can display the image: kitty is only sent PNG images.
+    fn displays(&self, image: &[u8]) -> bool {
+        match self {
+            Self::ITerm2 => true,
+            Self::Kitty => image.starts_with(b"\x89PNG\r\n\x1a\n"),
+        }
+    }
+
+    /**
+    Encode an image as an escape sequence displaying it inline.
+
+    # Arguments
+    - `image` - The bytes of a PNG (kitty) or any common image format (iTerm2).
+
+    # Returns
+    The escape sequence displaying the image.
+    */
+    pub fn encode(&self, image: &[u8]) -> String {
+        const KITTY_CHUNK: usize = 4096;
+
+        let data = BASE64_STANDARD.encode(image);
+
+        match self {
+            Self::ITerm2 => format!("\x1b]1337;File=inline=1;size={}:{}\x07", image.len(), data),
+            Self::Kitty => {
+                let chunks: Vec<&[u8]> = data.as_bytes().chunks(KITTY_CHUNK).collect();
+                chunks
+                    .iter()
+                    .enumerate()
+                    .map(|(index, chunk)| {
+                        let more = u8::from(index + 1 < chunks.len());
+                        let chunk = String::from_utf8_lossy(chunk);
+                        if index == 0 {
+                            format!("\x1b_Ga=T,f=100,m={};{}\x1b\\", more, chunk)
+                        } else {
+                            format!("\x1b_Gm={};{}\x1b\\", more, chunk)
+                        }
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Options for rendering results to the terminal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TerminalOptions {
+    /// Whether to emit ANSI colors and styles. Defaults to `false` if `NO_COLOR` is set.
+    pub color: bool,
+    /// The width of the longest chart bar, in cells. Defaults to 30.
+    pub chart_width: usize,
+    /// The protocol used to display images inline. Defaults to [`ImageProtocol::detect`].
+    pub image_protocol: Option<ImageProtocol>,
+    /// The labels and colors of charts to override; bars take the `#rrggbb` colors of their datasets.
+    pub chart_overrides: ChartOverrides,
+}
+
+impl Default for TerminalOptions {
+    fn default() -> Self {
+        Self {
+            color: env::var_os("NO_COLOR").is_none(),
+            chart_width: 30,
+            image_protocol: ImageProtocol::detect(),
+            chart_overrides: ChartOverrides::default(),
+        }
+    }
+}
+
+impl Chart {
+    /**
+    Render the chart as Unicode bars, one block per dataset.
+
+    Radar charts are approximated by bars along their axes.
+
+    # Arguments
+    - `width` - The width of the longest bar, in cells.
+
+    # Returns
+    The bars of the chart.
+
+    # Examples
+    ```
+    use shindan_maker::{Chart, ChartDataset};
+
+    let chart = Chart {
+        kind: "radar".to_string(),
+        labels: vec!["STR".to_string(), "DEX".to_string()],
+        datasets: vec![ChartDataset { label: None, data: vec![10.0, 5.0] }],
+    };
+
+    assert_eq!(chart.to_bars(4), "STR ████ 10\nDEX ██ 5\n");
+    ```
+    */
+    pub fn to_bars(&self, width: usize) -> String {
+        self.bars(width, |_, bar| bar.to_string())
+    }
+
+    /// Render the bars, painting each with the index of its dataset.
+    fn bars(&self, width: usize, paint: impl Fn(usize, &str) -> String) -> String {
+        const EIGHTHS: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+
+        let max = self.max_value();
+        // Labels come from the page, so they must not drive the terminal either.
+        let labels: Vec<String> = self.labels.iter().map(|label| without_controls(label)).collect();
+        let label_width = labels.iter().map(|label| label.chars().count()).max().unwrap_or(0);
+        let mut bars = String::new();
+
+        for (index, dataset) in self.datasets.iter().enumerate() {
+            if let Some(label) = &dataset.label {
+                bars.push_str(&format!("{}\n", without_controls(label)));
+            }
+
+            for (label, value) in labels.iter().zip(&dataset.data) {
+                let eighths = if max > 0.0 { (value / max * (width * 8) as f64).round() as usize } else { 0 };
+                let bar = format!("{}{}", "█".repeat(eighths / 8), EIGHTHS[eighths % 8]);
+                let padding = " ".repeat(label_width - label.chars().count());
+                bars.push_str(&format!("{}{} {} {}\n", label, padding, paint(index, &bar), value));
+            }
+        }
+
+        bars
+    }
+}
+
+impl ShindanResult {
+    /**
+    Render the result for the terminal, with images shown as links.
+
+    Text segments carrying `bold` or `color` (`#rrggbb`) data, as parsed from `<b>`,
+    `<strong>` and styled `<span>` elements, are styled accordingly. Control characters
+    of the result and its chart, which could drive the terminal, are left out.
+
+    # Arguments
+    - `options` - The terminal rendering options.
+
+    # Returns
+    The rendered result.
+    */
+    pub fn to_ansi(&self, options: &TerminalOptions) -> String {
+        self.render_ansi(options, |url| hyperlink(url, "[image]"))
+    }
+
+    fn render_ansi(&self, options: &TerminalOptions, mut image: impl FnMut(&str) -> String) -> String {
+        let style = |text: &str, codes: &[String]| {
+            if options.color && !codes.is_empty() {
+                format!("\x1b[{}m{}\x1b[0m", codes.join(";"), text)
+            } else {
+                text.to_string()
+            }
+        };
+
+        let mut output = style(&without_controls(&self.title), &["1".to_string()]);
+        output.push_str("\n\n");
+
+        for segment in self.segments.iter() {
+            let field = |key: &str| segment.data.get(key).and_then(Value::as_str);
+
+            match (segment.type_.as_ref(), field("text"), field("file"), field("url")) {
+                ("text", Some(text), _, _) => {
+                    let mut codes = Vec::new();
+                    if segment.data.get("bold").and_then(Value::as_bool).unwrap_or(false) {
+                        codes.push("1".to_string());
+                    }
+                    if let Some((r, g, b)) = field("color").and_then(parse_hex_color) {
+                        codes.push(format!("38;2;{};{};{}", r, g, b));
+                    }
+                    output.push_str(&style(&without_controls(text), &codes));
+                }
+                ("table", _, _, _) => {
+                    if let Some(table) = segment.as_table() {
+                        output.push_str(&without_controls(&table.to_text_block(&output)));
+                    }
+                }
+                ("link", text, _, Some(url)) => output.push_str(&hyperlink(url, text.unwrap_or(url))),
+                ("image", _, Some(file), _) => output.push_str(&image(file)),
+                ("video", _, Some(file), _) => output.push_str(&hyperlink(file, "[video]")),
+                ("audio", _, Some(file), _) => output.push_str(&hyperlink(file, "[audio]")),
+                _ => {}
+            }
+        }
+
+        if let Some(chart) = &self.chart {
+            let overrides = &options.chart_overrides;
+            let chart = chart.localize(overrides);
+            let paint = |index: usize, bar: &str| match overrides.colors.get(index).and_then(|color| parse_hex_color(color)) {
+                Some((r, g, b)) => style(bar, &[format!("38;2;{};{};{}", r, g, b)]),
+                None => bar.to_string(),
+            };
+
+            output.push_str("\n\n");
+            output.push_str(&chart.bars(options.chart_width, paint));
+        }
+
+        output
+    }
+}
+
+impl ShindanClient {
+    /**
+    Render a result for the terminal, downloading images to display them inline
+    when the terminal supports it. Images kitty cannot display, as it is only sent
+    PNG images, are shown as links.
+
+    # Arguments
+    - `result` - The result to render.
+    - `options` - The terminal rendering options.
+
+    # Returns
+    The rendered result.
+
+    # Errors
+    Returns error if an image cannot be downloaded.
+
+    # Examples
+    ```
+    use shindan_maker::{ShindanClient, ShindanDomain, TerminalOptions};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::En).unwrap();
+
+        let result = client.get_result("1222992", "test_user").await.unwrap();
+        let output = client
+            .render_terminal(&result, &TerminalOptions::default())
+            .await
+            .unwrap();
+
+        println!("{}", output);
+    }
+    ```
+    */
+    pub async fn render_terminal(&self, result: &ShindanResult, options: &TerminalOptions) -> Result<String> {
+        let Some(protocol) = options.image_protocol else {
+            return Ok(result.to_ansi(options));
+        };
+
+        let mut images = Vec::new();
+        for segment in result.segments.iter().filter(|segment| segment.type_ == "image") {
+            if let Some(url) = segment.data.get("file").and_then(Value::as_str) {
+                let image = self.fetch_bytes(url).await?;
+                images.push(protocol.displays(&image).then(|| protocol.encode(&image)));
+            }
+        }
+
+        let mut images = images.into_iter();
+        Ok(result.render_ansi(options, |url| match images.next().flatten() {
+            Some(image) => format!("\n{}\n", image),
+            None => hyperlink(url, "[image]"),
+        }))
+    }
+}
+
+fn hyperlink(url: &str, text: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", without_controls(url), without_controls(text))
+}
+
+/// The text without its control characters but for line breaks and tabs.
+fn without_controls(text: &str) -> String {
+    text.chars().filter(|&c| !c.is_control() || matches!(c, '\n' | '\t')).collect()
+}
+
+fn parse_hex_color(color: &str) -> Option<(u8, u8, u8)> {
+    let hex = color.strip_prefix('#')?;
+    let hex = match hex.len() {
+        3 => hex.chars().flat_map(|c| [c, c]).collect(),
+        6 => hex.to_string(),
+        _ => return None,
+    };
+
+    let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
@@ -0,0 +1,229 @@
+use serde_json::Value;
+
+use crate::segment::Segments;
+
+/// Shortcodes of common emoji, as used by Slack, Discord and GitHub.
+const SHORTCODES: &[(char, &str)] = &[
+    ('😀', "grinning"),
+    ('😁', "grin"),
+    ('😂', "joy"),
+    ('😃', "smiley"),
+    ('😄', "smile"),
+    ('😅', "sweat_smile"),
+    ('😆', "laughing"),
+    ('😇', "innocent"),
+    ('😉', "wink"),
+    ('😊', "blush"),
+    ('😍', "heart_eyes"),
+    ('😎', "sunglasses"),
+    ('😏', "smirk"),
+    ('😐', "neutral_face"),
+    ('😘', "kissing_heart"),
+    ('😜', "stuck_out_tongue_winking_eye"),
+    ('😢', "cry"),
+    ('😭', "sob"),
+    ('😱', "scream"),
+    ('😳', "flushed"),
+    ('😴', "sleeping"),
+    ('🙂', "slightly_smiling_face"),
+    ('🙏', "pray"),
+    ('🤔', "thinking"),
+    ('🤣', "rofl"),
+    ('🥰', "smiling_face_with_three_hearts"),
+    ('🥺', "pleading_face"),
+    ('👀', "eyes"),
+    ('👍', "+1"),
+    ('👎', "-1"),
+    ('👏', "clap"),
+    ('👑', "crown"),
+    ('💀', "skull"),
+    ('💕', "two_hearts"),
+    ('💖', "sparkling_heart"),
+    ('💯', "100"),
+    ('🌸', "cherry_blossom"),
+    ('🌟', "star2"),
+    ('🍀', "four_leaf_clover"),
+    ('🎉', "tada"),
+    ('🐱', "cat"),
+    ('🐶', "dog"),
+    ('🔥', "fire"),
+    ('🚀', "rocket"),
+    ('❤', "heart"),
+    ('✨', "sparkles"),
+    ('⭐', "star"),
+];
+
+/// How problem codepoints, e.g. zero-width spaces and bidi controls, are handled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProblemChars {
+    /// Leave them in the text.
+    Keep,
+    /// Remove them.
+    Strip,
+    /// Replace each of them with the given text.
+    Replace(String),
+}
+
+/// How emoji are represented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmojiStyle {
+    /// Leave emoji as they are.
+    Keep,
+    /// Replace emoji with shortcodes, e.g. `:fire:`, or their codepoint, e.g. `:1f9ff:`,
+    /// for emoji without a known shortcode.
+    Shortcode,
+    /// Remove emoji.
+    Strip,
+}
+
+/**
+Options for [`Segments::normalize_unicode`], for chat platforms choking on some Unicode.
+
+Only pictographic emoji are touched: symbols such as `♡` and `☆` are emoji only when
+followed by a variation selector, so kaomoji like `(＾▽＾)☆` are kept intact.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnicodeOptions {
+    /// How zero-width spaces, bidi controls and byte order marks are handled. Defaults to
+    /// [`ProblemChars::Strip`]. Zero-width joiners and variation selectors are always kept,
+    /// since emoji sequences and ideographic variants need them, unless their emoji is converted.
+    pub problem_chars: ProblemChars,
+    /// Convert fullwidth spaces (U+3000) to ASCII spaces. Defaults to `true`.
+    pub fullwidth_spaces: bool,
+    /// How emoji are represented. Defaults to [`EmojiStyle::Keep`].
+    pub emoji: EmojiStyle,
+}
+
+impl Default for UnicodeOptions {
+    fn default() -> Self {
+        Self {
+            problem_chars: ProblemChars::Strip,
+            fullwidth_spaces: true,
+            emoji: EmojiStyle::Keep,
+        }
+    }
+}
+
+impl UnicodeOptions {
+    /**
+    Normalize a text.
+
+    # Arguments
+    - `text` - The text to normalize.
+
+    # Returns
+    The normalized text.
+    */
+    pub fn apply(&self, text: &str) -> String {
+        let mut normalized = String::with_capacity(text.len());
+        let mut chars = text.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            let emoji_presentation = chars.peek() == Some(&'\u{FE0F}');
+
+            if self.emoji != EmojiStyle::Keep && (is_pictographic(c) || emoji_presentation) {
+                if self.emoji == EmojiStyle::Shortcode {
+                    normalized.push_str(&shortcode(c));
+                }
+                // The variation selector and joiners belong to the emoji.
+                while chars.next_if(|&next| is_problem(next) || is_sequence_part(next)).is_some() {}
+                continue;
+            }
+
+            if is_problem(c) {
+                match &self.problem_chars {
+                    ProblemChars::Keep => normalized.push(c),
+                    ProblemChars::Strip => {}
+                    ProblemChars::Replace(replacement) => normalized.push_str(replacement),
+                }
+            } else if c == '\u{3000}' && self.fullwidth_spaces {
+                normalized.push(' ');
+            } else {
+                normalized.push(c);
+            }
+        }
+
+        normalized
+    }
+}
+
+impl Segments {
+    /**
+    Normalize the Unicode of the text segments, for chat platforms choking on some codepoints.
+
+    # Arguments
+    - `options` - How problem codepoints, fullwidth spaces and emoji are handled.
+
+    # Returns
+    The segments with normalized text.
+
+    # Examples
+    ```
+    use serde_json::json;
+    use shindan_maker::{EmojiStyle, Segment, Segments, UnicodeOptions};
+
+    let segments = Segments(vec![
+        Segment::new("text", json!({"text": "Lucky\u{3000}item: 🔥 (＾▽＾)☆ ❤\u{FE0F}\u{200B}"})),
+    ]);
+
+    let normalized = segments.normalize_unicode(&UnicodeOptions {
+        emoji: EmojiStyle::Shortcode,
+        ..Default::default()
+    });
+
+    assert_eq!(normalized.to_string(), "Lucky item: :fire: (＾▽＾)☆ :heart:");
+    ```
+    */
+    pub fn normalize_unicode(&self, options: &UnicodeOptions) -> Segments {
+        Segments(
+            self.iter()
+                .map(|segment| {
+                    let mut segment = segment.clone();
+                    if let Some(text) = segment.data.get("text").and_then(Value::as_str) {
+                        segment.data["text"] = Value::String(options.apply(text));
+                    }
+                    segment
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Whether a codepoint is invisible or changes the direction of its neighbours.
+fn is_problem(c: char) -> bool {
+    matches!(
+        c,
+        '\u{200B}' | '\u{200C}' | '\u{200E}' | '\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{2069}'
+            | '\u{FEFF}'
+    )
+}
+
+/// Whether a codepoint joins or selects the presentation of the codepoints before it,
+/// i.e. a zero-width joiner or a variation selector.
+fn is_sequence_part(c: char) -> bool {
+    matches!(c, '\u{200D}' | '\u{FE00}'..='\u{FE0F}' | '\u{E0100}'..='\u{E01EF}')
+}
+
+/// Whether a codepoint is an emoji in its default presentation.
+fn is_pictographic(c: char) -> bool {
+    matches!(
+        c,
+        '\u{1F1E6}'..='\u{1F1FF}'
+            | '\u{1F300}'..='\u{1F64F}'
+            | '\u{1F680}'..='\u{1F6FF}'
+            | '\u{1F7E0}'..='\u{1F7EB}'
+            | '\u{1F900}'..='\u{1F9FF}'
+            | '\u{1FA70}'..='\u{1FAFF}'
+            | '\u{2728}'
+            | '\u{2B50}'
+    )
+}
+
+fn shortcode(c: char) -> String {
+    match SHORTCODES.iter().find(|(emoji, _)| *emoji == c) {
+        Some((_, name)) => format!(":{}:", name),
+        None => format!(":{:x}:", c as u32),
+    }
+}
@@ -0,0 +1,51 @@
+use std::fmt;
+#[cfg(any(feature = "segments", feature = "html"))]
+use reqwest::Response;
+use serde::{Deserialize, Serialize};
+
+/**
+The final URL and HTTP status of the response a result was scraped from.
+
+Results carry it in [`ShindanResult::response`](crate::ShindanResult::response), and
+errors raised while scraping a result page carry it as context, so a mis-routed or
+cached response can be told apart without enabling request logging.
+
+# Examples
+```no_run
+use shindan_maker::{ResponseInfo, ShindanClient, ShindanDomain};
+
+#[tokio::main]
+async fn main() {
+    let client = ShindanClient::new(ShindanDomain::En).unwrap();
+
+    if let Err(err) = client.get_result("1222992", "test_user").await {
+        if let Some(response) = err.downcast_ref::<ResponseInfo>() {
+            eprintln!("{} answered {}", response.url, response.status);
+        }
+    }
+}
+```
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResponseInfo {
+    /// The URL of the response, after following redirects.
+    pub url: String,
+    /// The HTTP status code.
+    pub status: u16,
+}
+
+impl ResponseInfo {
+    #[cfg(any(feature = "segments", feature = "html"))]
+    pub(crate) fn of(response: &Response) -> Self {
+        Self {
+            url: response.url().to_string(),
+            status: response.status().as_u16(),
+        }
+    }
+}
+
+impl fmt::Display for ResponseInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Response from {} with status {}", self.url, self.status)
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::BTreeMap;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+use serde::{Deserialize, Serialize};
+
+use crate::chart::Chart;
+use crate::segment::Segments;
+use crate::response_info::ResponseInfo;
+#[cfg(feature = "language")]
+use crate::render::{ImagePlaceholder, PlainTextOptions};
+use crate::shindan_domain::ShindanDomain;
+
+/// The version of the JSON schema written by [`ShindanResult::to_json`].
+pub const RESULT_SCHEMA_VERSION: u32 = 1;
+
+/// A complete result of a shindan submission.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShindanResult {
+    /// The ID of the shindan.
+    pub id: String,
+    /// The name the shindan was run with.
+    pub name: String,
+    /// The domain the shindan was run on.
+    pub domain: ShindanDomain,
+    /// The title of the shindan.
+    pub title: String,
+    /// The description of the shindan, if it has one.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The segments of the result.
+    pub segments: Segments,
+    /// The hashtags the site's share links post the result with, without `#`.
+    ///
+    /// Unlike the [tags](crate::ShindanMetadata::tags) of the shindan, they are chosen for sharing.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hashtags: Vec<String>,
+    /// The segments translated by the [`Translator`](crate::Translator) of the client, if one is configured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translation: Option<Segments>,
+    /// The language of the result text as an ISO 639-3 code, e.g. `eng`, detected with the `language` feature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// The chart of the result, if the shindan draws one.
+    #[serde(default)]
+    pub chart: Option<Chart>,
+    /// Scores read from the text by the [`ScoreRule`](crate::ScoreRule)s of the client, with the `scores` feature.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extracted_scores: BTreeMap<String, f64>,
+    /// Free-form metadata attached by the application, e.g. the chat the result was sent to.
+    #[serde(default)]
+    pub metadata: Map<String, Value>,
+    /// When the result was fetched.
+    pub fetched_at: DateTime<Utc>,
+    /// The final URL and status of the result page, if the result was submitted rather than built or archived.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<ResponseInfo>,
+}
+
+#[derive(Serialize)]
+struct ResultDocument<'a> {
+    version: u32,
+    #[serde(flatten)]
+    result: &'a ShindanResult,
+}
+
+#[derive(Deserialize)]
+struct OwnedResultDocument {
+    version: u32,
+    #[serde(flatten)]
+    result: ShindanResult,
+}
+
+impl ShindanResult {
+    /**
+    Create a new result fetched now.
+
+    # Arguments
+    - `id` - The ID of the shindan.
+    - `name` - The name the shindan was run with.
+    - `domain` - The domain the shindan was run on.
+    - `title` - The title of the shindan.
+    - `segments` - The segments of the result.
+
+    # Returns
+    A new result without description, hashtags, translation, language, chart, scores, metadata or response.
+
+    # Examples
+    ```
+    use shindan_maker::{Segments, ShindanDomain, ShindanResult};
+
+    let result = ShindanResult::new("1222992", "test_user", ShindanDomain::En, "Fantasy Stats", Segments(vec![]));
+    ```
+    */
+    pub fn new(id: &str, name: &str, domain: ShindanDomain, title: &str, segments: Segments) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            domain,
+            title: title.to_string(),
+            description: None,
+            segments,
+            hashtags: Vec::new(),
+            translation: None,
+            language: None,
+            chart: None,
+            extracted_scores: BTreeMap::new(),
+            metadata: Map::new(),
+            fetched_at: Utc::now(),
+            response: None,
+        }
+    }
+
+    /**
+    Get the URL of the shindan page.
+
+    # Returns
+    The URL of the shindan on its domain.
+
+    # Examples
+    ```
+    use shindan_maker::{Segments, ShindanDomain, ShindanResult};
+
+    let result = ShindanResult::new("1222992", "test_user", ShindanDomain::En, "Fantasy Stats", Segments(vec![]));
+
+    assert_eq!(result.url(), "https://en.shindanmaker.com/1222992");
+    ```
+    */
+    pub fn url(&self) -> String {
+        format!("{}{}", self.domain, self.id)
+    }
+
+    /**
+    Detect the language of the result text, with the `language` feature.
+
+    Submitted results are annotated already; this is for results built by hand or
+    archived without a language.
+
+    # Returns
+    The ISO 639-3 code of the language, e.g. `eng`, or `None` if the text is too
+    short or ambiguous to tell.
+
+    # Examples
+    ```
+    use shindan_maker::{Segment, Segments, ShindanDomain, ShindanResult};
+
+    let segments = Segments(vec![Segment::text("Today you will find a treasure hidden under the old bridge.")]);
+    let result = ShindanResult::new("1222992", "test_user", ShindanDomain::En, "Fortune", segments);
+
+    assert_eq!(Some("eng".to_string()), result.detect_language());
+    ```
+    */
+    #[cfg(feature = "language")]
+    pub fn detect_language(&self) -> Option<String> {
+        let text = self.segments.to_plain_text(&PlainTextOptions {
+            image: ImagePlaceholder::Omit,
+            ..Default::default()
+        });
+
+        whatlang::detect(&text).map(|info| info.lang().code().to_string())
+    }
+
+    /**
+    Serialize the result to JSON for archiving.
+
+    The document is an object holding a `version` field (see [`RESULT_SCHEMA_VERSION`])
+    next to the `id`, `name`, `domain`, `title`, `description`, `segments`, `hashtags`, `translation`, `language`, `chart`,
+    `metadata`, `fetched_at` (RFC 3339) and, when known, `response` fields of the result.
+
+    # Returns
+    The pretty-printed JSON document.
+
+    # Errors
+    Returns error if the metadata cannot be serialized.
+
+    # Examples
+    ```
+    use shindan_maker::{Segments, ShindanDomain, ShindanResult};
+
+    let result = ShindanResult::new("1222992", "test_user", ShindanDomain::En, "Fantasy Stats", Segments(vec![]));
+
+    let json = result.to_json().unwrap();
+    let restored = ShindanResult::from_json(&json).unwrap();
+
+    assert_eq!(restored.title, "Fantasy Stats");
+    assert_eq!(restored.fetched_at, result.fetched_at);
+    ```
+    */
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&ResultDocument {
+            version: RESULT_SCHEMA_VERSION,
+            result: self,
+        })?)
+    }
+
+    /**
+    Deserialize a result archived with [`to_json`](Self::to_json).
+
+    # Arguments
+    - `json` - The JSON document.
+
+    # Returns
+    The archived result.
+
+    # Errors
+    Returns error if the document is malformed or written by a newer schema version.
+    */
+    pub fn from_json(json: &str) -> Result<Self> {
+        let document: OwnedResultDocument = serde_json::from_str(json)?;
+
+        if document.version > RESULT_SCHEMA_VERSION {
+            return Err(anyhow!(
+                "Unsupported result schema version {} (expected at most {})",
+                document.version,
+                RESULT_SCHEMA_VERSION
+            ));
+        }
+
+        Ok(document.result)
+    }
+}
@@ -0,0 +1,258 @@
+use std::fmt;
+use std::sync::Mutex;
+use anyhow::Result;
+use std::time::Duration;
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap};
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::site_day;
+use crate::result::ShindanResult;
+use crate::shindan_domain::ShindanDomain;
+
+/// The identity of a result: the same name gets the same result for a site day.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    domain: String,
+    id: String,
+    name: String,
+    day: NaiveDate,
+}
+
+impl CacheKey {
+    /**
+    Create the key of a result.
+
+    # Arguments
+    - `domain` - The domain the shindan is run on.
+    - `id` - The shindan ID.
+    - `name` - The name the shindan is run with.
+    - `at` - When the shindan is run; only its site day is kept.
+
+    # Returns
+    The cache key.
+    */
+    pub fn new(domain: ShindanDomain, id: &str, name: &str, at: DateTime<Utc>) -> Self {
+        Self {
+            domain: domain.to_string(),
+            id: id.to_string(),
+            name: name.to_string(),
+            day: site_day::site_day(at),
+        }
+    }
+
+    /// The base URL of the domain the shindan is run on.
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// The shindan ID.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The name the shindan is run with.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The site day of the result.
+    pub fn day(&self) -> NaiveDate {
+        self.day
+    }
+}
+
+/// Formats the key as a single string, e.g. for a Redis key or a sled tree key.
+impl fmt::Display for CacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}|{}|{}|{}", self.domain, self.id, self.day, self.name)
+    }
+}
+
+/**
+A store for results, shared by every clone of a client.
+
+Implement it to share results between processes, e.g. on top of Redis or sled.
+A backend should drop entries once `expires_at` has passed; returning an expired
+entry is harmless but serves a result from a previous site day.
+
+# Examples
+```
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use shindan_maker::{CacheKey, ResultCache, ShindanClient, ShindanDomain, ShindanResult};
+
+#[derive(Debug)]
+struct NoCache;
+
+#[async_trait]
+impl ResultCache for NoCache {
+    async fn get(&self, _key: &CacheKey) -> Result<Option<ShindanResult>> {
+        Ok(None)
+    }
+
+    async fn put(&self, _key: CacheKey, _result: ShindanResult, _expires_at: DateTime<Utc>) -> Result<()> {
+        Ok(())
+    }
+
+    async fn invalidate(&self, _key: &CacheKey) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn main() -> Result<()> {
+    let client = ShindanClient::builder(ShindanDomain::En)
+        .result_cache_backend(NoCache)
+        .build()?;
+    Ok(())
+}
+```
+*/
+#[async_trait]
+pub trait ResultCache: fmt::Debug + Send + Sync {
+    /// Look up a result that has not expired yet.
+    async fn get(&self, key: &CacheKey) -> Result<Option<ShindanResult>>;
+
+    /// Store a result until `expires_at`.
+    async fn put(&self, key: CacheKey, result: ShindanResult, expires_at: DateTime<Utc>) -> Result<()>;
+
+    /// Remove a result.
+    async fn invalidate(&self, key: &CacheKey) -> Result<()>;
+}
+
+/// An in-memory [`ResultCache`] evicting the least recently used result once full.
+#[derive(Debug)]
+pub struct MemoryCache {
+    capacity: usize,
+    entries: Mutex<MemoryEntries>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryEntries {
+    tick: u64,
+    map: HashMap<CacheKey, MemoryEntry>,
+    /// The keys by the tick they were last used at, least recently used first.
+    recency: BTreeMap<u64, CacheKey>,
+    /// The keys by expiry and the tick they were stored at, soonest first.
+    expiry: BTreeMap<(DateTime<Utc>, u64), CacheKey>,
+}
+
+#[derive(Debug)]
+struct MemoryEntry {
+    result: ShindanResult,
+    expires_at: DateTime<Utc>,
+    stored: u64,
+    used: u64,
+}
+
+impl MemoryEntries {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn remove(&mut self, key: &CacheKey) -> Option<MemoryEntry> {
+        let entry = self.map.remove(key)?;
+        self.recency.remove(&entry.used);
+        self.expiry.remove(&(entry.expires_at, entry.stored));
+        Some(entry)
+    }
+
+    fn prune_expired(&mut self, now: DateTime<Utc>) {
+        while let Some(entry) = self.expiry.first_entry() {
+            if entry.key().0 > now {
+                break;
+            }
+            let key = entry.remove();
+            self.remove(&key);
+        }
+    }
+}
+
+impl MemoryCache {
+    /// The number of results kept by [`MemoryCache::default`].
+    pub const DEFAULT_CAPACITY: usize = 1024;
+
+    /**
+    Create an empty cache.
+
+    # Arguments
+    - `capacity` - The maximum number of results kept at once.
+
+    # Returns
+    A new in-memory cache.
+    */
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: Mutex::new(MemoryEntries::default()),
+        }
+    }
+
+    /// The number of results currently stored, including expired ones not yet pruned.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().map.len()
+    }
+
+    /// Whether no results are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for MemoryCache {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+#[async_trait]
+impl ResultCache for MemoryCache {
+    async fn get(&self, key: &CacheKey) -> Result<Option<ShindanResult>> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.prune_expired(Utc::now());
+        let tick = entries.next_tick();
+
+        let Some(entry) = entries.map.get_mut(key) else {
+            return Ok(None);
+        };
+        let used = std::mem::replace(&mut entry.used, tick);
+        let result = entry.result.clone();
+
+        entries.recency.remove(&used);
+        entries.recency.insert(tick, key.clone());
+        Ok(Some(result))
+    }
+
+    async fn put(&self, key: CacheKey, result: ShindanResult, expires_at: DateTime<Utc>) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.prune_expired(Utc::now());
+        let tick = entries.next_tick();
+
+        if entries.remove(&key).is_none() && entries.map.len() >= self.capacity {
+            if let Some((_, oldest)) = entries.recency.pop_first() {
+                entries.remove(&oldest);
+            }
+        }
+        entries.recency.insert(tick, key.clone());
+        entries.expiry.insert((expires_at, tick), key.clone());
+        entries.map.insert(key, MemoryEntry { result, expires_at, stored: tick, used: tick });
+
+        Ok(())
+    }
+
+    async fn invalidate(&self, key: &CacheKey) -> Result<()> {
+        self.entries.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+/// When a result fetched at `fetched_at` expires: at the end of its site day, or after `ttl` if sooner.
+pub(crate) fn expires_at(fetched_at: DateTime<Utc>, ttl: Option<Duration>) -> DateTime<Utc> {
+    let day_end = site_day::site_day_end(fetched_at);
+    match ttl.and_then(|ttl| chrono::Duration::from_std(ttl).ok()) {
+        Some(ttl) => day_end.min(fetched_at + ttl),
+        None => day_end,
+    }
+}
@@ -0,0 +1,29 @@
+//! The runtime services the client needs besides its HTTP connections.
+//!
+//! They come from Tokio or, with the `timer-agnostic` feature, from threads of
+//! their own that any executor can await.
+
+use std::time::Instant;
+
+/// Wait until `deadline`.
+pub(crate) async fn sleep_until(deadline: Instant) {
+    #[cfg(feature = "timer-agnostic")]
+    futures_timer::Delay::new(deadline.saturating_duration_since(Instant::now())).await;
+
+    #[cfg(not(feature = "timer-agnostic"))]
+    tokio::time::sleep_until(deadline.into()).await;
+}
+
+/// Run blocking work off the executor.
+#[cfg(feature = "history-sqlite")]
+pub(crate) async fn spawn_blocking<T, F>(f: F) -> anyhow::Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    #[cfg(feature = "timer-agnostic")]
+    return Ok(blocking::unblock(f).await);
+
+    #[cfg(not(feature = "timer-agnostic"))]
+    Ok(tokio::task::spawn_blocking(f).await?)
+}
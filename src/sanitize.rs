@@ -0,0 +1,167 @@
+use scraper::{ElementRef, Html, Node};
+
+/// Elements kept, with the attributes they may carry besides [`GLOBAL_ATTRIBUTES`]: those of result markup.
+const ALLOWED: &[(&str, &[&str])] = &[
+    ("a", &["href"]),
+    ("abbr", &[]),
+    ("audio", &["src", "controls"]),
+    ("b", &[]),
+    ("big", &[]),
+    ("blockquote", &[]),
+    ("br", &[]),
+    ("caption", &[]),
+    ("center", &[]),
+    ("code", &[]),
+    ("del", &[]),
+    ("div", &[]),
+    ("em", &[]),
+    ("h1", &[]),
+    ("h2", &[]),
+    ("h3", &[]),
+    ("h4", &[]),
+    ("h5", &[]),
+    ("h6", &[]),
+    ("hr", &[]),
+    ("i", &[]),
+    ("img", &["src", "data-src", "srcset", "alt", "width", "height"]),
+    ("ins", &[]),
+    ("li", &[]),
+    ("mark", &[]),
+    ("ol", &["start"]),
+    ("p", &[]),
+    ("picture", &[]),
+    ("pre", &[]),
+    ("q", &[]),
+    ("rp", &[]),
+    ("rt", &[]),
+    ("ruby", &[]),
+    ("s", &[]),
+    ("small", &[]),
+    ("source", &["src", "srcset", "type", "media"]),
+    ("span", &[]),
+    ("strong", &[]),
+    ("sub", &[]),
+    ("sup", &[]),
+    ("table", &[]),
+    ("tbody", &[]),
+    ("td", &["colspan", "rowspan"]),
+    ("tfoot", &[]),
+    ("th", &["colspan", "rowspan"]),
+    ("thead", &[]),
+    ("tr", &[]),
+    ("u", &[]),
+    ("ul", &[]),
+    ("video", &["src", "poster", "controls", "width", "height"]),
+    ("wbr", &[]),
+];
+
+/// Attributes kept on every allowed element.
+const GLOBAL_ATTRIBUTES: &[&str] = &["class", "title", "lang", "dir"];
+
+/// Elements dropped with their content, rather than replaced by it: their text is code, styles or foreign markup.
+const DROPPED: &[&str] = &[
+    "script", "style", "noscript", "template", "iframe", "frame", "frameset", "object", "embed",
+    "applet", "form", "select", "textarea", "svg", "math", "head", "title",
+];
+
+/// Elements without an end tag.
+const VOID: &[&str] = &["br", "hr", "img", "source", "wbr"];
+
+/// Attributes holding a URL, or a list of them for `srcset`.
+const URL_ATTRIBUTES: &[&str] = &["href", "src", "data-src", "srcset", "poster"];
+
+/**
+Serialize an HTML fragment keeping only the elements and attributes of result markup.
+
+Other elements are replaced by their content, except scripts, styles, frames, forms,
+SVG and MathML, which are dropped with it. URLs must be relative or use `http` or
+`https`, or `mailto` for links; attributes with any other URL are dropped.
+*/
+pub(crate) fn sanitize_fragment(html: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+    let mut sanitized = String::with_capacity(html.len());
+    write_children(fragment.root_element(), &mut sanitized);
+    sanitized
+}
+
+fn write_children(element: ElementRef, out: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => escape_into(text, false, out),
+            Node::Element(node) => {
+                let Some(child) = ElementRef::wrap(child) else {
+                    continue;
+                };
+                let name = node.name();
+                if DROPPED.contains(&name) {
+                    continue;
+                }
+                let Some(&(_, attributes)) = ALLOWED.iter().find(|(allowed, _)| *allowed == name) else {
+                    write_children(child, out);
+                    continue;
+                };
+
+                out.push('<');
+                out.push_str(name);
+                for (attribute, value) in node.attrs() {
+                    let allowed = attributes.contains(&attribute) || GLOBAL_ATTRIBUTES.contains(&attribute);
+                    if allowed && is_safe_value(name, attribute, value) {
+                        out.push(' ');
+                        out.push_str(attribute);
+                        out.push_str("=\"");
+                        escape_into(value, true, out);
+                        out.push('"');
+                    }
+                }
+                out.push('>');
+
+                if !VOID.contains(&name) {
+                    write_children(child, out);
+                    out.push_str("</");
+                    out.push_str(name);
+                    out.push('>');
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn is_safe_value(element: &str, attribute: &str, value: &str) -> bool {
+    match attribute {
+        "srcset" => value.split(',').all(|candidate| is_safe_url(candidate.split_whitespace().next().unwrap_or(""), false)),
+        _ if URL_ATTRIBUTES.contains(&attribute) => is_safe_url(value, element == "a"),
+        _ => true,
+    }
+}
+
+/// Whether a URL is relative or uses `http` or `https`, or `mailto` for links.
+fn is_safe_url(url: &str, link: bool) -> bool {
+    // Browsers ignore whitespace and control characters inside the scheme.
+    let url: String = url.chars().filter(|c| !c.is_whitespace() && !c.is_control()).collect();
+    let Some(colon) = url.find(':') else {
+        return true;
+    };
+    if url[..colon].contains(['/', '?', '#']) {
+        return true;
+    }
+
+    match url[..colon].to_ascii_lowercase().as_str() {
+        "http" | "https" => true,
+        "mailto" => link,
+        _ => false,
+    }
+}
+
+fn escape_into(text: &str, attribute: bool, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' if attribute => out.push_str("&quot;"),
+            '\u{a0}' => out.push_str("&nbsp;"),
+            _ => out.push(c),
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::future::Future;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use futures::future;
+use chrono::{NaiveDate, Utc};
+
+use crate::rt;
+use crate::site_day;
+use crate::client::ShindanClient;
+use crate::result::ShindanResult;
+
+/// The future returned by a delivery callback of a [`DailyScheduler`].
+pub type DeliveryFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+
+type Delivery = Arc<dyn Fn(ShindanResult) -> DeliveryFuture + Send + Sync>;
+
+struct DailyJob {
+    id: String,
+    name: String,
+    deliver: Delivery,
+    /// The site day the result was last delivered.
+    last_run: Option<NaiveDate>,
+}
+
+impl fmt::Debug for DailyJob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DailyJob")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("last_run", &self.last_run)
+            .finish_non_exhaustive()
+    }
+}
+
+/**
+Runs shindans once per site day and delivers their results, e.g. for a daily fortune bot.
+
+Each job runs a shindan with a name and hands the result to its callback, typically
+posting it to a channel. Jobs sharing a shindan and name submit it once per day.
+A failing run or delivery is retried after a delay, without holding up the other
+jobs; a job still failing is skipped until the next site day, which starts at midnight JST.
+
+# Examples
+```no_run
+use shindan_maker::{DailyScheduler, ShindanClient, ShindanDomain};
+
+#[tokio::main]
+async fn main() {
+    let client = ShindanClient::new(ShindanDomain::En).unwrap();
+
+    DailyScheduler::new(client)
+        .job("1222992", "alice", |result| async move {
+            println!("#general: {}", result.segments);
+            Ok(())
+        })
+        .run()
+        .await;
+}
+```
+*/
+#[derive(Debug)]
+pub struct DailyScheduler {
+    client: ShindanClient,
+    jobs: Vec<DailyJob>,
+    retries: u32,
+    retry_delay: Duration,
+}
+
+impl DailyScheduler {
+    /// Create a scheduler running shindans with a client, retrying a failing run or delivery 3 times, 30 seconds apart.
+    pub fn new(client: ShindanClient) -> Self {
+        Self {
+            client,
+            jobs: Vec::new(),
+            retries: 3,
+            retry_delay: Duration::from_secs(30),
+        }
+    }
+
+    /**
+    Add a job.
+
+    # Arguments
+    - `id` - The shindan ID.
+    - `name` - The name to run the shindan with.
+    - `deliver` - The callback receiving the result; its errors are logged and retried.
+    */
+    pub fn job<F, Fut>(mut self, id: &str, name: &str, deliver: F) -> Self
+    where
+        F: Fn(ShindanResult) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.jobs.push(DailyJob {
+            id: id.to_string(),
+            name: name.to_string(),
+            deliver: Arc::new(move |result| Box::pin(deliver(result))),
+            last_run: None,
+        });
+        self
+    }
+
+    /// Set the number of times a failing run or delivery is retried. Defaults to 3.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Set the time between retries of a failing run or delivery. Defaults to 30 seconds.
+    pub fn retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /**
+    Run the jobs not yet delivered on the current site day.
+
+    Jobs run concurrently, so that one being retried does not hold up the others.
+    A job whose run or delivery still fails after its retries stays due.
+
+    # Returns
+    The number of jobs delivered.
+    */
+    pub async fn run_due(&mut self) -> usize {
+        let today = site_day::site_today();
+        let mut groups: HashMap<(&str, &str), Vec<usize>> = HashMap::new();
+        for (index, job) in self.jobs.iter().enumerate().filter(|(_, job)| job.last_run != Some(today)) {
+            groups.entry((job.id.as_str(), job.name.as_str())).or_default().push(index);
+        }
+
+        let runs = groups.into_iter().map(|((id, name), jobs)| self.run_group(id, name, jobs));
+        let delivered: Vec<usize> = future::join_all(runs).await.into_iter().flatten().collect();
+
+        for &index in &delivered {
+            self.jobs[index].last_run = Some(today);
+        }
+        delivered.len()
+    }
+
+    /// Run the jobs now and then at the start of every site day, forever.
+    pub async fn run(mut self) {
+        loop {
+            self.run_due().await;
+
+            let now = Utc::now();
+            let wait = (site_day::site_day_end(now) - now).to_std().unwrap_or_default();
+            rt::sleep_until(Instant::now() + wait).await;
+        }
+    }
+
+    /// Run a shindan once for the jobs sharing it and deliver the result to each, returning those delivered.
+    async fn run_group(&self, id: &str, name: &str, jobs: Vec<usize>) -> Vec<usize> {
+        let result = match self.with_retries(id, || self.client.get_result(id, name)).await {
+            Ok(result) => result,
+            Err(err) => {
+                log::warn!(id = id; "Skipped a daily shindan for today: {:#}", err);
+                return Vec::new();
+            }
+        };
+
+        let mut delivered = Vec::with_capacity(jobs.len());
+        for index in jobs {
+            let deliver = &self.jobs[index].deliver;
+            match self.with_retries(id, || deliver(result.clone())).await {
+                Ok(()) => delivered.push(index),
+                Err(err) => log::warn!(id = id; "Failed to deliver a daily shindan: {:#}", err),
+            }
+        }
+        delivered
+    }
+
+    async fn with_retries<T, Fut>(&self, id: &str, mut run: impl FnMut() -> Fut) -> Result<T>
+    where
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match run().await {
+                Ok(value) => return Ok(value),
+                Err(err) if attempt < self.retries => {
+                    attempt += 1;
+                    log::debug!(id = id, attempt = attempt; "Retrying a daily shindan: {:#}", err);
+                    rt::sleep_until(Instant::now() + self.retry_delay).await;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+use anyhow::{Context, Result};
+use regex::Regex;
+
+use crate::result::ShindanResult;
+
+/**
+A rule extracting a score from the text of results, e.g. a rarity or a number of points.
+
+A keyword rule scores the longest of its keywords found in the text; keywords made
+of ASCII letters or digits only match whole words, so `R` does not match inside `STR`.
+A pattern rule scores the first match of a regex: the text captured by its `score`
+group, else its first group, else the whole match, read as a number or looked up
+in its [values](Self::values).
+
+# Examples
+```
+use shindan_maker::{ScoreRule, Segment, Segments, ShindanDomain, ShindanResult};
+
+let rules = [
+    ScoreRule::rarity("rarity"),
+    ScoreRule::points("points"),
+    ScoreRule::pattern("rank", r"Rank: (?<score>[SABC])").unwrap().values(&[("S", 4.0), ("A", 3.0), ("B", 2.0), ("C", 1.0)]),
+];
+let text = "Alice pulled an SSR card!\nAffection: ８５点\nRank: A";
+let result = ShindanResult::new("1", "Alice", ShindanDomain::En, "Gacha", Segments(vec![Segment::text(text)]));
+
+let scores = result.extract_scores(&rules);
+assert_eq!(Some(&4.0), scores.get("rarity"));
+assert_eq!(Some(&85.0), scores.get("points"));
+assert_eq!(Some(&3.0), scores.get("rank"));
+```
+*/
+#[derive(Debug, Clone)]
+pub struct ScoreRule {
+    name: String,
+    matcher: Matcher,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Keywords(Vec<(String, f64)>),
+    Pattern { regex: Regex, values: Vec<(String, f64)> },
+}
+
+impl ScoreRule {
+    /**
+    Create a rule scoring the longest keyword found in the text.
+
+    # Arguments
+    - `name` - The key of the score in [`ShindanResult::extracted_scores`].
+    - `keywords` - The keywords with their scores.
+    */
+    pub fn keywords(name: &str, keywords: &[(&str, f64)]) -> Self {
+        let mut keywords: Vec<(String, f64)> = keywords.iter().map(|(keyword, score)| (keyword.to_string(), *score)).collect();
+        keywords.sort_by_key(|(keyword, _)| std::cmp::Reverse(keyword.chars().count()));
+        Self { name: name.to_string(), matcher: Matcher::Keywords(keywords) }
+    }
+
+    /**
+    Create a rule scoring the first match of a regex.
+
+    # Arguments
+    - `name` - The key of the score in [`ShindanResult::extracted_scores`].
+    - `pattern` - The regex, whose `score` group or first group holds the score.
+
+    # Errors
+    Returns error if the regex is invalid.
+    */
+    pub fn pattern(name: &str, pattern: &str) -> Result<Self> {
+        let regex = Regex::new(pattern).with_context(|| format!("Invalid score pattern of {}", name))?;
+        Ok(Self { name: name.to_string(), matcher: Matcher::Pattern { regex, values: Vec::new() } })
+    }
+
+    /// Score the text captured by a pattern rule with a table, e.g. ranks; other captures are read as numbers.
+    pub fn values(mut self, table: &[(&str, f64)]) -> Self {
+        if let Matcher::Pattern { values, .. } = &mut self.matcher {
+            values.extend(table.iter().map(|(text, score)| (text.to_string(), *score)));
+        }
+        self
+    }
+
+    /// A keyword rule for gacha rarities: `UR` 5, `SSR` 4, `SR` 3, `R` 2, `N` 1.
+    pub fn rarity(name: &str) -> Self {
+        Self::keywords(name, &[("UR", 5.0), ("SSR", 4.0), ("SR", 3.0), ("R", 2.0), ("N", 1.0)])
+    }
+
+    /// A pattern rule for numbers of points, e.g. `85点`, `85 pts` or `85 points`.
+    pub fn points(name: &str) -> Self {
+        Self::pattern(name, r"(?i)(?<score>\d+(?:[.,]\d+)*)\s*(?:点|pts?\b|points?\b)").expect("Invalid points pattern")
+    }
+
+    /// The key of the score.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Score a text, or `None` if the rule does not match it.
+    pub fn score(&self, text: &str) -> Option<f64> {
+        match &self.matcher {
+            Matcher::Keywords(keywords) => keywords
+                .iter()
+                .find(|(keyword, _)| contains_keyword(text, keyword))
+                .map(|(_, score)| *score),
+            Matcher::Pattern { regex, values } => {
+                let captures = regex.captures(text)?;
+                let matched = captures.name("score").or_else(|| captures.get(1)).or_else(|| captures.get(0))?.as_str();
+                values
+                    .iter()
+                    .find(|(value, _)| value == matched)
+                    .map(|(_, score)| *score)
+                    .or_else(|| parse_number(matched))
+            }
+        }
+    }
+}
+
+impl ShindanResult {
+    /**
+    Score the text of the result with rules.
+
+    Clients configured with [`score_rules`](crate::ShindanClientBuilder::score_rules)
+    fill [`extracted_scores`](Self::extracted_scores) with this already.
+
+    # Arguments
+    - `rules` - The rules; a rule that does not match has no score.
+
+    # Returns
+    The scores by the names of their rules.
+    */
+    pub fn extract_scores(&self, rules: &[ScoreRule]) -> BTreeMap<String, f64> {
+        let text = self.segments.to_string();
+        rules
+            .iter()
+            .filter_map(|rule| rule.score(&text).map(|score| (rule.name.clone(), score)))
+            .collect()
+    }
+}
+
+/// Whether a keyword is in a text, as a whole word if it is alphanumeric ASCII.
+fn contains_keyword(text: &str, keyword: &str) -> bool {
+    if keyword.is_empty() {
+        return false;
+    }
+    if !keyword.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return text.contains(keyword);
+    }
+
+    text.match_indices(keyword).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + keyword.len()..].chars().next();
+        !before.is_some_and(|c| c.is_ascii_alphanumeric()) && !after.is_some_and(|c| c.is_ascii_alphanumeric())
+    })
+}
+
+/// Read a number, which may use full-width digits and commas as thousands separators.
+fn parse_number(text: &str) -> Option<f64> {
+    let ascii: String = text
+        .chars()
+        .filter(|c| !matches!(c, ',' | '，'))
+        .map(|c| match c {
+            '０'..='９' => char::from(b'0' + (c as u32 - '０' as u32) as u8),
+            '．' => '.',
+            _ => c,
+        })
+        .collect();
+    ascii.trim().parse().ok()
+}
@@ -0,0 +1,120 @@
+use std::collections::HashSet;
+use anyhow::Result;
+use scraper::Html;
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+
+use crate::http_utils;
+use crate::client::ShindanClient;
+use crate::selectors::Selectors;
+use crate::shindan_domain::ShindanDomain;
+
+/// A shindan found by [`ShindanClient::search`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub id: String,
+    pub title: String,
+    /// The domain the shindan was found on.
+    pub domain: ShindanDomain,
+}
+
+impl SearchHit {
+    /**
+    Get the URL of the shindan, on the domain it was found on.
+
+    # Examples
+    ```
+    use shindan_maker::{SearchHit, ShindanDomain};
+
+    let hit = SearchHit { id: "1222992".to_string(), title: "Fantasy Stats".to_string(), domain: ShindanDomain::En };
+
+    assert_eq!(hit.url(), "https://en.shindanmaker.com/1222992");
+    ```
+    */
+    pub fn url(&self) -> String {
+        format!("{}{}", self.domain, self.id)
+    }
+}
+
+impl ShindanClient {
+    /**
+    Search shindans by keyword.
+
+    # Arguments
+    - `keyword` - The keyword to search for.
+
+    # Returns
+    The shindans of the first page of results, in the order of the site.
+
+    # Errors
+    Returns error if the search page cannot be fetched.
+
+    # Examples
+    ```
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::En).unwrap();
+
+        for hit in client.search("fantasy").await.unwrap() {
+            println!("{}: {}", hit.id, hit.title);
+        }
+    }
+    ```
+    */
+    pub async fn search(&self, keyword: &str) -> Result<Vec<SearchHit>> {
+        self.fetch_hits(self.client.get(self.url("list/search")).query(&[("q", keyword)])).await
+    }
+
+    /**
+    List the shindans on the page of an author.
+
+    # Arguments
+    - `author` - The screen name of the author.
+
+    # Returns
+    The shindans of the first page, in the order of the site.
+
+    # Errors
+    Returns error if the page of the author cannot be fetched.
+    */
+    pub async fn author_shindans(&self, author: &str) -> Result<Vec<SearchHit>> {
+        self.fetch_hits(self.client.get(self.author_url(author))).await
+    }
+
+    /// The URL of the page of an author, whose screen name may hold any character.
+    pub(crate) fn author_url(&self, author: &str) -> String {
+        self.url(&format!("author/{}", http_utils::encode_path_segment(author)))
+    }
+
+    /// Fetch a page listing shindans and collect them.
+    pub(crate) async fn fetch_hits(&self, request: RequestBuilder) -> Result<Vec<SearchHit>> {
+        let response = self.send(request).await?.error_for_status()?;
+        let text = self.read_text(response).await?;
+
+        Ok(extract_hits(&self.selectors, &self.domain, &Html::parse_document(&text)))
+    }
+}
+
+/// Collect the links to shindans of a page, once per shindan.
+pub(crate) fn extract_hits(selectors: &Selectors, domain: &ShindanDomain, dom: &Html) -> Vec<SearchHit> {
+    let mut seen = HashSet::new();
+
+    dom.select(&selectors.link)
+        .filter_map(|link| {
+            let href = link.value().attr("href")?;
+            let id = href.trim_end_matches('/').rsplit('/').next()?;
+            if id.is_empty() || !id.bytes().all(|byte| byte.is_ascii_digit()) {
+                return None;
+            }
+
+            let title = link.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ");
+            if title.is_empty() || !seen.insert(id.to_string()) {
+                return None;
+            }
+
+            Some(SearchHit { id: id.to_string(), title, domain: domain.clone() })
+        })
+        .collect()
+}
@@ -1,8 +1,14 @@
 use std::fmt;
 use std::ops::Deref;
-use serde_json::Value;
+use scraper::Html;
+use anyhow::Result;
+use serde_json::{Map, Value};
 use serde::{Deserialize, Serialize};
 
+use crate::html_utils;
+use crate::selectors::SELECTORS;
+use crate::parse_options::ParseOptions;
+
 /// A segment of a shindan result.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Segment {
@@ -30,13 +36,92 @@ impl Segment {
     let segment = Segment::new("text", json!({"text": "Hello, world!"}));
     ```
     */
-    pub fn new(type_: &str, data: Value) -> Self {
+    pub fn new(type_: impl Into<String>, data: Value) -> Self {
         Segment {
-            type_: type_.to_string(),
+            type_: type_.into(),
             data,
         }
     }
 
+    /**
+    Create a text segment.
+
+    # Examples
+    ```
+    use serde_json::json;
+    use shindan_maker::Segment;
+
+    assert_eq!(Segment::text("Hello"), Segment::new("text", json!({"text": "Hello"})));
+    ```
+    */
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::with_field("text", "text", text.into())
+    }
+
+    /// Create an image segment. Its `alt` and `title` text, if any, are added by the parser.
+    pub fn image(url: impl Into<String>) -> Self {
+        Self::with_field("image", "file", url.into())
+    }
+
+    /// Create a video segment, as embedded by a few results. Its `poster` image, if any, is added by the parser.
+    pub fn video(url: impl Into<String>) -> Self {
+        Self::with_field("video", "file", url.into())
+    }
+
+    /// Create an audio segment, as embedded by a few results.
+    pub fn audio(url: impl Into<String>) -> Self {
+        Self::with_field("audio", "file", url.into())
+    }
+
+    /**
+    Create a link segment, as found in descriptions.
+
+    # Examples
+    ```
+    use serde_json::json;
+    use shindan_maker::Segment;
+
+    assert_eq!(
+        Segment::link("FAQ", "https://example.com/faq"),
+        Segment::new("link", json!({"text": "FAQ", "url": "https://example.com/faq"}))
+    );
+    ```
+    */
+    pub fn link(text: impl Into<String>, url: impl Into<String>) -> Self {
+        let mut data = Map::with_capacity(2);
+        data.insert("text".to_string(), Value::String(text.into()));
+        data.insert("url".to_string(), Value::String(url.into()));
+
+        Self::new("link", Value::Object(data))
+    }
+
+    fn with_field(type_: &'static str, key: &str, value: String) -> Self {
+        let mut data = Map::with_capacity(1);
+        data.insert(key.to_string(), Value::String(value));
+
+        Self::new(type_, Value::Object(data))
+    }
+
+    /**
+    Borrow the text of a text segment or the URL of an image segment.
+
+    # Examples
+    ```
+    use shindan_maker::Segment;
+
+    assert_eq!(Segment::text("Hello").as_str(), Some("Hello"));
+    ```
+    */
+    pub fn as_str(&self) -> Option<&str> {
+        let key = match self.type_.as_ref() {
+            "text" => "text",
+            "image" => "file",
+            _ => return None,
+        };
+
+        self.data.get(key).and_then(Value::as_str)
+    }
+
     /**
     Get the string representation of the segment.
 
@@ -54,11 +139,7 @@ impl Segment {
     ```
     */
     pub fn get_str(&self) -> Option<String> {
-        match self.type_.as_str() {
-            "text" => self.data.as_object().and_then(|map| map.get("text")).and_then(Value::as_str).map(String::from),
-            "image" => self.data.as_object().and_then(|map| map.get("file")).and_then(Value::as_str).map(String::from),
-            _ => None,
-        }
+        self.as_str().map(String::from)
     }
 }
 
@@ -71,9 +152,85 @@ impl PartialEq for Segment {
 impl Eq for Segment {}
 
 /// A collection of segments.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Segments(pub Vec<Segment>);
 
+impl Segments {
+    /**
+    Parse the segments of a saved result page.
+
+    # Arguments
+    - `html` - The HTML of a result page, as returned after submitting a shindan.
+
+    # Returns
+    The segments of the result.
+
+    # Errors
+    Returns error if the page has no result.
+
+    # Examples
+    ```
+    use shindan_maker::Segments;
+
+    let segments = Segments::from_html(r#"<span id="post_display">STR: 10<br>DEX: 8</span>"#).unwrap();
+    assert_eq!("STR: 10\nDEX: 8", segments.to_string());
+    ```
+    */
+    pub fn from_html(html: &str) -> Result<Self> {
+        Self::from_html_with(html, ParseOptions::default())
+    }
+
+    /**
+    Parse the segments of a saved result page with the given options, see [`ParseOptions`].
+
+    # Arguments
+    - `html` - The HTML of a result page.
+    - `options` - How the segments are parsed.
+
+    # Returns
+    The segments of the result.
+
+    # Errors
+    Returns error if the page has no result.
+    */
+    pub fn from_html_with(html: &str, options: ParseOptions) -> Result<Self> {
+        html_utils::get_segments(&SELECTORS, options, None, &Html::parse_document(html))
+    }
+
+    /**
+    Parse the segments of a possibly malformed or truncated result page.
+
+    Never panics nor fails: whatever could be parsed is returned, along with
+    warnings about what could not.
+
+    # Arguments
+    - `html` - The HTML of a result page.
+
+    # Returns
+    The segments parsed and the warnings raised.
+
+    # Examples
+    ```
+    use shindan_maker::Segments;
+
+    let parsed = Segments::from_html_lenient(r#"<span id="post_display">STR: 10<img alt="stats"><br>DEX: "#);
+    assert_eq!("STR: 10\nDEX: ", parsed.segments.to_string());
+    assert_eq!(1, parsed.warnings.len());
+    ```
+    */
+    pub fn from_html_lenient(html: &str) -> LenientSegments {
+        let (segments, warnings) = html_utils::get_segments_lenient(&SELECTORS, &Html::parse_document(html));
+        LenientSegments { segments, warnings }
+    }
+}
+
+/// Segments parsed by [`Segments::from_html_lenient`], with the problems met along the way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LenientSegments {
+    pub segments: Segments,
+    pub warnings: Vec<String>,
+}
+
 impl Deref for Segments {
     type Target = Vec<Segment>;
 
@@ -82,12 +239,18 @@ impl Deref for Segments {
     }
 }
 
+/// Text and the URLs of images, videos and audio are written as they are, and tables as
+/// their plain text on lines of their own.
 impl fmt::Display for Segments {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let str = self.iter()
-            .map(|segment| segment.get_str().unwrap())
-            .collect::<Vec<String>>()
-            .join("");
-        write!(f, "{}", str)
+        let mut output = String::new();
+        for segment in self.iter() {
+            match segment.type_.as_ref() {
+                "table" => output.extend(segment.as_table().map(|table| table.to_text_block(&output))),
+                "video" | "audio" => output.extend(segment.data.get("file").and_then(Value::as_str)),
+                _ => output.extend(segment.as_str()),
+            }
+        }
+        f.write_str(&output)
     }
 }
\ No newline at end of file
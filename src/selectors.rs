@@ -1,48 +1,261 @@
-use scraper::Selector;
+use std::fs;
+use std::path::Path;
 use once_cell::sync::Lazy;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use scraper::{ElementRef, Html, Selector};
 
-pub(crate) static SELECTORS: Lazy<Selectors> = Lazy::new(Selectors::new);
+/// The selectors compiled from the built-in [`SelectorConfig`].
+pub(crate) static SELECTORS: Lazy<Selectors> =
+    Lazy::new(|| Selectors::compile(&SelectorConfig::default()).expect("Failed to parse selector"));
 
-#[derive(Clone)]
+/**
+The CSS selectors used to scrape ShindanMaker pages.
+
+Every field is a fallback chain: the selectors are tried in order and the first
+one matching the page wins. Override them to keep working through markup changes
+of the site until a release ships.
+
+# Examples
+```
+use anyhow::Result;
+use shindan_maker::{SelectorConfig, ShindanClient, ShindanDomain};
+
+fn main() -> Result<()> {
+    let selectors = SelectorConfig::from_json(r##"{ "title": ["#shindanTitle", "h1.shindan-title"] }"##)?;
+
+    let client = ShindanClient::builder(ShindanDomain::En)
+        .selectors(selectors)
+        .build()?;
+    Ok(())
+}
+```
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SelectorConfig {
+    /// The shindan title, read from its `data-shindan_title` attribute or else its text.
+    pub title: Vec<String>,
+    /// The shindan description.
+    pub description: Vec<String>,
+    /// The sample result some shindans show before they are run.
+    pub preview: Vec<String>,
+    /// The tags the creator assigned to the shindan; every element matched is a tag.
+    pub tags: Vec<String>,
+    /// The link to the page of the shindan's author, whose text is their name.
+    pub author: Vec<String>,
+    /// The CSRF token input of the form.
+    pub token: Vec<String>,
+    /// The `randname` input of the form.
+    pub randname: Vec<String>,
+    /// The `type` input of the form.
+    #[serde(rename = "type")]
+    pub kind: Vec<String>,
+    /// The result text of a result page.
+    ///
+    /// With the `streaming` feature, the selectors are matched together rather than in order.
+    pub result: Vec<String>,
+    /// The title and result block rendered by the `html` feature.
+    pub title_and_result: Vec<String>,
+    /// The share links of a result page, whose hashtags are read from their URL.
+    pub share: Vec<String>,
+}
+
+impl Default for SelectorConfig {
+    fn default() -> Self {
+        Self {
+            title: vec!["#shindanTitle".into()],
+            description: vec!["#shindanDescriptionDisplay".into()],
+            preview: vec!["#shindanResultExample".into(), ".shindanResultExample".into()],
+            tags: vec!["a.shindanTag".into(), "#shindanTags a".into()],
+            author: vec!["a[href*='/author/']".into()],
+            token: vec!["input[name=_token]".into()],
+            randname: vec!["input[name=randname]".into()],
+            kind: vec!["input[name=type]".into()],
+            result: vec!["#post_display".into()],
+            title_and_result: vec!["#title_and_result".into()],
+            share: vec!["a[href*='intent/tweet']".into(), "a[href*='intent/post']".into()],
+        }
+    }
+}
+
+impl SelectorConfig {
+    /**
+    Parse a selector configuration from JSON.
+
+    Fields left out keep their built-in selectors.
+
+    # Arguments
+    - `json` - The JSON configuration.
+
+    # Errors
+    Returns error if the JSON is invalid.
+    */
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).context("Failed to parse the selector configuration")
+    }
+
+    /**
+    Parse a selector configuration from TOML.
+
+    Fields left out keep their built-in selectors.
+
+    # Arguments
+    - `toml` - The TOML configuration.
+
+    # Errors
+    Returns error if the TOML is invalid.
+    */
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        toml::from_str(toml).context("Failed to parse the selector configuration")
+    }
+
+    /**
+    Load a selector configuration from a `.json` or, with the `toml` feature, a `.toml` file.
+
+    # Arguments
+    - `path` - The path of the configuration file.
+
+    # Errors
+    Returns error if the file cannot be read or parsed.
+    */
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read the selector configuration {}", path.display()))?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            #[cfg(feature = "toml")]
+            Some("toml") => Self::from_toml(&content),
+            #[cfg(not(feature = "toml"))]
+            Some("toml") => Err(anyhow!("TOML selector configurations need the \"toml\" feature")),
+            _ => Self::from_json(&content),
+        }
+    }
+}
+
+/// A fallback chain of selectors, tried in order.
+#[derive(Debug, Clone)]
+pub(crate) struct Chain(Vec<(String, Selector)>);
+
+impl Chain {
+    fn compile(field: &str, selectors: &[String]) -> Result<Self> {
+        if selectors.is_empty() {
+            return Err(anyhow!("No selector configured for {}", field));
+        }
+
+        selectors
+            .iter()
+            .map(|source| {
+                Selector::parse(source)
+                    .map(|selector| (source.clone(), selector))
+                    .map_err(|err| anyhow!("Invalid {} selector {:?}: {}", field, source, err))
+            })
+            .collect::<Result<_>>()
+            .map(Self)
+    }
+
+    /// The first element matched by the first selector of the chain matching anything.
+    pub(crate) fn first<'a>(&self, dom: &'a Html) -> Option<ElementRef<'a>> {
+        self.matching(dom).map(|(_, element)| element)
+    }
+
+    /// Every element matched by the first selector of the chain matching anything.
+    pub(crate) fn all<'a>(&self, dom: &'a Html) -> Vec<ElementRef<'a>> {
+        self.0
+            .iter()
+            .map(|(_, selector)| dom.select(selector).collect::<Vec<_>>())
+            .find(|elements| !elements.is_empty())
+            .unwrap_or_default()
+    }
+
+    /// Like [`first`](Self::first), along with the selector that matched.
+    pub(crate) fn matching<'a>(&self, dom: &'a Html) -> Option<(&str, ElementRef<'a>)> {
+        self.0
+            .iter()
+            .find_map(|(source, selector)| Some((source.as_str(), dom.select(selector).next()?)))
+    }
+}
+
+/// The compiled selectors of a [`SelectorConfig`].
+#[derive(Debug, Clone)]
 pub(crate) struct Selectors {
-    pub(crate) shindan_title: Selector,
-    pub(crate) shindan_description_display: Selector,
-    pub(crate) form: Vec<Selector>,
+    pub(crate) shindan_title: Chain,
+    pub(crate) shindan_description_display: Chain,
+    pub(crate) preview: Chain,
+    pub(crate) tags: Chain,
+    pub(crate) author: Chain,
+    pub(crate) image: Selector,
+    pub(crate) og_image: Selector,
+    pub(crate) form: [Chain; 3],
+    pub(crate) link: Selector,
 
+    #[cfg(any(feature = "segments", feature = "html"))]
+    pub(crate) post_display: Chain,
     #[cfg(feature = "segments")]
-    pub(crate) post_display: Selector,
+    pub(crate) share: Chain,
+    #[cfg(feature = "streaming")]
+    pub(crate) post_display_css: Vec<String>,
 
-    #[cfg(feature = "html")]
-    pub(crate) title_and_result: Selector,
-    #[cfg(feature = "html")]
+    #[cfg(any(feature = "segments", feature = "html"))]
     pub(crate) script: Selector,
+
+    #[cfg(feature = "html")]
+    pub(crate) title_and_result: Chain,
     #[cfg(feature = "html")]
     pub(crate) effects: Vec<Selector>,
+    #[cfg(feature = "html")]
+    pub(crate) result_heading: Selector,
+    #[cfg(feature = "html")]
+    pub(crate) image_without_alt: Selector,
 }
 
 impl Selectors {
-    fn new() -> Self {
-        Self {
-            shindan_title: Selector::parse("#shindanTitle").expect("Failed to parse selector"),
-            shindan_description_display: Selector::parse("#shindanDescriptionDisplay").expect("Failed to parse selector"),
-            form: vec![
-                Selector::parse("input[name=_token]").expect("Failed to parse selector"),
-                Selector::parse("input[name=randname]").expect("Failed to parse selector"),
-                Selector::parse("input[name=type]").expect("Failed to parse selector"),
+    pub(crate) fn compile(config: &SelectorConfig) -> Result<Self> {
+        #[cfg(feature = "streaming")]
+        for selector in &config.result {
+            selector
+                .parse::<lol_html::Selector>()
+                .map_err(|err| anyhow!("Invalid result selector {:?} for streaming: {}", selector, err))?;
+        }
+
+        Ok(Self {
+            shindan_title: Chain::compile("title", &config.title)?,
+            shindan_description_display: Chain::compile("description", &config.description)?,
+            preview: Chain::compile("preview", &config.preview)?,
+            tags: Chain::compile("tags", &config.tags)?,
+            author: Chain::compile("author", &config.author)?,
+            image: Selector::parse("img").expect("Invalid image selector"),
+            og_image: Selector::parse("meta[property='og:image']").expect("Invalid og:image selector"),
+            form: [
+                Chain::compile("token", &config.token)?,
+                Chain::compile("randname", &config.randname)?,
+                Chain::compile("type", &config.kind)?,
             ],
+            link: Selector::parse("a[href]").expect("Invalid link selector"),
 
+            #[cfg(any(feature = "segments", feature = "html"))]
+            post_display: Chain::compile("result", &config.result)?,
             #[cfg(feature = "segments")]
-            post_display: Selector::parse("#post_display").expect("Invalid selector"),
+            share: Chain::compile("share", &config.share)?,
+            #[cfg(feature = "streaming")]
+            post_display_css: config.result.clone(),
 
-            #[cfg(feature = "html")]
-            title_and_result: Selector::parse("#title_and_result").expect("Failed to parse selector"),
-            #[cfg(feature = "html")]
+            #[cfg(any(feature = "segments", feature = "html"))]
             script: Selector::parse("script").expect("Invalid script selector"),
+
+            #[cfg(feature = "html")]
+            title_and_result: Chain::compile("title_and_result", &config.title_and_result)?,
             #[cfg(feature = "html")]
             effects: vec![
                 Selector::parse("span.shindanEffects[data-mode=ef_typing]").expect("Invalid script selector"),
                 Selector::parse("span.shindanEffects[data-mode=ef_shuffle]").expect("Invalid script selector"),
             ],
-        }
+            #[cfg(feature = "html")]
+            result_heading: Selector::parse("#shindanResultTitle, .shindanTitleImageContainer").expect("Invalid heading selector"),
+            #[cfg(feature = "html")]
+            image_without_alt: Selector::parse("img:not([alt])").expect("Invalid image selector"),
+        })
     }
-}
\ No newline at end of file
+}
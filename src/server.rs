@@ -0,0 +1,156 @@
+/*!
+A small HTTP service exposing a client, for bots written in other languages.
+
+| Route | Body | Response |
+| --- | --- | --- |
+| `GET /shindan/{id}/title` | | [`ShindanMetadata`] as JSON |
+| `POST /shindan/{id}/run` | `{"name": "..."}` | [`ShindanResult`] as JSON |
+| `POST /shindan/{id}/image` | `{"name": "..."}` | JPEG image, with the `image` feature |
+
+Failures answer `{"error": "..."}` with status 400 for bad requests and 502 when
+ShindanMaker could not be scraped.
+
+# Examples
+```no_run
+use anyhow::Result;
+use shindan_maker::server;
+use shindan_maker::ShindanDomain;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let client = server::default_client(ShindanDomain::En)?;
+    server::serve(client, "127.0.0.1:8080").await
+}
+```
+*/
+
+use std::time::Duration;
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::net::{TcpListener, ToSocketAddrs};
+use axum::{Json, Router};
+use axum::routing::{get, post};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+
+use crate::client::ShindanClient;
+use crate::shindan_id::ShindanId;
+use crate::result::ShindanResult;
+use crate::metadata::ShindanMetadata;
+use crate::shindan_domain::ShindanDomain;
+
+/// The body of the routes running a shindan.
+#[derive(Debug, Deserialize)]
+struct RunRequest {
+    name: String,
+}
+
+/// An error answered as `{"error": "..."}`.
+struct ApiError(StatusCode, String);
+
+impl ApiError {
+    fn upstream(err: anyhow::Error) -> Self {
+        Self(StatusCode::BAD_GATEWAY, format!("{:#}", err))
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+/**
+Build a client suited to a long-running service.
+
+Requests are limited to 30 per minute, forms and metadata are cached, and
+results are cached until the site day changes.
+
+# Arguments
+- `domain` - The domain of ShindanMaker to use.
+
+# Errors
+Returns error if the HTTP client cannot be built.
+*/
+pub fn default_client(domain: ShindanDomain) -> Result<ShindanClient> {
+    ShindanClient::builder(domain)
+        .rate_limit(30, Duration::from_secs(60))
+        .form_cache_ttl(Duration::from_secs(600))
+        .metadata_cache_ttl(Duration::from_secs(3600))
+        .result_cache()
+        .build()
+}
+
+/**
+Build the routes of the service, to serve or to nest in a larger application.
+
+# Arguments
+- `client` - The client answering the requests; configure its rate limit and caches on its builder.
+*/
+pub fn router(client: ShindanClient) -> Router {
+    let router = Router::new()
+        .route("/shindan/{id}/title", get(title))
+        .route("/shindan/{id}/run", post(run));
+
+    #[cfg(feature = "image")]
+    let router = router.route("/shindan/{id}/image", post(image));
+
+    router.with_state(client)
+}
+
+/**
+Serve the routes of the service until the process stops.
+
+# Arguments
+- `client` - The client answering the requests.
+- `addr` - The address to listen on, e.g. `127.0.0.1:8080`.
+
+# Errors
+Returns error if the address cannot be bound.
+*/
+pub async fn serve(client: ShindanClient, addr: impl ToSocketAddrs) -> Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    axum::serve(listener, router(client)).await?;
+    Ok(())
+}
+
+async fn title(State(client): State<ShindanClient>, Path(id): Path<String>) -> Result<Json<ShindanMetadata>, ApiError> {
+    let id = validate_id(&id)?;
+    client.get_metadata(id).await.map(Json).map_err(ApiError::upstream)
+}
+
+async fn run(
+    State(client): State<ShindanClient>,
+    Path(id): Path<String>,
+    Json(request): Json<RunRequest>,
+) -> Result<Json<ShindanResult>, ApiError> {
+    let (id, name) = (validate_id(&id)?, validate_name(&request.name)?);
+    client.get_result(id, name).await.map(Json).map_err(ApiError::upstream)
+}
+
+#[cfg(feature = "image")]
+async fn image(
+    State(client): State<ShindanClient>,
+    Path(id): Path<String>,
+    Json(request): Json<RunRequest>,
+) -> Result<Response, ApiError> {
+    let (id, name) = (validate_id(&id)?, validate_name(&request.name)?);
+    let image = client.get_image(id, name).await.map_err(ApiError::upstream)?;
+
+    Ok(([(axum::http::header::CONTENT_TYPE, "image/jpeg")], image).into_response())
+}
+
+fn validate_id(id: &str) -> Result<ShindanId, ApiError> {
+    ShindanId::new(id).map_err(|err| ApiError(StatusCode::BAD_REQUEST, err.to_string()))
+}
+
+fn validate_name(name: &str) -> Result<&str, ApiError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ApiError(StatusCode::BAD_REQUEST, "The name must not be empty".to_string()));
+    }
+
+    Ok(name)
+}
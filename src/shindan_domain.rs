@@ -1,27 +1,133 @@
 use std::fmt;
-use anyhow::anyhow;
+use url::Url;
 use std::str::FromStr;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
 
-/// A domain of ShindanMaker.
-#[derive(Debug, Clone, Copy)]
+/**
+A domain of ShindanMaker.
+
+Domains are ordered and hashable, so they can be used as map keys, and the regional
+domains of ShindanMaker can be iterated with [`ShindanDomain::ALL`].
+
+# Examples
+```
+use std::collections::HashMap;
+use shindan_maker::ShindanDomain;
+
+let hosts: HashMap<_, _> = ShindanDomain::ALL.iter().map(|domain| (domain.clone(), domain.host())).collect();
+
+assert_eq!("en.shindanmaker.com", hosts[&ShindanDomain::En]);
+```
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ShindanDomain {
     Jp,
     En,
     Cn,
     Kr,
     Th,
+    /// Any server serving the pages of ShindanMaker, e.g. a caching mirror or a test server.
+    Custom(Url),
+}
+
+impl ShindanDomain {
+    /// The regional domains of ShindanMaker, without custom ones.
+    pub const ALL: [ShindanDomain; 5] = [Self::Jp, Self::En, Self::Cn, Self::Kr, Self::Th];
+
+    /**
+    Point at a server serving the pages of ShindanMaker under a base URL.
+
+    Shindans are fetched from the base URL followed by their ID, and parsed as on
+    ShindanMaker itself.
+
+    # Arguments
+    - `base_url` - The HTTP or HTTPS base URL, e.g. `https://mirror.example/shindan/`.
+
+    # Errors
+    Returns error if the URL is invalid or not HTTP.
+
+    # Examples
+    ```
+    use shindan_maker::ShindanDomain;
+
+    let domain = ShindanDomain::custom("https://mirror.example/shindan").unwrap();
+
+    assert_eq!("https://mirror.example/shindan/", domain.to_string());
+    ```
+    */
+    pub fn custom(base_url: &str) -> Result<Self> {
+        let mut url = Url::parse(base_url).map_err(|err| anyhow!("Invalid base URL {:?}: {}", base_url, err))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(anyhow!("The base URL {:?} must be HTTP or HTTPS", base_url));
+        }
+
+        if !url.path().ends_with('/') {
+            url.set_path(&format!("{}/", url.path()));
+        }
+        Ok(Self::Custom(url))
+    }
+
+    /// The short name of the domain, e.g. `en`, as parsed and serialized; the base URL of custom domains.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Jp => "jp",
+            Self::En => "en",
+            Self::Cn => "cn",
+            Self::Kr => "kr",
+            Self::Th => "th",
+            Self::Custom(url) => url.as_str(),
+        }
+    }
+
+    /// The host name of the domain, e.g. `en.shindanmaker.com`.
+    pub fn host(&self) -> &str {
+        match self {
+            Self::Jp => "shindanmaker.com",
+            Self::En => "en.shindanmaker.com",
+            Self::Cn => "cn.shindanmaker.com",
+            Self::Kr => "kr.shindanmaker.com",
+            Self::Th => "th.shindanmaker.com",
+            Self::Custom(url) => url.host_str().unwrap_or_default(),
+        }
+    }
+
+    /**
+    The language of the pages of the domain, as a BCP 47 tag, or `None` for custom domains.
+
+    # Examples
+    ```
+    use shindan_maker::ShindanDomain;
+
+    assert_eq!(Some("ja"), ShindanDomain::Jp.lang());
+    assert_eq!(Some("zh-Hans"), ShindanDomain::Cn.lang());
+    ```
+    */
+    pub fn lang(&self) -> Option<&'static str> {
+        match self {
+            Self::Jp => Some("ja"),
+            Self::En => Some("en"),
+            Self::Cn => Some("zh-Hans"),
+            Self::Kr => Some("ko"),
+            Self::Th => Some("th"),
+            Self::Custom(_) => None,
+        }
+    }
+
+    /// The regional domain served by a host, `www.shindanmaker.com` included.
+    pub(crate) fn from_host(host: &str) -> Option<Self> {
+        let host = host.strip_prefix("www.").unwrap_or(host);
+        Self::ALL.into_iter().find(|domain| domain.host() == host)
+    }
 }
 
 impl fmt::Display for ShindanDomain {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let url = match self {
-            Self::Jp => "https://shindanmaker.com/",
-            Self::En => "https://en.shindanmaker.com/",
-            Self::Cn => "https://cn.shindanmaker.com/",
-            Self::Kr => "https://kr.shindanmaker.com/",
-            Self::Th => "https://th.shindanmaker.com/",
-        };
-        write!(f, "{}", url)
+        match self {
+            Self::Custom(url) => write!(f, "{}", url),
+            _ => write!(f, "https://{}/", self.host()),
+        }
     }
 }
 
@@ -29,14 +135,15 @@ impl FromStr for ShindanDomain {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> anyhow::Result<Self, Self::Err> {
-        match s.to_uppercase().as_str() {
-            "JP" => Ok(Self::Jp),
-            "EN" => Ok(Self::En),
-            "CN" => Ok(Self::Cn),
-            "KR" => Ok(Self::Kr),
-            "TH" => Ok(Self::Th),
-            _ => Err(anyhow!("Invalid domain")),
+        if let Some(domain) = Self::ALL.into_iter().find(|domain| domain.as_str().eq_ignore_ascii_case(s)) {
+            return Ok(domain);
+        }
+
+        if s.starts_with("http://") || s.starts_with("https://") {
+            return Self::custom(s);
         }
+
+        Err(anyhow!("Invalid domain"))
     }
 }
 
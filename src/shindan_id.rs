@@ -0,0 +1,174 @@
+use std::fmt;
+use url::Url;
+use std::str::FromStr;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::shindan_domain::ShindanDomain;
+
+/// The maximum number of digits of an ID.
+const MAX_LEN: usize = 12;
+
+/**
+The ID of a shindan, e.g. `1222992`.
+
+IDs are validated when converted from strings: they must be 1 to 12 ASCII digits,
+so an ID with stray spaces fails with a clear error before any request is sent.
+Client methods take anything converting into an ID, so a link pasted by a user
+can be passed as is: the ID is taken from links, whose domain is ignored. Use
+[`ShindanId::parse`] to also get the domain of a link.
+
+# Examples
+```
+use shindan_maker::ShindanId;
+
+assert_eq!("1222992", "1222992".parse::<ShindanId>().unwrap().as_str());
+assert_eq!("1222992", ShindanId::try_from("https://en.shindanmaker.com/1222992?sm_source=share").unwrap().as_str());
+assert!(ShindanId::try_from("1222992 ").is_err());
+```
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ShindanId(String);
+
+/// Convert an argument of a client method into an ID.
+pub(crate) fn convert<T>(id: T) -> Result<ShindanId>
+where
+    T: TryInto<ShindanId, Error: Into<anyhow::Error>>,
+{
+    id.try_into().map_err(Into::into)
+}
+
+impl ShindanId {
+    /**
+    Validate an ID.
+
+    # Arguments
+    - `id` - The numeric ID, e.g. `1222992`.
+
+    # Errors
+    Returns error if the ID is not 1 to 12 ASCII digits.
+    */
+    pub fn new(id: &str) -> Result<Self> {
+        if id.is_empty() || id.len() > MAX_LEN || !id.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(anyhow!("Invalid shindan ID {:?}: expected 1 to {} digits", id, MAX_LEN));
+        }
+
+        Ok(Self(id.to_string()))
+    }
+
+    /**
+    Parse a link to a shindan into its domain and ID.
+
+    Query strings and fragments, e.g. the `sm_source` of share links, are ignored.
+    Links to other hosts than ShindanMaker's give a [`ShindanDomain::Custom`] domain.
+
+    # Arguments
+    - `link` - The link, e.g. `https://en.shindanmaker.com/1222992`.
+
+    # Returns
+    The domain and the ID of the shindan.
+
+    # Errors
+    Returns error if the link is not an HTTP URL ending with a numeric ID.
+
+    # Examples
+    ```
+    use shindan_maker::{ShindanDomain, ShindanId};
+
+    let (domain, id) = ShindanId::parse("https://en.shindanmaker.com/1222992?sm_source=share").unwrap();
+
+    assert_eq!(ShindanDomain::En, domain);
+    assert_eq!("1222992", id.as_str());
+    ```
+    */
+    pub fn parse(link: &str) -> Result<(ShindanDomain, Self)> {
+        let url = Url::parse(link.trim()).map_err(|err| anyhow!("Invalid shindan link {:?}: {}", link, err))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(anyhow!("The shindan link {:?} must be HTTP or HTTPS", link));
+        }
+
+        let id = url
+            .path_segments()
+            .and_then(|mut segments| segments.rfind(|segment| !segment.is_empty()))
+            .and_then(|segment| Self::new(segment).ok())
+            .ok_or_else(|| anyhow!("No shindan ID in the link {:?}", link))?;
+
+        let domain = match url.host_str().and_then(ShindanDomain::from_host) {
+            Some(domain) => domain,
+            None => {
+                let path = url.path().trim_end_matches('/');
+                let base = format!("{}{}", &url[..url::Position::BeforePath], &path[..path.len() - id.0.len()]);
+                ShindanDomain::custom(&base)?
+            }
+        };
+
+        Ok((domain, id))
+    }
+
+    /// The ID as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for ShindanId {
+    type Err = anyhow::Error;
+
+    /// Validate an ID, or take it from a link to a shindan.
+    fn from_str(s: &str) -> Result<Self> {
+        if s.starts_with("http://") || s.starts_with("https://") {
+            return Self::parse(s).map(|(_, id)| id);
+        }
+
+        Self::new(s)
+    }
+}
+
+impl TryFrom<&str> for ShindanId {
+    type Error = anyhow::Error;
+
+    fn try_from(id: &str) -> Result<Self> {
+        id.parse()
+    }
+}
+
+impl TryFrom<String> for ShindanId {
+    type Error = anyhow::Error;
+
+    fn try_from(id: String) -> Result<Self> {
+        id.parse()
+    }
+}
+
+impl TryFrom<&String> for ShindanId {
+    type Error = anyhow::Error;
+
+    fn try_from(id: &String) -> Result<Self> {
+        id.parse()
+    }
+}
+
+impl From<&ShindanId> for ShindanId {
+    fn from(id: &ShindanId) -> Self {
+        id.clone()
+    }
+}
+
+impl From<ShindanId> for String {
+    fn from(id: ShindanId) -> Self {
+        id.0
+    }
+}
+
+impl AsRef<str> for ShindanId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ShindanId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt;
+use std::hash::Hash;
+use std::error::Error;
+use anyhow::Result;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use futures::future::{BoxFuture, FutureExt, Shared};
+
+type Call<V> = Shared<BoxFuture<'static, Result<V, SharedError>>>;
+
+/// An error shared by every caller of a coalesced call.
+#[derive(Clone)]
+pub(crate) struct SharedError(Arc<anyhow::Error>);
+
+impl SharedError {
+    /// The error of the call.
+    pub(crate) fn inner(&self) -> &anyhow::Error {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SharedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Display for SharedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl Error for SharedError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.source()
+    }
+}
+
+/// Coalesces concurrent calls with the same key into one, fanning its result out to every caller.
+pub(crate) struct SingleFlight<K, V> {
+    calls: Mutex<HashMap<K, Call<V>>>,
+}
+
+impl<K, V> SingleFlight<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone + Send + Sync + 'static,
+{
+    /// Run `call` unless a call with the same key is already in flight, and wait for its result.
+    pub(crate) async fn run<F>(&self, key: K, call: F) -> Result<V>
+    where
+        F: Future<Output = Result<V>> + Send + 'static,
+    {
+        let shared = self
+            .calls
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_insert_with(|| call.map(|result| result.map_err(|err| SharedError(Arc::new(err)))).boxed().shared())
+            .clone();
+
+        let result = shared.clone().await;
+
+        let mut calls = self.calls.lock().unwrap();
+        if calls.get(&key).is_some_and(|call| call.ptr_eq(&shared)) {
+            calls.remove(&key);
+        }
+
+        result.map_err(anyhow::Error::new)
+    }
+}
+
+impl<K, V> Default for SingleFlight<K, V> {
+    fn default() -> Self {
+        Self {
+            calls: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K, V> fmt::Debug for SingleFlight<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SingleFlight")
+            .field("in_flight", &self.calls.lock().unwrap().len())
+            .finish()
+    }
+}
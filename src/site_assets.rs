@@ -0,0 +1,228 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::html_utils;
+use crate::client::ShindanClient;
+
+/// The file the versions of refreshed assets are recorded in, in their directory.
+const VERSIONS_FILE: &str = "versions.json";
+
+/**
+The versions of the stylesheet and scripts of ShindanMaker, as the `id` query of their URLs,
+e.g. `cbfb28ec9001aee269676b04e227a3b9` in `https://cn.shindanmaker.com/css/app.css?id=cbfb28ec9001aee269676b04e227a3b9`.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetVersions {
+    /// The URL the assets are under, e.g. `https://cn.shindanmaker.com/`.
+    pub base_url: String,
+    /// The version of `css/app.css`.
+    pub app_css: String,
+    /// The version of `js/app.js`.
+    pub app_js: String,
+    /// The version of `js/chart.js`.
+    pub chart_js: String,
+}
+
+impl AssetVersions {
+    /// The versions the HTML built by the client links to by default.
+    pub fn builtin() -> Self {
+        Self {
+            base_url: "https://cn.shindanmaker.com/".to_string(),
+            app_css: "cbfb28ec9001aee269676b04e227a3b9".to_string(),
+            app_js: "163959a7e23bfa7264a0ddefb3c36f13".to_string(),
+            chart_js: "391e335afc72362acd6bf1ea1ba6b74c".to_string(),
+        }
+    }
+
+    /// The versions a page of the site links to; those it does not link to stay built-in.
+    pub(crate) fn from_page(html: &str) -> Self {
+        Self::from_page_or(html, Self::builtin())
+    }
+
+    /// The versions a page of the site links to; those it does not link to stay as in `versions`.
+    fn from_page_or(html: &str, mut versions: Self) -> Self {
+        for (path, version) in [("css/app.css", &mut versions.app_css), ("js/app.js", &mut versions.app_js), ("js/chart.js", &mut versions.chart_js)] {
+            if let Some(found) = find_version(html, path) {
+                *version = found;
+            }
+        }
+        if let Some(base_url) = find_base_url(html, "css/app.css?id=").or_else(|| find_base_url(html, "js/app.js?id=")) {
+            versions.base_url = base_url;
+        }
+        versions
+    }
+
+    /**
+    Read the versions of the assets refreshed into a directory by [`ShindanClient::refresh_site_assets`].
+
+    # Errors
+    Returns error if the directory holds no refreshed assets.
+    */
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self> {
+        let path = dir.as_ref().join(VERSIONS_FILE);
+        let json = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// The URL of the version of an asset, by its path, e.g. `css/app.css`.
+    fn url(&self, path: &str, version: &str) -> String {
+        format!("{}{}?id={}", self.base_url, path, version)
+    }
+
+    /// The assets, as their path, version, and whether they are a stylesheet.
+    fn assets(&self) -> [(&'static str, &str, bool); 3] {
+        [("css/app.css", &self.app_css, true), ("js/app.js", &self.app_js, false), ("js/chart.js", &self.chart_js, false)]
+    }
+}
+
+/// The version of the first reference to an asset in a page.
+fn find_version(html: &str, path: &str) -> Option<String> {
+    let marker = format!("{}?id=", path);
+    let rest = &html[html.find(&marker)? + marker.len()..];
+    let version: String = rest.chars().take_while(char::is_ascii_alphanumeric).collect();
+    (!version.is_empty()).then_some(version)
+}
+
+/// The absolute URL the first reference to an asset in a page is under.
+fn find_base_url(html: &str, marker: &str) -> Option<String> {
+    let end = html.find(marker)?;
+    let start = html[..end].rfind(['"', '\''])? + 1;
+    let base_url = &html[start..end];
+    (base_url.starts_with("https://") || base_url.starts_with("http://")).then(|| base_url.to_string())
+}
+
+/**
+Where the stylesheet and scripts of the HTML built by the client come from, see
+[`ShindanClientBuilder::site_assets`](crate::ShindanClientBuilder::site_assets).
+
+The built-in versions drift from the live site over time, which can break the layout
+of results using new widgets; the other modes inline current or chosen versions.
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SiteAssets {
+    /// Link the [built-in versions](AssetVersions::builtin).
+    #[default]
+    Builtin,
+    /// Inline the versions each result page links to.
+    Live,
+    /// Inline chosen versions, e.g. the last ones known to render well.
+    Pinned(AssetVersions),
+    /// Inline the assets refreshed into a directory by [`ShindanClient::refresh_site_assets`], without downloading them.
+    Directory(PathBuf),
+}
+
+impl ShindanClient {
+    /**
+    Download the current stylesheet and scripts of the site into a directory, e.g. a
+    `static` directory to build HTML from with [`SiteAssets::Directory`].
+
+    The versions are those the home page of the client's domain links to; assets it does
+    not link to, e.g. `js/chart.js`, keep the version previously refreshed into the
+    directory, or the built-in one. The files are written under their paths, e.g.
+    `css/app.css`, and their versions to `versions.json`.
+
+    # Arguments
+    - `dir` - The directory to write to, created if missing.
+
+    # Returns
+    The versions written.
+
+    # Errors
+    Returns error if the home page or an asset cannot be downloaded, or the files cannot be written.
+
+    # Examples
+    ```no_run
+    use shindan_maker::{AssetVersions, ShindanClient, ShindanDomain, SiteAssets};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::Jp).unwrap();
+        let versions = client.refresh_site_assets("static").await.unwrap();
+        assert_eq!(versions, AssetVersions::load("static").unwrap());
+
+        let client = ShindanClient::builder(ShindanDomain::Jp).site_assets(SiteAssets::Directory("static".into())).build().unwrap();
+    }
+    ```
+    */
+    pub async fn refresh_site_assets(&self, dir: impl AsRef<Path>) -> Result<AssetVersions> {
+        let dir = dir.as_ref();
+        let response = self.send(self.client.get(self.url(""))).await?.error_for_status()?;
+        let home = self.read_text(response).await?;
+
+        let previous = AssetVersions::load(dir).unwrap_or_else(|_| AssetVersions::builtin());
+        let versions = AssetVersions::from_page_or(&home, previous);
+
+        for (path, version, _) in versions.assets() {
+            let asset = self.fetch_asset(&versions.url(path, version)).await?;
+            let file = dir.join(path);
+            if let Some(parent) = file.parent() {
+                fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            fs::write(&file, &asset.bytes).with_context(|| format!("Failed to write {}", file.display()))?;
+        }
+
+        let path = dir.join(VERSIONS_FILE);
+        fs::write(&path, serde_json::to_string_pretty(&versions)?).with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(versions)
+    }
+
+    /**
+    Inline the stylesheet and scripts of HTML built from a result page, per the site assets mode,
+    and record their versions in a `shindan-maker-assets` meta tag.
+
+    Assets that cannot be downloaded or read, e.g. offline, keep linking to their built-in versions.
+    */
+    pub(crate) async fn apply_site_assets(&self, mut html: String, response_text: &str) -> String {
+        let builtin = AssetVersions::builtin();
+        // The versions to inline, and the directory to read them from instead of downloading them.
+        let (versions, dir) = match &self.site_assets {
+            SiteAssets::Builtin => (None, None),
+            SiteAssets::Live => (Some(AssetVersions::from_page(response_text)), None),
+            SiteAssets::Pinned(versions) => (Some(versions.clone()), None),
+            SiteAssets::Directory(dir) => match AssetVersions::load(dir) {
+                Ok(versions) => (Some(versions), Some(dir)),
+                Err(err) => {
+                    log::warn!("Falling back to the built-in assets: {:#}", err);
+                    (None, None)
+                }
+            },
+        };
+
+        let mut recorded = Vec::new();
+        for (index, (path, builtin_version, stylesheet)) in builtin.assets().into_iter().enumerate() {
+            let builtin_url = builtin.url(path, builtin_version);
+            if !html.contains(&builtin_url) {
+                continue;
+            }
+            let Some(versions) = &versions else {
+                recorded.push(format!("{}={}", path, builtin_version));
+                continue;
+            };
+            let (_, version, _) = versions.assets()[index];
+
+            let text = match dir {
+                Some(dir) => fs::read(dir.join(path)).with_context(|| format!("Failed to read {}", dir.join(path).display())),
+                None => self.fetch_asset(&versions.url(path, version)).await.map(|asset| asset.bytes),
+            };
+            let text = match text {
+                Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+                Err(err) => {
+                    log::warn!("Falling back to the built-in {}: {:#}", path, err);
+                    recorded.push(format!("{}={}", path, builtin_version));
+                    continue;
+                }
+            };
+            recorded.push(format!("{}={}", path, version));
+            html = if stylesheet {
+                html_utils::replace_tag(&html, &builtin_url, "<link", ">", &format!("<style>{}</style>", text.replace("</style", "<\\/style")))
+            } else {
+                html_utils::replace_tag(&html, &builtin_url, "<script", "</script>", &format!("<script>{}</script>", text.replace("</script", "<\\/script")))
+            };
+        }
+
+        let tag = format!(r#"<meta name="shindan-maker-assets" content="{}">"#, recorded.join(" "));
+        html.replacen("</head>", &format!("    {}\n</head>", tag), 1)
+    }
+}
@@ -0,0 +1,50 @@
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, TimeZone, Utc};
+
+/// The UTC offset of Japan Standard Time, where ShindanMaker rolls results over at midnight.
+const JST_OFFSET_SECS: i32 = 9 * 60 * 60;
+
+fn jst() -> FixedOffset {
+    FixedOffset::east_opt(JST_OFFSET_SECS).expect("Invalid JST offset")
+}
+
+/**
+Get the site day (the date in Japan Standard Time) of an instant.
+
+ShindanMaker results are stable per name for a site day.
+
+# Arguments
+- `at` - The instant.
+
+# Returns
+The date in JST.
+
+# Examples
+```
+use chrono::{NaiveDate, TimeZone, Utc};
+use shindan_maker::site_day;
+
+let at = Utc.with_ymd_and_hms(2024, 1, 1, 15, 0, 0).unwrap();
+assert_eq!(site_day(at), NaiveDate::from_ymd_opt(2024, 1, 2).unwrap());
+```
+*/
+pub fn site_day(at: DateTime<Utc>) -> NaiveDate {
+    at.with_timezone(&jst()).date_naive()
+}
+
+/// Get the current site day.
+pub fn site_today() -> NaiveDate {
+    site_day(Utc::now())
+}
+
+/// Get the instant the site day of `at` ends.
+#[cfg_attr(not(feature = "segments"), allow(dead_code))]
+pub(crate) fn site_day_end(at: DateTime<Utc>) -> DateTime<Utc> {
+    let next_day = site_day(at) + Duration::days(1);
+    let midnight = next_day.and_hms_opt(0, 0, 0).expect("Invalid midnight");
+
+    jst()
+        .from_local_datetime(&midnight)
+        .single()
+        .expect("JST has no ambiguous local times")
+        .with_timezone(&Utc)
+}
@@ -0,0 +1,183 @@
+use anyhow::Result;
+use futures::StreamExt;
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::client::ShindanClient;
+use crate::shindan_id::{self, ShindanId};
+use crate::result::ShindanResult;
+use crate::render::PlainTextOptions;
+
+/// How often a result text came up, with the name replaced by `{name}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextVariant {
+    /// The plain text of the result, with `{name}` where the submitted name was.
+    pub text: String,
+    /// The number of runs that produced this text.
+    pub count: usize,
+}
+
+/// The distribution of one chart value across runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChartStat {
+    /// The label of the value, prefixed by its dataset when the chart has several.
+    pub label: String,
+    /// The number of runs whose chart had this value.
+    pub count: usize,
+    /// The lowest value seen.
+    pub min: f64,
+    /// The highest value seen.
+    pub max: f64,
+    /// The average value.
+    pub mean: f64,
+}
+
+/// Outcome frequencies aggregated over many results of a shindan.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatsReport {
+    /// The number of results aggregated.
+    pub runs: usize,
+    /// The number of submissions that failed, when the report comes from [`ShindanClient::analyze`].
+    pub failures: usize,
+    /// The distinct result texts, most frequent first.
+    pub text_variants: Vec<TextVariant>,
+    /// The chart values, in the order of their labels.
+    pub chart_stats: Vec<ChartStat>,
+}
+
+impl StatsReport {
+    /**
+    Aggregate results, e.g. replayed from a history store.
+
+    # Arguments
+    - `results` - The results of one shindan.
+
+    # Returns
+    The aggregated report.
+
+    # Examples
+    ```
+    use shindan_maker::{Segment, Segments, ShindanDomain, ShindanResult, StatsReport};
+
+    let results = ["Alice", "Bob"].map(|name| {
+        let text = format!("{} is a knight", name);
+        ShindanResult::new("1222992", name, ShindanDomain::En, "Fantasy Stats", Segments(vec![Segment::text(text)]))
+    });
+
+    let report = StatsReport::aggregate(&results);
+    assert_eq!("{name} is a knight", report.text_variants[0].text);
+    assert_eq!(2, report.text_variants[0].count);
+    ```
+    */
+    pub fn aggregate<'a>(results: impl IntoIterator<Item = &'a ShindanResult>) -> Self {
+        let mut report = Self::default();
+        let mut texts: HashMap<String, usize> = HashMap::new();
+        let mut values: Vec<(String, Vec<f64>)> = Vec::new();
+
+        for result in results {
+            report.runs += 1;
+
+            let mut text = result.segments.to_plain_text(&PlainTextOptions::default());
+            if !result.name.is_empty() {
+                text = text.replace(&result.name, "{name}");
+            }
+            *texts.entry(text).or_default() += 1;
+
+            let Some(chart) = &result.chart else { continue };
+            for dataset in &chart.datasets {
+                for (label, value) in chart.labels.iter().zip(&dataset.data) {
+                    let label = match &dataset.label {
+                        Some(dataset_label) if chart.datasets.len() > 1 => format!("{} / {}", dataset_label, label),
+                        _ => label.clone(),
+                    };
+
+                    match values.iter_mut().find(|(known, _)| *known == label) {
+                        Some((_, known_values)) => known_values.push(*value),
+                        None => values.push((label, vec![*value])),
+                    }
+                }
+            }
+        }
+
+        report.text_variants = texts
+            .into_iter()
+            .map(|(text, count)| TextVariant { text, count })
+            .collect();
+        report.text_variants.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.text.cmp(&b.text)));
+
+        report.chart_stats = values
+            .into_iter()
+            .map(|(label, values)| ChartStat {
+                label,
+                count: values.len(),
+                min: values.iter().copied().fold(f64::INFINITY, f64::min),
+                max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                mean: values.iter().sum::<f64>() / values.len() as f64,
+            })
+            .collect();
+
+        report
+    }
+}
+
+impl ShindanClient {
+    /**
+    Run a shindan for generated names and aggregate the outcomes.
+
+    Useful for creators balancing their result pools. The names are `user1` to
+    `user{runs}`, so repeated analyses on the same site day see the same results.
+
+    # Arguments
+    - `id` - The ID of the shindan.
+    - `runs` - The number of names to run the shindan for.
+    - `concurrency` - The maximum number of submissions in flight.
+
+    # Returns
+    The aggregated report; failed submissions are counted but not aggregated.
+
+    # Errors
+    Returns the first error if every submission failed.
+
+    # Examples
+    ```
+    use shindan_maker::{ShindanClient, ShindanDomain};
+
+    #[tokio::main]
+    async fn main() {
+        let client = ShindanClient::new(ShindanDomain::En).unwrap();
+
+        let report = client.analyze("1222992", 50, 4).await.unwrap();
+
+        for stat in report.chart_stats {
+            println!("{}: {} to {} (mean {:.1})", stat.label, stat.min, stat.max, stat.mean);
+        }
+    }
+    ```
+    */
+    pub async fn analyze(&self, id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>, runs: usize, concurrency: usize) -> Result<StatsReport> {
+        let id = shindan_id::convert(id)?;
+        let jobs = (1..=runs).map(|i| (id.to_string(), format!("user{}", i)));
+        let outcomes: Vec<_> = self.submit_stream(jobs, concurrency).collect().await;
+
+        let mut results = Vec::new();
+        let mut errors = Vec::new();
+        for (_, outcome) in outcomes {
+            match outcome {
+                Ok(result) => results.push(result),
+                Err(err) => errors.push(err),
+            }
+        }
+
+        let failures = errors.len();
+        if results.is_empty() {
+            if let Some(err) = errors.into_iter().next() {
+                return Err(err.context("Every submission failed"));
+            }
+        }
+
+        Ok(StatsReport {
+            failures,
+            ..StatsReport::aggregate(&results)
+        })
+    }
+}
@@ -0,0 +1,376 @@
+use std::sync::{Arc, Mutex};
+use anyhow::{anyhow, Context, Result};
+use lol_html::send::{Element, EndTagHandler, HtmlRewriter, Settings};
+use lol_html::{element, text, OutputSink};
+
+use crate::html_utils::{self, decode_entities, ListLevel};
+use crate::selectors::Selectors;
+use crate::image_source::ImageSources;
+use crate::table::TableBuilder;
+use crate::media_source::MediaSources;
+use crate::parse_options::ParseOptions;
+use crate::segment::{Segment, Segments};
+
+/// What the handlers have seen of the result element so far.
+#[derive(Debug, Default)]
+struct State {
+    found: bool,
+    depth: usize,
+    /// The sources of the `picture` being read, if any.
+    picture: Option<ImageSources>,
+    /// The sources of the `video` or `audio` being read, if any.
+    media: Option<MediaSources>,
+    /// The rows of the `table` being read, if any.
+    table: Option<TableBuilder>,
+    /// Whether the text being read is in a cell of the table.
+    in_cell: bool,
+    /// The tag and `style` attribute of the styled inline element being read, if any.
+    styled: Option<(String, Option<String>)>,
+    /// The numbering of the lists being read, outermost first.
+    lists: Vec<ListLevel>,
+    /// The items being read: the index of their segment, their nesting level, marker and text so far.
+    items: Vec<(usize, usize, String, String)>,
+    /// The submitted name, kept out of the second decode of the text.
+    name: Option<String>,
+    text: String,
+    segments: Vec<Segment>,
+}
+
+impl State {
+    /// Add the text read in the current cell of the table to it.
+    fn end_cell(&mut self) {
+        if !std::mem::take(&mut self.in_cell) {
+            return;
+        }
+        let text = decode_entities(&std::mem::take(&mut self.text));
+        if let Some(table) = &mut self.table {
+            table.push_text(&text);
+        }
+    }
+
+    /// Write out the items being read at the given nesting level or deeper.
+    fn end_items(&mut self, level: usize) {
+        while self.items.last().is_some_and(|item| item.1 >= level) {
+            let Some((index, level, marker, text)) = self.items.pop() else {
+                break;
+            };
+            self.segments[index] = html_utils::list_item(level, &marker, &decode_entities(&text), self.name.as_deref());
+        }
+    }
+}
+
+/// Ends the list an end tag closes, and its items.
+fn end_list(state: Arc<Mutex<State>>) -> EndTagHandler<'static> {
+    Box::new(move |_| {
+        let mut state = state.lock().unwrap();
+        if state.lists.pop().is_some() {
+            let level = state.lists.len();
+            state.end_items(level);
+        }
+        Ok(())
+    })
+}
+
+/// Discards the rewritten document; only the handlers' side effects matter.
+struct Discard;
+
+impl OutputSink for Discard {
+    fn handle_chunk(&mut self, _: &[u8]) {}
+}
+
+/**
+Extracts the segments of a result page while its body is still being received.
+
+Unlike parsing the response into a DOM, only the text of the current node is
+kept in memory, so peak memory no longer grows with the size of the page.
+*/
+pub(crate) struct SegmentExtractor {
+    rewriter: HtmlRewriter<'static, Discard>,
+    state: Arc<Mutex<State>>,
+    options: ParseOptions,
+}
+
+impl SegmentExtractor {
+    pub(crate) fn new(selectors: &Selectors, options: ParseOptions, name: Option<&str>) -> Self {
+        let display = selectors.post_display_css.join(", ");
+        let children = selectors
+            .post_display_css
+            .iter()
+            .map(|selector| format!("{} > *", selector))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let picture_sources = selectors
+            .post_display_css
+            .iter()
+            .map(|selector| format!("{0} > picture source, {0} > picture img", selector))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let row_paths = ["", " > tbody", " > tfoot"].map(|section| format!("table{} > tr", section));
+        let table_rows = css_under(selectors, &row_paths);
+        let table_head_rows = css_under(selectors, &["table > thead > tr".to_string()]);
+        let cell_paths = ["", " > thead", " > tbody", " > tfoot"]
+            .iter()
+            .flat_map(|section| ["th", "td"].map(|cell| format!("table{} > tr > {}", section, cell)))
+            .collect::<Vec<_>>();
+        let table_cells = css_under(selectors, &cell_paths);
+        let list_paths = ["ul", "ol"];
+        let nested_list_paths = ["ul ul", "ul ol", "ol ul", "ol ol"];
+        let top_lists = css_under(selectors, &list_paths.map(String::from));
+        let nested_lists = css_under(selectors, &nested_list_paths.map(String::from));
+        let list_items = list_paths
+            .iter()
+            .chain(&nested_list_paths)
+            .map(|list| format!("{} > li", list))
+            .collect::<Vec<_>>();
+        let list_items = css_under(selectors, &list_items);
+        let media_sources = selectors
+            .post_display_css
+            .iter()
+            .map(|selector| format!("{0} > video source, {0} > audio source", selector))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let state = Arc::new(Mutex::new(State { name: name.map(String::from), ..State::default() }));
+
+        let display_state = state.clone();
+        let child_state = state.clone();
+        let picture_state = state.clone();
+        let media_state = state.clone();
+        let row_state = state.clone();
+        let head_row_state = state.clone();
+        let cell_state = state.clone();
+        let top_list_state = state.clone();
+        let nested_list_state = state.clone();
+        let item_state = state.clone();
+        let text_state = state.clone();
+
+        let settings = Settings {
+            element_content_handlers: vec![
+                element!(display, move |_| {
+                    display_state.lock().unwrap().found = true;
+                    Ok(())
+                }),
+                element!(children, move |el| {
+                    let mut state = child_state.lock().unwrap();
+                    if state.depth > 0 {
+                        return Ok(());
+                    }
+
+                    match el.tag_name().as_str() {
+                        "br" => state.segments.push(Segment::text("\n")),
+                        "img" => {
+                            let attributes = attributes_of(el);
+                            let sources = ImageSources::of(attributes.iter().map(|(name, value)| (name.as_str(), value.as_str())));
+                            state.segments.extend(sources.segment());
+                        }
+                        "picture" => state.picture = Some(ImageSources::default()),
+                        "table" => state.table = Some(TableBuilder::default()),
+                        name @ ("video" | "audio") => {
+                            let attributes = attributes_of(el);
+                            state.media = MediaSources::of(name, attributes.iter().map(|(name, value)| (name.as_str(), value.as_str())));
+                        }
+                        tag if html_utils::STYLED_TAGS.contains(&tag) => {
+                            state.styled = Some((tag.to_string(), el.get_attribute("style").map(|style| decode_entities(&style))));
+                        }
+                        _ => {}
+                    }
+
+                    if el.can_have_content() {
+                        state.depth += 1;
+                        let end_state = child_state.clone();
+                        let on_end_tag: EndTagHandler<'static> = Box::new(move |_| {
+                            let mut state = end_state.lock().unwrap();
+                            state.depth -= 1;
+                            if let Some(image) = state.picture.take().and_then(|sources| sources.segment()) {
+                                state.segments.push(image);
+                            }
+                            if let Some(media) = state.media.take().and_then(|media| media.segment()) {
+                                state.segments.push(media);
+                            }
+                            if state.depth == 0 {
+                                if let Some((tag, style)) = state.styled.take() {
+                                    let text = decode_entities(&std::mem::take(&mut state.text));
+                                    let segment = html_utils::styled_text(&tag, style.as_deref(), &text, state.name.as_deref());
+                                    state.segments.extend(segment);
+                                }
+                            }
+                            if state.depth == 0 && state.table.is_some() {
+                                state.end_cell();
+                                if let Some(table) = state.table.take().and_then(|table| table.finish(state.name.as_deref())) {
+                                    state.segments.push(Segment::table(table));
+                                }
+                            }
+                            Ok(())
+                        });
+                        el.on_end_tag(on_end_tag)?;
+                    }
+
+                    Ok(())
+                }),
+                element!(picture_sources, move |el| {
+                    let attributes = attributes_of(el);
+                    if let Some(picture) = &mut picture_state.lock().unwrap().picture {
+                        picture.add_element(attributes.iter().map(|(name, value)| (name.as_str(), value.as_str())));
+                    }
+                    Ok(())
+                }),
+                element!(media_sources, move |el| {
+                    let attributes = attributes_of(el);
+                    if let Some(media) = &mut media_state.lock().unwrap().media {
+                        media.add_source_element(attributes.iter().map(|(name, value)| (name.as_str(), value.as_str())));
+                    }
+                    Ok(())
+                }),
+                element!(table_rows, move |_| {
+                    let mut state = row_state.lock().unwrap();
+                    state.end_cell();
+                    if let Some(table) = &mut state.table {
+                        table.start_row(false);
+                    }
+                    Ok(())
+                }),
+                element!(table_head_rows, move |_| {
+                    let mut state = head_row_state.lock().unwrap();
+                    state.end_cell();
+                    if let Some(table) = &mut state.table {
+                        table.start_row(true);
+                    }
+                    Ok(())
+                }),
+                element!(table_cells, move |el| {
+                    let mut state = cell_state.lock().unwrap();
+                    state.end_cell();
+                    if let Some(table) = &mut state.table {
+                        table.start_cell(el.tag_name() == "th");
+                        state.in_cell = true;
+                    }
+
+                    if el.can_have_content() {
+                        let end_state = cell_state.clone();
+                        let on_end_tag: EndTagHandler<'static> = Box::new(move |_| {
+                            end_state.lock().unwrap().end_cell();
+                            Ok(())
+                        });
+                        el.on_end_tag(on_end_tag)?;
+                    }
+                    Ok(())
+                }),
+                element!(top_lists, move |el| {
+                    let mut state = top_list_state.lock().unwrap();
+                    if state.segments.last().and_then(Segment::as_str).is_some_and(|text| !text.ends_with('\n')) {
+                        state.segments.push(Segment::text("\n"));
+                    }
+                    state.lists.push(ListLevel::of(&el.tag_name(), el.get_attribute("start").as_deref()));
+                    el.on_end_tag(end_list(top_list_state.clone()))?;
+                    Ok(())
+                }),
+                element!(nested_lists, move |el| {
+                    let mut state = nested_list_state.lock().unwrap();
+                    if !state.lists.is_empty() {
+                        state.lists.push(ListLevel::of(&el.tag_name(), el.get_attribute("start").as_deref()));
+                        el.on_end_tag(end_list(nested_list_state.clone()))?;
+                    }
+                    Ok(())
+                }),
+                element!(list_items, move |el| {
+                    let mut state = item_state.lock().unwrap();
+                    let Some(level) = state.lists.len().checked_sub(1) else {
+                        return Ok(());
+                    };
+
+                    // An item without an end tag ends at the next item of its list.
+                    state.end_items(level);
+                    let marker = state.lists[level].next_marker();
+                    let index = state.segments.len();
+                    state.items.push((index, level, marker, String::new()));
+                    state.segments.push(Segment::text(""));
+
+                    if el.can_have_content() {
+                        let end_state = item_state.clone();
+                        let on_end_tag: EndTagHandler<'static> = Box::new(move |_| {
+                            end_state.lock().unwrap().end_items(level);
+                            Ok(())
+                        });
+                        el.on_end_tag(on_end_tag)?;
+                    }
+                    Ok(())
+                }),
+                text!(display, move |chunk| {
+                    let mut state = text_state.lock().unwrap();
+                    if state.depth > 0 {
+                        let level = state.lists.len();
+                        if state.in_cell {
+                            state.text.push_str(chunk.as_str());
+                        } else if let Some(item) = state.items.last_mut().filter(|item| item.1 + 1 == level) {
+                            item.3.push_str(chunk.as_str());
+                        } else if state.styled.is_some() {
+                            state.text.push_str(chunk.as_str());
+                        }
+                        return Ok(());
+                    }
+
+                    state.text.push_str(chunk.as_str());
+                    if chunk.last_in_text_node() && !state.text.is_empty() {
+                        let text = html_utils::decode_text(&decode_entities(&std::mem::take(&mut state.text)), state.name.as_deref());
+                        state.segments.push(Segment::text(text));
+                    }
+
+                    Ok(())
+                }),
+            ],
+            ..Settings::new_send()
+        };
+
+        Self {
+            rewriter: HtmlRewriter::new(settings, Discard),
+            state,
+            options,
+        }
+    }
+
+    /// Feed the next chunk of the response body.
+    pub(crate) fn write(&mut self, chunk: &[u8]) -> Result<()> {
+        self.rewriter
+            .write(chunk)
+            .map_err(|err| anyhow!("Failed to parse the result page: {}", err))
+    }
+
+    /// Finish parsing and take the segments.
+    pub(crate) fn finish(self) -> Result<Segments> {
+        self.rewriter
+            .end()
+            .map_err(|err| anyhow!("Failed to parse the result page: {}", err))?;
+
+        let mut state = Arc::try_unwrap(self.state)
+            .ok()
+            .context("Failed to take the parsed segments")?
+            .into_inner()
+            .unwrap();
+
+        if !state.found {
+            return Err(anyhow!("Failed to get the next element"));
+        }
+        // Lists cut off by the end of the page end there, as in a DOM.
+        state.end_items(0);
+
+        Ok(html_utils::finish_segments(self.options, Segments(state.segments)))
+    }
+}
+
+/// The CSS selectors of the given paths under every result element, as one selector list.
+fn css_under(selectors: &Selectors, paths: &[String]) -> String {
+    selectors
+        .post_display_css
+        .iter()
+        .flat_map(|selector| paths.iter().map(move |path| format!("{} > {}", selector, path)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The attributes of an element, decoded as a DOM parser would.
+fn attributes_of(el: &Element) -> Vec<(String, String)> {
+    el.attributes()
+        .iter()
+        .map(|attribute| (attribute.name(), decode_entities(&attribute.value())))
+        .collect()
+}
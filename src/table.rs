@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+
+use crate::segment::Segment;
+#[cfg(any(feature = "onebot", feature = "satori"))]
+use crate::segment::Segments;
+use crate::html_utils::decode_text;
+
+/**
+A table of a shindan result, e.g. a stat sheet.
+
+The first row becomes the headers when it is in a `thead` or made of `th` cells only.
+
+# Examples
+```
+use shindan_maker::{Segments, TableSegment};
+
+let html = r#"<span id="post_display"><table>
+    <tr><th>Stat</th><th>Value</th></tr>
+    <tr><td>STR</td><td>10</td></tr>
+    <tr><td>Luck</td><td>100</td></tr>
+</table></span>"#;
+
+let segments = Segments::from_html(html).unwrap();
+let table = segments[0].as_table().unwrap();
+
+assert_eq!(vec!["Stat", "Value"], table.headers);
+assert_eq!("Stat | Value\n-----+------\nSTR  | 10\nLuck | 100", table.to_plain_text());
+```
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableSegment {
+    /// The header cells; empty if the table has no header row.
+    pub headers: Vec<String>,
+    /// The text of the cells of each body row.
+    pub rows: Vec<Vec<String>>,
+}
+
+impl TableSegment {
+    /// Render the table as text, with its columns aligned and the headers underlined.
+    pub fn to_plain_text(&self) -> String {
+        let columns = self.rows.iter().map(Vec::len).chain([self.headers.len()]).max().unwrap_or(0);
+        let mut widths = vec![0; columns];
+        for row in [&self.headers].into_iter().chain(&self.rows) {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let line = |row: &[String]| {
+            let cells: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(i, width)| format!("{:width$}", row.get(i).map(String::as_str).unwrap_or_default()))
+                .collect();
+            cells.join(" | ").trim_end().to_string()
+        };
+
+        let mut lines = Vec::with_capacity(self.rows.len() + 2);
+        if !self.headers.is_empty() {
+            lines.push(line(&self.headers));
+            lines.push(widths.iter().map(|width| "-".repeat(*width)).collect::<Vec<_>>().join("-+-"));
+        }
+        lines.extend(self.rows.iter().map(|row| line(row)));
+        lines.join("\n")
+    }
+
+    /// Render the table as plain text on lines of its own, for output that cannot show tables.
+    ///
+    /// # Arguments
+    /// - `output` - The output so far, which the table starts a new line of.
+    pub(crate) fn to_text_block(&self, output: &str) -> String {
+        format!("{}{}\n", line_break_before(output), self.to_plain_text())
+    }
+}
+
+/// The line break a block of lines starts with, unless the output so far is empty or ends a line.
+pub(crate) fn line_break_before(output: &str) -> &'static str {
+    if output.is_empty() || output.ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    }
+}
+
+#[cfg(any(feature = "onebot", feature = "satori"))]
+impl Segments {
+    /// The segments, each with the segment before it, for output that falls back to [`Segment::table_text`].
+    pub(crate) fn with_previous(&self) -> impl Iterator<Item = (Option<&Segment>, &Segment)> {
+        std::iter::once(None).chain(self.iter().map(Some)).zip(self.iter())
+    }
+}
+
+impl Segment {
+    /// The text a table segment falls back to in output that cannot show tables, on lines
+    /// of its own after the text of the segment before it, if any.
+    #[cfg(any(feature = "onebot", feature = "satori"))]
+    pub(crate) fn table_text(&self, previous: Option<&Segment>) -> Option<String> {
+        let previous = previous.and_then(|segment| segment.data.get("text")).and_then(serde_json::Value::as_str).unwrap_or_default();
+        self.as_table().map(|table| table.to_text_block(previous))
+    }
+
+    /// Create a table segment.
+    pub fn table(table: TableSegment) -> Self {
+        Self::new("table", serde_json::to_value(table).unwrap_or_default())
+    }
+
+    /// Read the table of a table segment.
+    pub fn as_table(&self) -> Option<TableSegment> {
+        if self.type_ != "table" {
+            return None;
+        }
+        serde_json::from_value(self.data.clone()).ok()
+    }
+}
+
+/// Collects the rows and cells of a table as its elements are visited in document order.
+#[derive(Debug, Default)]
+pub(crate) struct TableBuilder {
+    /// The rows read so far, and whether each is a header row.
+    rows: Vec<(bool, Vec<String>)>,
+    in_head: bool,
+}
+
+impl TableBuilder {
+    /// Start a row, which is in a `thead` if `head`.
+    pub(crate) fn start_row(&mut self, head: bool) {
+        self.in_head = head;
+        self.rows.push((true, Vec::new()));
+    }
+
+    /// Start a cell of the current row, which is a `th` if `header`.
+    pub(crate) fn start_cell(&mut self, header: bool) {
+        let head = self.in_head;
+        if self.rows.is_empty() {
+            self.start_row(head);
+        }
+        if let Some((row_header, cells)) = self.rows.last_mut() {
+            *row_header &= head || header;
+            cells.push(String::new());
+        }
+    }
+
+    /// Add text to the current cell.
+    pub(crate) fn push_text(&mut self, text: &str) {
+        if let Some(cell) = self.rows.last_mut().and_then(|(_, cells)| cells.last_mut()) {
+            cell.push_str(text);
+        }
+    }
+
+    /// The table, with references decoded but for those of the submitted name, and whitespace
+    /// collapsed in its cells; `None` if it has no cells.
+    pub(crate) fn finish(self, name: Option<&str>) -> Option<TableSegment> {
+        let mut rows: Vec<(bool, Vec<String>)> = self
+            .rows
+            .into_iter()
+            .filter(|(_, cells)| !cells.is_empty())
+            .map(|(header, cells)| {
+                let cells = cells
+                    .iter()
+                    .map(|cell| decode_text(cell, name).split_whitespace().collect::<Vec<_>>().join(" "))
+                    .collect();
+                (header, cells)
+            })
+            .collect();
+
+        if rows.is_empty() {
+            return None;
+        }
+
+        let headers = match rows.first() {
+            Some((true, _)) => rows.remove(0).1,
+            _ => Vec::new(),
+        };
+        Some(TableSegment { headers, rows: rows.into_iter().map(|(_, cells)| cells).collect() })
+    }
+}
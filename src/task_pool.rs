@@ -0,0 +1,220 @@
+use std::future::Future;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use anyhow::Result;
+use tokio::sync::oneshot;
+
+use crate::client::ShindanClient;
+use crate::metadata::ShindanMetadata;
+use crate::shindan_id::ShindanId;
+#[cfg(feature = "segments")]
+use crate::result::ShindanResult;
+
+/// The kind of a job of a [`ShindanTaskPool`], each with its own concurrency limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TaskKind {
+    /// Fetching titles, descriptions and other metadata. Defaults to 8 at a time.
+    Metadata,
+    /// Submitting shindans. Defaults to 4 at a time.
+    Submission,
+    /// Rendering results, e.g. to images. Defaults to 1 at a time.
+    Render,
+}
+
+impl TaskKind {
+    const ALL: [TaskKind; 3] = [TaskKind::Metadata, TaskKind::Submission, TaskKind::Render];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn default_limit(self) -> usize {
+        match self {
+            TaskKind::Metadata => 8,
+            TaskKind::Submission => 4,
+            TaskKind::Render => 1,
+        }
+    }
+}
+
+/// Which jobs of a [`ShindanTaskPool`] go first when their kind is at its limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Priority {
+    /// Jobs a user is waiting for, e.g. chat commands; they go before every batch job.
+    Interactive,
+    /// Background jobs, e.g. crawling; they go in order once no interactive job waits.
+    Batch,
+}
+
+/**
+Runs the jobs of a client with a concurrency limit per kind of job, letting interactive
+jobs skip ahead of batch ones, e.g. so that a crawler sharing the client with a chat bot
+does not keep the bot's users waiting.
+
+The pool is cheap to clone; clones share their limits and queues.
+
+# Examples
+```
+use shindan_maker::{Priority, ShindanClient, ShindanDomain, ShindanTaskPool, TaskKind};
+
+#[tokio::main]
+async fn main() {
+    let client = ShindanClient::new(ShindanDomain::En).unwrap();
+    let pool = ShindanTaskPool::new(client).limit(TaskKind::Submission, 2);
+
+    let crawler = pool.clone();
+    tokio::spawn(async move {
+        for id in ["1222992", "1000001"] {
+            let _ = crawler.get_metadata(id, Priority::Batch).await;
+        }
+    });
+
+    let result = pool.get_result("1222992", "Alice", Priority::Interactive).await.unwrap();
+    println!("{}", result.segments);
+}
+```
+*/
+#[derive(Debug, Clone)]
+pub struct ShindanTaskPool {
+    client: ShindanClient,
+    slots: [Arc<Slots>; 3],
+}
+
+/// The permits and queued jobs of a kind.
+#[derive(Debug)]
+struct Slots {
+    state: Mutex<SlotState>,
+}
+
+#[derive(Debug)]
+struct SlotState {
+    available: usize,
+    interactive: VecDeque<oneshot::Sender<Permit>>,
+    batch: VecDeque<oneshot::Sender<Permit>>,
+}
+
+/// A running job of a kind, which hands its slot to the next queued job when dropped.
+#[derive(Debug)]
+struct Permit {
+    slots: Option<Arc<Slots>>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Some(slots) = self.slots.take() {
+            slots.release();
+        }
+    }
+}
+
+impl Slots {
+    fn new(limit: usize) -> Self {
+        Self {
+            state: Mutex::new(SlotState { available: limit.max(1), interactive: VecDeque::new(), batch: VecDeque::new() }),
+        }
+    }
+
+    async fn acquire(self: &Arc<Self>, priority: Priority) -> Permit {
+        let receiver = {
+            let mut state = self.state.lock().unwrap();
+            let queued = match priority {
+                Priority::Interactive => !state.interactive.is_empty(),
+                Priority::Batch => !state.interactive.is_empty() || !state.batch.is_empty(),
+            };
+            if state.available > 0 && !queued {
+                state.available -= 1;
+                return Permit { slots: Some(self.clone()) };
+            }
+
+            let (sender, receiver) = oneshot::channel();
+            match priority {
+                Priority::Interactive => state.interactive.push_back(sender),
+                Priority::Batch => state.batch.push_back(sender),
+            }
+            receiver
+        };
+
+        // The sender is only dropped along with the pool, which this future borrows.
+        receiver.await.expect("The task pool dropped a queued job")
+    }
+
+    /// Hand a slot to the next queued job still waiting, or free it.
+    fn release(self: &Arc<Self>) {
+        let mut state = self.state.lock().unwrap();
+        while let Some(sender) = state.interactive.pop_front().or_else(|| state.batch.pop_front()) {
+            match sender.send(Permit { slots: Some(self.clone()) }) {
+                Ok(()) => return,
+                // The job was cancelled; disarm its permit, which would release the slot again.
+                Err(mut permit) => permit.slots = None,
+            }
+        }
+        state.available += 1;
+    }
+}
+
+impl ShindanTaskPool {
+    /// Create a pool running jobs with a client, with the default limits of [`TaskKind`].
+    pub fn new(client: ShindanClient) -> Self {
+        Self {
+            client,
+            slots: TaskKind::ALL.map(|kind| Arc::new(Slots::new(kind.default_limit()))),
+        }
+    }
+
+    /// Set how many jobs of a kind run at a time, at least 1.
+    pub fn limit(mut self, kind: TaskKind, limit: usize) -> Self {
+        self.slots[kind.index()] = Arc::new(Slots::new(limit));
+        self
+    }
+
+    /**
+    Run a job once its kind has a free slot.
+
+    # Arguments
+    - `kind` - The kind of the job.
+    - `priority` - Whether the job goes before batch jobs.
+    - `job` - The job, given a clone of the client.
+
+    # Returns
+    The output of the job.
+    */
+    pub async fn run<F, Fut, T>(&self, kind: TaskKind, priority: Priority, job: F) -> T
+    where
+        F: FnOnce(ShindanClient) -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let _permit = self.slots[kind.index()].acquire(priority).await;
+        job(self.client.clone()).await
+    }
+
+    /// Get the metadata of a shindan as a [`TaskKind::Metadata`] job, see [`ShindanClient::get_metadata`].
+    pub async fn get_metadata(
+        &self,
+        id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>,
+        priority: Priority,
+    ) -> Result<ShindanMetadata> {
+        self.run(TaskKind::Metadata, priority, |client| async move { client.get_metadata(id).await }).await
+    }
+
+    /// Get the result of a shindan as a [`TaskKind::Submission`] job, see [`ShindanClient::get_result`].
+    #[cfg(feature = "segments")]
+    pub async fn get_result(
+        &self,
+        id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>,
+        name: &str,
+        priority: Priority,
+    ) -> Result<ShindanResult> {
+        self.run(TaskKind::Submission, priority, |client| async move { client.get_result(id, name).await }).await
+    }
+
+    /// Capture the result of a shindan as a [`TaskKind::Render`] job, see [`ShindanClient::get_image`].
+    #[cfg(feature = "image")]
+    pub async fn get_image(
+        &self,
+        id: impl TryInto<ShindanId, Error: Into<anyhow::Error>>,
+        name: &str,
+        priority: Priority,
+    ) -> Result<Vec<u8>> {
+        self.run(TaskKind::Render, priority, |client| async move { client.get_image(id, name).await }).await
+    }
+}
@@ -0,0 +1,205 @@
+/*!
+Hand-written fixtures and a mock ShindanMaker server for testing code built on this crate.
+
+The fixtures are not captures of live pages: they keep only the markup this crate reads,
+so they test code built on the parsed results rather than the parsers against the live site.
+
+Enable the `test-util` feature, preferably as a dev-dependency:
+
+```toml
+[dev-dependencies]
+shindan-maker = { version = "*", features = ["test-util"] }
+```
+
+# Examples
+```
+use shindan_maker::test_util::{MockShindan, PLAIN_TEXT};
+
+#[tokio::main]
+async fn main() {
+    let server = MockShindan::start().await;
+    let client = server.client().unwrap();
+
+    let (segments, title) = client
+        .get_segments_with_title(PLAIN_TEXT.id, "test_user")
+        .await
+        .unwrap();
+
+    assert_eq!(PLAIN_TEXT.title, title);
+    assert!(segments.to_string().starts_with("test_user's stats:"));
+}
+```
+*/
+
+use anyhow::Result;
+use reqwest::Url;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+use crate::client::ShindanClient;
+use crate::builder::ShindanClientBuilder;
+use crate::shindan_domain::ShindanDomain;
+
+/// The session cookie set by the mock server.
+pub const SESSION_COOKIE: &str = "fixture-session";
+
+const PAGE: &str = include_str!("fixtures/page.html");
+
+/// A hand-written shindan: its form page and its result page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fixture {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    /// The result page, with `{name}` where the submitted name goes.
+    pub result: &'static str,
+}
+
+impl Fixture {
+    /// The form page of the shindan.
+    pub fn page(&self) -> String {
+        PAGE.replace("{id}", self.id)
+            .replace("{title}", self.title)
+            .replace("{description}", self.description)
+    }
+
+    /// The result page of the shindan for a name, escaped once as the site does.
+    pub fn result_for(&self, name: &str) -> String {
+        let name = name
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+            .replace('\'', "&#039;");
+        self.result.replace("{name}", &name)
+    }
+}
+
+/// A result made of text and line breaks only.
+pub const PLAIN_TEXT: Fixture = Fixture {
+    id: "1222992",
+    title: "Fantasy Stats",
+    description: "Find out your fantasy stats!<br>Results change daily.",
+    result: include_str!("fixtures/plain_text.html"),
+};
+
+/// A result with a lazily loaded image.
+pub const IMAGES: Fixture = Fixture {
+    id: "1000001",
+    title: "Spirit Animal",
+    description: "Which animal guides you?",
+    result: include_str!("fixtures/images.html"),
+};
+
+/// A result with a Chart.js radar chart.
+pub const CHART: Fixture = Fixture {
+    id: "1000002",
+    title: "Battle Stats",
+    description: "How strong are you?",
+    result: include_str!("fixtures/chart.html"),
+};
+
+/// A result with a typing effect and its `<noscript>` fallback.
+pub const EFFECTS: Fixture = Fixture {
+    id: "1000003",
+    title: "Lucky Draw",
+    description: "Draw a card!",
+    result: include_str!("fixtures/effects.html"),
+};
+
+/// Every fixture.
+pub const FIXTURES: [Fixture; 4] = [PLAIN_TEXT, IMAGES, CHART, EFFECTS];
+
+/// Answers a submission with the result page for the submitted name.
+struct ResultResponder(Fixture);
+
+impl Respond for ResultResponder {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let body = String::from_utf8_lossy(&request.body);
+        let name = Url::parse(&format!("http://fixture/?{}", body))
+            .ok()
+            .and_then(|url| {
+                url.query_pairs()
+                    .find(|(key, _)| key == "user_input_value_1")
+                    .map(|(_, value)| value.into_owned())
+            })
+            .unwrap_or_default();
+
+        ResponseTemplate::new(200).set_body_raw(self.0.result_for(&name), "text/html; charset=UTF-8")
+    }
+}
+
+/// A local server answering like ShindanMaker for the fixtures.
+#[derive(Debug)]
+pub struct MockShindan {
+    server: MockServer,
+}
+
+impl MockShindan {
+    /// Start a server serving every fixture in [`FIXTURES`].
+    pub async fn start() -> Self {
+        Self::with_fixtures(&FIXTURES).await
+    }
+
+    /**
+    Start a server serving some fixtures.
+
+    # Arguments
+    - `fixtures` - The shindans to serve; other IDs answer 404.
+
+    # Returns
+    The running server, stopped when dropped.
+    */
+    pub async fn with_fixtures(fixtures: &[Fixture]) -> Self {
+        let server = MockServer::start().await;
+
+        for fixture in fixtures {
+            let route = format!("/{}", fixture.id);
+
+            Mock::given(method("GET"))
+                .and(path(route.as_str()))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .insert_header("Set-Cookie", format!("_session={}; path=/", SESSION_COOKIE))
+                        .set_body_raw(fixture.page(), "text/html; charset=UTF-8"),
+                )
+                .mount(&server)
+                .await;
+
+            Mock::given(method("POST"))
+                .and(path(route.as_str()))
+                .respond_with(ResultResponder(*fixture))
+                .mount(&server)
+                .await;
+        }
+
+        Self { server }
+    }
+
+    /// The base URL of the server, e.g. `http://127.0.0.1:1234`.
+    pub fn uri(&self) -> String {
+        self.server.uri()
+    }
+
+    /// The underlying server, for mounting extra mocks or inspecting requests.
+    pub fn server(&self) -> &MockServer {
+        &self.server
+    }
+
+    /// A client builder pointed at the server, with polite mode disabled.
+    pub fn builder(&self) -> ShindanClientBuilder {
+        ShindanClient::builder(ShindanDomain::En)
+            .base_url(&format!("{}/", self.uri()))
+            .polite(false)
+    }
+
+    /**
+    A client pointed at the server.
+
+    # Errors
+    Returns error if the HTTP client cannot be built.
+    */
+    pub fn client(&self) -> Result<ShindanClient> {
+        self.builder().build()
+    }
+}
@@ -0,0 +1,84 @@
+use std::fmt;
+use serde_json::Value;
+use async_trait::async_trait;
+use anyhow::{anyhow, Result};
+
+use crate::segment::{Segment, Segments};
+
+/**
+A machine translation service, e.g. DeepL or Google Translate, for the text of results.
+
+It is called once per result with the text segments that carry words, in order;
+line breaks and images are kept as they are, so the translated result has the
+same layout as the original. The target language is up to the implementation.
+
+# Examples
+```
+use anyhow::Result;
+use async_trait::async_trait;
+use shindan_maker::{ShindanClient, ShindanDomain, Translator};
+
+#[derive(Debug)]
+struct Shout;
+
+#[async_trait]
+impl Translator for Shout {
+    async fn translate(&self, texts: &[String]) -> Result<Vec<String>> {
+        Ok(texts.iter().map(|text| text.to_uppercase()).collect())
+    }
+}
+
+fn main() -> Result<()> {
+    let client = ShindanClient::builder(ShindanDomain::Jp)
+        .translator(Shout)
+        .build()?;
+    Ok(())
+}
+```
+*/
+#[async_trait]
+pub trait Translator: fmt::Debug + Send + Sync {
+    /// Translate texts, returning one translation per text in the same order.
+    async fn translate(&self, texts: &[String]) -> Result<Vec<String>>;
+}
+
+impl Segments {
+    /**
+    Translate the text segments, keeping line breaks, images and other segments as they are.
+
+    # Arguments
+    - `translator` - The translation service.
+
+    # Returns
+    The translated segments.
+
+    # Errors
+    Returns error if the translator fails or returns another number of texts than it was given.
+    */
+    pub async fn translate(&self, translator: &dyn Translator) -> Result<Segments> {
+        let is_words = |segment: &Segment| segment.type_ == "text" && segment.as_str().is_some_and(|text| !text.trim().is_empty());
+
+        let texts: Vec<String> = self.iter().filter(|segment| is_words(segment)).filter_map(Segment::get_str).collect();
+        if texts.is_empty() {
+            return Ok(self.clone());
+        }
+
+        let translations = translator.translate(&texts).await?;
+        if translations.len() != texts.len() {
+            return Err(anyhow!("The translator returned {} texts for {}", translations.len(), texts.len()));
+        }
+
+        let mut translations = translations.into_iter();
+        Ok(Segments(
+            self.iter()
+                .map(|segment| {
+                    let mut segment = segment.clone();
+                    if is_words(&segment) {
+                        segment.data["text"] = Value::String(translations.next().unwrap_or_default());
+                    }
+                    segment
+                })
+                .collect(),
+        ))
+    }
+}
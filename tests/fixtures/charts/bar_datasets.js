@@ -0,0 +1,12 @@
+var ctx = document.getElementById("chart").getContext("2d");
+var chart = new Chart(ctx, {
+    "type": "bar",
+    "data": {
+        "labels": ["Mon", "Tue", "Wed"],
+        "datasets": [
+            { "label": "today", "data": [3, 1, 4] },
+            { "data": [1, 5, 9] },
+            { "label": "tomorrow", "data": [2, 6, 5] }
+        ]
+    }
+});
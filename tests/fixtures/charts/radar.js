@@ -0,0 +1,15 @@
+window.addEventListener('load', function () {
+    new Chart(document.getElementById('chart_1222992'), {
+        type: 'radar',
+        data: {
+            labels: ['STR', 'DEX', 'INT', 'LUK'],
+            datasets: [{
+                label: 'status',
+                data: [72, 48.5, 100, 0],
+                backgroundColor: 'rgba(54, 162, 235, 0.2)',
+                borderColor: 'rgb(54, 162, 235)'
+            }]
+        },
+        options: { scale: { ticks: { beginAtZero: true, max: 100 } } }
+    });
+});